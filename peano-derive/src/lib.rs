@@ -1,46 +1,333 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, DeriveInput};
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Fields, Generics, Index, LitStr, Member,
+    Path,
+};
 
-#[proc_macro_derive(Component)]
+#[proc_macro_derive(Component, attributes(component))]
 pub fn derive_component(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as DeriveInput);
-    let name = input.ident;
-    let type_name = name.to_string();
+    let mut input = parse_macro_input!(input as DeriveInput);
+    if let Err(err) = reject_unions(&input, "Component") {
+        return err.to_compile_error().into();
+    }
+    let attrs = match ComponentAttrs::parse(&input) {
+        Ok(attrs) => attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    add_component_bounds(&mut input.generics);
+    if attrs.scene.is_some() && !input.generics.params.is_empty() {
+        // Saving needs the serde impls of this instantiation
+        let name = &input.ident;
+        let (_, ty_generics, _) = input.generics.split_for_impl();
+        let bound = parse_quote! {
+            #name #ty_generics: serde::Serialize + serde::de::DeserializeOwned
+        };
+        input.generics.make_where_clause().predicates.push(bound);
+    }
+    let fields = component_fields(&input);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let storage = match attrs.storage {
+        Some(StorageType::Sparse) => quote! { StorageType::Sparse },
+        _ => quote! { StorageType::Table },
+    };
+    let on_add = option_tokens(attrs.on_add.as_ref());
+    let on_insert = option_tokens(attrs.on_insert.as_ref());
+    let on_replace = option_tokens(attrs.on_replace.as_ref());
+    let on_remove = option_tokens(attrs.on_remove.as_ref());
+    let scene = match attrs.scene {
+        Some(Scene::Plain) => quote! { Some(SceneRegistration::of::<#name #ty_generics>()) },
+        Some(Scene::Mapped) => quote! { Some(SceneRegistration::mapped::<#name #ty_generics>()) },
+        None => quote! { None },
+    };
 
-    quote! {
-        impl Component for #name {
-            fn get_type_id(&self) -> usize {
-                get_component_id::<Self>()
-            }
+    let component_impl = |generic_registration| {
+        quote! {
+            impl #impl_generics Component for #name #ty_generics #where_clause {
+                fn get_type_id(&self) -> usize {
+                    get_component_id::<Self>()
+                }
 
-            fn as_any(&self) -> &dyn std::any::Any {
-                self
-            }
+                fn as_any(&self) -> &dyn std::any::Any {
+                    self
+                }
 
-            fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
-                self
+                fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+                    self
+                }
+
+                #generic_registration
             }
         }
+    };
 
-        inventory::submit! {
-            ComponentRegistration {
-                type_id: typeid::ConstTypeId::of::<#name>(),
-                name: #type_name,
+    if input.generics.params.is_empty() {
+        let type_name = name.to_string();
+        let key = match attrs.id {
+            Some(id) => quote! { #id },
+            None => quote! { concat!(module_path!(), "::", #type_name) },
+        };
+        let component_impl = component_impl(quote! {});
+        quote! {
+            #component_impl
+
+            inventory::submit! {
+                ComponentRegistration {
+                    type_id: typeid::ConstTypeId::of::<#name>(),
+                    name: #type_name,
+                    key: #key,
+                    fields: &[#(#fields),*],
+                    cast: |ptr| ptr as *mut #name as *mut dyn std::any::Any,
+                    storage: #storage,
+                    on_add: #on_add,
+                    on_insert: #on_insert,
+                    on_replace: #on_replace,
+                    on_remove: #on_remove,
+                    scene: #scene,
+                }
             }
         }
+        .into()
+    } else {
+        if let Some(id) = attrs.id {
+            return syn::Error::new_spanned(id, "generic components cannot have an explicit id")
+                .to_compile_error()
+                .into();
+        }
+        component_impl(quote! {
+            fn generic_registration() -> Option<ComponentRegistration> {
+                Some(ComponentRegistration {
+                    type_id: typeid::ConstTypeId::of::<Self>(),
+                    name: std::any::type_name::<Self>(),
+                    key: std::any::type_name::<Self>(),
+                    fields: Vec::leak(vec![#(#fields),*]),
+                    cast: |ptr| ptr as *mut Self as *mut dyn std::any::Any,
+                    storage: #storage,
+                    on_add: #on_add,
+                    on_insert: #on_insert,
+                    on_replace: #on_replace,
+                    on_remove: #on_remove,
+                    scene: #scene,
+                })
+            }
+        })
+        .into()
     }
-    .into()
+}
+
+/**
+ * Unions cannot be dropped or reflected safely, so they
+ * cannot be components, resources or events
+ */
+fn reject_unions(input: &DeriveInput, derive: &str) -> syn::Result<()> {
+    match &input.data {
+        Data::Union(data) => Err(syn::Error::new_spanned(
+            data.union_token,
+            format!("`{derive}` cannot be derived for unions"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/**
+ * Requires every type parameter to be `Send + Sync + 'static`,
+ * which components and resources have to be
+ */
+fn add_component_bounds(generics: &mut Generics) {
+    let params: Vec<_> = generics.type_params().map(|p| p.ident.clone()).collect();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        where_clause
+            .predicates
+            .push(parse_quote! { #param: Send + Sync + 'static });
+    }
+}
+
+fn option_tokens(path: Option<&Path>) -> proc_macro2::TokenStream {
+    match path {
+        Some(path) => quote! { Some(#path) },
+        None => quote! { None },
+    }
+}
+
+enum StorageType {
+    Table,
+    Sparse,
+}
+
+/**
+ * The options given with `#[component(...)]`
+ */
+#[derive(Default)]
+struct ComponentAttrs {
+    id: Option<LitStr>,
+    storage: Option<StorageType>,
+    on_add: Option<Path>,
+    on_insert: Option<Path>,
+    on_replace: Option<Path>,
+    on_remove: Option<Path>,
+    scene: Option<Scene>,
+}
+
+/**
+ * How a component is saved in scenes
+ */
+enum Scene {
+    Plain,
+    Mapped,
+}
+
+impl ComponentAttrs {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut attrs = Self::default();
+        for attr in input
+            .attrs
+            .iter()
+            .filter(|a| a.path().is_ident("component"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("id") {
+                    let id: LitStr = meta.value()?.parse()?;
+                    if id.value().is_empty() {
+                        return Err(syn::Error::new_spanned(id, "component id cannot be empty"));
+                    }
+                    attrs.id = Some(id);
+                } else if meta.path.is_ident("storage") {
+                    let storage: LitStr = meta.value()?.parse()?;
+                    attrs.storage = Some(match storage.value().as_str() {
+                        "table" => StorageType::Table,
+                        "sparse" => StorageType::Sparse,
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                storage,
+                                "unknown storage, expected \"table\" or \"sparse\"",
+                            ))
+                        }
+                    });
+                } else if meta.path.is_ident("on_add") {
+                    attrs.on_add = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("on_insert") {
+                    attrs.on_insert = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("on_replace") {
+                    attrs.on_replace = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("on_remove") {
+                    attrs.on_remove = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("scene") {
+                    attrs.scene.get_or_insert(Scene::Plain);
+                } else if meta.path.is_ident("map_entities") {
+                    attrs.scene = Some(Scene::Mapped);
+                } else {
+                    return Err(meta.error(
+                        "unknown component attribute, expected `id`, `storage`, `scene`, \
+                         `map_entities` or a hook like `on_add`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(attrs)
+    }
+}
+
+/**
+ * Builds a `ComponentField` for every field of a struct
+ *
+ * Enums have no fields with a fixed offset, so
+ * no fields are reflected for them.
+ */
+fn component_fields(input: &DeriveInput) -> Vec<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Vec::new();
+    };
+    let name = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let name = quote! { #name #ty_generics };
+
+    let members: Vec<(Member, String)> = match &data.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|f| {
+                let ident = f.ident.clone().unwrap();
+                (Member::Named(ident.clone()), ident.to_string())
+            })
+            .collect(),
+        Fields::Unnamed(fields) => (0..fields.unnamed.len())
+            .map(|i| (Member::Unnamed(Index::from(i)), i.to_string()))
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    members
+        .into_iter()
+        .zip(data.fields.iter())
+        .map(|((member, field_name), field)| {
+            let ty = &field.ty;
+            quote! {
+                ComponentField {
+                    name: #field_name,
+                    type_name: std::any::type_name::<#ty>,
+                    type_id: typeid::ConstTypeId::of::<#ty>(),
+                    offset: std::mem::offset_of!(#name, #member),
+                    get: |component| {
+                        component
+                            .downcast_ref::<#name>()
+                            .map(|c| &c.#member as &dyn std::any::Any)
+                    },
+                    get_mut: |component| {
+                        component
+                            .downcast_mut::<#name>()
+                            .map(|c| &mut c.#member as &mut dyn std::any::Any)
+                    },
+                    set: |component, value| match component.downcast_mut::<#name>() {
+                        Some(c) => {
+                            c.#member = *value.downcast::<#ty>()?;
+                            Ok(())
+                        }
+                        None => Err(value),
+                    },
+                }
+            }
+        })
+        .collect()
 }
 
 #[proc_macro_derive(Resource)]
 pub fn derive_resource(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as DeriveInput);
-    let name = input.ident;
-    let type_name = name.to_string();
+    let mut input = parse_macro_input!(input as DeriveInput);
+    if let Err(err) = reject_unions(&input, "Resource") {
+        return err.to_compile_error().into();
+    }
+    add_component_bounds(&mut input.generics);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let generic = !input.generics.params.is_empty();
+
+    let generic_registration = generic.then(|| {
+        quote! {
+            fn generic_registration() -> Option<ResourceRegistration> {
+                Some(ResourceRegistration {
+                    type_id: typeid::ConstTypeId::of::<Self>(),
+                    name: std::any::type_name::<Self>(),
+                })
+            }
+        }
+    });
+    let registration = (!generic).then(|| {
+        let type_name = name.to_string();
+        quote! {
+            inventory::submit! {
+                ResourceRegistration {
+                    type_id: typeid::ConstTypeId::of::<#name>(),
+                    name: #type_name,
+                }
+            }
+        }
+    });
 
     quote! {
-        impl Resource for #name {
+        impl #impl_generics Resource for #name #ty_generics #where_clause {
             fn get_type_id(&self) -> usize {
                 get_resource_id::<Self>()
             }
@@ -52,12 +339,55 @@ pub fn derive_resource(input: TokenStream) -> TokenStream {
             fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
                 self
             }
+
+            #generic_registration
         }
 
+        #registration
+    }
+    .into()
+}
+
+#[proc_macro_derive(Event)]
+pub fn derive_event(input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input as DeriveInput);
+    if let Err(err) = reject_unions(&input, "Event") {
+        return err.to_compile_error().into();
+    }
+    add_component_bounds(&mut input.generics);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    if !input.generics.params.is_empty() {
+        return quote! {
+            impl #impl_generics Event for #name #ty_generics #where_clause {
+                fn generic_registration() -> Option<EventRegistration> {
+                    Some(EventRegistration {
+                        name: std::any::type_name::<Self>(),
+                        update: Events::<Self>::update_world,
+                    })
+                }
+            }
+        }
+        .into();
+    }
+
+    let type_name = name.to_string();
+    let events_name = format!("Events<{type_name}>");
+    quote! {
+        impl Event for #name {}
+
         inventory::submit! {
             ResourceRegistration {
-                type_id: typeid::ConstTypeId::of::<#name>(),
+                type_id: typeid::ConstTypeId::of::<Events<#name>>(),
+                name: #events_name,
+            }
+        }
+
+        inventory::submit! {
+            EventRegistration {
                 name: #type_name,
+                update: Events::<#name>::update_world,
             }
         }
     }
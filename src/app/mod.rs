@@ -0,0 +1,470 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::ecs::observer::ObserverFunction;
+use crate::ecs::scheduler::{Schedule, SystemConfig};
+use crate::ecs::system::IntoSystem;
+use crate::ecs::task::Tasks;
+use crate::prelude::*;
+
+/**
+ * The schedules an `App` runs
+ *
+ * `Startup` runs once before the first frame. Every frame then runs
+ * `PreUpdate`, `FixedUpdate` as many times as `FixedTime` says,
+ * `Update`, `PostUpdate` and `Render`, in that order.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScheduleLabel {
+    Startup,
+    PreUpdate,
+    FixedUpdate,
+    Update,
+    PostUpdate,
+    Render,
+}
+
+impl ScheduleLabel {
+    pub const ALL: [ScheduleLabel; 6] = [
+        ScheduleLabel::Startup,
+        ScheduleLabel::PreUpdate,
+        ScheduleLabel::FixedUpdate,
+        ScheduleLabel::Update,
+        ScheduleLabel::PostUpdate,
+        ScheduleLabel::Render,
+    ];
+}
+
+/**
+ * The time of the current frame
+ */
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct Time {
+    delta: Duration,
+    elapsed: Duration,
+    frame: u64,
+}
+
+impl Time {
+    /**
+     * The time between the previous frame and this one
+     */
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_secs(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /**
+     * The time since the first frame
+     */
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /**
+     * The number of frames run so far, counting this one
+     */
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    fn advance(&mut self, delta: Duration) {
+        self.delta = delta;
+        self.elapsed += delta;
+        self.frame += 1;
+    }
+}
+
+/**
+ * The timestep of the `FixedUpdate` schedule
+ *
+ * Frame time is accumulated and `FixedUpdate` runs once for every
+ * whole step, so it runs at the same rate whatever the frame rate.
+ * At most `max_steps` run per frame and the whole steps beyond are
+ * dropped, to keep a slow frame from making the next one slower still.
+ * Only the time into the next step is kept.
+ */
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct FixedTime {
+    step: Duration,
+    max_steps: u32,
+    accumulated: Duration,
+}
+
+impl FixedTime {
+    pub const DEFAULT_STEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+    pub fn new(step: Duration) -> Self {
+        assert!(!step.is_zero(), "The fixed timestep cannot be zero");
+        Self {
+            step,
+            max_steps: 8,
+            accumulated: Duration::ZERO,
+        }
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    pub fn step_secs(&self) -> f32 {
+        self.step.as_secs_f32()
+    }
+
+    /**
+     * Sets how many steps can run in one frame.
+     *
+     * Panics if it is zero, since `FixedUpdate` would never run.
+     */
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        assert!(
+            max_steps > 0,
+            "At least one fixed step has to run per frame"
+        );
+        self.max_steps = max_steps;
+    }
+
+    /**
+     * How far the accumulated time is into the next step, from 0 up to
+     * but not including 1
+     *
+     * Useful to interpolate rendered state between fixed steps.
+     */
+    pub fn overstep(&self) -> f32 {
+        self.accumulated.as_secs_f32() / self.step.as_secs_f32()
+    }
+
+    /**
+     * Accumulates a frame's time and returns how many steps to run.
+     */
+    fn accumulate(&mut self, delta: Duration) -> u32 {
+        let accumulated = (self.accumulated + delta).as_nanos();
+        let step = self.step.as_nanos();
+        // The remainder is shorter than the step, so it fits
+        self.accumulated = Duration::from_nanos((accumulated % step) as u64);
+        (accumulated / step).min(self.max_steps as u128) as u32
+    }
+}
+
+impl Default for FixedTime {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_STEP)
+    }
+}
+
+/**
+ * Stops the runner at the end of the frame it is sent in
+ */
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AppExit;
+
+/**
+ * This is a trait for the parts an `App` is assembled from
+ *
+ * A plugin adds its resources, systems and observers in `build`.
+ * Each plugin can be added to an app only once. It is also
+ * implemented for functions taking `&mut App`.
+ */
+pub trait Plugin: 'static {
+    fn build(&self, app: &mut App);
+
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl<F: Fn(&mut App) + 'static> Plugin for F {
+    fn build(&self, app: &mut App) {
+        self(app);
+    }
+}
+
+type Runner = Box<dyn FnOnce(App)>;
+
+/**
+ * A `World` together with the schedules that run on it
+ *
+ * Apps are assembled from plugins and handed to a runner, which
+ * calls `update` once per frame. Without a runner set, `run` uses
+ * a `HeadlessRunner` that runs until `AppExit` is sent.
+ */
+pub struct App {
+    world: World,
+    schedules: HashMap<ScheduleLabel, Schedule>,
+    plugins: Vec<String>,
+    runner: Option<Runner>,
+    last_update: Option<Instant>,
+    started: bool,
+}
+
+impl App {
+    pub fn new() -> Self {
+        let mut world = World::new();
+        world.init_resource::<Time>().unwrap();
+        world.init_resource::<FixedTime>().unwrap();
+        world.add_event::<AppExit>().unwrap();
+
+        Self {
+            world,
+            schedules: ScheduleLabel::ALL
+                .into_iter()
+                .map(|label| (label, Schedule::new()))
+                .collect(),
+            plugins: Vec::new(),
+            runner: None,
+            last_update: None,
+            started: false,
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    /**
+     * Builds a plugin into the app.
+     *
+     * Panics if the plugin was already added.
+     */
+    pub fn add_plugin(&mut self, plugin: impl Plugin) -> &mut Self {
+        let name = plugin.name().to_string();
+        assert!(
+            !self.plugins.contains(&name),
+            "Plugin `{name}` was added twice"
+        );
+        self.plugins.push(name);
+        plugin.build(self);
+        self
+    }
+
+    /**
+     * Checks if a plugin of the given type was added.
+     */
+    pub fn is_plugin_added<P: Plugin>(&self) -> bool {
+        let name = std::any::type_name::<P>();
+        self.plugins.iter().any(|p| p == name)
+    }
+
+    /**
+     * Adds a system to one of the app's schedules.
+     */
+    pub fn add_system<M>(
+        &mut self,
+        label: ScheduleLabel,
+        system: impl IntoSystem<M>,
+    ) -> SystemConfig<'_> {
+        self.schedule_mut(label).add_system(system)
+    }
+
+    /**
+     * Adds a sync point to one of the app's schedules, see `Schedule`.
+     */
+    pub fn add_sync_point(&mut self, label: ScheduleLabel) -> &mut Self {
+        self.schedule_mut(label).add_sync_point();
+        self
+    }
+
+    pub fn schedule_mut(&mut self, label: ScheduleLabel) -> &mut Schedule {
+        self.schedules.get_mut(&label).unwrap()
+    }
+
+    /**
+     * Inserts a resource, replacing any previous one.
+     *
+     * Panics if the type is not registered as a resource.
+     */
+    pub fn insert_resource<T: Resource>(&mut self, resource: T) -> &mut Self {
+        self.world
+            .insert_resource(resource)
+            .unwrap_or_else(|e| panic!("{e}"));
+        self
+    }
+
+    /**
+     * Inserts the default value of a resource if it does not exist yet.
+     *
+     * Panics if the type is not registered as a resource.
+     */
+    pub fn init_resource<T: Resource + Default>(&mut self) -> &mut Self {
+        self.world
+            .init_resource::<T>()
+            .unwrap_or_else(|e| panic!("{e}"));
+        self
+    }
+
+    /**
+     * Adds an `Events<T>` resource, see `World::add_event`.
+     *
+     * Panics if the type is not registered as an event.
+     */
+    pub fn add_event<T: Event>(&mut self) -> &mut Self {
+        self.world
+            .add_event::<T>()
+            .unwrap_or_else(|e| panic!("{e}"));
+        self
+    }
+
+    /**
+     * Adds an observer, see `World::observe`.
+     */
+    pub fn observe<Marker: 'static, F: ObserverFunction<Marker>>(
+        &mut self,
+        observer: F,
+    ) -> &mut Self {
+        self.world.observe(observer);
+        self
+    }
+
+    /**
+     * Replaces the function `run` hands the app to.
+     */
+    pub fn set_runner(&mut self, runner: impl FnOnce(App) + 'static) -> &mut Self {
+        self.runner = Some(Box::new(runner));
+        self
+    }
+
+    /**
+     * Runs one frame, timed by the wall clock.
+     *
+     * The first frame has a delta of zero.
+     */
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let delta = self
+            .last_update
+            .map_or(Duration::ZERO, |last| now.duration_since(last));
+        self.last_update = Some(now);
+        self.update_by(delta);
+    }
+
+    /**
+     * Runs one frame as if `delta` had passed since the previous one.
+     *
+     * Runs `Startup` first if it has not run yet.
+     */
+    pub fn update_by(&mut self, delta: Duration) {
+        if !self.started {
+            self.started = true;
+            self.run_schedule(ScheduleLabel::Startup);
+        }
+
+        self.world.resource_mut::<Time>().unwrap().advance(delta);
+        let steps = self
+            .world
+            .resource_mut::<FixedTime>()
+            .unwrap()
+            .accumulate(delta);
+
+        self.run_schedule(ScheduleLabel::PreUpdate);
+        for _ in 0..steps {
+            self.run_schedule(ScheduleLabel::FixedUpdate);
+        }
+        self.run_schedule(ScheduleLabel::Update);
+        self.run_schedule(ScheduleLabel::PostUpdate);
+        self.run_schedule(ScheduleLabel::Render);
+
+        Tasks::apply(&mut self.world);
+        self.world.update_events();
+        if let Some(tick) = self.world.clear_trackers() {
+            for schedule in self.schedules.values_mut() {
+                schedule.check_change_ticks(tick);
+            }
+        }
+    }
+
+    /**
+     * Runs a single schedule once.
+     */
+    pub fn run_schedule(&mut self, label: ScheduleLabel) {
+        let schedule = self.schedules.get_mut(&label).unwrap();
+        schedule.run(&mut self.world);
+    }
+
+    /**
+     * Checks if `AppExit` is still buffered in its `Events`.
+     *
+     * Every frame ends by swapping the event buffers, which drops the
+     * events of the frame before. Right after a frame, it is therefore
+     * true if `AppExit` was sent during that frame or since.
+     */
+    pub fn should_exit(&self) -> bool {
+        self.world
+            .resource::<Events<AppExit>>()
+            .is_ok_and(|events| !events.is_empty())
+    }
+
+    /**
+     * Hands the app to its runner.
+     */
+    pub fn run(&mut self) {
+        let mut app = std::mem::take(self);
+        let runner = app
+            .runner
+            .take()
+            .unwrap_or_else(|| Box::new(|mut app| HeadlessRunner::new().run(&mut app)));
+        runner(app);
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/**
+ * Runs an app without a window, for servers and tests
+ *
+ * It runs frames back to back until `AppExit` is sent or the frame
+ * limit is reached. With a fixed delta every frame is timed the
+ * same, which makes runs reproducible.
+ */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeadlessRunner {
+    frames: Option<u64>,
+    delta: Option<Duration>,
+}
+
+impl HeadlessRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Stops after the given number of frames.
+     */
+    pub fn frames(mut self, frames: u64) -> Self {
+        self.frames = Some(frames);
+        self
+    }
+
+    /**
+     * Times every frame as if `delta` had passed instead of
+     * using the wall clock.
+     */
+    pub fn fixed_delta(mut self, delta: Duration) -> Self {
+        self.delta = Some(delta);
+        self
+    }
+
+    pub fn run(self, app: &mut App) {
+        let mut frame = 0;
+        while self.frames.is_none_or(|frames| frame < frames) {
+            match self.delta {
+                Some(delta) => app.update_by(delta),
+                None => app.update(),
+            }
+            frame += 1;
+            if app.should_exit() {
+                break;
+            }
+        }
+    }
+}
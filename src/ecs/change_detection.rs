@@ -0,0 +1,199 @@
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use super::EntityId;
+
+/**
+ * How many ticks pass between two `World::check_change_ticks` passes
+ */
+pub const CHECK_TICK_THRESHOLD: u32 = 1 << 28;
+
+/**
+ * The oldest a tick gets after `World::check_change_ticks`
+ *
+ * Ticks keep aging until the next pass, so this leaves room for
+ * two thresholds below `u32::MAX / 2`.
+ */
+pub const MAX_CHANGE_AGE: u32 = u32::MAX / 2 - 2 * CHECK_TICK_THRESHOLD;
+
+/**
+ * Checks if `tick` happened after `last_run`, as seen from `this_run`.
+ *
+ * Ticks are compared by their age relative to `this_run`, so the
+ * counter can wrap around as long as no tick gets older than
+ * `u32::MAX / 2`. `World::check_change_ticks` makes sure of that.
+ */
+pub fn is_newer(tick: u32, last_run: u32, this_run: u32) -> bool {
+    this_run.wrapping_sub(tick) < this_run.wrapping_sub(last_run)
+}
+
+/**
+ * Clamps a tick older than `MAX_CHANGE_AGE`, as seen from `change_tick`,
+ * to exactly that age.
+ */
+pub fn check_tick(tick: &mut u32, change_tick: u32) {
+    if change_tick.wrapping_sub(*tick) > MAX_CHANGE_AGE {
+        *tick = change_tick.wrapping_sub(MAX_CHANGE_AGE);
+    }
+}
+
+/**
+ * When a component was added and last changed
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComponentTicks {
+    pub added: u32,
+    pub changed: u32,
+}
+
+impl ComponentTicks {
+    pub fn new(tick: u32) -> Self {
+        Self {
+            added: tick,
+            changed: tick,
+        }
+    }
+
+    pub fn is_added(&self, last_run: u32, this_run: u32) -> bool {
+        is_newer(self.added, last_run, this_run)
+    }
+
+    pub fn is_changed(&self, last_run: u32, this_run: u32) -> bool {
+        is_newer(self.changed, last_run, this_run)
+    }
+
+    pub fn set_changed(&mut self, tick: u32) {
+        self.changed = tick;
+    }
+
+    /**
+     * Clamps both ticks, see `check_tick`.
+     */
+    pub fn check_ticks(&mut self, change_tick: u32) {
+        check_tick(&mut self.added, change_tick);
+        check_tick(&mut self.changed, change_tick);
+    }
+}
+
+/**
+ * The ticks a system compares change ticks against
+ *
+ * `last_run` is the tick of the system's previous run and
+ * `this_run` the tick it writes changes with.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SystemTicks {
+    pub last_run: u32,
+    pub this_run: u32,
+}
+
+/**
+ * A mutable reference to a component that records writes
+ *
+ * Dereferencing it mutably marks the component as changed,
+ * reading it does not.
+ */
+pub struct Mut<'w, T> {
+    value: &'w mut T,
+    ticks: &'w mut ComponentTicks,
+    system: SystemTicks,
+}
+
+impl<'w, T> Mut<'w, T> {
+    pub(crate) fn new(
+        value: &'w mut T,
+        ticks: &'w mut ComponentTicks,
+        system: SystemTicks,
+    ) -> Self {
+        Self {
+            value,
+            ticks,
+            system,
+        }
+    }
+
+    /**
+     * Checks if the component was added since the system last ran.
+     */
+    pub fn is_added(&self) -> bool {
+        self.ticks
+            .is_added(self.system.last_run, self.system.this_run)
+    }
+
+    /**
+     * Checks if the component changed since the system last ran.
+     */
+    pub fn is_changed(&self) -> bool {
+        self.ticks
+            .is_changed(self.system.last_run, self.system.this_run)
+    }
+
+    pub fn set_changed(&mut self) {
+        self.ticks.set_changed(self.system.this_run);
+    }
+
+    /**
+     * Gets the component without marking it as changed.
+     */
+    pub fn bypass_change_detection(&mut self) -> &mut T {
+        self.value
+    }
+
+    /**
+     * Marks the component as changed and returns the reference.
+     */
+    pub fn into_inner(self) -> &'w mut T {
+        self.ticks.set_changed(self.system.this_run);
+        self.value
+    }
+}
+
+impl<T> Deref for Mut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> DerefMut for Mut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.set_changed();
+        self.value
+    }
+}
+
+/**
+ * A system parameter listing the entities that lost a `T`
+ * since the system last ran
+ *
+ * Despawned entities are listed too. Removals are kept for two
+ * frames, so systems that run every frame see each one once.
+ */
+pub struct RemovedComponents<'w, T> {
+    removed: &'w [(EntityId, u32)],
+    ticks: SystemTicks,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'w, T> RemovedComponents<'w, T> {
+    pub(crate) fn new(removed: &'w [(EntityId, u32)], ticks: SystemTicks) -> Self {
+        Self {
+            removed,
+            ticks,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = EntityId> + use<'w, T> {
+        let ticks = self.ticks;
+        self.removed
+            .iter()
+            .filter(move |(_, tick)| is_newer(*tick, ticks.last_run, ticks.this_run))
+            .map(|(id, _)| *id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
@@ -0,0 +1,229 @@
+use super::world::{ResourceError, World};
+use super::{Component, EntityId, Resource, try_get_resource_id};
+
+type Command = Box<dyn FnOnce(&mut World) + Send + Sync>;
+
+/**
+ * A queue of changes to apply to a `World` later
+ *
+ * Every function system owns its own queue, so systems running in
+ * parallel never contend on a shared buffer. The scheduler applies
+ * the queues at its sync points, in the order systems were added.
+ */
+#[derive(Default)]
+pub struct CommandQueue {
+    commands: Vec<Command>,
+}
+
+impl CommandQueue {
+    pub fn push(&mut self, command: impl FnOnce(&mut World) + Send + Sync + 'static) {
+        self.commands.push(Box::new(command));
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /**
+     * Applies every queued command in order, emptying the queue.
+     *
+     * Entities reserved by `Commands::spawn` are spawned first.
+     */
+    pub fn apply(&mut self, world: &mut World) {
+        world.flush_entities();
+        for command in self.commands.drain(..) {
+            command(world);
+        }
+    }
+}
+
+/**
+ * A system parameter for making structural changes to the world
+ *
+ * Commands are queued while the system runs and applied at the
+ * schedule's next sync point.
+ */
+pub struct Commands<'w, 's> {
+    queue: &'s mut CommandQueue,
+    world: &'w World,
+}
+
+impl<'w, 's> Commands<'w, 's> {
+    pub fn new(queue: &'s mut CommandQueue, world: &'w World) -> Self {
+        Self { queue, world }
+    }
+
+    /**
+     * Queues an arbitrary change to the world.
+     */
+    pub fn add(&mut self, command: impl FnOnce(&mut World) + Send + Sync + 'static) {
+        self.queue.push(command);
+    }
+
+    /**
+     * Reserves a new entity.
+     *
+     * The id is usable right away, the entity itself is
+     * spawned when the commands are applied.
+     */
+    pub fn spawn(&mut self) -> EntityCommands<'_, 'w, 's> {
+        let id = self.world.reserve_entity();
+        EntityCommands { id, commands: self }
+    }
+
+    /**
+     * Queues changes to an existing entity.
+     *
+     * Commands for entities that no longer exist when they are
+     * applied are ignored.
+     */
+    pub fn entity(&mut self, id: EntityId) -> EntityCommands<'_, 'w, 's> {
+        EntityCommands { id, commands: self }
+    }
+
+    /**
+     * Queues despawning an entity.
+     */
+    pub fn despawn(&mut self, entity: EntityId) {
+        self.add(move |world| {
+            world.despawn(entity);
+        });
+    }
+
+    /**
+     * Queues setting a component on an entity.
+     */
+    pub fn insert<T: Component>(&mut self, entity: EntityId, component: T) {
+        self.entity(entity).insert(component);
+    }
+
+    /**
+     * Queues removing a component from an entity.
+     */
+    pub fn remove<T: Component>(&mut self, entity: EntityId) {
+        self.entity(entity).remove::<T>();
+    }
+
+    /**
+     * Queues inserting a resource, replacing any previous value.
+     *
+     * Fails right away if `T` was not registered with the `Resource`
+     * derive, so the error is not left for the sync point.
+     */
+    pub fn insert_resource<T: Resource>(&mut self, resource: T) -> Result<(), ResourceError> {
+        if try_get_resource_id::<T>().is_none() {
+            return Err(ResourceError::NotRegistered(std::any::type_name::<T>()));
+        }
+        self.add(move |world| {
+            world
+                .insert_resource(resource)
+                .expect("The resource was checked when queued");
+        });
+        Ok(())
+    }
+
+    /**
+     * Queues removing a resource.
+     */
+    pub fn remove_resource<T: Resource>(&mut self) {
+        self.add(|world| {
+            let _ = world.remove_resource::<T>();
+        });
+    }
+}
+
+/**
+ * Queues changes to a single entity, see `Commands::entity`
+ */
+pub struct EntityCommands<'a, 'w, 's> {
+    id: EntityId,
+    commands: &'a mut Commands<'w, 's>,
+}
+
+impl EntityCommands<'_, '_, '_> {
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+
+    fn queue(
+        &mut self,
+        command: impl FnOnce(&mut World, EntityId) + Send + Sync + 'static,
+    ) -> &mut Self {
+        let id = self.id;
+        self.commands.add(move |world| command(world, id));
+        self
+    }
+
+    /**
+     * Queues setting a component, replacing the previous one.
+     */
+    pub fn insert<T: Component>(&mut self, component: T) -> &mut Self {
+        self.queue(move |world, id| {
+            if let Some(mut e) = world.get_mut(id) {
+                e.set_component(component);
+            }
+        })
+    }
+
+    /**
+     * Queues adding another instance of a component.
+     */
+    pub fn add<T: Component>(&mut self, component: T) -> &mut Self {
+        self.queue(move |world, id| {
+            if let Some(mut e) = world.get_mut(id) {
+                e.add_component(component);
+            }
+        })
+    }
+
+    /**
+     * Queues removing every instance of a component.
+     */
+    pub fn remove<T: Component>(&mut self) -> &mut Self {
+        self.queue(|world, id| {
+            if let Some(mut e) = world.get_mut(id) {
+                e.remove_component::<T>();
+            }
+        })
+    }
+
+    /**
+     * Queues despawning the entity.
+     */
+    pub fn despawn(&mut self) {
+        self.queue(|world, id| {
+            world.despawn(id);
+        });
+    }
+
+    /**
+     * Queues despawning the entity and all of its descendants.
+     */
+    pub fn despawn_recursive(&mut self) {
+        self.queue(|world, id| {
+            world.despawn_recursive(id);
+        });
+    }
+
+    /**
+     * Queues making the entity a child of `parent`, see `World::set_parent`.
+     */
+    pub fn set_parent(&mut self, parent: EntityId) -> &mut Self {
+        self.queue(move |world, id| {
+            world.set_parent(id, parent);
+        })
+    }
+
+    /**
+     * Queues detaching the entity from its parent.
+     */
+    pub fn remove_parent(&mut self) -> &mut Self {
+        self.queue(|world, id| {
+            world.remove_parent(id);
+        })
+    }
+}
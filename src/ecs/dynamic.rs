@@ -0,0 +1,361 @@
+use std::alloc::Layout;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock};
+
+use typeid::ConstTypeId;
+
+use super::mappings::table::ComponentInfo;
+use super::observer::Lifecycle;
+use super::world::World;
+use super::{
+    COMPONENT_REGISTRATIONS, Component, ComponentRegistration, DuplicateComponent, EntityMut,
+    EntityRef, StorageType, build_component_registrations, stable_id, static_component_count,
+};
+
+/**
+ * Describes how to store a component type registered at runtime
+ *
+ * Dynamic components have no Rust type. They are stored with the
+ * given layout, dropped with `drop` and accessed by their ID.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct ComponentDescriptor {
+    pub layout: Layout,
+    pub drop: Option<unsafe fn(*mut u8)>,
+    pub storage: StorageType,
+}
+
+impl ComponentDescriptor {
+    /**
+     * Describes a component made of plain bytes, such as one
+     * defined in an asset file
+     */
+    pub fn data(layout: Layout) -> Self {
+        Self {
+            layout,
+            drop: None,
+            storage: StorageType::Table,
+        }
+    }
+
+    /**
+     * Whether the component is plain bytes that can be read
+     * and written with `EntityRef::get_bytes` and friends
+     */
+    pub fn is_data(&self) -> bool {
+        self.drop.is_none()
+    }
+}
+
+/**
+ * A component registered after startup
+ *
+ * Instantiations of generic components have a registration,
+ * components from `register_component` do not.
+ */
+struct DynamicComponent {
+    key: &'static str,
+    descriptor: ComponentDescriptor,
+    registration: Option<&'static ComponentRegistration>,
+}
+
+static DYNAMIC_COMPONENTS: RwLock<Vec<DynamicComponent>> = RwLock::new(Vec::new());
+
+fn find_duplicate(dynamics: &[DynamicComponent], key: &str) -> Option<&'static str> {
+    let hash = stable_id(key);
+    COMPONENT_REGISTRATIONS
+        .get_or_init(build_component_registrations)
+        .iter()
+        .map(|r| r.key)
+        .chain(dynamics.iter().map(|c| c.key))
+        .find(|&k| k == key || stable_id(k) == hash)
+}
+
+/**
+ * Registers a component type after startup, for a loaded plugin
+ * or script or for data-only components from asset files
+ *
+ * Dynamic components get the IDs after every derived component.
+ * The key has to be unique among all components, derived or
+ * dynamic. Registrations last until the process exits.
+ */
+pub fn register_component(
+    key: &str,
+    descriptor: ComponentDescriptor,
+) -> Result<usize, DuplicateComponent> {
+    let mut dynamics = DYNAMIC_COMPONENTS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    if let Some(first) = find_duplicate(&dynamics, key) {
+        return Err(DuplicateComponent {
+            first: first.to_string(),
+            second: key.to_string(),
+        });
+    }
+
+    dynamics.push(DynamicComponent {
+        key: String::from(key).leak(),
+        descriptor,
+        registration: None,
+    });
+    Ok(static_component_count() + dynamics.len() - 1)
+}
+
+thread_local! {
+    /**
+     * The IDs of the generic components this thread already looked up,
+     * so only the first lookup takes the lock
+     */
+    static GENERIC_IDS: RefCell<HashMap<ConstTypeId, usize>> = RefCell::new(HashMap::new());
+}
+
+/**
+ * Returns the ID of an instantiation of a generic component,
+ * registering it the first time
+ *
+ * Returns None if `T` is not generic.
+ */
+pub(super) fn generic_id<T: Component>() -> Option<usize> {
+    let type_id = ConstTypeId::of::<T>();
+    if let Some(id) = GENERIC_IDS.with_borrow(|ids| ids.get(&type_id).copied()) {
+        return Some(id);
+    }
+    let id = register_generic::<T>(type_id)?;
+    GENERIC_IDS.with_borrow_mut(|ids| ids.insert(type_id, id));
+    Some(id)
+}
+
+fn register_generic<T: Component>(type_id: ConstTypeId) -> Option<usize> {
+    let position = |dynamics: &[DynamicComponent]| {
+        dynamics
+            .iter()
+            .position(|c| c.registration.is_some_and(|r| r.type_id == type_id))
+            .map(|index| static_component_count() + index)
+    };
+
+    if let Some(id) = position(
+        &DYNAMIC_COMPONENTS
+            .read()
+            .unwrap_or_else(PoisonError::into_inner),
+    ) {
+        return Some(id);
+    }
+
+    let registration = T::generic_registration()?;
+    let mut dynamics = DYNAMIC_COMPONENTS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(id) = position(&dynamics) {
+        return Some(id);
+    }
+    if let Some(first) = find_duplicate(&dynamics, registration.key) {
+        let duplicate = DuplicateComponent {
+            first: first.to_string(),
+            second: registration.key.to_string(),
+        };
+        panic!("{duplicate}");
+    }
+
+    let drop: unsafe fn(*mut u8) = |ptr| unsafe { std::ptr::drop_in_place(ptr.cast::<T>()) };
+    let registration: &'static ComponentRegistration = Box::leak(Box::new(registration));
+    dynamics.push(DynamicComponent {
+        key: registration.key,
+        descriptor: ComponentDescriptor {
+            layout: Layout::new::<T>(),
+            drop: std::mem::needs_drop::<T>().then_some(drop),
+            storage: registration.storage,
+        },
+        registration: Some(registration),
+    });
+    Some(static_component_count() + dynamics.len() - 1)
+}
+
+pub(super) fn registration(id: usize) -> Option<&'static ComponentRegistration> {
+    let index = id.checked_sub(static_component_count())?;
+    DYNAMIC_COMPONENTS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(index)?
+        .registration
+}
+
+/**
+ * Returns the descriptor of a dynamic component
+ *
+ * If the ID does not belong to a dynamic component, it returns None.
+ */
+pub fn component_descriptor(id: usize) -> Option<ComponentDescriptor> {
+    let index = id.checked_sub(static_component_count())?;
+    DYNAMIC_COMPONENTS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(index)
+        .filter(|c| c.registration.is_none())
+        .map(|c| c.descriptor)
+}
+
+pub(super) fn get(id: usize) -> Option<(&'static str, ComponentDescriptor)> {
+    let index = id.checked_sub(static_component_count())?;
+    DYNAMIC_COMPONENTS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(index)
+        .map(|c| (c.key, c.descriptor))
+}
+
+pub(super) fn find(mut predicate: impl FnMut(&str) -> bool) -> Option<usize> {
+    DYNAMIC_COMPONENTS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .position(|c| predicate(c.key))
+        .map(|index| static_component_count() + index)
+}
+
+pub(super) fn count() -> usize {
+    DYNAMIC_COMPONENTS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .len()
+}
+
+fn data_size(component: usize) -> Option<usize> {
+    component_descriptor(component)
+        .filter(ComponentDescriptor::is_data)
+        .map(|descriptor| descriptor.layout.size())
+}
+
+impl<'w> EntityRef<'w> {
+    /**
+     * Gets the bytes of a data-only dynamic component.
+     *
+     * If the component is not present or not a data-only
+     * dynamic component, it returns None.
+     */
+    pub fn get_bytes(&self, component: usize) -> Option<&'w [u8]> {
+        let size = data_size(component)?;
+        let ptr = self.table.get_ptr(self.id, component)?;
+        Some(unsafe { std::slice::from_raw_parts(ptr, size) })
+    }
+
+    /**
+     * Gets a pointer to a dynamic component.
+     *
+     * If the component is not present or not dynamic, it returns None.
+     */
+    pub fn get_ptr_by_id(&self, component: usize) -> Option<*const u8> {
+        component_descriptor(component)?;
+        self.table
+            .get_ptr(self.id, component)
+            .map(|ptr| ptr as *const u8)
+    }
+}
+
+impl EntityMut<'_> {
+    /**
+     * Gets the bytes of a data-only dynamic component for writing.
+     *
+     * The component is marked as changed whether or not it is written.
+     * If the component is not present or not a data-only dynamic
+     * component, it returns None.
+     */
+    pub fn get_bytes_mut(&mut self, component: usize) -> Option<&mut [u8]> {
+        let size = data_size(component)?;
+        let id = self.id;
+        let ptr = self.table_mut().get_mut_ptr(id, component)?;
+        Some(unsafe { std::slice::from_raw_parts_mut(ptr, size) })
+    }
+
+    /**
+     * Sets a data-only dynamic component from its bytes.
+     *
+     * Returns false without changing anything if the component
+     * is not a data-only dynamic component or the length of
+     * `bytes` does not match its layout.
+     */
+    pub fn insert_bytes(&mut self, component: usize, bytes: &[u8]) -> bool {
+        if data_size(component) != Some(bytes.len()) {
+            return false;
+        }
+        unsafe { self.insert_by_id(component, bytes.as_ptr()) }
+    }
+
+    /**
+     * Sets a dynamic component, dropping the previous one if it exists.
+     *
+     * Returns false if the component is not dynamic.
+     *
+     * # Safety
+     *
+     * `value` must point to a valid value for the component's
+     * descriptor, and ownership of it moves into the world. Every
+     * byte of a data-only component must be initialized.
+     */
+    pub unsafe fn insert_by_id(&mut self, component: usize, value: *const u8) -> bool {
+        let Some(descriptor) = component_descriptor(component) else {
+            return false;
+        };
+        let info = ComponentInfo {
+            id: component,
+            layout: descriptor.layout,
+            drop: descriptor.drop,
+            storage: descriptor.storage,
+        };
+        let id = self.id;
+        if self.table().has_by_id(id, component) {
+            self.world.trigger(Lifecycle::Replace, id, component);
+        }
+        // The replace hooks may have removed the component.
+        let added = !self.table().has_by_id(id, component);
+        if !unsafe { self.table_mut().insert_by_id(id, info, value) } {
+            return false;
+        }
+        if added {
+            self.world.trigger(Lifecycle::Add, id, component);
+        }
+        self.world.trigger(Lifecycle::Insert, id, component);
+        true
+    }
+
+    /**
+     * Removes every instance of a component by its ID, dropping them.
+     *
+     * If the component is not present, it returns false.
+     */
+    pub fn remove_by_id(&mut self, component: usize) -> bool {
+        let id = self.id;
+        self.world.trigger_removal(id, component);
+        self.table_mut().remove_by_id(id, component)
+    }
+}
+
+impl World {
+    /**
+     * Iterates over the entities that have every given component.
+     *
+     * Unlike `World::query` it only needs component IDs, so it
+     * also works for dynamic components.
+     */
+    pub fn query_by_id<'a>(
+        &'a self,
+        components: &'a [usize],
+    ) -> impl Iterator<Item = EntityRef<'a>> + 'a {
+        let table = self.table();
+        // Sparse components are not part of archetypes, so they are
+        // checked for each entity instead.
+        let (sparse, dense): (Vec<usize>, Vec<usize>) = components.iter().partition(|&&c| {
+            table
+                .info(c)
+                .is_some_and(|info| info.storage == StorageType::Sparse)
+        });
+        table
+            .archetypes()
+            .iter()
+            .filter(move |archetype| dense.iter().all(|&c| archetype.has_component(c)))
+            .flat_map(|archetype| archetype.entities())
+            .filter(move |&&id| sparse.iter().all(|&c| table.has_by_id(id, c)))
+            .map(move |&id| EntityRef::new(id, table))
+    }
+}
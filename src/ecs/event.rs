@@ -0,0 +1,193 @@
+use std::any::Any;
+
+use typeid::ConstTypeId;
+
+use super::world::World;
+use super::{Event, Resource, ResourceRegistration, get_resource_id};
+
+/**
+ * A double-buffered channel of events of one type
+ *
+ * Events sent during a frame stay readable for that frame and the
+ * next one, then `update` drops them. Readers that run every frame
+ * therefore see each event exactly once, no matter if they run
+ * before or after the writer.
+ *
+ * Deriving `Event` registers `Events<T>` as a resource and hooks it
+ * into `World::update_events`. Add it to a world with `World::add_event`.
+ */
+pub struct Events<T: Event> {
+    front: Vec<T>,
+    back: Vec<T>,
+    front_start: usize,
+}
+
+impl<T: Event> Events<T> {
+    pub fn new() -> Self {
+        Self {
+            front: Vec::new(),
+            back: Vec::new(),
+            front_start: 0,
+        }
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.back.push(event);
+    }
+
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        self.back.extend(events);
+    }
+
+    /**
+     * The number of events ever sent
+     */
+    pub fn count(&self) -> usize {
+        self.front_start + self.front.len() + self.back.len()
+    }
+
+    /**
+     * The number of events still buffered
+     */
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.front.is_empty() && self.back.is_empty()
+    }
+
+    /**
+     * Iterates over the buffered events sent after the first `count`.
+     */
+    pub fn iter_since(&self, count: usize) -> impl Iterator<Item = &T> {
+        let back_start = self.front_start + self.front.len();
+        let front_skip = count.saturating_sub(self.front_start).min(self.front.len());
+        let back_skip = count.saturating_sub(back_start).min(self.back.len());
+        self.front[front_skip..]
+            .iter()
+            .chain(&self.back[back_skip..])
+    }
+
+    /**
+     * Swaps the buffers, dropping the events of the previous frame.
+     */
+    pub fn update(&mut self) {
+        self.front_start += self.front.len();
+        self.front = std::mem::take(&mut self.back);
+    }
+
+    /**
+     * Drops every buffered event.
+     */
+    pub fn clear(&mut self) {
+        self.update();
+        self.update();
+    }
+
+    /**
+     * Updates the world's `Events<T>`, if it has one.
+     *
+     * This function is for use by the `Event` derive macro.
+     * It is not intended to be used directly.
+     */
+    pub fn update_world(world: &mut World) {
+        if let Ok(events) = world.resource_mut::<Self>() {
+            events.update();
+        }
+    }
+}
+
+impl<T: Event> Default for Events<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Event> Resource for Events<T> {
+    fn get_type_id(&self) -> usize {
+        get_resource_id::<Self>()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn generic_registration() -> Option<ResourceRegistration> {
+        T::generic_registration().map(|_| ResourceRegistration {
+            type_id: ConstTypeId::of::<Self>(),
+            name: std::any::type_name::<Self>(),
+        })
+    }
+}
+
+/**
+ * A system parameter for sending events of type `T`
+ *
+ * Panics when the system runs if the world has no `Events<T>`.
+ */
+pub struct EventWriter<'w, T: Event> {
+    events: &'w mut Events<T>,
+}
+
+impl<'w, T: Event> EventWriter<'w, T> {
+    pub(crate) fn new(events: &'w mut Events<T>) -> Self {
+        Self { events }
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.events.send(event);
+    }
+
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        self.events.send_batch(events);
+    }
+}
+
+/**
+ * A system parameter for reading events of type `T`
+ *
+ * Every reader keeps its own position, so each system sees
+ * an event once regardless of what other readers do.
+ * Panics when the system runs if the world has no `Events<T>`.
+ */
+pub struct EventReader<'w, 's, T: Event> {
+    events: &'w Events<T>,
+    cursor: &'s mut usize,
+}
+
+impl<'w, 's, T: Event> EventReader<'w, 's, T> {
+    pub(crate) fn new(events: &'w Events<T>, cursor: &'s mut usize) -> Self {
+        Self { events, cursor }
+    }
+
+    /**
+     * Iterates over the events this reader has not seen yet.
+     */
+    pub fn read(&mut self) -> impl Iterator<Item = &'w T> + use<'w, T> {
+        let since = std::mem::replace(self.cursor, self.events.count());
+        self.events.iter_since(since)
+    }
+
+    /**
+     * The number of events this reader has not seen yet
+     */
+    pub fn len(&self) -> usize {
+        self.events.iter_since(*self.cursor).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /**
+     * Marks every pending event as seen.
+     */
+    pub fn clear(&mut self) {
+        *self.cursor = self.events.count();
+    }
+}
@@ -0,0 +1,164 @@
+use std::ops::Deref;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::ecs::scene::MapEntities;
+use crate::math::transform::{GlobalTransform, Transform};
+use crate::prelude::*;
+
+/**
+ * The entity this entity is a child of
+ *
+ * Maintained by `World::set_parent`, `World::remove_parent` and
+ * despawning, together with the parent's `Children`.
+ */
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[component(map_entities)]
+pub struct Parent(EntityId);
+
+impl Parent {
+    pub(crate) fn new(parent: EntityId) -> Self {
+        Self(parent)
+    }
+
+    pub fn get(&self) -> EntityId {
+        self.0
+    }
+}
+
+/**
+ * The entities that have this entity as their `Parent`
+ *
+ * Children are kept in the order they were attached.
+ */
+#[derive(Component, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[component(map_entities)]
+pub struct Children(Vec<EntityId>);
+
+impl MapEntities for Parent {
+    fn map_entities(self, map: &dyn Fn(EntityId) -> Option<EntityId>) -> Option<Self> {
+        map(self.0).map(Self)
+    }
+}
+
+impl Children {
+    pub(crate) fn new(children: Vec<EntityId>) -> Self {
+        Self(children)
+    }
+
+    pub(crate) fn push(&mut self, child: EntityId) {
+        self.0.push(child);
+    }
+
+    pub(crate) fn remove(&mut self, child: EntityId) {
+        self.0.retain(|&c| c != child);
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<EntityId> {
+        self.0
+    }
+}
+
+impl Deref for Children {
+    type Target = [EntityId];
+
+    fn deref(&self) -> &[EntityId] {
+        &self.0
+    }
+}
+
+impl MapEntities for Children {
+    fn map_entities(self, map: &dyn Fn(EntityId) -> Option<EntityId>) -> Option<Self> {
+        let children: Vec<_> = self.0.into_iter().filter_map(map).collect();
+        (!children.is_empty()).then_some(Self(children))
+    }
+}
+
+struct Propagation<'a, 'w, 's, 'd> {
+    nodes: &'a Query<'w, 's, (&'d Transform, &'d Parent)>,
+    children: &'a Query<'w, 's, &'d Children>,
+    globals: &'a Query<'w, 's, &'d mut GlobalTransform>,
+}
+
+// `Query` holds a raw pointer to the world, so it is not `Sync` on its
+// own. Sharing the context between rayon threads is sound because:
+// - `nodes` and `children` are only read, and components are `Sync`;
+// - `globals` is only written through `set_global`, once per entity.
+//   Roots are distinct, and `propagate` only descends into a child from
+//   the entity its `Parent` names, so subtrees never overlap and no two
+//   threads touch the same `GlobalTransform`.
+unsafe impl Sync for Propagation<'_, '_, '_, '_> {}
+
+/**
+ * Adds `propagate_transforms` to `PostUpdate`, labeled
+ * `"propagate_transforms"` so other systems can order against it
+ */
+pub struct TransformPlugin;
+
+impl Plugin for TransformPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(ScheduleLabel::PostUpdate, propagate_transforms)
+            .label("propagate_transforms");
+    }
+}
+
+/**
+ * Computes the `GlobalTransform` of every entity from its
+ * `Transform` and those of its ancestors
+ *
+ * Each root's subtree is handled in parallel on the rayon pool.
+ * Entities without a `Transform` break the chain: neither they
+ * nor their descendants are updated.
+ */
+pub fn propagate_transforms<'d>(
+    roots: Query<(EntityId, &Transform), Without<Parent>>,
+    nodes: Query<(&'d Transform, &'d Parent)>,
+    children: Query<&'d Children>,
+    globals: Query<&'d mut GlobalTransform>,
+) {
+    let ctx = Propagation {
+        nodes: &nodes,
+        children: &children,
+        globals: &globals,
+    };
+    let roots: Vec<_> = roots.iter().map(|(id, t)| (id, *t)).collect();
+
+    roots.par_iter().for_each(|(root, transform)| {
+        unsafe { set_global(&ctx, *root, transform) };
+        propagate(&ctx, *root, transform);
+    });
+}
+
+fn propagate(ctx: &Propagation, entity: EntityId, global: &Transform) {
+    let Some(children) = ctx.children.get(entity) else {
+        return;
+    };
+    for &child in children.iter() {
+        let Some((local, parent)) = ctx.nodes.get(child) else {
+            continue;
+        };
+        // Only descend through the child's actual parent, so no entity
+        // is reached twice even if a `Children` list is out of date.
+        if parent.get() != entity {
+            continue;
+        }
+
+        let child_global = global.mul_transform(local);
+        unsafe { set_global(ctx, child, &child_global) };
+        propagate(ctx, child, &child_global);
+    }
+}
+
+/**
+ * # Safety
+ *
+ * No other thread may access the `GlobalTransform` of `entity`.
+ */
+unsafe fn set_global(ctx: &Propagation, entity: EntityId, transform: &Transform) {
+    if let Some(mut global) = unsafe { ctx.globals.get_unchecked(entity) }
+        && global.0 != *transform
+    {
+        global.0 = *transform;
+    }
+}
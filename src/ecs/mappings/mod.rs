@@ -2,13 +2,20 @@ pub mod spatial;
 pub mod table;
 
 /**
+ * This is a trait for structures that index the entities of a `World`
  *
+ * Queries are run against a mapping rather than the world directly
  */
 pub trait Mapping {}
 
+/**
+ * This is a trait for queries that can be answered by a `Mapping`
+ */
 pub trait Query {
     type Mapping: Mapping;
-    type Out;
+    type Out<'m>
+    where
+        Self: 'm;
 
-    fn query(&self, map: Self::Mapping) -> Self::Out;
+    fn query<'m>(&'m self, map: &'m Self::Mapping) -> Self::Out<'m>;
 }
@@ -1 +1,427 @@
+use std::collections::{HashMap, HashSet};
 
+use super::{Mapping, Query};
+use crate::ecs::change_detection::{SystemTicks, is_newer};
+use crate::ecs::query::{Changed, Instances, Many, QueryState};
+use crate::ecs::{Component, EntityId, get_component_id, world::World};
+use crate::math::geometry::{Aabb, Bounded, Sphere};
+use crate::math::vector::Vec3;
+
+/**
+ * This is a trait for components that give an entity a place in space
+ *
+ * Entities with such a component can be indexed by a `SpatialGrid`
+ */
+pub trait Spatial: Component {
+    fn bounds(&self) -> Aabb;
+}
+
+type Cell = [i32; 3];
+
+/**
+ * How far from the origin cell coordinates go, in cells. Points
+ * farther away share the cells at the edge.
+ */
+const CELL_LIMIT: f32 = (1 << 24) as f32;
+
+/**
+ * The most cells an entity is stored in. Larger entities are kept
+ * aside and looked at by every query.
+ */
+const MAX_ENTRY_CELLS: u64 = 4096;
+
+struct SpatialEntry {
+    bounds: Aabb,
+    /**
+     * The corner cells of the entity, None if it is too large or
+     * its bounds are infinite
+     */
+    cells: Option<(Cell, Cell)>,
+}
+
+/**
+ * A uniform grid indexing entities by their bounds
+ *
+ * Each entity is stored in every cell its bounds touch. Updates
+ * only touch the grid when an entity crosses a cell boundary.
+ * Entities touching more than `MAX_ENTRY_CELLS` cells, or with infinite
+ * bounds, are kept in a list every query looks through.
+ */
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<EntityId>>,
+    large: Vec<EntityId>,
+    entries: HashMap<EntityId, SpatialEntry>,
+    /**
+     * The change tick of the last `sync`
+     */
+    synced: Option<u32>,
+}
+
+impl Mapping for SpatialGrid {}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "Cell size must be positive");
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            large: Vec::new(),
+            entries: HashMap::new(),
+            synced: None,
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.entries.contains_key(&id)
+    }
+
+    /**
+     * Gets the bounds an entity was last indexed with.
+     */
+    pub fn bounds(&self, id: EntityId) -> Option<Aabb> {
+        self.entries.get(&id).map(|e| e.bounds)
+    }
+
+    fn cell_of(&self, p: Vec3) -> Cell {
+        p.to_array()
+            .map(|c| (c / self.cell_size).floor().clamp(-CELL_LIMIT, CELL_LIMIT) as i32)
+    }
+
+    fn cell_range(&self, bounds: &Aabb) -> (Cell, Cell) {
+        (self.cell_of(bounds.min), self.cell_of(bounds.max))
+    }
+
+    fn cell_count((min, max): (Cell, Cell)) -> u64 {
+        (0..3)
+            .map(|i| (max[i] as i64 - min[i] as i64 + 1).max(0) as u64)
+            .fold(1, u64::saturating_mul)
+    }
+
+    /**
+     * The corner cells to store bounds in, None if they are too large
+     * or infinite.
+     */
+    fn cells_for(&self, bounds: &Aabb) -> Option<(Cell, Cell)> {
+        let range = self.cell_range(bounds);
+        (bounds.min.is_finite()
+            && bounds.max.is_finite()
+            && Self::cell_count(range) <= MAX_ENTRY_CELLS)
+            .then_some(range)
+    }
+
+    fn cells_in(range: (Cell, Cell)) -> impl Iterator<Item = Cell> {
+        let (min, max) = range;
+        (min[0]..=max[0]).flat_map(move |x| {
+            (min[1]..=max[1]).flat_map(move |y| (min[2]..=max[2]).map(move |z| [x, y, z]))
+        })
+    }
+
+    /**
+     * Inserts or moves an entity in the grid.
+     *
+     * Cells are only rewritten if the entity crossed a cell boundary.
+     * Bounds with a NaN coordinate have no place, so the entity is
+     * removed instead.
+     */
+    pub fn update(&mut self, id: EntityId, bounds: Aabb) {
+        let corners = [bounds.min, bounds.max].map(Vec3::to_array);
+        if corners.as_flattened().iter().any(|c| c.is_nan()) {
+            self.remove(id);
+            return;
+        }
+        let cells = self.cells_for(&bounds);
+
+        if let Some(entry) = self.entries.get_mut(&id) {
+            entry.bounds = bounds;
+            if entry.cells == cells {
+                return;
+            }
+            let old = std::mem::replace(&mut entry.cells, cells);
+            self.unlink(id, old);
+        } else {
+            self.entries.insert(id, SpatialEntry { bounds, cells });
+        }
+
+        match cells {
+            Some(range) => {
+                for cell in Self::cells_in(range) {
+                    self.cells.entry(cell).or_default().push(id);
+                }
+            }
+            None => self.large.push(id),
+        }
+    }
+
+    /**
+     * Removes an entity from the grid.
+     *
+     * If the entity is not indexed, it returns false.
+     */
+    pub fn remove(&mut self, id: EntityId) -> bool {
+        match self.entries.remove(&id) {
+            Some(entry) => {
+                self.unlink(id, entry.cells);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn unlink(&mut self, id: EntityId, cells: Option<(Cell, Cell)>) {
+        let Some(range) = cells else {
+            self.large.retain(|&e| e != id);
+            return;
+        };
+        for cell in Self::cells_in(range) {
+            if let Some(ids) = self.cells.get_mut(&cell) {
+                ids.retain(|&e| e != id);
+                if ids.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    /**
+     * Removes every entity from the grid.
+     */
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.large.clear();
+        self.entries.clear();
+        self.synced = None;
+    }
+
+    /**
+     * Brings the grid in line with every entity holding a `T` in the world.
+     *
+     * Only entities whose `T` was added, changed or removed since the
+     * previous sync are visited, so a grid should always be synced with
+     * the same component type. The first sync, and any sync after the
+     * world forgot removals the grid has not seen yet, rebuild the grid.
+     *
+     * Entities with several instances of `T` are indexed by bounds
+     * enclosing all of them.
+     */
+    pub fn sync<T: Spatial>(&mut self, world: &World) {
+        let table = world.table();
+        let this_run = world.increment_change_tick();
+        let last_run = self
+            .synced
+            .filter(|&last_run| !is_newer(table.removed_since(), last_run, this_run));
+        let Some(last_run) = last_run else {
+            self.clear();
+            for (id, instances) in QueryState::<(EntityId, Many<&T>)>::new().iter(table) {
+                self.update(id, Self::enclose(instances));
+            }
+            self.synced = Some(this_run);
+            return;
+        };
+        self.synced = Some(this_run);
+
+        let ticks = SystemTicks { last_run, this_run };
+        let component = get_component_id::<T>();
+        for &(id, tick) in table.removed(component) {
+            if is_newer(tick, last_run, this_run) && !table.has_by_id(id, component) {
+                self.remove(id);
+            }
+        }
+        let changed = QueryState::<(EntityId, Many<&T>), Changed<T>>::new();
+        // The query only reads, and the world is borrowed for its whole run.
+        for (id, instances) in unsafe { changed.iter_unchecked(table, ticks) } {
+            self.update(id, Self::enclose(instances));
+        }
+    }
+
+    fn enclose<T: Spatial>(instances: Instances<'_, T>) -> Aabb {
+        instances
+            .iter()
+            .map(Spatial::bounds)
+            .reduce(|a, b| a.merge(&b))
+            .expect("Instances are never empty")
+    }
+
+    /**
+     * Visits every entity stored in the cells touched by `bounds` and
+     * every large entity, once each.
+     *
+     * Bounds touching more cells than there are entities visit every
+     * entity instead.
+     */
+    fn candidates(&self, bounds: &Aabb) -> impl Iterator<Item = (EntityId, &Aabb)> {
+        let range = self.cell_range(bounds);
+        let ids: Vec<EntityId> = if Self::cell_count(range) > self.entries.len() as u64 {
+            self.entries.keys().copied().collect()
+        } else {
+            let mut visited = HashSet::new();
+            Self::cells_in(range)
+                .filter_map(|cell| self.cells.get(&cell))
+                .flatten()
+                .chain(&self.large)
+                .filter(|&&id| visited.insert(id))
+                .copied()
+                .collect()
+        };
+        ids.into_iter().map(|id| (id, &self.entries[&id].bounds))
+    }
+
+    /**
+     * The cells exactly `ring` cells away from `center` along some axis.
+     */
+    fn shell(center: Cell, ring: i32) -> impl Iterator<Item = Cell> {
+        let [x, y, z] = center;
+        (-ring..=ring).flat_map(move |dx| {
+            (-ring..=ring).flat_map(move |dy| {
+                // Columns on the sides of the shell are whole, the
+                // ones inside only have their two ends on it.
+                let side = dx.abs() == ring || dy.abs() == ring;
+                let step = if side { 1 } else { 2 * ring as usize };
+                (-ring..=ring)
+                    .step_by(step)
+                    .map(move |dz| [x + dx, y + dy, z + dz])
+            })
+        })
+    }
+
+    fn shell_len(ring: i32) -> u64 {
+        let outer = 2 * ring as u64 + 1;
+        let inner = (2 * ring as u64).saturating_sub(1);
+        outer.pow(3) - inner.pow(3)
+    }
+
+    /**
+     * The cell coordinates enclosing every occupied cell.
+     */
+    fn occupied_range(&self) -> Option<(Cell, Cell)> {
+        let mut cells = self.cells.keys();
+        let first = *cells.next()?;
+        Some(cells.fold((first, first), |(min, max), c| {
+            (
+                [min[0].min(c[0]), min[1].min(c[1]), min[2].min(c[2])],
+                [max[0].max(c[0]), max[1].max(c[1]), max[2].max(c[2])],
+            )
+        }))
+    }
+}
+
+/**
+ * Finds entities whose bounds come within `radius` of `center`
+ */
+pub struct WithinRadius {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl Query for WithinRadius {
+    type Mapping = SpatialGrid;
+    type Out<'m> = Vec<EntityId>;
+
+    fn query<'m>(&'m self, map: &'m SpatialGrid) -> Vec<EntityId> {
+        let radius_squared = self.radius * self.radius;
+        map.candidates(&Sphere::new(self.center, self.radius).aabb())
+            .filter(|(_, b)| b.distance_squared_to_point(self.center) <= radius_squared)
+            .map(|(id, _)| id)
+            .collect()
+    }
+}
+
+/**
+ * Finds entities whose bounds overlap an axis-aligned box
+ */
+pub struct Overlapping(pub Aabb);
+
+impl Query for Overlapping {
+    type Mapping = SpatialGrid;
+    type Out<'m> = Vec<EntityId>;
+
+    fn query<'m>(&'m self, map: &'m SpatialGrid) -> Vec<EntityId> {
+        map.candidates(&self.0)
+            .filter(|(_, b)| b.intersects(&self.0))
+            .map(|(id, _)| id)
+            .collect()
+    }
+}
+
+/**
+ * Finds the `k` entities whose bounds are closest to `point`
+ *
+ * Results are sorted from nearest to farthest.
+ */
+pub struct Nearest {
+    pub point: Vec3,
+    pub k: usize,
+}
+
+impl Query for Nearest {
+    type Mapping = SpatialGrid;
+    type Out<'m> = Vec<(EntityId, f32)>;
+
+    fn query<'m>(&'m self, map: &'m SpatialGrid) -> Vec<(EntityId, f32)> {
+        if self.k == 0 {
+            return Vec::new();
+        }
+
+        let mut visited = HashSet::new();
+        let mut found: Vec<(EntityId, f32)> = Vec::new();
+        let mut visit = |ids: &[EntityId], found: &mut Vec<(EntityId, f32)>| {
+            for &id in ids {
+                if visited.insert(id) {
+                    let d = map.entries[&id]
+                        .bounds
+                        .distance_squared_to_point(self.point);
+                    found.push((id, d));
+                }
+            }
+        };
+        visit(&map.large, &mut found);
+
+        let center = map.cell_of(self.point);
+        let max_ring = map.occupied_range().map_or(-1, |(min, max)| {
+            (0..3)
+                .map(|i| (center[i] - min[i]).abs().max((max[i] - center[i]).abs()))
+                .max()
+                .unwrap_or(0)
+        });
+
+        for ring in 0..=max_ring {
+            // Past this, reading every occupied cell is cheaper.
+            if SpatialGrid::shell_len(ring) > map.cells.len() as u64 {
+                for ids in map.cells.values() {
+                    visit(ids, &mut found);
+                }
+                break;
+            }
+            for cell in SpatialGrid::shell(center, ring) {
+                if let Some(ids) = map.cells.get(&cell) {
+                    visit(ids, &mut found);
+                }
+            }
+
+            // Anything not yet visited lies at least `ring` whole cells away
+            if found.len() >= self.k {
+                found.sort_by(|a, b| a.1.total_cmp(&b.1));
+                let reach = ring as f32 * map.cell_size;
+                if found[self.k - 1].1 <= reach * reach {
+                    break;
+                }
+            }
+        }
+
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+        found.truncate(self.k);
+        found.into_iter().map(|(id, d)| (id, d.sqrt())).collect()
+    }
+}
@@ -1 +1,1111 @@
+use std::alloc::{self, Layout};
+use std::cell::UnsafeCell;
+use std::collections::HashMap;
+use std::mem::MaybeUninit;
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicU32, Ordering};
 
+use super::Mapping;
+use crate::ecs::change_detection::{ComponentTicks, Mut, SystemTicks};
+use crate::ecs::{Component, EntityId, StorageType, component_storage, get_component_id};
+
+/**
+ * Describes how to store a component type without knowing it
+ *
+ * Columns use this to move, drop and deallocate components
+ * through raw pointers.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct ComponentInfo {
+    pub id: usize,
+    pub layout: Layout,
+    pub drop: Option<unsafe fn(*mut u8)>,
+    pub storage: StorageType,
+}
+
+impl ComponentInfo {
+    pub fn of<T: Component>() -> Self {
+        unsafe fn drop_ptr<T>(ptr: *mut u8) {
+            unsafe { ptr::drop_in_place(ptr as *mut T) }
+        }
+
+        let id = get_component_id::<T>();
+        Self {
+            id,
+            layout: Layout::new::<T>(),
+            drop: std::mem::needs_drop::<T>().then_some(drop_ptr::<T> as unsafe fn(*mut u8)),
+            storage: component_storage(id),
+        }
+    }
+}
+
+/**
+ * A type-erased growable array of components of a single type
+ */
+pub(crate) struct BlobVec {
+    item: Layout,
+    drop: Option<unsafe fn(*mut u8)>,
+    data: NonNull<u8>,
+    len: usize,
+    capacity: usize,
+}
+
+impl BlobVec {
+    fn new(info: &ComponentInfo) -> Self {
+        let item = info.layout.pad_to_align();
+        Self {
+            item,
+            drop: info.drop,
+            data: NonNull::new(ptr::without_provenance_mut(item.align())).unwrap(),
+            len: 0,
+            capacity: if item.size() == 0 { usize::MAX } else { 0 },
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    fn grow(&mut self) {
+        let new_capacity = (self.capacity * 2).max(4);
+        let new_layout =
+            Layout::from_size_align(self.item.size() * new_capacity, self.item.align())
+                .expect("Column capacity overflow");
+
+        let data = unsafe {
+            if self.capacity == 0 {
+                alloc::alloc(new_layout)
+            } else {
+                let old_layout = Layout::from_size_align_unchecked(
+                    self.item.size() * self.capacity,
+                    self.item.align(),
+                );
+                alloc::realloc(self.data.as_ptr(), old_layout, new_layout.size())
+            }
+        };
+
+        self.data = NonNull::new(data).unwrap_or_else(|| alloc::handle_alloc_error(new_layout));
+        self.capacity = new_capacity;
+    }
+
+    pub(crate) fn get_ptr(&self, row: usize) -> *mut u8 {
+        debug_assert!(row < self.len);
+        unsafe { self.data.as_ptr().add(row * self.item.size()) }
+    }
+
+    pub(crate) fn as_ptr(&self) -> *mut u8 {
+        self.data.as_ptr()
+    }
+
+    /**
+     * Moves the value behind `value` into the end of the array.
+     *
+     * The caller must not drop the value afterwards.
+     */
+    unsafe fn push(&mut self, value: *const u8) {
+        if self.len == self.capacity {
+            self.grow();
+        }
+        unsafe {
+            ptr::copy_nonoverlapping(
+                value,
+                self.data.as_ptr().add(self.len * self.item.size()),
+                self.item.size(),
+            );
+        }
+        self.len += 1;
+    }
+
+    /**
+     * Moves the value at `row` into `dst` and fills the hole with the last value.
+     */
+    unsafe fn swap_remove_into(&mut self, row: usize, dst: *mut u8) {
+        let last = self.get_ptr(self.len - 1);
+        let removed = self.get_ptr(row);
+        unsafe {
+            ptr::copy_nonoverlapping(removed, dst, self.item.size());
+            if row != self.len - 1 {
+                ptr::copy_nonoverlapping(last, removed, self.item.size());
+            }
+        }
+        self.len -= 1;
+    }
+
+    /**
+     * Moves the value at `row` into `dst`, shifting later values down.
+     */
+    unsafe fn remove_into(&mut self, row: usize, dst: *mut u8) {
+        let removed = self.get_ptr(row);
+        unsafe {
+            ptr::copy_nonoverlapping(removed, dst, self.item.size());
+            ptr::copy(
+                removed.add(self.item.size()),
+                removed,
+                (self.len - row - 1) * self.item.size(),
+            );
+        }
+        self.len -= 1;
+    }
+
+    /**
+     * Moves the value at `row` onto the end of `dst`, another array of
+     * the same type, and fills the hole with the last value.
+     */
+    unsafe fn swap_remove_to(&mut self, row: usize, dst: &mut BlobVec) {
+        if dst.len == dst.capacity {
+            dst.grow();
+        }
+        unsafe {
+            self.swap_remove_into(row, dst.data.as_ptr().add(dst.len * dst.item.size()));
+        }
+        dst.len += 1;
+    }
+
+    /**
+     * Drops the value at `row` and fills the hole with the last value.
+     */
+    unsafe fn swap_remove_drop(&mut self, row: usize) {
+        let last = self.get_ptr(self.len - 1);
+        let removed = self.get_ptr(row);
+        unsafe {
+            if let Some(drop) = self.drop {
+                drop(removed);
+            }
+            if row != self.len - 1 {
+                ptr::copy_nonoverlapping(last, removed, self.item.size());
+            }
+        }
+        self.len -= 1;
+    }
+}
+
+impl Drop for BlobVec {
+    fn drop(&mut self) {
+        if let Some(drop) = self.drop {
+            for row in 0..self.len {
+                unsafe { drop(self.get_ptr(row)) }
+            }
+        }
+        if self.item.size() != 0 && self.capacity != 0 {
+            unsafe {
+                alloc::dealloc(
+                    self.data.as_ptr(),
+                    Layout::from_size_align_unchecked(
+                        self.item.size() * self.capacity,
+                        self.item.align(),
+                    ),
+                );
+            }
+        }
+    }
+}
+
+pub type ArchetypeId = usize;
+
+/**
+ * The position of an entity's components inside a `Table`
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Location {
+    pub archetype: ArchetypeId,
+    pub row: usize,
+}
+
+/**
+ * A group of entities that share the exact same set of components
+ *
+ * Every component type is stored contiguously in its own column,
+ * and row `n` of every column belongs to `entities()[n]`. Each
+ * column keeps the change ticks of its rows alongside it.
+ */
+pub struct Archetype {
+    id: ArchetypeId,
+    components: Vec<usize>,
+    columns: Vec<BlobVec>,
+    ticks: Vec<Vec<UnsafeCell<ComponentTicks>>>,
+    entities: Vec<EntityId>,
+}
+
+impl Archetype {
+    pub fn id(&self) -> ArchetypeId {
+        self.id
+    }
+
+    /**
+     * The sorted ids of the components stored in this archetype
+     */
+    pub fn components(&self) -> &[usize] {
+        &self.components
+    }
+
+    pub fn entities(&self) -> &[EntityId] {
+        &self.entities
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn has_component(&self, id: usize) -> bool {
+        self.column_index(id).is_some()
+    }
+
+    fn column_index(&self, id: usize) -> Option<usize> {
+        self.components.binary_search(&id).ok()
+    }
+
+    pub(crate) fn column_by_id(&self, id: usize) -> Option<&BlobVec> {
+        self.column_index(id).map(|i| &self.columns[i])
+    }
+
+    /**
+     * Gets the column of a component type as a slice.
+     *
+     * If the archetype does not store the component, it returns None.
+     */
+    pub fn column<T: Component>(&self) -> Option<&[T]> {
+        self.column_by_id(get_component_id::<T>())
+            .map(|c| unsafe { std::slice::from_raw_parts(c.as_ptr() as *const T, c.len()) })
+    }
+
+    /**
+     * Gets the column of a component type as a mutable slice.
+     *
+     * Writes through the slice are not recorded as changes.
+     * If the archetype does not store the component, it returns None.
+     */
+    pub fn column_mut<T: Component>(&mut self) -> Option<&mut [T]> {
+        self.column_index(get_component_id::<T>()).map(|i| {
+            let c = &self.columns[i];
+            unsafe { std::slice::from_raw_parts_mut(c.as_ptr() as *mut T, c.len()) }
+        })
+    }
+
+    /**
+     * Removes a row, moving the last row into its place.
+     *
+     * Returns the entity that was moved, if any.
+     */
+    fn swap_remove_entity(&mut self, row: usize) -> Option<EntityId> {
+        self.entities.swap_remove(row);
+        self.entities.get(row).copied()
+    }
+}
+
+/**
+ * Storage for a component type kept outside of archetypes
+ *
+ * Adding or removing a sparse component never moves the entity
+ * to another archetype. Rows are looked up by entity index.
+ */
+pub struct SparseSet {
+    values: BlobVec,
+    ticks: Vec<UnsafeCell<ComponentTicks>>,
+    entities: Vec<EntityId>,
+    rows: Vec<Option<usize>>,
+}
+
+impl SparseSet {
+    fn new(info: &ComponentInfo) -> Self {
+        Self {
+            values: BlobVec::new(info),
+            ticks: Vec::new(),
+            entities: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    fn row(&self, id: EntityId) -> Option<usize> {
+        let row = (*self.rows.get(id.index() as usize)?)?;
+        (self.entities[row] == id).then_some(row)
+    }
+
+    /**
+     * Moves `value` into the set for an entity that has none yet.
+     */
+    unsafe fn push(&mut self, id: EntityId, value: *const u8, tick: u32) {
+        debug_assert!(self.row(id).is_none());
+        let index = id.index() as usize;
+        if self.rows.len() <= index {
+            self.rows.resize(index + 1, None);
+        }
+        self.rows[index] = Some(self.entities.len());
+        self.entities.push(id);
+        self.ticks.push(UnsafeCell::new(ComponentTicks::new(tick)));
+        unsafe { self.values.push(value) };
+    }
+
+    /**
+     * Moves an entity's value into `dst`, or drops it if no
+     * destination is given.
+     */
+    unsafe fn remove(&mut self, id: EntityId, dst: Option<*mut u8>) -> bool {
+        let Some(row) = self.row(id) else {
+            return false;
+        };
+        unsafe {
+            match dst {
+                Some(dst) => self.values.swap_remove_into(row, dst),
+                None => self.values.swap_remove_drop(row),
+            }
+        }
+        self.ticks.swap_remove(row);
+        self.entities.swap_remove(row);
+        self.rows[id.index() as usize] = None;
+        if let Some(moved) = self.entities.get(row) {
+            self.rows[moved.index() as usize] = Some(row);
+        }
+        true
+    }
+}
+
+/**
+ * Where the components of one type are found for the rows of an archetype
+ *
+ * Table components are read from the archetype's column by row,
+ * sparse ones from the component's sparse set by entity.
+ */
+#[derive(Clone, Copy)]
+pub enum Column<'w> {
+    Dense {
+        values: *mut u8,
+        ticks: *mut ComponentTicks,
+        size: usize,
+    },
+    Sparse(&'w SparseSet),
+}
+
+impl Column<'_> {
+    /**
+     * Gets pointers to a component and its ticks.
+     *
+     * If the entity has no such component, it returns None.
+     *
+     * # Safety
+     *
+     * `row` must be the entity's row in the archetype
+     * the column was taken from.
+     */
+    pub(crate) unsafe fn get(
+        &self,
+        id: EntityId,
+        row: usize,
+    ) -> Option<(*mut u8, *mut ComponentTicks)> {
+        match *self {
+            Column::Dense {
+                values,
+                ticks,
+                size,
+            } => Some(unsafe { (values.add(row * size), ticks.add(row)) }),
+            Column::Sparse(set) => {
+                let row = set.row(id)?;
+                Some((
+                    set.values.get_ptr(row),
+                    UnsafeCell::raw_get(&set.ticks[row]),
+                ))
+            }
+        }
+    }
+}
+
+/**
+ * Archetype-based columnar storage for components
+ *
+ * Entities with the same set of components share an archetype,
+ * so iterating over a component type touches contiguous memory.
+ * Adding or removing a component moves the entity's row into
+ * the archetype matching its new component set.
+ *
+ * Components declared with `StorageType::Sparse` are kept in a
+ * sparse set per type instead and are not part of the archetype.
+ *
+ * An entity can hold several instances of a component type. The
+ * first lives in the archetype column, the rest are kept together
+ * in a side array for that entity and component.
+ *
+ * Every write through the table is stamped with the current change
+ * tick, and every removal is logged until `clear_removed`.
+ */
+pub struct Table {
+    archetypes: Vec<Archetype>,
+    archetype_ids: HashMap<Vec<usize>, ArchetypeId>,
+    locations: Vec<Option<Location>>,
+    infos: Vec<Option<ComponentInfo>>,
+    extras: HashMap<(usize, EntityId), BlobVec>,
+    sparse: HashMap<usize, SparseSet>,
+    change_tick: AtomicU32,
+    removed: Vec<Vec<(EntityId, u32)>>,
+    removed_since: u32,
+}
+
+impl Mapping for Table {}
+
+impl Table {
+    pub fn new() -> Self {
+        let mut table = Self {
+            archetypes: Vec::new(),
+            archetype_ids: HashMap::new(),
+            locations: Vec::new(),
+            infos: Vec::new(),
+            extras: HashMap::new(),
+            sparse: HashMap::new(),
+            change_tick: AtomicU32::new(1),
+            removed: Vec::new(),
+            removed_since: 0,
+        };
+        table.archetype_for(Vec::new());
+        table
+    }
+
+    pub fn archetypes(&self) -> &[Archetype] {
+        &self.archetypes
+    }
+
+    pub fn archetype(&self, id: ArchetypeId) -> &Archetype {
+        &self.archetypes[id]
+    }
+
+    /**
+     * The tick writes through the table are currently stamped with
+     */
+    pub fn change_tick(&self) -> u32 {
+        self.change_tick.load(Ordering::Relaxed)
+    }
+
+    /**
+     * Claims the current change tick and advances the counter.
+     *
+     * Every system run claims its own tick, so later writes
+     * are always newer than the ones it made.
+     */
+    pub fn increment_change_tick(&self) -> u32 {
+        self.change_tick.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /**
+     * Moves the change tick ahead by `ticks`.
+     */
+    pub fn advance_change_tick(&self, ticks: u32) {
+        self.change_tick.fetch_add(ticks, Ordering::Relaxed);
+    }
+
+    /**
+     * Gets the change ticks of an entity's component.
+     *
+     * If the component is not present, it returns None.
+     */
+    pub fn ticks(&self, id: EntityId, component: usize) -> Option<ComponentTicks> {
+        let (_, ticks) = self.slot(id, component)?;
+        Some(unsafe { *ticks })
+    }
+
+    /**
+     * The entities that lost a component, with the tick of the removal
+     */
+    pub fn removed(&self, component: usize) -> &[(EntityId, u32)] {
+        self.removed.get(component).map_or(&[], Vec::as_slice)
+    }
+
+    /**
+     * Clamps the ticks of every component, see `World::check_change_ticks`.
+     */
+    pub fn check_change_ticks(&mut self, change_tick: u32) {
+        let archetypes = self
+            .archetypes
+            .iter_mut()
+            .flat_map(|a| a.ticks.iter_mut().flatten());
+        let sparse = self
+            .sparse
+            .values_mut()
+            .flat_map(|set| set.ticks.iter_mut());
+        for ticks in archetypes.chain(sparse) {
+            ticks.get_mut().check_ticks(change_tick);
+        }
+    }
+
+    /**
+     * The tick passed to the last `clear_removed`
+     *
+     * Removals at or before it may already be forgotten.
+     */
+    pub fn removed_since(&self) -> u32 {
+        self.removed_since
+    }
+
+    /**
+     * Forgets removals that happened at or before `tick`.
+     */
+    pub fn clear_removed(&mut self, tick: u32) {
+        self.removed_since = tick;
+        let now = self.change_tick();
+        for removed in &mut self.removed {
+            removed.retain(|&(_, t)| crate::ecs::change_detection::is_newer(t, tick, now));
+        }
+    }
+
+    /**
+     * Gets the location of an entity's row.
+     *
+     * If the entity is not stored in the table, it returns None.
+     */
+    pub fn location(&self, id: EntityId) -> Option<Location> {
+        self.locations
+            .get(id.index() as usize)
+            .copied()
+            .flatten()
+            .filter(|loc| self.archetypes[loc.archetype].entities[loc.row] == id)
+    }
+
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.location(id).is_some()
+    }
+
+    /**
+     * Adds an entity with no components to the table.
+     *
+     * If the entity is already present, it returns false.
+     */
+    pub fn insert_entity(&mut self, id: EntityId) -> bool {
+        if self.contains(id) {
+            return false;
+        }
+
+        let index = id.index() as usize;
+        if self.locations.len() <= index {
+            self.locations.resize(index + 1, None);
+        }
+
+        let empty = &mut self.archetypes[0];
+        empty.entities.push(id);
+        self.locations[index] = Some(Location {
+            archetype: 0,
+            row: empty.len() - 1,
+        });
+        true
+    }
+
+    /**
+     * Removes an entity from the table, dropping all of its components.
+     *
+     * If the entity is not present, it returns false.
+     */
+    pub fn remove_entity(&mut self, id: EntityId) -> bool {
+        let Some(loc) = self.location(id) else {
+            return false;
+        };
+
+        let tick = self.change_tick();
+        let archetype = &mut self.archetypes[loc.archetype];
+        for (column, ticks) in archetype.columns.iter_mut().zip(&mut archetype.ticks) {
+            unsafe { column.swap_remove_drop(loc.row) };
+            ticks.swap_remove(loc.row);
+        }
+        for &component in &archetype.components {
+            self.extras.remove(&(component, id));
+            log_removed(&mut self.removed, component, id, tick);
+        }
+        for (&component, set) in &mut self.sparse {
+            if unsafe { set.remove(id, None) } {
+                self.extras.remove(&(component, id));
+                log_removed(&mut self.removed, component, id, tick);
+            }
+        }
+        self.fix_moved(loc, id);
+        true
+    }
+
+    /**
+     * Gets a component of an entity.
+     *
+     * If the component is not present, it returns None.
+     */
+    pub fn get<T: Component>(&self, id: EntityId) -> Option<&T> {
+        let ptr = self.get_ptr(id, get_component_id::<T>())?;
+        Some(unsafe { &*(ptr as *const T) })
+    }
+
+    /**
+     * Gets a mutable component of an entity.
+     *
+     * Writing through it marks the component as changed.
+     * If the component is not present, it returns None.
+     */
+    pub fn get_mut<T: Component>(&mut self, id: EntityId) -> Option<Mut<'_, T>> {
+        let (value, ticks) = self.slot(id, get_component_id::<T>())?;
+        let system = SystemTicks {
+            last_run: 0,
+            this_run: self.change_tick(),
+        };
+        Some(unsafe { Mut::new(&mut *(value as *mut T), &mut *ticks, system) })
+    }
+
+    /**
+     * Gets a pointer to a component of an entity by its component id.
+     */
+    pub(crate) fn get_ptr(&self, id: EntityId, component: usize) -> Option<*mut u8> {
+        self.slot(id, component).map(|(value, _)| value)
+    }
+
+    /**
+     * Gets a pointer to a component of an entity for writing,
+     * marking the component as changed.
+     */
+    pub(crate) fn get_mut_ptr(&mut self, id: EntityId, component: usize) -> Option<*mut u8> {
+        let ptr = self.get_ptr(id, component)?;
+        self.mark_changed(id, component);
+        Some(ptr)
+    }
+
+    /**
+     * Gets pointers to a component of an entity and its ticks.
+     */
+    fn slot(&self, id: EntityId, component: usize) -> Option<(*mut u8, *mut ComponentTicks)> {
+        let loc = self.location(id)?;
+        unsafe {
+            self.column(self.archetype(loc.archetype), component)?
+                .get(id, loc.row)
+        }
+    }
+
+    /**
+     * Gets the storage of a component type for the rows of an archetype.
+     *
+     * If the archetype cannot hold the component, it returns None.
+     */
+    pub(crate) fn column<'w>(
+        &'w self,
+        archetype: &'w Archetype,
+        component: usize,
+    ) -> Option<Column<'w>> {
+        if let Some(set) = self.sparse.get(&component) {
+            return Some(Column::Sparse(set));
+        }
+        let i = archetype.column_index(component)?;
+        Some(Column::Dense {
+            values: archetype.columns[i].as_ptr(),
+            ticks: UnsafeCell::raw_get(archetype.ticks[i].as_ptr()),
+            size: archetype.columns[i].item.size(),
+        })
+    }
+
+    /**
+     * Checks if an entity has a component.
+     */
+    pub fn has<T: Component>(&self, id: EntityId) -> bool {
+        self.has_by_id(id, get_component_id::<T>())
+    }
+
+    pub fn has_by_id(&self, id: EntityId, component: usize) -> bool {
+        self.slot(id, component).is_some()
+    }
+
+    /**
+     * Inserts a component for an entity.
+     *
+     * If the component was already present, it is replaced and the
+     * previous value is returned. If the entity is not in the table,
+     * the component is handed back as an error.
+     */
+    pub fn insert<T: Component>(&mut self, id: EntityId, component: T) -> Result<Option<T>, T> {
+        let info = ComponentInfo::of::<T>();
+        let Some(loc) = self.location(id) else {
+            return Err(component);
+        };
+
+        if let Some(ptr) = self.get_ptr(id, info.id) {
+            let old = unsafe { ptr::replace(ptr as *mut T, component) };
+            self.mark_changed(id, info.id);
+            return Ok(Some(old));
+        }
+
+        let component = MaybeUninit::new(component);
+        unsafe { self.insert_new(id, loc, info, component.as_ptr() as *const u8) };
+        Ok(None)
+    }
+
+    /**
+     * Inserts a type-erased component for an entity.
+     *
+     * Drops the previous component if it exists.
+     *
+     * # Safety
+     *
+     * `value` must point to a valid value described by `info`, and
+     * ownership of it moves into the table.
+     */
+    pub unsafe fn insert_by_id(
+        &mut self,
+        id: EntityId,
+        info: ComponentInfo,
+        value: *const u8,
+    ) -> bool {
+        let Some(loc) = self.location(id) else {
+            return false;
+        };
+
+        match self.get_ptr(id, info.id) {
+            Some(ptr) => {
+                unsafe {
+                    if let Some(drop) = info.drop {
+                        drop(ptr);
+                    }
+                    ptr::copy_nonoverlapping(value, ptr, info.layout.size());
+                }
+                self.mark_changed(id, info.id);
+            }
+            None => unsafe { self.insert_new(id, loc, info, value) },
+        }
+        true
+    }
+
+    unsafe fn insert_new(
+        &mut self,
+        id: EntityId,
+        loc: Location,
+        info: ComponentInfo,
+        value: *const u8,
+    ) {
+        self.register(info);
+        let tick = self.change_tick();
+
+        if info.storage == StorageType::Sparse {
+            let set = self
+                .sparse
+                .entry(info.id)
+                .or_insert_with(|| SparseSet::new(&info));
+            unsafe { set.push(id, value, tick) };
+            return;
+        }
+
+        let mut components = self.archetypes[loc.archetype].components.clone();
+        let pos = components.binary_search(&info.id).unwrap_err();
+        components.insert(pos, info.id);
+        let target = self.archetype_for(components);
+
+        let new_loc = self.move_row(id, loc, target, None);
+        let archetype = &mut self.archetypes[target];
+        let column = archetype.column_index(info.id).unwrap();
+        unsafe { archetype.columns[column].push(value) };
+        archetype.ticks[column].push(UnsafeCell::new(ComponentTicks::new(tick)));
+        debug_assert_eq!(archetype.columns[column].len(), new_loc.row + 1);
+    }
+
+    /**
+     * Removes a component from an entity.
+     *
+     * Every instance is removed and the first one is returned.
+     * If the component is not present, it returns None.
+     */
+    pub fn remove<T: Component>(&mut self, id: EntityId) -> Option<T> {
+        let mut out = MaybeUninit::<T>::uninit();
+        if unsafe {
+            self.take_by_id(
+                id,
+                get_component_id::<T>(),
+                Some(out.as_mut_ptr() as *mut u8),
+            )
+        } {
+            Some(unsafe { out.assume_init() })
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Removes every instance of a component from an entity by its id, dropping them.
+     *
+     * If the component is not present, it returns false.
+     */
+    pub fn remove_by_id(&mut self, id: EntityId, component: usize) -> bool {
+        unsafe { self.take_by_id(id, component, None) }
+    }
+
+    /**
+     * Moves a component out of an entity into `dst`, or drops it
+     * if no destination is given.
+     */
+    unsafe fn take_by_id(&mut self, id: EntityId, component: usize, dst: Option<*mut u8>) -> bool {
+        let Some(loc) = self.location(id) else {
+            return false;
+        };
+
+        if let Some(set) = self.sparse.get_mut(&component) {
+            if !unsafe { set.remove(id, dst) } {
+                return false;
+            }
+        } else {
+            let source = &self.archetypes[loc.archetype];
+            let Some(pos) = source.column_index(component) else {
+                return false;
+            };
+
+            let mut components = source.components.clone();
+            components.remove(pos);
+            let target = self.archetype_for(components);
+            self.move_row(id, loc, target, dst.map(|dst| (component, dst)));
+        }
+        self.extras.remove(&(component, id));
+        let tick = self.change_tick();
+        log_removed(&mut self.removed, component, id, tick);
+        true
+    }
+
+    /**
+     * Adds another instance of a component to an entity.
+     *
+     * Returns the index of the new instance, 0 being the one stored
+     * in the archetype column. If the entity is not in the table, the
+     * component is handed back as an error.
+     */
+    pub fn add<T: Component>(&mut self, id: EntityId, component: T) -> Result<usize, T> {
+        if !self.has::<T>(id) {
+            return self.insert(id, component).map(|_| 0);
+        }
+
+        let info = ComponentInfo::of::<T>();
+        self.mark_changed(id, info.id);
+        let extras = self
+            .extras
+            .entry((info.id, id))
+            .or_insert_with(|| BlobVec::new(&info));
+        let component = MaybeUninit::new(component);
+        unsafe { extras.push(component.as_ptr() as *const u8) };
+        Ok(extras.len())
+    }
+
+    /**
+     * Returns how many instances of a component an entity has.
+     */
+    pub fn count<T: Component>(&self, id: EntityId) -> usize {
+        let component = get_component_id::<T>();
+        if !self.has_by_id(id, component) {
+            return 0;
+        }
+        1 + self.extras.get(&(component, id)).map_or(0, BlobVec::len)
+    }
+
+    /**
+     * Gets the instances of a component after the first one.
+     */
+    pub(crate) fn extras_ptr(&self, id: EntityId, component: usize) -> (*mut u8, usize) {
+        match self.extras.get(&(component, id)) {
+            Some(extras) => (extras.as_ptr(), extras.len()),
+            None => (ptr::null_mut(), 0),
+        }
+    }
+
+    /**
+     * Iterates over every instance of a component on an entity.
+     */
+    pub fn get_all<T: Component>(&self, id: EntityId) -> impl Iterator<Item = &T> {
+        let (ptr, len) = self.extras_ptr(id, get_component_id::<T>());
+        let rest: &[T] = if len == 0 {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(ptr as *const T, len) }
+        };
+        self.get::<T>(id).into_iter().chain(rest)
+    }
+
+    /**
+     * Iterates mutably over every instance of a component on an entity.
+     *
+     * The component is marked as changed up front.
+     */
+    pub fn get_all_mut<T: Component>(&mut self, id: EntityId) -> impl Iterator<Item = &mut T> {
+        let component = get_component_id::<T>();
+        self.mark_changed(id, component);
+        let (ptr, len) = self.extras_ptr(id, component);
+        let rest: &mut [T] = if len == 0 {
+            &mut []
+        } else {
+            unsafe { std::slice::from_raw_parts_mut(ptr as *mut T, len) }
+        };
+        let first = self
+            .get_ptr(id, component)
+            .map(|ptr| unsafe { &mut *(ptr as *mut T) });
+        first.into_iter().chain(rest)
+    }
+
+    /**
+     * Removes a single instance of a component from an entity.
+     *
+     * Later instances shift down to fill the gap. If there is no
+     * instance at `index`, it returns None.
+     */
+    pub fn remove_at<T: Component>(&mut self, id: EntityId, index: usize) -> Option<T> {
+        let key = (get_component_id::<T>(), id);
+        let extras = self.extras.get_mut(&key).filter(|e| e.len() > 0);
+
+        let Some(extras) = extras else {
+            return if index == 0 {
+                self.remove::<T>(id)
+            } else {
+                None
+            };
+        };
+        if index > extras.len() {
+            return None;
+        }
+
+        let mut out = MaybeUninit::<T>::uninit();
+        unsafe { extras.remove_into(index.saturating_sub(1), out.as_mut_ptr() as *mut u8) };
+        if extras.len() == 0 {
+            self.extras.remove(&key);
+        }
+
+        let out = unsafe { out.assume_init() };
+        if index == 0 {
+            let mut first = self.get_mut::<T>(id).unwrap();
+            Some(std::mem::replace(&mut *first, out))
+        } else {
+            self.mark_changed(id, key.0);
+            Some(out)
+        }
+    }
+
+    /**
+     * Gets the storage description of a component type.
+     *
+     * If the component has never been stored in the table, it returns None.
+     */
+    pub fn info(&self, component: usize) -> Option<ComponentInfo> {
+        self.infos.get(component).copied().flatten()
+    }
+
+    fn mark_changed(&mut self, id: EntityId, component: usize) {
+        let tick = self.change_tick();
+        if let Some((_, ticks)) = self.slot(id, component) {
+            unsafe { (*ticks).set_changed(tick) };
+        }
+    }
+
+    /**
+     * The ids of every component an entity has, including sparse ones
+     */
+    pub fn components_of(&self, id: EntityId) -> Vec<usize> {
+        let Some(loc) = self.location(id) else {
+            return Vec::new();
+        };
+        let mut components = self.archetypes[loc.archetype].components.clone();
+        components.extend(
+            self.sparse
+                .iter()
+                .filter(|(_, set)| set.row(id).is_some())
+                .map(|(&component, _)| component),
+        );
+        components
+    }
+
+    fn register(&mut self, info: ComponentInfo) {
+        if self.infos.len() <= info.id {
+            self.infos.resize(info.id + 1, None);
+        }
+        self.infos[info.id].get_or_insert(info);
+    }
+
+    fn archetype_for(&mut self, components: Vec<usize>) -> ArchetypeId {
+        if let Some(&id) = self.archetype_ids.get(&components) {
+            return id;
+        }
+
+        let id = self.archetypes.len();
+        let columns = components
+            .iter()
+            .map(|&c| BlobVec::new(&self.infos[c].expect("Component info missing")))
+            .collect();
+        self.archetypes.push(Archetype {
+            id,
+            components: components.clone(),
+            columns,
+            ticks: components.iter().map(|_| Vec::new()).collect(),
+            entities: Vec::new(),
+        });
+        self.archetype_ids.insert(components, id);
+        id
+    }
+
+    /**
+     * Moves an entity's row to another archetype.
+     *
+     * Columns missing from the target are moved into `taken` if it
+     * names them, otherwise they are dropped. Columns missing from the
+     * source are left for the caller to push.
+     */
+    fn move_row(
+        &mut self,
+        id: EntityId,
+        loc: Location,
+        target: ArchetypeId,
+        taken: Option<(usize, *mut u8)>,
+    ) -> Location {
+        let (source, dest) = pair_mut(&mut self.archetypes, loc.archetype, target);
+
+        for (i, &component) in source.components.iter().enumerate() {
+            let column = &mut source.columns[i];
+            let ticks = source.ticks[i].swap_remove(loc.row);
+            match dest.column_index(component) {
+                Some(j) => {
+                    unsafe { column.swap_remove_to(loc.row, &mut dest.columns[j]) };
+                    dest.ticks[j].push(ticks);
+                }
+                None => match taken {
+                    Some((c, dst)) if c == component => unsafe {
+                        column.swap_remove_into(loc.row, dst)
+                    },
+                    _ => unsafe { column.swap_remove_drop(loc.row) },
+                },
+            }
+        }
+
+        dest.entities.push(id);
+        let new_loc = Location {
+            archetype: target,
+            row: dest.len() - 1,
+        };
+        self.fix_moved(loc, id);
+        self.locations[id.index() as usize] = Some(new_loc);
+        new_loc
+    }
+
+    /**
+     * Removes `id` from the entity list at `loc` and patches the
+     * location of the entity that was swapped into its row.
+     */
+    fn fix_moved(&mut self, loc: Location, id: EntityId) {
+        let archetype = &mut self.archetypes[loc.archetype];
+        debug_assert_eq!(archetype.entities[loc.row], id);
+        if let Some(moved) = archetype.swap_remove_entity(loc.row) {
+            self.locations[moved.index() as usize] = Some(loc);
+        }
+        self.locations[id.index() as usize] = None;
+    }
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn log_removed(removed: &mut Vec<Vec<(EntityId, u32)>>, component: usize, id: EntityId, tick: u32) {
+    if removed.len() <= component {
+        removed.resize_with(component + 1, Vec::new);
+    }
+    removed[component].push((id, tick));
+}
+
+fn pair_mut<T>(items: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+    assert_ne!(a, b);
+    if a < b {
+        let (left, right) = items.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = items.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
@@ -1,40 +1,132 @@
+pub mod change_detection;
+pub mod commands;
+pub mod dynamic;
+pub mod event;
+pub mod hierarchy;
 pub mod mappings;
+pub mod observer;
+pub mod query;
+pub mod scene;
 pub mod scheduler;
 pub mod system;
 pub mod task;
 pub mod world;
 
+use change_detection::Mut;
+use mappings::table::Table;
+use observer::Lifecycle;
+use scene::SceneRegistration;
 use typeid::ConstTypeId;
+use world::World;
+
+use serde::{Deserialize, Serialize};
 
 use std::any::Any;
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::sync::OnceLock;
+use std::sync::{OnceLock, PoisonError, RwLock};
 
 pub use peano_derive::Component;
+pub use peano_derive::Event;
 pub use peano_derive::Resource;
 
 /**
  * This is a trait for components in the ECS
  *
  * It allows for components to be queried by Systems
- * An Entity can have multiple components of the same type
+ * An Entity can have multiple components of the same type,
+ * see `EntityMut::add_component` and `query::Many`
  */
-pub trait Component: Any {
+pub trait Component: Any + Send + Sync {
     fn get_type_id(&self) -> usize;
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /**
+     * Builds the registration of a generic component
+     *
+     * Generic types cannot be collected by `inventory`, so every
+     * instantiation is registered the first time its ID is needed.
+     * It is intended to be used by the `Component` derive macro
+     */
+    fn generic_registration() -> Option<ComponentRegistration>
+    where
+        Self: Sized,
+    {
+        None
+    }
+}
+
+/**
+ * How the components of a type are stored
+ *
+ * Chosen with `#[component(storage = "table")]` or
+ * `#[component(storage = "sparse")]`.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum StorageType {
+    /**
+     * In the columns of archetypes, which is fastest to iterate
+     */
+    #[default]
+    Table,
+    /**
+     * In a sparse set outside of archetypes, which is fastest to
+     * add and remove since the entity is never moved
+     */
+    Sparse,
 }
 
+/**
+ * A function run when a component is added to or removed from an entity
+ */
+pub type ComponentHook = fn(&mut World, EntityId);
+
 /**
  * This is a trait for resources in the ECS
  *
  * It allows for resources to be queried by Systems
  * You cannot have multiple resources of the same type
  */
-pub trait Resource: Any {
+pub trait Resource: Any + Send + Sync {
     fn get_type_id(&self) -> usize;
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /**
+     * Builds the registration of a generic resource
+     *
+     * It is intended to be used by the `Resource` derive macro,
+     * see `Component::generic_registration`
+     */
+    fn generic_registration() -> Option<ResourceRegistration>
+    where
+        Self: Sized,
+    {
+        None
+    }
+}
+
+/**
+ * This is a trait for events in the ECS
+ *
+ * Events are sent with `EventWriter` and read with `EventReader`,
+ * see `event::Events`. Deriving it registers `Events<Self>`
+ * as a resource.
+ */
+pub trait Event: Send + Sync + 'static {
+    /**
+     * Builds the registration of a generic event
+     *
+     * It is intended to be used by the `Event` derive macro,
+     * see `World::add_event`
+     */
+    fn generic_registration() -> Option<EventRegistration>
+    where
+        Self: Sized,
+    {
+        None
+    }
 }
 
 /**
@@ -46,8 +138,86 @@ pub trait Resource: Any {
 pub struct ComponentRegistration {
     pub type_id: ConstTypeId,
     pub name: &'static str,
+    /**
+     * The fully-qualified path of the type, or the id given
+     * with `#[component(id = "...")]`
+     */
+    pub key: &'static str,
+    pub fields: &'static [ComponentField],
+    pub cast: unsafe fn(*mut u8) -> *mut dyn Any,
+    pub storage: StorageType,
+    /**
+     * The hooks run at each `Lifecycle` event, before any observers
+     */
+    pub on_add: Option<ComponentHook>,
+    pub on_insert: Option<ComponentHook>,
+    pub on_replace: Option<ComponentHook>,
+    pub on_remove: Option<ComponentHook>,
+    /**
+     * How the component is saved in scenes, if it is
+     */
+    pub scene: Option<SceneRegistration>,
+}
+
+impl ComponentRegistration {
+    /**
+     * A hash of the key that stays the same across builds
+     *
+     * Unlike the component ID, it does not depend on which other
+     * components exist, so it can be persisted or sent over the network.
+     */
+    pub const fn stable_id(&self) -> u64 {
+        stable_id(self.key)
+    }
+
+    /**
+     * Gets a field of the component by its name.
+     *
+     * Fields of tuple structs are named by their index.
+     */
+    pub fn field(&self, name: &str) -> Option<&'static ComponentField> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /**
+     * Gets the hook run at a lifecycle event, if any.
+     */
+    pub fn hook(&self, event: Lifecycle) -> Option<ComponentHook> {
+        match event {
+            Lifecycle::Add => self.on_add,
+            Lifecycle::Insert => self.on_insert,
+            Lifecycle::Replace => self.on_replace,
+            Lifecycle::Remove => self.on_remove,
+        }
+    }
+}
+
+/**
+ * Runtime metadata about a field of a component
+ *
+ * It is emitted by the `Component` derive macro so that editors
+ * and debuggers can inspect and edit components by name.
+ * The thunks take the whole component and return None if it
+ * is not of the type the field belongs to.
+ */
+pub struct ComponentField {
+    pub name: &'static str,
+    pub type_name: fn() -> &'static str,
+    pub type_id: ConstTypeId,
+    pub offset: usize,
+    pub get: fn(&dyn Any) -> Option<&dyn Any>,
+    pub get_mut: fn(&mut dyn Any) -> Option<&mut dyn Any>,
+    /**
+     * Hands the value back if it is not of the field's type
+     */
+    pub set: FieldSetter,
 }
 
+/**
+ * Replaces a field of a component, see `ComponentField::set`
+ */
+pub type FieldSetter = fn(&mut dyn Any, Box<dyn Any>) -> Result<(), Box<dyn Any>>;
+
 /**
  * This is a struct to handle the registration inventory
  *
@@ -59,22 +229,128 @@ pub struct ResourceRegistration {
     pub name: &'static str,
 }
 
+/**
+ * This is a struct to handle the registration inventory
+ *
+ * It is intended to be used by the `Event` derive macro
+ * It is not intended to be used directly
+ */
+pub struct EventRegistration {
+    pub name: &'static str,
+    pub update: fn(&mut World),
+}
+
 inventory::collect!(ComponentRegistration);
 inventory::collect!(ResourceRegistration);
+inventory::collect!(EventRegistration);
 
 static COMPONENT_IDS: OnceLock<HashMap<ConstTypeId, usize>> = OnceLock::new();
 static RESOURCE_IDS: OnceLock<HashMap<ConstTypeId, usize>> = OnceLock::new();
+static COMPONENT_REGISTRATIONS: OnceLock<Vec<&'static ComponentRegistration>> = OnceLock::new();
+/**
+ * Instantiations of generic resources, in the order they were first used
+ */
+static GENERIC_RESOURCES: RwLock<Vec<ConstTypeId>> = RwLock::new(Vec::new());
+/**
+ * Instantiations of generic events, in the order they were first added
+ */
+static GENERIC_EVENTS: RwLock<Vec<EventRegistration>> = RwLock::new(Vec::new());
+
+thread_local! {
+    /**
+     * The IDs of the generic resources this thread already looked up,
+     * see `dynamic::generic_id`
+     */
+    static GENERIC_RESOURCE_IDS: RefCell<HashMap<ConstTypeId, usize>> =
+        RefCell::new(HashMap::new());
+}
 
 fn build_component_ids() -> HashMap<ConstTypeId, usize> {
+    COMPONENT_REGISTRATIONS
+        .get_or_init(build_component_registrations)
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (r.type_id, i))
+        .collect()
+}
+
+fn build_component_registrations() -> Vec<&'static ComponentRegistration> {
     let mut entries: Vec<_> = inventory::iter::<ComponentRegistration>
         .into_iter()
         .collect();
-    entries.sort_by_key(|e| e.name);
+    entries.sort_by_key(|e| e.key);
+
+    if let Err(err) = check_component_keys(&entries) {
+        panic!("{err}");
+    }
     entries
-        .into_iter()
-        .enumerate()
-        .map(|(i, r)| (r.type_id, i))
-        .collect()
+}
+
+/**
+ * Two components registered with the same key or stable id
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateComponent {
+    pub first: String,
+    pub second: String,
+}
+
+impl std::fmt::Display for DuplicateComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.first == self.second {
+            write!(
+                f,
+                "two components are registered as `{}`, give one of them \
+                 another id with `#[component(id = \"...\")]`",
+                self.first
+            )
+        } else {
+            write!(
+                f,
+                "components `{}` and `{}` have the same stable id, give one of them \
+                 another id with `#[component(id = \"...\")]`",
+                self.first, self.second
+            )
+        }
+    }
+}
+
+impl std::error::Error for DuplicateComponent {}
+
+fn check_component_keys(
+    sorted: &[&'static ComponentRegistration],
+) -> Result<(), DuplicateComponent> {
+    let mut stable_ids = HashMap::new();
+    for (i, r) in sorted.iter().enumerate() {
+        if i > 0 && sorted[i - 1].key == r.key {
+            return Err(DuplicateComponent {
+                first: r.key.to_string(),
+                second: r.key.to_string(),
+            });
+        }
+        if let Some(first) = stable_ids.insert(r.stable_id(), r.key) {
+            return Err(DuplicateComponent {
+                first: first.to_string(),
+                second: r.key.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Hashes a component key into its stable id with FNV-1a
+ */
+pub const fn stable_id(key: &str) -> u64 {
+    let bytes = key.as_bytes();
+    let mut hash = 0xcbf29ce484222325u64;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+        i += 1;
+    }
+    hash
 }
 
 fn build_resource_ids() -> HashMap<ConstTypeId, usize> {
@@ -95,68 +371,376 @@ fn build_resource_ids() -> HashMap<ConstTypeId, usize> {
  * This function is for use by the `Component` derive macro.
  * It is not intended to be used directly.
  */
-pub fn get_component_id<T: 'static>() -> usize {
-    *COMPONENT_IDS
+pub fn get_component_id<T: Component>() -> usize {
+    COMPONENT_IDS
         .get_or_init(build_component_ids)
         .get(&ConstTypeId::of::<T>())
+        .copied()
+        .or_else(dynamic::generic_id::<T>)
         .expect("Component not registered")
 }
 
+/**
+ * Returns the name a component was registered under
+ *
+ * Dynamic components are named by their key.
+ * Panics if no component has the given ID.
+ */
+pub fn component_name(id: usize) -> &'static str {
+    match component_registration(id) {
+        Some(registration) => registration.name,
+        None => component_key(id),
+    }
+}
+
+/**
+ * Returns the key a component was registered under
+ *
+ * Panics if no component has the given ID.
+ */
+pub fn component_key(id: usize) -> &'static str {
+    match component_registration(id) {
+        Some(registration) => registration.key,
+        None => dynamic::get(id).expect("Component not registered").0,
+    }
+}
+
+/**
+ * Returns the registration of the component with the given ID
+ *
+ * Dynamic components have no registration, see `dynamic::register_component`.
+ */
+pub fn component_registration(id: usize) -> Option<&'static ComponentRegistration> {
+    COMPONENT_REGISTRATIONS
+        .get_or_init(build_component_registrations)
+        .get(id)
+        .copied()
+        .or_else(|| dynamic::registration(id))
+}
+
+/**
+ * Returns how the component with the given ID is stored
+ *
+ * Panics if no component has the given ID.
+ */
+pub fn component_storage(id: usize) -> StorageType {
+    match component_registration(id) {
+        Some(registration) => registration.storage,
+        None => {
+            dynamic::get(id)
+                .expect("Component not registered")
+                .1
+                .storage
+        }
+    }
+}
+
+/**
+ * Returns the ID of the component registered under the given key
+ *
+ * If no component has that key, it returns None.
+ */
+pub fn component_id_by_key(key: &str) -> Option<usize> {
+    COMPONENT_REGISTRATIONS
+        .get_or_init(build_component_registrations)
+        .binary_search_by_key(&key, |r| r.key)
+        .ok()
+        .or_else(|| dynamic::find(|k| k == key))
+}
+
+/**
+ * Returns the ID of the component with the given stable id
+ *
+ * If no component has that stable id, it returns None.
+ */
+pub fn component_id_by_stable_id(id: u64) -> Option<usize> {
+    COMPONENT_REGISTRATIONS
+        .get_or_init(build_component_registrations)
+        .iter()
+        .position(|r| r.stable_id() == id)
+        .or_else(|| dynamic::find(|k| stable_id(k) == id))
+}
+
 /**
  * Returns the resource ID for the given type
  *
  * This function is for use by the `Resource` derive macro.
  * It is not intended to be used directly.
  */
-pub fn get_resource_id<T: 'static>() -> usize {
-    *RESOURCE_IDS
-        .get_or_init(build_resource_ids)
-        .get(&ConstTypeId::of::<T>())
-        .expect("Resource not registered")
+pub fn get_resource_id<T: Resource>() -> usize {
+    try_get_resource_id::<T>().expect("Resource not registered")
 }
 
 /**
- * Represents an entity in the ECS.
+ * Returns the resource ID for the given type
  *
- * Each entity has a unique ID and a list of components.
+ * If the type was never registered with the `Resource` derive, it returns None.
+ */
+pub fn try_get_resource_id<T: Resource>() -> Option<usize> {
+    let type_id = ConstTypeId::of::<T>();
+    let statics = RESOURCE_IDS.get_or_init(build_resource_ids);
+    if let Some(&id) = statics.get(&type_id) {
+        return Some(id);
+    }
+    if let Some(id) = GENERIC_RESOURCE_IDS.with_borrow(|ids| ids.get(&type_id).copied()) {
+        return Some(id);
+    }
+
+    let registration = T::generic_registration()?;
+    let index = {
+        let generics = GENERIC_RESOURCES
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        generics.iter().position(|&t| t == registration.type_id)
+    };
+    let index = index.unwrap_or_else(|| {
+        let mut generics = GENERIC_RESOURCES
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        match generics.iter().position(|&t| t == registration.type_id) {
+            Some(index) => index,
+            None => {
+                generics.push(registration.type_id);
+                generics.len() - 1
+            }
+        }
+    });
+    let id = statics.len() + index;
+    GENERIC_RESOURCE_IDS.with_borrow_mut(|ids| ids.insert(type_id, id));
+    Some(id)
+}
+
+/**
+ * Registers an instantiation of a generic event, if it was not yet
+ */
+pub(crate) fn register_generic_event(registration: EventRegistration) {
+    let mut generics = GENERIC_EVENTS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    if generics.iter().all(|r| r.name != registration.name) {
+        generics.push(registration);
+    }
+}
+
+/**
+ * Calls `f` with every registered event type, generic or not
+ */
+pub(crate) fn for_each_event(mut f: impl FnMut(&EventRegistration)) {
+    inventory::iter::<EventRegistration>
+        .into_iter()
+        .for_each(&mut f);
+    GENERIC_EVENTS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .for_each(f);
+}
+
+/**
+ * Returns the number of registered resource types
+ */
+pub fn resource_count() -> usize {
+    let generics = GENERIC_RESOURCES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .len();
+    RESOURCE_IDS.get_or_init(build_resource_ids).len() + generics
+}
+
+/**
+ * Returns the number of registered component types,
+ * including dynamic ones
+ */
+pub fn component_count() -> usize {
+    static_component_count() + dynamic::count()
+}
+
+fn static_component_count() -> usize {
+    COMPONENT_IDS.get_or_init(build_component_ids).len()
+}
+
+/**
+ * A generational handle to an entity in a `World`.
  *
+ * The index identifies the slot in the world and the generation
+ * is bumped every time that slot is recycled, so a handle to a
+ * despawned entity never aliases a newer one.
  */
-pub struct Entity {
-    pub id: u32,
-    pub(crate) components: Vec<Option<Box<dyn Component>>>,
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub struct EntityId {
+    index: u32,
+    generation: u32,
 }
 
-impl Entity {
-    pub(crate) fn new(id: u32) -> Self {
+impl EntityId {
+    pub(crate) fn new(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    pub fn index(self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(self) -> u32 {
+        self.generation
+    }
+
+    /**
+     * Packs the id into a single `u64`, generation in the high bits.
+     */
+    pub fn to_bits(self) -> u64 {
+        ((self.generation as u64) << 32) | self.index as u64
+    }
+
+    pub fn from_bits(bits: u64) -> Self {
         Self {
-            id,
-            components: Vec::with_capacity(COMPONENT_IDS.get().unwrap().len()),
+            index: bits as u32,
+            generation: (bits >> 32) as u32,
         }
     }
+}
+
+impl std::fmt::Display for EntityId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}v{}", self.index, self.generation)
+    }
+}
+
+/**
+ * A read-only view of an entity in a `World`.
+ *
+ * Components are stored in the world's table, so the view
+ * borrows the world for as long as it lives.
+ */
+pub struct EntityRef<'w> {
+    id: EntityId,
+    table: &'w Table,
+}
+
+impl<'w> EntityRef<'w> {
+    pub(crate) fn new(id: EntityId, table: &'w Table) -> Self {
+        Self { id, table }
+    }
+
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+
+    /**
+     * Gets a component from the entity.
+     *
+     * If the component is not present, it returns None.
+     */
+    pub fn get_component<T: Component>(&self) -> Option<&'w T> {
+        self.table.get::<T>(self.id)
+    }
+
+    /**
+     * Gets a component from the entity by its component ID.
+     *
+     * If the component is not present or is dynamic, it returns None.
+     */
+    pub fn get_by_id(&self, component: usize) -> Option<&'w dyn Any> {
+        let ptr = self.table.get_ptr(self.id, component)?;
+        Some(unsafe { &*(component_registration(component)?.cast)(ptr) })
+    }
+
+    /**
+     * Iterates over every instance of a component on the entity.
+     */
+    pub fn get_components<T: Component>(&self) -> impl Iterator<Item = &'w T> + use<'w, T> {
+        self.table.get_all::<T>(self.id)
+    }
+
+    /**
+     * Returns how many instances of a component the entity has.
+     */
+    pub fn component_count<T: Component>(&self) -> usize {
+        self.table.count::<T>(self.id)
+    }
+
+    /**
+     * Checks if the entity has a component.
+     *
+     * If the component is not present, it returns false.
+     */
+    pub fn has_component<T: Component>(&self) -> bool {
+        self.table.has::<T>(self.id)
+    }
+}
+
+/**
+ * A mutable view of an entity in a `World`.
+ *
+ * Adding, replacing and removing components through it runs
+ * their hooks and observers, see `observer::Lifecycle`.
+ */
+pub struct EntityMut<'w> {
+    id: EntityId,
+    world: &'w mut World,
+}
+
+impl<'w> EntityMut<'w> {
+    pub(crate) fn new(id: EntityId, world: &'w mut World) -> Self {
+        Self { id, world }
+    }
+
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+
+    fn table(&self) -> &Table {
+        self.world.table()
+    }
+
+    fn table_mut(&mut self) -> &mut Table {
+        self.world.table_mut()
+    }
 
     /**
      * Sets a component for the entity.
      *
-     * Drops the previous component if it exists.
+     * Drops the previous component if it exists. Only the first
+     * instance is replaced if the entity has several. If a hook or
+     * observer of the old component despawns the entity, the new one
+     * is dropped.
      */
-    pub fn set_component(&mut self, component: Option<Box<dyn Component>>, id: usize) {
-        self.components[id] = component;
+    pub fn set_component<T: Component>(&mut self, component: T) {
+        let id = self.id;
+        let component_id = get_component_id::<T>();
+        if self.has_component::<T>() {
+            self.world.trigger(Lifecycle::Replace, id, component_id);
+        }
+        // The replace hooks may have removed the component or
+        // despawned the entity.
+        if !self.world.contains(id) {
+            return;
+        }
+        let added = !self.has_component::<T>();
+        let _ = self.table_mut().insert(id, component);
+        if added {
+            self.world.trigger(Lifecycle::Add, id, component_id);
+        }
+        self.world.trigger(Lifecycle::Insert, id, component_id);
     }
 
     /**
      * Adds a component to the entity.
      *
-     * If the component is already present, it returns None.
+     * If the component is already present, another instance is added.
+     * Returns the index of the new instance, or None if a hook or
+     * observer has despawned the entity and the component was dropped.
      */
-    pub fn add_component(&mut self, component: Box<dyn Component>) -> Option<()> {
-        let id = component.get_type_id();
-
-        if self.components[id].is_none() {
-            self.components[id] = Some(component);
-            Some(())
-        } else {
-            None
+    pub fn add_component<T: Component>(&mut self, component: T) -> Option<usize> {
+        let id = self.id;
+        let index = self.table_mut().add(id, component).ok()?;
+        if index == 0 {
+            self.world
+                .trigger(Lifecycle::Add, id, get_component_id::<T>());
         }
+        self.world
+            .trigger(Lifecycle::Insert, id, get_component_id::<T>());
+        Some(index)
     }
 
     /**
@@ -165,32 +749,90 @@ impl Entity {
      * If the component is not present, it returns None.
      */
     pub fn get_component<T: Component>(&self) -> Option<&T> {
-        let id = get_component_id::<T>() as usize;
-        self.components[id]
-            .as_ref()
-            .and_then(|c| c.as_any().downcast_ref::<T>())
+        self.table().get::<T>(self.id)
     }
 
     /**
      * Gets a mutable component from the entity.
      *
+     * Writing through it marks the component as changed.
      * If the component is not present, it returns None.
      */
-    pub fn get_component_mut<T: Component>(&mut self) -> Option<&mut T> {
-        let id = get_component_id::<T>() as usize;
-        self.components[id]
-            .as_mut()
-            .and_then(|c| c.as_any_mut().downcast_mut::<T>())
+    pub fn get_component_mut<T: Component>(&mut self) -> Option<Mut<'_, T>> {
+        let id = self.id;
+        self.table_mut().get_mut::<T>(id)
+    }
+
+    /**
+     * Gets a component from the entity by its component ID.
+     *
+     * If the component is not present or is dynamic, it returns None.
+     */
+    pub fn get_by_id(&self, component: usize) -> Option<&dyn Any> {
+        let ptr = self.table().get_ptr(self.id, component)?;
+        Some(unsafe { &*(component_registration(component)?.cast)(ptr) })
+    }
+
+    /**
+     * Gets a mutable component from the entity by its component ID.
+     *
+     * The component is marked as changed whether or not it is written.
+     * If the component is not present or is dynamic, it returns None.
+     */
+    pub fn get_mut_by_id(&mut self, component: usize) -> Option<&mut dyn Any> {
+        let cast = component_registration(component)?.cast;
+        let id = self.id;
+        let ptr = self.table_mut().get_mut_ptr(id, component)?;
+        Some(unsafe { &mut *cast(ptr) })
+    }
+
+    /**
+     * Iterates over every instance of a component on the entity.
+     */
+    pub fn get_components<T: Component>(&self) -> impl Iterator<Item = &T> {
+        self.table().get_all::<T>(self.id)
+    }
+
+    /**
+     * Iterates mutably over every instance of a component on the entity.
+     *
+     * The component is marked as changed whether or not it is written.
+     */
+    pub fn get_components_mut<T: Component>(&mut self) -> impl Iterator<Item = &mut T> {
+        let id = self.id;
+        self.table_mut().get_all_mut::<T>(id)
+    }
+
+    /**
+     * Returns how many instances of a component the entity has.
+     */
+    pub fn component_count<T: Component>(&self) -> usize {
+        self.table().count::<T>(self.id)
     }
 
     /**
      * Removes a component from the entity.
      *
+     * Every instance is removed and the first one is returned.
      * If the component is not present, it returns None.
      */
-    pub fn remove_component<T: Component>(&mut self) -> Option<Box<dyn Component>> {
-        let id = get_component_id::<T>() as usize;
-        self.components[id].take()
+    pub fn remove_component<T: Component>(&mut self) -> Option<T> {
+        let id = self.id;
+        self.world.trigger_removal(id, get_component_id::<T>());
+        self.table_mut().remove::<T>(id)
+    }
+
+    /**
+     * Removes a single instance of a component from the entity.
+     *
+     * If there is no instance at `index`, it returns None.
+     */
+    pub fn remove_component_at<T: Component>(&mut self, index: usize) -> Option<T> {
+        let id = self.id;
+        if index == 0 && self.component_count::<T>() == 1 {
+            self.world.trigger_removal(id, get_component_id::<T>());
+        }
+        self.table_mut().remove_at::<T>(id, index)
     }
 
     /**
@@ -199,7 +841,6 @@ impl Entity {
      * If the component is not present, it returns false.
      */
     pub fn has_component<T: Component>(&self) -> bool {
-        let id = get_component_id::<T>() as usize;
-        self.components[id].is_some()
+        self.table().has::<T>(self.id)
     }
 }
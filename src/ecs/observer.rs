@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::ecs::change_detection::{SystemTicks, check_tick};
+use crate::ecs::system::{SystemAccess, SystemParam, SystemParamItem};
+use crate::ecs::world::World;
+use crate::ecs::{Component, EntityId, get_component_id};
+
+/**
+ * The points in a component's life that hooks and observers run at
+ *
+ * For a single insert or removal they run in declaration order.
+ * `Add` and `Insert` run after the component is stored,
+ * `Replace` and `Remove` run while the old value is still there.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Lifecycle {
+    /**
+     * The entity did not have the component before
+     */
+    Add,
+    /**
+     * The component was added or overwritten
+     */
+    Insert,
+    /**
+     * The component is about to be overwritten or removed
+     */
+    Replace,
+    /**
+     * The last instance of the component is about to be removed,
+     * including when the entity is despawned
+     */
+    Remove,
+}
+
+/**
+ * This is a trait for the lifecycle events observers can watch
+ *
+ * It is implemented for `OnAdd`, `OnInsert`, `OnReplace` and `OnRemove`.
+ */
+pub trait LifecycleEvent: Send + Sync + 'static {
+    const LIFECYCLE: Lifecycle;
+}
+
+/**
+ * Triggers an observer when a component is added, see `Lifecycle::Add`
+ */
+pub struct OnAdd;
+
+/**
+ * Triggers an observer when a component is inserted, see `Lifecycle::Insert`
+ */
+pub struct OnInsert;
+
+/**
+ * Triggers an observer before a component is replaced, see `Lifecycle::Replace`
+ */
+pub struct OnReplace;
+
+/**
+ * Triggers an observer before a component is removed, see `Lifecycle::Remove`
+ */
+pub struct OnRemove;
+
+impl LifecycleEvent for OnAdd {
+    const LIFECYCLE: Lifecycle = Lifecycle::Add;
+}
+
+impl LifecycleEvent for OnInsert {
+    const LIFECYCLE: Lifecycle = Lifecycle::Insert;
+}
+
+impl LifecycleEvent for OnReplace {
+    const LIFECYCLE: Lifecycle = Lifecycle::Replace;
+}
+
+impl LifecycleEvent for OnRemove {
+    const LIFECYCLE: Lifecycle = Lifecycle::Remove;
+}
+
+/**
+ * The first parameter of an observer, telling it which entity
+ * the event `E` happened to for its component `T`
+ */
+pub struct Trigger<E, T> {
+    entity: EntityId,
+    _marker: PhantomData<fn() -> (E, T)>,
+}
+
+impl<E, T> Trigger<E, T> {
+    pub fn entity(&self) -> EntityId {
+        self.entity
+    }
+}
+
+impl<E, T> Clone for Trigger<E, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E, T> Copy for Trigger<E, T> {}
+
+/**
+ * This is a trait for functions that can observe lifecycle events
+ *
+ * It is implemented for every function taking a `Trigger` followed
+ * by `SystemParam`s, like
+ * `fn create_body(trigger: Trigger<OnAdd, Collider>, mut commands: Commands)`.
+ */
+pub trait ObserverFunction<Marker>: Send + Sync + 'static {
+    type Event: LifecycleEvent;
+    type Component: Component;
+    type Param: SystemParam;
+
+    fn run(
+        &mut self,
+        trigger: Trigger<Self::Event, Self::Component>,
+        param: SystemParamItem<Self::Param>,
+    );
+}
+
+/**
+ * An observer with its types erased, so observers of
+ * different functions can be stored together
+ *
+ * Observers are shared so one can run again from the commands of
+ * its own run.
+ */
+trait ObserverSystem: Send + Sync {
+    fn run(&self, world: &mut World, entity: EntityId);
+    fn check_change_tick(&self, change_tick: u32);
+}
+
+type ObserverState<Marker, F> = <<F as ObserverFunction<Marker>>::Param as SystemParam>::State;
+
+/**
+ * An observer built from a function, see `World::observe`
+ *
+ * Like systems, it keeps its parameters' state between runs.
+ * Its commands are applied right after it runs, and an event they
+ * cause that runs it again runs it with a fresh state.
+ */
+struct FunctionObserver<Marker, F: ObserverFunction<Marker>> {
+    func: Mutex<F>,
+    state: Mutex<Option<ObserverState<Marker, F>>>,
+    last_run: AtomicU32,
+    _marker: PhantomData<fn() -> Marker>,
+}
+
+impl<Marker: 'static, F: ObserverFunction<Marker>> ObserverSystem for FunctionObserver<Marker, F> {
+    fn run(&self, world: &mut World, entity: EntityId) {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_else(|| F::Param::init_state(world, &mut SystemAccess::default()));
+        let ticks = SystemTicks {
+            last_run: self.last_run.load(Ordering::Relaxed),
+            this_run: world.increment_change_tick(),
+        };
+        let trigger = Trigger {
+            entity,
+            _marker: PhantomData,
+        };
+
+        let ptr: *mut World = world;
+        let param = unsafe { F::Param::get_param(&mut state, ptr, ticks) };
+        self.func.lock().unwrap().run(trigger, param);
+        self.last_run.store(ticks.this_run, Ordering::Relaxed);
+        F::Param::apply(&mut state, world);
+        self.state.lock().unwrap().get_or_insert(state);
+    }
+
+    fn check_change_tick(&self, change_tick: u32) {
+        let mut last_run = self.last_run.load(Ordering::Relaxed);
+        check_tick(&mut last_run, change_tick);
+        self.last_run.store(last_run, Ordering::Relaxed);
+    }
+}
+
+/**
+ * Every observer of a world, by the event and component they watch
+ */
+#[derive(Default)]
+pub(crate) struct Observers {
+    observers: HashMap<(Lifecycle, usize), Vec<Arc<dyn ObserverSystem>>>,
+    /**
+     * The components whose removal events are running, by entity
+     */
+    removing: Vec<(EntityId, usize)>,
+}
+
+impl Observers {
+    /**
+     * Clamps the last run tick of every observer, see `World::check_change_ticks`.
+     */
+    pub(crate) fn check_change_ticks(&self, change_tick: u32) {
+        for observer in self.observers.values().flatten() {
+            observer.check_change_tick(change_tick);
+        }
+    }
+
+    pub(crate) fn add<Marker: 'static, F: ObserverFunction<Marker>>(&mut self, func: F) {
+        let key = (
+            <F::Event as LifecycleEvent>::LIFECYCLE,
+            get_component_id::<F::Component>(),
+        );
+        self.observers
+            .entry(key)
+            .or_default()
+            .push(Arc::new(FunctionObserver {
+                func: Mutex::new(func),
+                state: Mutex::new(None),
+                last_run: AtomicU32::new(0),
+                _marker: PhantomData,
+            }));
+    }
+}
+
+impl World {
+    /**
+     * Adds an observer run every time its `Trigger`'s event
+     * happens to its component on any entity.
+     *
+     * Observers run after the component's hooks, in the order they
+     * were added. They can spawn, despawn and change components
+     * through `Commands`, which are applied right after each run.
+     */
+    pub fn observe<Marker: 'static, F: ObserverFunction<Marker>>(&mut self, observer: F) {
+        self.observers_mut().add(observer);
+    }
+
+    /**
+     * Runs the replace and remove events of a component
+     * that is about to be removed.
+     *
+     * They do not run again if their hooks or observers remove the
+     * same component or despawn the entity.
+     */
+    pub(crate) fn trigger_removal(&mut self, entity: EntityId, component: usize) {
+        let key = (entity, component);
+        if self.observers_mut().removing.contains(&key) {
+            return;
+        }
+        self.observers_mut().removing.push(key);
+        self.trigger(Lifecycle::Replace, entity, component);
+        self.trigger(Lifecycle::Remove, entity, component);
+        self.observers_mut().removing.retain(|&k| k != key);
+    }
+
+    /**
+     * Runs the hook and observers of a lifecycle event.
+     *
+     * Nothing runs if the entity does not have the component,
+     * e.g. because an earlier hook removed it or despawned the entity.
+     */
+    pub(crate) fn trigger(&mut self, event: Lifecycle, entity: EntityId, component: usize) {
+        if !self.table().has_by_id(entity, component) {
+            return;
+        }
+        let hook = crate::ecs::component_registration(component).and_then(|r| r.hook(event));
+        if let Some(hook) = hook {
+            hook(self, entity);
+        }
+
+        // The observers stay registered while they run, so events they
+        // cause run them too. Ones added meanwhile wait for the next event.
+        let Some(observers) = self
+            .observers_mut()
+            .observers
+            .get(&(event, component))
+            .cloned()
+        else {
+            return;
+        };
+        for observer in observers {
+            if !self.table().has_by_id(entity, component) {
+                break;
+            }
+            observer.run(self, entity);
+        }
+    }
+}
+
+macro_rules! impl_observer_function {
+    ($($param:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<Func, E: LifecycleEvent, T: Component, $($param: SystemParam),*>
+            ObserverFunction<fn(Trigger<E, T>, $($param,)*)> for Func
+        where
+            Func: Send + Sync + 'static,
+            for<'a> &'a mut Func: FnMut(Trigger<E, T>, $($param),*)
+                + FnMut(Trigger<E, T>, $(SystemParamItem<$param>),*),
+        {
+            type Event = E;
+            type Component = T;
+            type Param = ($($param,)*);
+
+            fn run(&mut self, trigger: Trigger<E, T>, param: SystemParamItem<($($param,)*)>) {
+                #[allow(clippy::too_many_arguments)]
+                fn call_inner<E, T, $($param),*>(
+                    mut f: impl FnMut(Trigger<E, T>, $($param),*),
+                    trigger: Trigger<E, T>,
+                    $($param: $param),*
+                ) {
+                    f(trigger, $($param),*)
+                }
+                let ($($param,)*) = param;
+                call_inner(self, trigger, $($param),*)
+            }
+        }
+    };
+}
+
+impl_observer_function!();
+impl_observer_function!(P0);
+impl_observer_function!(P0, P1);
+impl_observer_function!(P0, P1, P2);
+impl_observer_function!(P0, P1, P2, P3);
+impl_observer_function!(P0, P1, P2, P3, P4);
+impl_observer_function!(P0, P1, P2, P3, P4, P5);
+impl_observer_function!(P0, P1, P2, P3, P4, P5, P6);
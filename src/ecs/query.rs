@@ -0,0 +1,1196 @@
+use std::marker::PhantomData;
+
+use super::change_detection::{ComponentTicks, Mut, SystemTicks};
+use super::mappings::{
+    Query,
+    table::{Archetype, Column, Table},
+};
+use super::{Component, EntityId, StorageType, component_storage, get_component_id};
+
+/**
+ * The set of components something reads and writes
+ *
+ * Used to reject queries that would alias a component mutably
+ * and to decide which systems can run side by side.
+ */
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct Access {
+    reads: Vec<usize>,
+    writes: Vec<usize>,
+}
+
+impl Access {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Records a shared access to a component.
+     *
+     * Panics if the component is already accessed mutably.
+     */
+    pub fn add_read(&mut self, component: usize) {
+        assert!(
+            !self.writes.contains(&component),
+            "Component {component} is accessed both mutably and immutably"
+        );
+        if !self.reads.contains(&component) {
+            self.reads.push(component);
+        }
+    }
+
+    /**
+     * Records an exclusive access to a component.
+     *
+     * Panics if the component is already accessed in any way.
+     */
+    pub fn add_write(&mut self, component: usize) {
+        assert!(
+            !self.reads.contains(&component) && !self.writes.contains(&component),
+            "Component {component} is accessed mutably more than once"
+        );
+        self.writes.push(component);
+    }
+
+    pub fn reads(&self) -> &[usize] {
+        &self.reads
+    }
+
+    pub fn writes(&self) -> &[usize] {
+        &self.writes
+    }
+
+    /**
+     * Checks if both accesses can happen at the same time.
+     */
+    pub fn is_compatible(&self, other: &Access) -> bool {
+        !self
+            .writes
+            .iter()
+            .any(|c| other.reads.contains(c) || other.writes.contains(c))
+            && !other.writes.iter().any(|c| self.reads.contains(c))
+    }
+
+    /**
+     * Adds the reads and writes of another access without checking
+     * for conflicts.
+     *
+     * Used to sum up the parameters of a system, which may touch the
+     * same component as long as they never see the same entity.
+     */
+    pub fn extend(&mut self, other: &Access) {
+        for &c in &other.writes {
+            self.reads.retain(|&r| r != c);
+            if !self.writes.contains(&c) {
+                self.writes.push(c);
+            }
+        }
+        for &c in &other.reads {
+            if !self.reads.contains(&c) && !self.writes.contains(&c) {
+                self.reads.push(c);
+            }
+        }
+    }
+}
+
+/**
+ * The access of a query, with the components its entities
+ * must and must not have
+ *
+ * Queries whose filters rule each other out, like `With<A>` and
+ * `Without<A>`, never see the same entity, so their accesses
+ * never conflict.
+ */
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct FilteredAccess {
+    access: Access,
+    with: Vec<usize>,
+    without: Vec<usize>,
+}
+
+impl FilteredAccess {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn access(&self) -> &Access {
+        &self.access
+    }
+
+    pub fn access_mut(&mut self) -> &mut Access {
+        &mut self.access
+    }
+
+    /**
+     * Records that every matched entity has a component.
+     */
+    pub fn add_with(&mut self, component: usize) {
+        if !self.with.contains(&component) {
+            self.with.push(component);
+        }
+    }
+
+    /**
+     * Records that no matched entity has a component.
+     */
+    pub fn add_without(&mut self, component: usize) {
+        if !self.without.contains(&component) {
+            self.without.push(component);
+        }
+    }
+
+    /**
+     * Checks if no entity can be matched by both.
+     */
+    pub fn is_disjoint(&self, other: &FilteredAccess) -> bool {
+        self.with.iter().any(|c| other.without.contains(c))
+            || other.with.iter().any(|c| self.without.contains(c))
+    }
+
+    /**
+     * Checks if both queries can hand out items at the same time.
+     */
+    pub fn is_compatible(&self, other: &FilteredAccess) -> bool {
+        self.access.is_compatible(&other.access) || self.is_disjoint(other)
+    }
+}
+
+/**
+ * This is a trait for the items a query yields for each entity
+ *
+ * It is implemented for `&T`, `&mut T`, `Many<&T>`, `Many<&mut T>`,
+ * `Option<Q>`, `EntityId` and tuples of those.
+ *
+ * # Safety
+ *
+ * `update_access` must record every component that `fetch` reads
+ * or writes, with the matching kind of access. `update_filter` may
+ * only record components that every fetched entity has.
+ */
+pub unsafe trait QueryData {
+    type Item<'w>;
+    type Fetch<'w>;
+    type State: Copy + Send + Sync + 'static;
+
+    fn init_state() -> Self::State;
+    fn update_access(state: Self::State, access: &mut Access);
+
+    /**
+     * Records the components every fetched entity has.
+     */
+    fn update_filter(_state: Self::State, _filter: &mut FilteredAccess) {}
+    fn matches(state: Self::State, archetype: &Archetype) -> bool;
+
+    /**
+     * Prepares to fetch items from an archetype that `matches`.
+     *
+     * # Safety
+     *
+     * The archetype must match and the caller must hold the access
+     * described by `update_access` for `'w`.
+     */
+    unsafe fn fetch_archetype<'w>(
+        state: Self::State,
+        table: &'w Table,
+        archetype: &'w Archetype,
+        ticks: SystemTicks,
+    ) -> Self::Fetch<'w>;
+
+    /**
+     * Checks if the entity in a row has the data.
+     *
+     * Sparse components are not part of the archetype, so
+     * matching archetypes can still have rows without them.
+     *
+     * # Safety
+     *
+     * `row` must be in bounds of the archetype.
+     */
+    unsafe fn contains(_fetch: &Self::Fetch<'_>, _entity: EntityId, _row: usize) -> bool {
+        true
+    }
+
+    /**
+     * Fetches the item for a row.
+     *
+     * # Safety
+     *
+     * `row` must be in bounds of the archetype, `contains` must
+     * hold for it and each row may only be fetched once while
+     * its items are alive.
+     */
+    unsafe fn fetch<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        entity: EntityId,
+        row: usize,
+    ) -> Self::Item<'w>;
+}
+
+/**
+ * Marks query data that never hands out mutable access
+ *
+ * # Safety
+ *
+ * `update_access` must only ever record reads.
+ */
+pub unsafe trait ReadOnlyQueryData: QueryData {}
+
+/**
+ * This is a trait for conditions on a query
+ *
+ * Filters decide which entities are visited without fetching anything.
+ * `matches` rules out whole archetypes, `filter` single rows.
+ *
+ * # Safety
+ *
+ * `update_filter` may only record components that `filter` checks
+ * for, since queries it rules apart may alias each other.
+ */
+pub unsafe trait QueryFilter {
+    type State: Copy + Send + Sync + 'static;
+    type Fetch<'w>;
+
+    fn init_state() -> Self::State;
+
+    /**
+     * Records the components the filter reads.
+     */
+    fn update_access(_state: Self::State, _access: &mut Access) {}
+
+    /**
+     * Records the components passing entities must or must not have.
+     */
+    fn update_filter(_state: Self::State, _filter: &mut FilteredAccess) {}
+    fn matches(state: Self::State, archetype: &Archetype) -> bool;
+
+    /**
+     * Prepares to filter the rows of an archetype that `matches`.
+     *
+     * # Safety
+     *
+     * Same as `QueryData::fetch_archetype`.
+     */
+    unsafe fn fetch_archetype<'w>(
+        state: Self::State,
+        table: &'w Table,
+        archetype: &'w Archetype,
+        ticks: SystemTicks,
+    ) -> Self::Fetch<'w>;
+
+    /**
+     * Checks if a row passes the filter.
+     *
+     * # Safety
+     *
+     * `row` must be in bounds of the archetype.
+     */
+    unsafe fn filter(fetch: &mut Self::Fetch<'_>, entity: EntityId, row: usize) -> bool;
+}
+
+unsafe impl QueryData for EntityId {
+    type Item<'w> = EntityId;
+    type Fetch<'w> = ();
+    type State = ();
+
+    fn init_state() {}
+    fn update_access(_: (), _: &mut Access) {}
+    fn matches(_: (), _: &Archetype) -> bool {
+        true
+    }
+
+    unsafe fn fetch_archetype<'w>(
+        _: (),
+        _: &'w Table,
+        _: &'w Archetype,
+        _: SystemTicks,
+    ) -> Self::Fetch<'w> {
+    }
+
+    unsafe fn fetch<'w>(_: &mut (), entity: EntityId, _: usize) -> Self::Item<'w> {
+        entity
+    }
+}
+
+unsafe impl ReadOnlyQueryData for EntityId {}
+
+/**
+ * The state of a query over one component type
+ *
+ * Sparse components can be on entities of any archetype,
+ * so every archetype matches them and rows are checked one by one.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComponentState {
+    id: usize,
+    sparse: bool,
+}
+
+impl ComponentState {
+    pub fn of<T: Component>() -> Self {
+        let id = get_component_id::<T>();
+        Self {
+            id,
+            sparse: component_storage(id) == StorageType::Sparse,
+        }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn matches(&self, archetype: &Archetype) -> bool {
+        self.sparse || archetype.has_component(self.id)
+    }
+}
+
+/**
+ * Gets pointers to a component and its ticks from a fetched column.
+ */
+unsafe fn column_get(
+    column: &Option<Column<'_>>,
+    entity: EntityId,
+    row: usize,
+) -> Option<(*mut u8, *mut ComponentTicks)> {
+    column.as_ref().and_then(|c| unsafe { c.get(entity, row) })
+}
+
+unsafe impl<T: Component> QueryData for &T {
+    type Item<'w> = &'w T;
+    type Fetch<'w> = Option<Column<'w>>;
+    type State = ComponentState;
+
+    fn init_state() -> ComponentState {
+        ComponentState::of::<T>()
+    }
+
+    fn update_access(state: ComponentState, access: &mut Access) {
+        access.add_read(state.id);
+    }
+
+    fn update_filter(state: ComponentState, filter: &mut FilteredAccess) {
+        filter.add_with(state.id);
+    }
+
+    fn matches(state: ComponentState, archetype: &Archetype) -> bool {
+        state.matches(archetype)
+    }
+
+    unsafe fn fetch_archetype<'w>(
+        state: ComponentState,
+        table: &'w Table,
+        archetype: &'w Archetype,
+        _: SystemTicks,
+    ) -> Self::Fetch<'w> {
+        table.column(archetype, state.id)
+    }
+
+    unsafe fn contains(fetch: &Self::Fetch<'_>, entity: EntityId, row: usize) -> bool {
+        unsafe { column_get(fetch, entity, row).is_some() }
+    }
+
+    unsafe fn fetch<'w>(fetch: &mut Self::Fetch<'w>, entity: EntityId, row: usize) -> &'w T {
+        unsafe {
+            let (ptr, _) = column_get(fetch, entity, row).unwrap();
+            &*(ptr as *const T)
+        }
+    }
+}
+
+unsafe impl<T: Component> ReadOnlyQueryData for &T {}
+
+unsafe impl<T: Component> QueryData for &mut T {
+    type Item<'w> = Mut<'w, T>;
+    type Fetch<'w> = (Option<Column<'w>>, SystemTicks);
+    type State = ComponentState;
+
+    fn init_state() -> ComponentState {
+        ComponentState::of::<T>()
+    }
+
+    fn update_access(state: ComponentState, access: &mut Access) {
+        access.add_write(state.id);
+    }
+
+    fn update_filter(state: ComponentState, filter: &mut FilteredAccess) {
+        filter.add_with(state.id);
+    }
+
+    fn matches(state: ComponentState, archetype: &Archetype) -> bool {
+        state.matches(archetype)
+    }
+
+    unsafe fn fetch_archetype<'w>(
+        state: ComponentState,
+        table: &'w Table,
+        archetype: &'w Archetype,
+        ticks: SystemTicks,
+    ) -> Self::Fetch<'w> {
+        (table.column(archetype, state.id), ticks)
+    }
+
+    unsafe fn contains(fetch: &Self::Fetch<'_>, entity: EntityId, row: usize) -> bool {
+        unsafe { column_get(&fetch.0, entity, row).is_some() }
+    }
+
+    unsafe fn fetch<'w>(fetch: &mut Self::Fetch<'w>, entity: EntityId, row: usize) -> Mut<'w, T> {
+        unsafe {
+            let (ptr, component_ticks) = column_get(&fetch.0, entity, row).unwrap();
+            Mut::new(&mut *(ptr as *mut T), &mut *component_ticks, fetch.1)
+        }
+    }
+}
+
+unsafe impl<Q: QueryData> QueryData for Option<Q> {
+    type Item<'w> = Option<Q::Item<'w>>;
+    type Fetch<'w> = Option<Q::Fetch<'w>>;
+    type State = Q::State;
+
+    fn init_state() -> Q::State {
+        Q::init_state()
+    }
+
+    fn update_access(state: Q::State, access: &mut Access) {
+        Q::update_access(state, access);
+    }
+
+    fn matches(_: Q::State, _: &Archetype) -> bool {
+        true
+    }
+
+    unsafe fn fetch_archetype<'w>(
+        state: Q::State,
+        table: &'w Table,
+        archetype: &'w Archetype,
+        ticks: SystemTicks,
+    ) -> Self::Fetch<'w> {
+        Q::matches(state, archetype)
+            .then(|| unsafe { Q::fetch_archetype(state, table, archetype, ticks) })
+    }
+
+    unsafe fn fetch<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        entity: EntityId,
+        row: usize,
+    ) -> Self::Item<'w> {
+        match fetch {
+            Some(f) if unsafe { Q::contains(f, entity, row) } => {
+                Some(unsafe { Q::fetch(f, entity, row) })
+            }
+            _ => None,
+        }
+    }
+}
+
+unsafe impl<Q: ReadOnlyQueryData> ReadOnlyQueryData for Option<Q> {}
+
+/**
+ * Fetches every instance of a component on each entity
+ *
+ * `Many<&T>` yields `Instances` and `Many<&mut T>` yields
+ * `InstancesMut`. Entities without a `T` are not matched.
+ */
+pub struct Many<Q>(PhantomData<Q>);
+
+/**
+ * Every instance of a component on one entity
+ */
+pub struct Instances<'w, T> {
+    first: &'w T,
+    rest: &'w [T],
+}
+
+impl<'w, T> Instances<'w, T> {
+    pub fn first(&self) -> &'w T {
+        self.first
+    }
+
+    pub fn len(&self) -> usize {
+        1 + self.rest.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'w T> + use<'w, T> {
+        std::iter::once(self.first).chain(self.rest)
+    }
+}
+
+impl<'w, T> IntoIterator for Instances<'w, T> {
+    type Item = &'w T;
+    type IntoIter = std::iter::Chain<std::iter::Once<&'w T>, std::slice::Iter<'w, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        std::iter::once(self.first).chain(self.rest)
+    }
+}
+
+/**
+ * Every instance of a component on one entity, mutably
+ *
+ * Mutable access to any instance marks the component as changed.
+ */
+pub struct InstancesMut<'w, T> {
+    first: &'w mut T,
+    rest: &'w mut [T],
+    ticks: &'w mut ComponentTicks,
+    this_run: u32,
+}
+
+impl<T> InstancesMut<'_, T> {
+    pub fn first(&mut self) -> &mut T {
+        self.ticks.set_changed(self.this_run);
+        self.first
+    }
+
+    pub fn len(&self) -> usize {
+        1 + self.rest.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        std::iter::once(&*self.first).chain(self.rest.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.ticks.set_changed(self.this_run);
+        std::iter::once(&mut *self.first).chain(self.rest.iter_mut())
+    }
+}
+
+impl<'w, T> IntoIterator for InstancesMut<'w, T> {
+    type Item = &'w mut T;
+    type IntoIter = std::iter::Chain<std::iter::Once<&'w mut T>, std::slice::IterMut<'w, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ticks.set_changed(self.this_run);
+        std::iter::once(self.first).chain(self.rest)
+    }
+}
+
+unsafe impl<T: Component> QueryData for Many<&T> {
+    type Item<'w> = Instances<'w, T>;
+    type Fetch<'w> = (&'w Table, Option<Column<'w>>, usize);
+    type State = ComponentState;
+
+    fn init_state() -> ComponentState {
+        ComponentState::of::<T>()
+    }
+
+    fn update_access(state: ComponentState, access: &mut Access) {
+        access.add_read(state.id);
+    }
+
+    fn update_filter(state: ComponentState, filter: &mut FilteredAccess) {
+        filter.add_with(state.id);
+    }
+
+    fn matches(state: ComponentState, archetype: &Archetype) -> bool {
+        state.matches(archetype)
+    }
+
+    unsafe fn fetch_archetype<'w>(
+        state: ComponentState,
+        table: &'w Table,
+        archetype: &'w Archetype,
+        _: SystemTicks,
+    ) -> Self::Fetch<'w> {
+        (table, table.column(archetype, state.id), state.id)
+    }
+
+    unsafe fn contains(fetch: &Self::Fetch<'_>, entity: EntityId, row: usize) -> bool {
+        unsafe { column_get(&fetch.1, entity, row).is_some() }
+    }
+
+    unsafe fn fetch<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        entity: EntityId,
+        row: usize,
+    ) -> Self::Item<'w> {
+        let (table, column, component) = *fetch;
+        let (ptr, len) = table.extras_ptr(entity, component);
+        unsafe {
+            let (first, _) = column_get(&column, entity, row).unwrap();
+            Instances {
+                first: &*(first as *const T),
+                rest: slice_or_empty(ptr as *const T, len),
+            }
+        }
+    }
+}
+
+unsafe impl<T: Component> ReadOnlyQueryData for Many<&T> {}
+
+unsafe impl<T: Component> QueryData for Many<&mut T> {
+    type Item<'w> = InstancesMut<'w, T>;
+    type Fetch<'w> = (&'w Table, Option<Column<'w>>, usize, SystemTicks);
+    type State = ComponentState;
+
+    fn init_state() -> ComponentState {
+        ComponentState::of::<T>()
+    }
+
+    fn update_access(state: ComponentState, access: &mut Access) {
+        access.add_write(state.id);
+    }
+
+    fn update_filter(state: ComponentState, filter: &mut FilteredAccess) {
+        filter.add_with(state.id);
+    }
+
+    fn matches(state: ComponentState, archetype: &Archetype) -> bool {
+        state.matches(archetype)
+    }
+
+    unsafe fn fetch_archetype<'w>(
+        state: ComponentState,
+        table: &'w Table,
+        archetype: &'w Archetype,
+        ticks: SystemTicks,
+    ) -> Self::Fetch<'w> {
+        (table, table.column(archetype, state.id), state.id, ticks)
+    }
+
+    unsafe fn contains(fetch: &Self::Fetch<'_>, entity: EntityId, row: usize) -> bool {
+        unsafe { column_get(&fetch.1, entity, row).is_some() }
+    }
+
+    unsafe fn fetch<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        entity: EntityId,
+        row: usize,
+    ) -> Self::Item<'w> {
+        let (table, column, component, ticks) = *fetch;
+        let (ptr, len) = table.extras_ptr(entity, component);
+        unsafe {
+            let (first, component_ticks) = column_get(&column, entity, row).unwrap();
+            InstancesMut {
+                first: &mut *(first as *mut T),
+                ticks: &mut *component_ticks,
+                this_run: ticks.this_run,
+                rest: if len == 0 {
+                    &mut []
+                } else {
+                    std::slice::from_raw_parts_mut(ptr as *mut T, len)
+                },
+            }
+        }
+    }
+}
+
+unsafe fn slice_or_empty<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+    if len == 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(ptr, len) }
+    }
+}
+
+/**
+ * Only matches entities that have a `T`, without fetching it
+ */
+pub struct With<T>(PhantomData<T>);
+
+unsafe impl<T: Component> QueryFilter for With<T> {
+    type State = ComponentState;
+    type Fetch<'w> = Option<Column<'w>>;
+
+    fn init_state() -> ComponentState {
+        ComponentState::of::<T>()
+    }
+
+    fn update_filter(state: ComponentState, filter: &mut FilteredAccess) {
+        filter.add_with(state.id);
+    }
+
+    fn matches(state: ComponentState, archetype: &Archetype) -> bool {
+        state.matches(archetype)
+    }
+
+    unsafe fn fetch_archetype<'w>(
+        state: ComponentState,
+        table: &'w Table,
+        archetype: &'w Archetype,
+        _: SystemTicks,
+    ) -> Self::Fetch<'w> {
+        table.column(archetype, state.id)
+    }
+
+    unsafe fn filter(fetch: &mut Self::Fetch<'_>, entity: EntityId, row: usize) -> bool {
+        unsafe { column_get(fetch, entity, row).is_some() }
+    }
+}
+
+/**
+ * Only matches entities that do not have a `T`
+ */
+pub struct Without<T>(PhantomData<T>);
+
+unsafe impl<T: Component> QueryFilter for Without<T> {
+    type State = ComponentState;
+    type Fetch<'w> = Option<Column<'w>>;
+
+    fn init_state() -> ComponentState {
+        ComponentState::of::<T>()
+    }
+
+    fn update_filter(state: ComponentState, filter: &mut FilteredAccess) {
+        filter.add_without(state.id);
+    }
+
+    fn matches(state: ComponentState, archetype: &Archetype) -> bool {
+        state.sparse || !archetype.has_component(state.id)
+    }
+
+    unsafe fn fetch_archetype<'w>(
+        state: ComponentState,
+        table: &'w Table,
+        archetype: &'w Archetype,
+        _: SystemTicks,
+    ) -> Self::Fetch<'w> {
+        table.column(archetype, state.id)
+    }
+
+    unsafe fn filter(fetch: &mut Self::Fetch<'_>, entity: EntityId, row: usize) -> bool {
+        unsafe { column_get(fetch, entity, row).is_none() }
+    }
+}
+
+/**
+ * Only matches entities whose `T` was added since the system last ran
+ *
+ * Outside of systems every component counts as added.
+ */
+pub struct Added<T>(PhantomData<T>);
+
+unsafe impl<T: Component> QueryFilter for Added<T> {
+    type State = ComponentState;
+    type Fetch<'w> = (Option<Column<'w>>, SystemTicks);
+
+    fn init_state() -> ComponentState {
+        ComponentState::of::<T>()
+    }
+
+    fn update_access(state: ComponentState, access: &mut Access) {
+        access.add_read(state.id);
+    }
+
+    fn update_filter(state: ComponentState, filter: &mut FilteredAccess) {
+        filter.add_with(state.id);
+    }
+
+    fn matches(state: ComponentState, archetype: &Archetype) -> bool {
+        state.matches(archetype)
+    }
+
+    unsafe fn fetch_archetype<'w>(
+        state: ComponentState,
+        table: &'w Table,
+        archetype: &'w Archetype,
+        ticks: SystemTicks,
+    ) -> Self::Fetch<'w> {
+        (table.column(archetype, state.id), ticks)
+    }
+
+    unsafe fn filter(fetch: &mut Self::Fetch<'_>, entity: EntityId, row: usize) -> bool {
+        let (column, ticks) = fetch;
+        unsafe {
+            column_get(column, entity, row)
+                .is_some_and(|(_, t)| (*t).is_added(ticks.last_run, ticks.this_run))
+        }
+    }
+}
+
+/**
+ * Only matches entities whose `T` was added or written to
+ * since the system last ran
+ *
+ * Outside of systems every component counts as changed.
+ */
+pub struct Changed<T>(PhantomData<T>);
+
+unsafe impl<T: Component> QueryFilter for Changed<T> {
+    type State = ComponentState;
+    type Fetch<'w> = (Option<Column<'w>>, SystemTicks);
+
+    fn init_state() -> ComponentState {
+        ComponentState::of::<T>()
+    }
+
+    fn update_access(state: ComponentState, access: &mut Access) {
+        access.add_read(state.id);
+    }
+
+    fn update_filter(state: ComponentState, filter: &mut FilteredAccess) {
+        filter.add_with(state.id);
+    }
+
+    fn matches(state: ComponentState, archetype: &Archetype) -> bool {
+        state.matches(archetype)
+    }
+
+    unsafe fn fetch_archetype<'w>(
+        state: ComponentState,
+        table: &'w Table,
+        archetype: &'w Archetype,
+        ticks: SystemTicks,
+    ) -> Self::Fetch<'w> {
+        (table.column(archetype, state.id), ticks)
+    }
+
+    unsafe fn filter(fetch: &mut Self::Fetch<'_>, entity: EntityId, row: usize) -> bool {
+        let (column, ticks) = fetch;
+        unsafe {
+            column_get(column, entity, row)
+                .is_some_and(|(_, t)| (*t).is_changed(ticks.last_run, ticks.this_run))
+        }
+    }
+}
+
+macro_rules! impl_query_tuple {
+    ($(($name:ident, $state:ident, $fetch:ident)),*) => {
+        #[allow(non_snake_case, clippy::unused_unit)]
+        unsafe impl<$($name: QueryData),*> QueryData for ($($name,)*) {
+            type Item<'w> = ($($name::Item<'w>,)*);
+            type Fetch<'w> = ($($name::Fetch<'w>,)*);
+            type State = ($($name::State,)*);
+
+            fn init_state() -> Self::State {
+                ($($name::init_state(),)*)
+            }
+
+            fn update_access(state: Self::State, _access: &mut Access) {
+                let ($($state,)*) = state;
+                $($name::update_access($state, _access);)*
+            }
+
+            fn update_filter(state: Self::State, _filter: &mut FilteredAccess) {
+                let ($($state,)*) = state;
+                $($name::update_filter($state, _filter);)*
+            }
+
+            fn matches(state: Self::State, _archetype: &Archetype) -> bool {
+                let ($($state,)*) = state;
+                true $(&& $name::matches($state, _archetype))*
+            }
+
+            unsafe fn fetch_archetype<'w>(state: Self::State, _table: &'w Table, _archetype: &'w Archetype, _ticks: SystemTicks) -> Self::Fetch<'w> {
+                let ($($state,)*) = state;
+                ($(unsafe { $name::fetch_archetype($state, _table, _archetype, _ticks) },)*)
+            }
+
+            unsafe fn contains(fetch: &Self::Fetch<'_>, _entity: EntityId, _row: usize) -> bool {
+                let ($($fetch,)*) = fetch;
+                true $(&& unsafe { $name::contains($fetch, _entity, _row) })*
+            }
+
+            unsafe fn fetch<'w>(fetch: &mut Self::Fetch<'w>, _entity: EntityId, _row: usize) -> Self::Item<'w> {
+                let ($($fetch,)*) = fetch;
+                ($(unsafe { $name::fetch($fetch, _entity, _row) },)*)
+            }
+        }
+
+        unsafe impl<$($name: ReadOnlyQueryData),*> ReadOnlyQueryData for ($($name,)*) {}
+
+        #[allow(non_snake_case, clippy::unused_unit)]
+        unsafe impl<$($name: QueryFilter),*> QueryFilter for ($($name,)*) {
+            type State = ($($name::State,)*);
+            type Fetch<'w> = ($($name::Fetch<'w>,)*);
+
+            fn init_state() -> Self::State {
+                ($($name::init_state(),)*)
+            }
+
+            fn update_access(state: Self::State, _access: &mut Access) {
+                let ($($state,)*) = state;
+                $($name::update_access($state, _access);)*
+            }
+
+            fn update_filter(state: Self::State, _filter: &mut FilteredAccess) {
+                let ($($state,)*) = state;
+                $($name::update_filter($state, _filter);)*
+            }
+
+            fn matches(state: Self::State, _archetype: &Archetype) -> bool {
+                let ($($state,)*) = state;
+                true $(&& $name::matches($state, _archetype))*
+            }
+
+            unsafe fn fetch_archetype<'w>(state: Self::State, _table: &'w Table, _archetype: &'w Archetype, _ticks: SystemTicks) -> Self::Fetch<'w> {
+                let ($($state,)*) = state;
+                ($(unsafe { $name::fetch_archetype($state, _table, _archetype, _ticks) },)*)
+            }
+
+            unsafe fn filter(fetch: &mut Self::Fetch<'_>, _entity: EntityId, _row: usize) -> bool {
+                let ($($fetch,)*) = fetch;
+                true $(&& unsafe { $name::filter($fetch, _entity, _row) })*
+            }
+        }
+    };
+}
+
+impl_query_tuple!();
+impl_query_tuple!((A, a, fa));
+impl_query_tuple!((A, a, fa), (B, b, fb));
+impl_query_tuple!((A, a, fa), (B, b, fb), (C, c, fc));
+impl_query_tuple!((A, a, fa), (B, b, fb), (C, c, fc), (D, d, fd));
+impl_query_tuple!((A, a, fa), (B, b, fb), (C, c, fc), (D, d, fd), (E, e, fe));
+impl_query_tuple!(
+    (A, a, fa),
+    (B, b, fb),
+    (C, c, fc),
+    (D, d, fd),
+    (E, e, fe),
+    (F, f, ff)
+);
+impl_query_tuple!(
+    (A, a, fa),
+    (B, b, fb),
+    (C, c, fc),
+    (D, d, fd),
+    (E, e, fe),
+    (F, f, ff),
+    (G, g, fg)
+);
+impl_query_tuple!(
+    (A, a, fa),
+    (B, b, fb),
+    (C, c, fc),
+    (D, d, fd),
+    (E, e, fe),
+    (F, f, ff),
+    (G, g, fg),
+    (H, h, fh)
+);
+
+/**
+ * A typed query over the components of a `Table`
+ *
+ * `D` is the data fetched for each entity, e.g. `(&Transform, &mut Velocity)`,
+ * and `F` restricts which entities are visited, e.g.
+ * `(With<Collider>, Without<Static>)` or `Changed<Transform>`.
+ *
+ * Creating the state panics if `D` would alias a component mutably.
+ */
+pub struct QueryState<D: QueryData, F: QueryFilter = ()> {
+    data: D::State,
+    filter: F::State,
+    access: FilteredAccess,
+}
+
+impl<D: QueryData, F: QueryFilter> QueryState<D, F> {
+    pub fn new() -> Self {
+        let data = D::init_state();
+        let filter = F::init_state();
+        let mut access = FilteredAccess::new();
+        D::update_access(data, access.access_mut());
+        D::update_filter(data, &mut access);
+        F::update_filter(filter, &mut access);
+
+        let mut filter_access = Access::new();
+        F::update_access(filter, &mut filter_access);
+        for &c in filter_access.reads() {
+            if !access.access().writes().contains(&c) {
+                access.access_mut().add_read(c);
+            }
+        }
+
+        Self {
+            data,
+            filter,
+            access,
+        }
+    }
+
+    /**
+     * The components this query reads and writes
+     */
+    pub fn access(&self) -> &Access {
+        self.access.access()
+    }
+
+    /**
+     * The access of this query, with the components its filters
+     * require and rule out
+     */
+    pub fn filtered_access(&self) -> &FilteredAccess {
+        &self.access
+    }
+
+    pub fn matches(&self, archetype: &Archetype) -> bool {
+        D::matches(self.data, archetype) && F::matches(self.filter, archetype)
+    }
+
+    /**
+     * Iterates over every matching entity.
+     */
+    pub fn iter<'w>(&self, table: &'w Table) -> QueryIter<'w, D, F>
+    where
+        D: ReadOnlyQueryData,
+    {
+        unsafe { self.iter_unchecked(table, outside_ticks(table)) }
+    }
+
+    /**
+     * Iterates mutably over every matching entity.
+     */
+    pub fn iter_mut<'w>(&self, table: &'w mut Table) -> QueryIter<'w, D, F> {
+        let ticks = outside_ticks(table);
+        unsafe { self.iter_unchecked(table, ticks) }
+    }
+
+    /**
+     * Iterates over every matching entity without checking borrows.
+     *
+     * Change filters compare against `ticks.last_run` and
+     * writes are stamped with `ticks.this_run`.
+     *
+     * # Safety
+     *
+     * Nothing else may access the components in `access()`
+     * in a conflicting way while the items are alive.
+     */
+    pub unsafe fn iter_unchecked<'w>(
+        &self,
+        table: &'w Table,
+        ticks: SystemTicks,
+    ) -> QueryIter<'w, D, F> {
+        QueryIter {
+            state: Self {
+                data: self.data,
+                filter: self.filter,
+                access: FilteredAccess::new(),
+            },
+            table,
+            ticks,
+            archetypes: table.archetypes().iter(),
+            current: None,
+        }
+    }
+
+    /**
+     * Gets the item for a single entity.
+     *
+     * If the entity does not match the query, it returns None.
+     */
+    pub fn get<'w>(&self, table: &'w Table, entity: EntityId) -> Option<D::Item<'w>>
+    where
+        D: ReadOnlyQueryData,
+    {
+        unsafe { self.get_unchecked(table, entity, outside_ticks(table)) }
+    }
+
+    /**
+     * Gets the mutable item for a single entity.
+     *
+     * If the entity does not match the query, it returns None.
+     */
+    pub fn get_mut<'w>(&self, table: &'w mut Table, entity: EntityId) -> Option<D::Item<'w>> {
+        let ticks = outside_ticks(table);
+        unsafe { self.get_unchecked(table, entity, ticks) }
+    }
+
+    /**
+     * Gets the item for a single entity without checking borrows.
+     *
+     * # Safety
+     *
+     * Same as `iter_unchecked`.
+     */
+    pub unsafe fn get_unchecked<'w>(
+        &self,
+        table: &'w Table,
+        entity: EntityId,
+        ticks: SystemTicks,
+    ) -> Option<D::Item<'w>> {
+        let loc = table.location(entity)?;
+        let archetype = table.archetype(loc.archetype);
+        if !self.matches(archetype) {
+            return None;
+        }
+        unsafe {
+            let mut filter = F::fetch_archetype(self.filter, table, archetype, ticks);
+            if !F::filter(&mut filter, entity, loc.row) {
+                return None;
+            }
+            let mut fetch = D::fetch_archetype(self.data, table, archetype, ticks);
+            if !D::contains(&fetch, entity, loc.row) {
+                return None;
+            }
+            Some(D::fetch(&mut fetch, entity, loc.row))
+        }
+    }
+}
+
+/**
+ * The ticks used for queries run outside of systems
+ */
+fn outside_ticks(table: &Table) -> SystemTicks {
+    SystemTicks {
+        last_run: 0,
+        this_run: table.change_tick(),
+    }
+}
+
+impl<D: QueryData, F: QueryFilter> Default for QueryState<D, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D: ReadOnlyQueryData, F: QueryFilter> Query for QueryState<D, F> {
+    type Mapping = Table;
+    type Out<'m>
+        = QueryIter<'m, D, F>
+    where
+        Self: 'm;
+
+    fn query<'m>(&'m self, map: &'m Table) -> QueryIter<'m, D, F> {
+        self.iter(map)
+    }
+}
+
+/**
+ * An iterator over the items of a `QueryState`
+ */
+pub struct QueryIter<'w, D: QueryData, F: QueryFilter> {
+    state: QueryState<D, F>,
+    table: &'w Table,
+    ticks: SystemTicks,
+    archetypes: std::slice::Iter<'w, Archetype>,
+    current: Option<(&'w Archetype, D::Fetch<'w>, F::Fetch<'w>, usize)>,
+}
+
+impl<'w, D: QueryData, F: QueryFilter> Iterator for QueryIter<'w, D, F> {
+    type Item = D::Item<'w>;
+
+    fn next(&mut self) -> Option<D::Item<'w>> {
+        loop {
+            if let Some((archetype, fetch, filter, row)) = &mut self.current {
+                while *row < archetype.len() {
+                    let current = *row;
+                    *row += 1;
+                    let entity = archetype.entities()[current];
+                    if unsafe {
+                        F::filter(filter, entity, current) && D::contains(fetch, entity, current)
+                    } {
+                        return Some(unsafe { D::fetch(fetch, entity, current) });
+                    }
+                }
+            }
+
+            let archetype = self
+                .archetypes
+                .by_ref()
+                .find(|a| !a.is_empty() && self.state.matches(a))?;
+            let (data, filter) = (self.state.data, self.state.filter);
+            let fetch = unsafe { D::fetch_archetype(data, self.table, archetype, self.ticks) };
+            let filter = unsafe { F::fetch_archetype(filter, self.table, archetype, self.ticks) };
+            self.current = Some((archetype, fetch, filter, 0));
+        }
+    }
+}
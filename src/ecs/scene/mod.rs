@@ -0,0 +1,368 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+mod toml_unit;
+
+/**
+ * The ways saving or loading a scene can fail
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SceneError {
+    /**
+     * The scene names a component that is not saved in scenes
+     */
+    UnknownComponent(String),
+    /**
+     * A component or the scene could not be encoded
+     */
+    Serialize(String),
+    /**
+     * A component or the scene could not be decoded
+     */
+    Deserialize(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownComponent(key) => write!(
+                f,
+                "component `{key}` is not saved in scenes, derive it with `#[component(scene)]`"
+            ),
+            Self::Serialize(err) => write!(f, "failed to serialize scene: {err}"),
+            Self::Deserialize(err) => write!(f, "failed to deserialize scene: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+/**
+ * This is a trait for components that hold `EntityId`s
+ *
+ * Entities get new ids when a scene is spawned, so every
+ * reference to an entity of the scene is rewritten through
+ * `map` before the component is inserted. `map` returns None
+ * for entities outside the scene, and those links are dropped.
+ * Derive the component with `#[component(map_entities)]`.
+ */
+pub trait MapEntities: Sized {
+    /**
+     * Returns None if the component is left with nothing to refer
+     * to, so it is not inserted.
+     */
+    fn map_entities(self, map: &dyn Fn(EntityId) -> Option<EntityId>) -> Option<Self>;
+}
+
+/**
+ * A way of encoding single components inside a `Scene`
+ *
+ * `toml::Value` is used for the human-readable formats
+ * and bincode bytes for `BinaryScene`.
+ */
+pub trait SceneValue: Serialize + DeserializeOwned {
+    fn encode<T: Serialize>(value: &T) -> Result<Self, SceneError>;
+    fn decode<T: DeserializeOwned>(&self) -> Result<T, SceneError>;
+    fn hooks(registration: &SceneRegistration) -> &SceneHooks<Self>;
+}
+
+impl SceneValue for toml::Value {
+    fn encode<T: Serialize>(value: &T) -> Result<Self, SceneError> {
+        toml::Value::try_from(toml_unit::UnitAsTable(value))
+            .map_err(|err| SceneError::Serialize(err.to_string()))
+    }
+
+    fn decode<T: DeserializeOwned>(&self) -> Result<T, SceneError> {
+        T::deserialize(toml_unit::TableAsUnit(self.clone()))
+            .map_err(|err| SceneError::Deserialize(err.to_string()))
+    }
+
+    fn hooks(registration: &SceneRegistration) -> &SceneHooks<Self> {
+        &registration.text
+    }
+}
+
+impl SceneValue for Vec<u8> {
+    fn encode<T: Serialize>(value: &T) -> Result<Self, SceneError> {
+        bincode::serde::encode_to_vec(value, bincode::config::standard())
+            .map_err(|err| SceneError::Serialize(err.to_string()))
+    }
+
+    fn decode<T: DeserializeOwned>(&self) -> Result<T, SceneError> {
+        bincode::serde::decode_from_slice(self, bincode::config::standard())
+            .map(|(value, _)| value)
+            .map_err(|err| SceneError::Deserialize(err.to_string()))
+    }
+
+    fn hooks(registration: &SceneRegistration) -> &SceneHooks<Self> {
+        &registration.binary
+    }
+}
+
+type LoadFn<V> =
+    fn(&mut EntityMut, &V, &dyn Fn(EntityId) -> Option<EntityId>) -> Result<(), SceneError>;
+
+/**
+ * The functions saving and loading one component type in one format
+ */
+pub struct SceneHooks<V> {
+    save: fn(&EntityRef) -> Result<Vec<V>, SceneError>,
+    load: LoadFn<V>,
+}
+
+/**
+ * How a component type is saved in scenes
+ *
+ * It is built by the `Component` derive macro for components
+ * marked with `#[component(scene)]` or `#[component(map_entities)]`,
+ * see `ComponentRegistration::scene`.
+ */
+pub struct SceneRegistration {
+    text: SceneHooks<toml::Value>,
+    binary: SceneHooks<Vec<u8>>,
+}
+
+impl SceneRegistration {
+    pub const fn of<T: Component + Serialize + DeserializeOwned>() -> Self {
+        Self {
+            text: SceneHooks {
+                save: save::<T, _>,
+                load: load::<T, _>,
+            },
+            binary: SceneHooks {
+                save: save::<T, _>,
+                load: load::<T, _>,
+            },
+        }
+    }
+
+    /**
+     * Builds the registration of a component holding `EntityId`s,
+     * which are remapped when a scene is spawned.
+     */
+    pub const fn mapped<T: Component + Serialize + DeserializeOwned + MapEntities>() -> Self {
+        Self {
+            text: SceneHooks {
+                save: save::<T, _>,
+                load: load_mapped::<T, _>,
+            },
+            binary: SceneHooks {
+                save: save::<T, _>,
+                load: load_mapped::<T, _>,
+            },
+        }
+    }
+}
+
+fn save<T: Component + Serialize, V: SceneValue>(entity: &EntityRef) -> Result<Vec<V>, SceneError> {
+    entity.get_components::<T>().map(V::encode).collect()
+}
+
+fn load<T: Component + DeserializeOwned, V: SceneValue>(
+    entity: &mut EntityMut,
+    value: &V,
+    _map: &dyn Fn(EntityId) -> Option<EntityId>,
+) -> Result<(), SceneError> {
+    entity.add_component(value.decode::<T>()?);
+    Ok(())
+}
+
+fn load_mapped<T: Component + DeserializeOwned + MapEntities, V: SceneValue>(
+    entity: &mut EntityMut,
+    value: &V,
+    map: &dyn Fn(EntityId) -> Option<EntityId>,
+) -> Result<(), SceneError> {
+    if let Some(component) = value.decode::<T>()?.map_entities(map) {
+        entity.add_component(component);
+    }
+    Ok(())
+}
+
+/**
+ * Gets the scene registration of the component with the given key.
+ */
+fn registration(key: &str) -> Option<&'static SceneRegistration> {
+    component_registration(component_id_by_key(key)?)?
+        .scene
+        .as_ref()
+}
+
+/**
+ * One instance of a component in a `Scene`
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneComponent<V> {
+    pub key: String,
+    pub value: V,
+}
+
+/**
+ * An entity in a `Scene`
+ *
+ * The id is the one the entity had when it was saved and
+ * is only used to resolve references between entities.
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneEntity<V> {
+    pub id: EntityId,
+    pub components: Vec<SceneComponent<V>>,
+}
+
+/**
+ * A set of entities and their components, detached from any `World`
+ *
+ * The default `Scene` stores components as `toml::Value`s and can
+ * be written as RON, for authored scenes, or TOML. `BinaryScene`
+ * stores them as bincode for fast save games.
+ *
+ * Only components derived with `#[component(scene)]` or
+ * `#[component(map_entities)]` are saved, under their
+ * `ComponentRegistration::key`.
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scene<V = toml::Value> {
+    pub entities: Vec<SceneEntity<V>>,
+}
+
+/**
+ * A `Scene` encoded with bincode
+ */
+pub type BinaryScene = Scene<Vec<u8>>;
+
+impl<V: SceneValue> Scene<V> {
+    /**
+     * Saves every entity of the world.
+     */
+    pub fn from_world(world: &World) -> Result<Self, SceneError> {
+        Self::save_all(world.iter())
+    }
+
+    /**
+     * Saves the given entities.
+     *
+     * Dead entities are skipped. References to entities left out
+     * of the scene are dropped when it is spawned, see `MapEntities`.
+     */
+    pub fn from_entities(
+        world: &World,
+        entities: impl IntoIterator<Item = EntityId>,
+    ) -> Result<Self, SceneError> {
+        let mut seen = HashSet::new();
+        let entities = entities
+            .into_iter()
+            .filter(|&id| seen.insert(id))
+            .filter_map(|id| world.get(id));
+        Self::save_all(entities)
+    }
+
+    fn save_all<'w>(entities: impl Iterator<Item = EntityRef<'w>>) -> Result<Self, SceneError> {
+        let registrations: Vec<_> = (0..component_count())
+            .filter_map(|id| {
+                let registration = component_registration(id)?;
+                Some((registration.key, registration.scene.as_ref()?))
+            })
+            .collect();
+
+        let mut saved = Vec::new();
+        for entity in entities {
+            let mut components = Vec::new();
+            for &(key, registration) in &registrations {
+                for value in (V::hooks(registration).save)(&entity)? {
+                    components.push(SceneComponent {
+                        key: key.to_string(),
+                        value,
+                    });
+                }
+            }
+            saved.push(SceneEntity {
+                id: entity.id(),
+                components,
+            });
+        }
+        Ok(Self { entities: saved })
+    }
+
+    /**
+     * Spawns the entities of the scene into the world.
+     *
+     * Returns the new id of every saved entity. References between
+     * entities of the scene are remapped to the new ids. If any
+     * component fails to load, nothing is spawned.
+     */
+    pub fn spawn(&self, world: &mut World) -> Result<HashMap<EntityId, EntityId>, SceneError> {
+        for component in self.entities.iter().flat_map(|e| &e.components) {
+            if registration(&component.key).is_none() {
+                return Err(SceneError::UnknownComponent(component.key.clone()));
+            }
+        }
+
+        let ids: HashMap<_, _> = self
+            .entities
+            .iter()
+            .map(|entity| (entity.id, world.spawn()))
+            .collect();
+        let map = |id: EntityId| ids.get(&id).copied();
+
+        for entity in &self.entities {
+            if let Err(err) = Self::load_entity(world, entity, ids[&entity.id], &map) {
+                for &id in ids.values() {
+                    world.despawn(id);
+                }
+                return Err(err);
+            }
+        }
+        Ok(ids)
+    }
+
+    fn load_entity(
+        world: &mut World,
+        entity: &SceneEntity<V>,
+        id: EntityId,
+        map: &dyn Fn(EntityId) -> Option<EntityId>,
+    ) -> Result<(), SceneError> {
+        let mut target = world.get_mut(id).expect("scene entities were just spawned");
+        for component in &entity.components {
+            let registration = registration(&component.key)
+                .expect("scene components were checked before spawning");
+            (V::hooks(registration).load)(&mut target, &component.value, map)?;
+        }
+        Ok(())
+    }
+}
+
+impl Scene {
+    pub fn to_ron(&self) -> Result<String, SceneError> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| SceneError::Serialize(err.to_string()))
+    }
+
+    pub fn from_ron(source: &str) -> Result<Self, SceneError> {
+        ron::from_str(source).map_err(|err| SceneError::Deserialize(err.to_string()))
+    }
+
+    pub fn to_toml(&self) -> Result<String, SceneError> {
+        toml::to_string(self).map_err(|err| SceneError::Serialize(err.to_string()))
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, SceneError> {
+        toml::from_str(source).map_err(|err| SceneError::Deserialize(err.to_string()))
+    }
+}
+
+impl BinaryScene {
+    pub fn to_bincode(&self) -> Result<Vec<u8>, SceneError> {
+        bincode::serde::encode_to_vec(self, bincode::config::standard())
+            .map_err(|err| SceneError::Serialize(err.to_string()))
+    }
+
+    pub fn from_bincode(bytes: &[u8]) -> Result<Self, SceneError> {
+        bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+            .map(|(scene, _)| scene)
+            .map_err(|err| SceneError::Deserialize(err.to_string()))
+    }
+}
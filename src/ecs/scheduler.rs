@@ -1 +1,356 @@
+use rayon::prelude::*;
 
+use super::system::{IntoSystem, System};
+use super::world::World;
+
+struct SystemNode {
+    system: Box<dyn System>,
+    labels: Vec<String>,
+    before: Vec<String>,
+    after: Vec<String>,
+    segment: usize,
+}
+
+/**
+ * A set of systems run together on a `World`
+ *
+ * Systems are ordered by their explicit `before`/`after` constraints
+ * and by their access sets: systems that touch the same data mutably
+ * never overlap, everything else runs in parallel on the rayon pool.
+ * Conflicting systems with no explicit order run in the order they
+ * were added, and are listed by `Schedule::ambiguities`.
+ *
+ * Sync points split the schedule into segments. Every system in a
+ * segment finishes, and its deferred commands are applied, before
+ * the next segment starts.
+ */
+#[derive(Default)]
+pub struct Schedule {
+    nodes: Vec<SystemNode>,
+    segments: Vec<Vec<Vec<usize>>>,
+    sync_points: usize,
+    ambiguities: Vec<(String, String)>,
+    dirty: bool,
+}
+
+/**
+ * Configures ordering for a system that was just added to a `Schedule`
+ */
+pub struct SystemConfig<'a> {
+    node: &'a mut SystemNode,
+}
+
+impl SystemConfig<'_> {
+    /**
+     * Adds a label other systems can order themselves against.
+     */
+    pub fn label(self, label: impl Into<String>) -> Self {
+        self.node.labels.push(label.into());
+        self
+    }
+
+    /**
+     * Runs this system before every system with the label.
+     */
+    pub fn before(self, label: impl Into<String>) -> Self {
+        self.node.before.push(label.into());
+        self
+    }
+
+    /**
+     * Runs this system after every system with the label.
+     */
+    pub fn after(self, label: impl Into<String>) -> Self {
+        self.node.after.push(label.into());
+        self
+    }
+}
+
+#[derive(Clone, Copy)]
+struct WorldPtr(*mut World);
+
+unsafe impl Send for WorldPtr {}
+unsafe impl Sync for WorldPtr {}
+
+impl WorldPtr {
+    fn get(self) -> *mut World {
+        self.0
+    }
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Adds a system to the schedule.
+     *
+     * The system is labeled with its name by default.
+     */
+    pub fn add_system<M>(&mut self, system: impl IntoSystem<M>) -> SystemConfig<'_> {
+        let system = system.into_system();
+        let name = system.name().to_string();
+        self.nodes.push(SystemNode {
+            system: Box::new(system),
+            labels: vec![name],
+            before: Vec::new(),
+            after: Vec::new(),
+            segment: self.sync_points,
+        });
+        self.dirty = true;
+        SystemConfig {
+            node: self.nodes.last_mut().unwrap(),
+        }
+    }
+
+    /**
+     * Adds a sync point after every system added so far.
+     *
+     * Commands queued by those systems are applied at the sync point,
+     * so systems added later see their effects.
+     */
+    pub fn add_sync_point(&mut self) {
+        self.sync_points += 1;
+        self.dirty = true;
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /**
+     * Pairs of conflicting systems that have no explicit order.
+     *
+     * Only up to date after the schedule has been built or run.
+     */
+    pub fn ambiguities(&self) -> &[(String, String)] {
+        &self.ambiguities
+    }
+
+    /**
+     * Initializes new systems and rebuilds the dependency graph.
+     *
+     * Panics if the ordering constraints reference an unknown
+     * label, form a cycle or point back across a sync point.
+     */
+    pub fn build(&mut self, world: &mut World) {
+        if !self.dirty {
+            return;
+        }
+
+        for node in &mut self.nodes {
+            node.system.initialize(world);
+        }
+
+        let n = self.nodes.len();
+        let mut edges = vec![Vec::new(); n];
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            for label in &node.before {
+                for j in self.labeled(label, &node.labels[0]) {
+                    if j != i && self.same_segment(i, j) {
+                        edges[i].push(j);
+                    }
+                }
+            }
+            for label in &node.after {
+                for j in self.labeled(label, &node.labels[0]) {
+                    if j != i && self.same_segment(j, i) {
+                        edges[j].push(i);
+                    }
+                }
+            }
+        }
+
+        let order = topological_order(&edges).unwrap_or_else(|| {
+            panic!("System ordering constraints form a cycle");
+        });
+        let mut reach = reachability(&edges, &order);
+
+        self.ambiguities.clear();
+        for i in 0..n {
+            for j in i + 1..n {
+                let (a, b) = (&self.nodes[i].system, &self.nodes[j].system);
+                if reach[i][j]
+                    || reach[j][i]
+                    || self.nodes[i].segment != self.nodes[j].segment
+                    || a.access().is_compatible(b.access())
+                {
+                    continue;
+                }
+                edges[i].push(j);
+                add_reach(&mut reach, i, j);
+                self.ambiguities
+                    .push((a.name().to_string(), b.name().to_string()));
+            }
+        }
+
+        let order = topological_order(&edges).expect("Implicit edges never form a cycle");
+        let mut level = vec![0; n];
+        for &i in &order {
+            for &j in &edges[i] {
+                level[j] = level[j].max(level[i] + 1);
+            }
+        }
+
+        self.segments = vec![Vec::new(); self.sync_points + 1];
+        for (i, &l) in level.iter().enumerate() {
+            let levels = &mut self.segments[self.nodes[i].segment];
+            if levels.len() <= l {
+                levels.resize(l + 1, Vec::new());
+            }
+            levels[l].push(i);
+        }
+        self.dirty = false;
+    }
+
+    /**
+     * Checks if an edge from `from` to `to` stays inside one segment.
+     *
+     * Edges into a later segment already hold, edges into an
+     * earlier one can never hold and panic.
+     */
+    fn same_segment(&self, from: usize, to: usize) -> bool {
+        let (a, b) = (&self.nodes[from], &self.nodes[to]);
+        assert!(
+            a.segment <= b.segment,
+            "System `{}` must run before `{}`, but comes after it across a sync point",
+            a.labels[0],
+            b.labels[0]
+        );
+        a.segment == b.segment
+    }
+
+    fn labeled(&self, label: &str, from: &str) -> Vec<usize> {
+        let matches: Vec<_> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.labels.iter().any(|l| l == label))
+            .map(|(i, _)| i)
+            .collect();
+        assert!(
+            !matches.is_empty(),
+            "System `{from}` is ordered against unknown label `{label}`"
+        );
+        matches
+    }
+
+    /**
+     * Runs every system once.
+     *
+     * Deferred work such as commands is applied at each sync point
+     * and at the end, in the order the systems were added.
+     */
+    pub fn run(&mut self, world: &mut World) {
+        self.build(world);
+
+        for (segment, levels) in self.segments.iter().enumerate() {
+            let ptr = WorldPtr(world);
+            for level in levels {
+                let mut systems: Vec<_> = self
+                    .nodes
+                    .iter_mut()
+                    .enumerate()
+                    .filter(|(i, _)| level.contains(i))
+                    .map(|(_, n)| &mut n.system)
+                    .collect();
+
+                if systems.len() == 1 {
+                    unsafe { systems[0].run(ptr.get()) };
+                } else {
+                    systems
+                        .par_iter_mut()
+                        .for_each(|system| unsafe { system.run(ptr.get()) });
+                }
+            }
+
+            for node in self.nodes.iter_mut().filter(|n| n.segment == segment) {
+                node.system.apply(world);
+            }
+        }
+    }
+
+    /**
+     * Clamps the last run tick of every system, see `World::check_change_ticks`.
+     */
+    pub fn check_change_ticks(&mut self, change_tick: u32) {
+        for node in &mut self.nodes {
+            node.system.check_change_tick(change_tick);
+        }
+    }
+}
+
+/**
+ * Orders the nodes so every edge points forward.
+ *
+ * If the graph has a cycle, it returns None.
+ */
+fn topological_order(edges: &[Vec<usize>]) -> Option<Vec<usize>> {
+    let mut incoming = vec![0; edges.len()];
+    for targets in edges {
+        for &j in targets {
+            incoming[j] += 1;
+        }
+    }
+
+    let mut ready: Vec<_> = (0..edges.len())
+        .rev()
+        .filter(|&i| incoming[i] == 0)
+        .collect();
+    let mut order = Vec::with_capacity(edges.len());
+    while let Some(i) = ready.pop() {
+        order.push(i);
+        for &j in &edges[i] {
+            incoming[j] -= 1;
+            if incoming[j] == 0 {
+                ready.push(j);
+            }
+        }
+    }
+
+    (order.len() == edges.len()).then_some(order)
+}
+
+/**
+ * Computes which nodes can reach which others through the edges.
+ */
+fn reachability(edges: &[Vec<usize>], order: &[usize]) -> Vec<Vec<bool>> {
+    let mut reach = vec![vec![false; edges.len()]; edges.len()];
+    for &i in order.iter().rev() {
+        for &j in &edges[i] {
+            reach[i][j] = true;
+            let (row_i, row_j) = if i < j {
+                let (left, right) = reach.split_at_mut(j);
+                (&mut left[i], &right[0])
+            } else {
+                let (left, right) = reach.split_at_mut(i);
+                (&mut right[0], &left[j])
+            };
+            for (r, &via) in row_i.iter_mut().zip(row_j.iter()) {
+                *r |= via;
+            }
+        }
+    }
+    reach
+}
+
+/**
+ * Updates the reachability matrix after adding an edge from `from` to `to`.
+ */
+fn add_reach(reach: &mut [Vec<bool>], from: usize, to: usize) {
+    let mut targets = reach[to].clone();
+    targets[to] = true;
+    for (x, row) in reach.iter_mut().enumerate() {
+        if x == from || row[from] {
+            for (r, &t) in row.iter_mut().zip(&targets) {
+                *r |= t;
+            }
+        }
+    }
+}
@@ -1,4 +1,38 @@
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::ecs::change_detection::{RemovedComponents, SystemTicks, check_tick};
+use crate::ecs::commands::{CommandQueue, Commands};
+use crate::ecs::event::{EventReader, EventWriter, Events};
+use crate::ecs::query::{
+    Access, FilteredAccess, QueryData, QueryFilter, QueryIter, QueryState, ReadOnlyQueryData,
+};
 use crate::ecs::world::World;
+use crate::ecs::{Component, EntityId, Event, Resource, get_component_id, get_resource_id};
+
+/**
+ * The components and resources a system reads and writes
+ *
+ * `queries` keeps the access of each `Query` parameter with its
+ * filters, so queries of one system only conflict if they can
+ * see the same entity.
+ */
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct SystemAccess {
+    pub components: Access,
+    pub resources: Access,
+    pub queries: Vec<FilteredAccess>,
+}
+
+impl SystemAccess {
+    /**
+     * Checks if two systems can run at the same time.
+     */
+    pub fn is_compatible(&self, other: &SystemAccess) -> bool {
+        self.components.is_compatible(&other.components)
+            && self.resources.is_compatible(&other.resources)
+    }
+}
 
 /**
  * This is a trait for systems in the ECS
@@ -9,10 +43,513 @@ use crate::ecs::world::World;
  * create
  */
 pub trait System: Send + Sync {
+    fn name(&self) -> &str;
+
+    /**
+     * The data this system touches while running.
+     *
+     * Only meaningful after `initialize`.
+     */
+    fn access(&self) -> &SystemAccess;
+
+    /**
+     * Prepares the system's state before its first run.
+     */
+    fn initialize(&mut self, world: &mut World);
+
     /**
      * This function is only to be called by the
      * scheduler. It is not intended to be called
      * directly by the user.
+     *
+     * # Safety
+     *
+     * `world` must be valid for the duration of the call and
+     * no other system may access the same data concurrently.
      */
     unsafe fn run(&mut self, world: *mut World);
+
+    /**
+     * Applies any work the system deferred while running,
+     * such as queued commands.
+     */
+    fn apply(&mut self, world: &mut World);
+
+    /**
+     * Clamps the tick of the system's last run, see `World::check_change_ticks`.
+     */
+    fn check_change_tick(&mut self, change_tick: u32);
+}
+
+/**
+ * This is a trait for the parameters of function systems
+ *
+ * It is implemented for `Query`, `Res`, `ResMut`, `Commands`,
+ * `RemovedComponents`, `EventReader`, `EventWriter` and tuples of those.
+ *
+ * # Safety
+ *
+ * `init_state` must record in `access` everything that
+ * `get_param` hands out.
+ */
+pub unsafe trait SystemParam: Sized {
+    type State: Send + Sync + 'static;
+    type Item<'w, 's>: SystemParam<State = Self::State>;
+
+    fn init_state(world: &mut World, access: &mut SystemAccess) -> Self::State;
+
+    /**
+     * Creates the parameter for one run of the system.
+     *
+     * `ticks` tells change detection which changes are new to the system.
+     *
+     * # Safety
+     *
+     * The caller must hold the access recorded by `init_state`.
+     */
+    unsafe fn get_param<'w, 's>(
+        state: &'s mut Self::State,
+        world: *mut World,
+        ticks: SystemTicks,
+    ) -> Self::Item<'w, 's>;
+
+    fn apply(_state: &mut Self::State, _world: &mut World) {}
+}
+
+pub type SystemParamItem<'w, 's, P> = <P as SystemParam>::Item<'w, 's>;
+
+/**
+ * A system parameter iterating over entities, see `QueryState`
+ */
+pub struct Query<'w, 's, D: QueryData, F: QueryFilter = ()> {
+    world: *mut World,
+    state: &'s QueryState<D, F>,
+    ticks: SystemTicks,
+    _world: PhantomData<&'w World>,
+}
+
+impl<D: QueryData, F: QueryFilter> Query<'_, '_, D, F> {
+    /**
+     * Iterates over every matching entity.
+     */
+    pub fn iter(&self) -> QueryIter<'_, D, F>
+    where
+        D: ReadOnlyQueryData,
+    {
+        unsafe { self.state.iter_unchecked((*self.world).table(), self.ticks) }
+    }
+
+    /**
+     * Iterates mutably over every matching entity.
+     */
+    pub fn iter_mut(&mut self) -> QueryIter<'_, D, F> {
+        unsafe { self.state.iter_unchecked((*self.world).table(), self.ticks) }
+    }
+
+    /**
+     * Gets the item for a single entity.
+     *
+     * If the entity does not match the query, it returns None.
+     */
+    pub fn get(&self, entity: EntityId) -> Option<D::Item<'_>>
+    where
+        D: ReadOnlyQueryData,
+    {
+        unsafe {
+            self.state
+                .get_unchecked((*self.world).table(), entity, self.ticks)
+        }
+    }
+
+    /**
+     * Gets the mutable item for a single entity.
+     *
+     * If the entity does not match the query, it returns None.
+     */
+    pub fn get_mut(&mut self, entity: EntityId) -> Option<D::Item<'_>> {
+        unsafe {
+            self.state
+                .get_unchecked((*self.world).table(), entity, self.ticks)
+        }
+    }
+    /**
+     * Gets the mutable item for a single entity through a shared reference.
+     *
+     * Lets disjoint entities be written from several threads at once.
+     *
+     * # Safety
+     *
+     * No two items for the same entity may be alive at the same time.
+     */
+    pub unsafe fn get_unchecked(&self, entity: EntityId) -> Option<D::Item<'_>> {
+        unsafe {
+            self.state
+                .get_unchecked((*self.world).table(), entity, self.ticks)
+        }
+    }
+}
+
+impl<'a, D: ReadOnlyQueryData, F: QueryFilter> IntoIterator for &'a Query<'_, '_, D, F> {
+    type Item = D::Item<'a>;
+    type IntoIter = QueryIter<'a, D, F>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, D: QueryData, F: QueryFilter> IntoIterator for &'a mut Query<'_, '_, D, F> {
+    type Item = D::Item<'a>;
+    type IntoIter = QueryIter<'a, D, F>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+unsafe impl<D: QueryData + 'static, F: QueryFilter + 'static> SystemParam for Query<'_, '_, D, F> {
+    type State = QueryState<D, F>;
+    type Item<'w, 's> = Query<'w, 's, D, F>;
+
+    fn init_state(_world: &mut World, access: &mut SystemAccess) -> QueryState<D, F> {
+        let state = QueryState::new();
+        let filtered = state.filtered_access();
+        assert!(
+            access
+                .queries
+                .iter()
+                .all(|other| other.is_compatible(filtered)),
+            "Query<{}, {}> conflicts with another query of the system, rule them apart with `Without`",
+            std::any::type_name::<D>(),
+            std::any::type_name::<F>()
+        );
+        access.components.extend(state.access());
+        access.queries.push(filtered.clone());
+        state
+    }
+
+    unsafe fn get_param<'w, 's>(
+        state: &'s mut QueryState<D, F>,
+        world: *mut World,
+        ticks: SystemTicks,
+    ) -> Query<'w, 's, D, F> {
+        Query {
+            world,
+            state,
+            ticks,
+            _world: PhantomData,
+        }
+    }
 }
+
+/**
+ * A system parameter borrowing a resource immutably
+ *
+ * Panics when the system runs if the resource is missing.
+ */
+pub struct Res<'w, T: Resource> {
+    value: &'w T,
+}
+
+impl<T: Resource> Deref for Res<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+unsafe impl<T: Resource> SystemParam for Res<'_, T> {
+    type State = usize;
+    type Item<'w, 's> = Res<'w, T>;
+
+    fn init_state(_world: &mut World, access: &mut SystemAccess) -> usize {
+        let id = get_resource_id::<T>();
+        access.resources.add_read(id);
+        id
+    }
+
+    unsafe fn get_param<'w, 's>(
+        _state: &'s mut usize,
+        world: *mut World,
+        _ticks: SystemTicks,
+    ) -> Self::Item<'w, 's> {
+        let ptr = unsafe { World::resource_ptr::<T>(world) }.unwrap_or_else(|e| panic!("{e}"));
+        Res {
+            value: unsafe { &*ptr },
+        }
+    }
+}
+
+/**
+ * A system parameter borrowing a resource mutably
+ *
+ * Panics when the system runs if the resource is missing.
+ */
+pub struct ResMut<'w, T: Resource> {
+    value: &'w mut T,
+}
+
+impl<T: Resource> Deref for ResMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T: Resource> DerefMut for ResMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+    }
+}
+
+unsafe impl<T: Resource> SystemParam for ResMut<'_, T> {
+    type State = usize;
+    type Item<'w, 's> = ResMut<'w, T>;
+
+    fn init_state(_world: &mut World, access: &mut SystemAccess) -> usize {
+        let id = get_resource_id::<T>();
+        access.resources.add_write(id);
+        id
+    }
+
+    unsafe fn get_param<'w, 's>(
+        _state: &'s mut usize,
+        world: *mut World,
+        _ticks: SystemTicks,
+    ) -> Self::Item<'w, 's> {
+        let ptr = unsafe { World::resource_ptr::<T>(world) }.unwrap_or_else(|e| panic!("{e}"));
+        ResMut {
+            value: unsafe { &mut *ptr },
+        }
+    }
+}
+
+unsafe impl SystemParam for Commands<'_, '_> {
+    type State = CommandQueue;
+    type Item<'w, 's> = Commands<'w, 's>;
+
+    fn init_state(_world: &mut World, _access: &mut SystemAccess) -> CommandQueue {
+        CommandQueue::default()
+    }
+
+    unsafe fn get_param<'w, 's>(
+        state: &'s mut CommandQueue,
+        world: *mut World,
+        _ticks: SystemTicks,
+    ) -> Self::Item<'w, 's> {
+        Commands::new(state, unsafe { &*world })
+    }
+
+    fn apply(state: &mut CommandQueue, world: &mut World) {
+        state.apply(world);
+    }
+}
+
+unsafe impl<T: Component> SystemParam for RemovedComponents<'_, T> {
+    type State = usize;
+    type Item<'w, 's> = RemovedComponents<'w, T>;
+
+    fn init_state(_world: &mut World, access: &mut SystemAccess) -> usize {
+        let id = get_component_id::<T>();
+        let mut read = Access::new();
+        read.add_read(id);
+        access.components.extend(&read);
+        id
+    }
+
+    unsafe fn get_param<'w, 's>(
+        state: &'s mut usize,
+        world: *mut World,
+        ticks: SystemTicks,
+    ) -> Self::Item<'w, 's> {
+        let table = unsafe { (*world).table() };
+        RemovedComponents::new(table.removed(*state), ticks)
+    }
+}
+
+unsafe impl<T: Event> SystemParam for EventWriter<'_, T> {
+    type State = usize;
+    type Item<'w, 's> = EventWriter<'w, T>;
+
+    fn init_state(_world: &mut World, access: &mut SystemAccess) -> usize {
+        let id = get_resource_id::<Events<T>>();
+        access.resources.add_write(id);
+        id
+    }
+
+    unsafe fn get_param<'w, 's>(
+        _state: &'s mut usize,
+        world: *mut World,
+        _ticks: SystemTicks,
+    ) -> Self::Item<'w, 's> {
+        let ptr =
+            unsafe { World::resource_ptr::<Events<T>>(world) }.unwrap_or_else(|e| panic!("{e}"));
+        EventWriter::new(unsafe { &mut *ptr })
+    }
+}
+
+unsafe impl<T: Event> SystemParam for EventReader<'_, '_, T> {
+    type State = usize;
+    type Item<'w, 's> = EventReader<'w, 's, T>;
+
+    fn init_state(_world: &mut World, access: &mut SystemAccess) -> usize {
+        access.resources.add_read(get_resource_id::<Events<T>>());
+        0
+    }
+
+    unsafe fn get_param<'w, 's>(
+        state: &'s mut usize,
+        world: *mut World,
+        _ticks: SystemTicks,
+    ) -> Self::Item<'w, 's> {
+        let ptr =
+            unsafe { World::resource_ptr::<Events<T>>(world) }.unwrap_or_else(|e| panic!("{e}"));
+        EventReader::new(unsafe { &*ptr }, state)
+    }
+}
+
+/**
+ * This is a trait for functions that can be turned into systems
+ *
+ * It is implemented for every function whose arguments are all
+ * `SystemParam`s. `Marker` only exists to tell the impls apart.
+ */
+pub trait SystemParamFunction<Marker>: Send + Sync + 'static {
+    type Param: SystemParam;
+
+    fn run(&mut self, param: SystemParamItem<Self::Param>);
+}
+
+/**
+ * A system built from a function, see `IntoSystem`
+ */
+pub struct FunctionSystem<Marker, F: SystemParamFunction<Marker>> {
+    func: F,
+    state: Option<<F::Param as SystemParam>::State>,
+    access: SystemAccess,
+    last_run: u32,
+    _marker: PhantomData<fn() -> Marker>,
+}
+
+impl<Marker: 'static, F: SystemParamFunction<Marker>> System for FunctionSystem<Marker, F> {
+    fn name(&self) -> &str {
+        std::any::type_name::<F>()
+    }
+
+    fn access(&self) -> &SystemAccess {
+        &self.access
+    }
+
+    fn initialize(&mut self, world: &mut World) {
+        if self.state.is_none() {
+            self.state = Some(F::Param::init_state(world, &mut self.access));
+        }
+    }
+
+    unsafe fn run(&mut self, world: *mut World) {
+        let state = self
+            .state
+            .as_mut()
+            .expect("System was run before being initialized");
+        let ticks = SystemTicks {
+            last_run: self.last_run,
+            this_run: unsafe { (*world).increment_change_tick() },
+        };
+        let param = unsafe { F::Param::get_param(state, world, ticks) };
+        self.func.run(param);
+        self.last_run = ticks.this_run;
+    }
+
+    fn apply(&mut self, world: &mut World) {
+        if let Some(state) = &mut self.state {
+            F::Param::apply(state, world);
+        }
+    }
+
+    fn check_change_tick(&mut self, change_tick: u32) {
+        check_tick(&mut self.last_run, change_tick);
+    }
+}
+
+/**
+ * Conversion into a `System`
+ *
+ * Any function taking only system parameters can be passed where
+ * a system is expected.
+ */
+pub trait IntoSystem<Marker> {
+    type System: System + 'static;
+
+    fn into_system(self) -> Self::System;
+}
+
+#[doc(hidden)]
+pub struct IsFunctionSystem;
+
+impl<Marker: 'static, F: SystemParamFunction<Marker>> IntoSystem<(IsFunctionSystem, Marker)> for F {
+    type System = FunctionSystem<Marker, F>;
+
+    fn into_system(self) -> Self::System {
+        FunctionSystem {
+            func: self,
+            state: None,
+            access: SystemAccess::default(),
+            last_run: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+macro_rules! impl_system_function {
+    ($($param:ident),*) => {
+        #[allow(non_snake_case, clippy::unused_unit)]
+        unsafe impl<$($param: SystemParam),*> SystemParam for ($($param,)*) {
+            type State = ($($param::State,)*);
+            type Item<'w, 's> = ($($param::Item<'w, 's>,)*);
+
+            fn init_state(_world: &mut World, _access: &mut SystemAccess) -> Self::State {
+                ($($param::init_state(_world, _access),)*)
+            }
+
+            unsafe fn get_param<'w, 's>(state: &'s mut Self::State, _world: *mut World, _ticks: SystemTicks) -> Self::Item<'w, 's> {
+                let ($($param,)*) = state;
+                ($(unsafe { $param::get_param($param, _world, _ticks) },)*)
+            }
+
+            fn apply(state: &mut Self::State, _world: &mut World) {
+                let ($($param,)*) = state;
+                $($param::apply($param, _world);)*
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<Func, $($param: SystemParam),*> SystemParamFunction<fn($($param,)*)> for Func
+        where
+            Func: Send + Sync + 'static,
+            for<'a> &'a mut Func: FnMut($($param),*) + FnMut($(SystemParamItem<$param>),*),
+        {
+            type Param = ($($param,)*);
+
+            fn run(&mut self, param: SystemParamItem<($($param,)*)>) {
+                #[allow(clippy::too_many_arguments)]
+                fn call_inner<$($param),*>(mut f: impl FnMut($($param),*), $($param: $param),*) {
+                    f($($param),*)
+                }
+                let ($($param,)*) = param;
+                call_inner(self, $($param),*)
+            }
+        }
+    };
+}
+
+impl_system_function!();
+impl_system_function!(A);
+impl_system_function!(A, B);
+impl_system_function!(A, B, C);
+impl_system_function!(A, B, C, D);
+impl_system_function!(A, B, C, D, E);
+impl_system_function!(A, B, C, D, E, F);
+impl_system_function!(A, B, C, D, E, F, G);
+impl_system_function!(A, B, C, D, E, F, G, H);
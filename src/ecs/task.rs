@@ -1 +1,213 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 
+use tokio::runtime::{Handle, Runtime};
+use tokio::task::AbortHandle;
+
+use crate::prelude::*;
+
+type Completion = Box<dyn FnOnce(&mut World) + Send>;
+
+struct Finished {
+    owner: Option<EntityId>,
+    apply: Completion,
+}
+
+/**
+ * A handle to a task spawned through `Tasks`
+ */
+#[derive(Clone, Debug)]
+pub struct TaskHandle {
+    abort: AbortHandle,
+}
+
+impl TaskHandle {
+    /**
+     * Stops the task. Its result will never reach the world.
+     */
+    pub fn cancel(&self) {
+        self.abort.abort();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.abort.is_finished()
+    }
+}
+
+/**
+ * A resource for running async work alongside the ECS
+ *
+ * Tasks run on a tokio runtime. When a task finishes its result is
+ * queued and handed to the world the next time `Tasks::apply` runs,
+ * at the end of every frame, so systems never observe a half-applied
+ * result. Tasks spawned for an owning entity are cancelled when that
+ * entity is despawned.
+ */
+#[derive(Resource)]
+pub struct Tasks {
+    runtime: Option<Runtime>,
+    handle: Handle,
+    finished: Arc<Mutex<Vec<Finished>>>,
+    owned: Mutex<HashMap<EntityId, Vec<AbortHandle>>>,
+}
+
+impl Tasks {
+    /**
+     * Creates a task pool with its own multi-threaded runtime.
+     */
+    pub fn new() -> Self {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .thread_name("peano-task")
+            .enable_all()
+            .build()
+            .expect("Failed to start the task runtime");
+        let mut tasks = Self::with_handle(runtime.handle().clone());
+        tasks.runtime = Some(runtime);
+        tasks
+    }
+
+    /**
+     * Creates a task pool running on an existing runtime.
+     */
+    pub fn with_handle(handle: Handle) -> Self {
+        Self {
+            runtime: None,
+            handle,
+            finished: Arc::new(Mutex::new(Vec::new())),
+            owned: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    fn spawn_inner<F, T>(
+        &self,
+        owner: Option<EntityId>,
+        future: F,
+        on_complete: impl FnOnce(&mut World, T) + Send + 'static,
+    ) -> TaskHandle
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let finished = self.finished.clone();
+        let join = self.handle.spawn(async move {
+            let value = future.await;
+            finished.lock().unwrap().push(Finished {
+                owner,
+                apply: Box::new(move |world| on_complete(world, value)),
+            });
+        });
+
+        let abort = join.abort_handle();
+        if let Some(owner) = owner {
+            let mut owned = self.owned.lock().unwrap();
+            let handles = owned.entry(owner).or_default();
+            handles.retain(|h| !h.is_finished());
+            handles.push(abort.clone());
+        }
+        TaskHandle { abort }
+    }
+
+    /**
+     * Spawns a task and runs `on_complete` with its result on a later frame.
+     */
+    pub fn spawn<F, T>(
+        &self,
+        future: F,
+        on_complete: impl FnOnce(&mut World, T) + Send + 'static,
+    ) -> TaskHandle
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.spawn_inner(None, future, on_complete)
+    }
+
+    /**
+     * Spawns a task owned by an entity.
+     *
+     * `on_complete` runs on a later frame with the owner and the result,
+     * unless the owner was despawned first, which cancels the task.
+     */
+    pub fn spawn_owned<F, T>(
+        &self,
+        owner: EntityId,
+        future: F,
+        on_complete: impl FnOnce(&mut World, EntityId, T) + Send + 'static,
+    ) -> TaskHandle
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.spawn_inner(Some(owner), future, move |world, value| {
+            on_complete(world, owner, value)
+        })
+    }
+
+    /**
+     * Spawns a task whose result is set as a component on its owner.
+     */
+    pub fn spawn_component<F, C>(&self, owner: EntityId, future: F) -> TaskHandle
+    where
+        F: Future<Output = C> + Send + 'static,
+        C: Component,
+    {
+        self.spawn_owned(owner, future, |world, owner, component| {
+            if let Some(mut entity) = world.get_mut(owner) {
+                entity.set_component(component);
+            }
+        })
+    }
+
+    /**
+     * Cancels every task owned by an entity.
+     */
+    pub fn cancel_owned(&self, owner: EntityId) {
+        if let Some(handles) = self.owned.lock().unwrap().remove(&owner) {
+            for handle in handles {
+                handle.abort();
+            }
+        }
+    }
+
+    /**
+     * Hands the results of finished tasks to the world.
+     *
+     * Results of tasks whose owner no longer exists are dropped.
+     * Does nothing if the world has no `Tasks` resource.
+     */
+    pub fn apply(world: &mut World) {
+        let Ok(tasks) = world.resource::<Tasks>() else {
+            return;
+        };
+        let finished = std::mem::take(&mut *tasks.finished.lock().unwrap());
+        tasks.owned.lock().unwrap().retain(|_, handles| {
+            handles.retain(|h| !h.is_finished());
+            !handles.is_empty()
+        });
+
+        for task in finished {
+            if task.owner.is_none_or(|owner| world.contains(owner)) {
+                (task.apply)(world);
+            }
+        }
+    }
+}
+
+impl Default for Tasks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Tasks {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
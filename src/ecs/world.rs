@@ -1,8 +1,628 @@
-use super::{Entity, Resource, mappings::Mapping, system::System};
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use super::{
+    EntityId, EntityMut, EntityRef, Event, Resource,
+    change_detection::CHECK_TICK_THRESHOLD,
+    event::Events,
+    for_each_event,
+    hierarchy::{Children, Parent},
+    mappings::table::Table,
+    observer::Observers,
+    query::{QueryData, QueryFilter, QueryIter, QueryState, ReadOnlyQueryData},
+    register_generic_event, resource_count,
+    scheduler::{Schedule, SystemConfig},
+    system::IntoSystem,
+    task::Tasks,
+    try_get_resource_id,
+};
+
+/**
+ * The ways accessing a resource on a `World` can fail
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceError {
+    /**
+     * The type was never registered with the `Resource` derive
+     */
+    NotRegistered(&'static str),
+    /**
+     * The resource is registered but not present in the world
+     */
+    Missing(&'static str),
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotRegistered(name) => {
+                write!(
+                    f,
+                    "resource `{name}` is not registered, derive `Resource` for it"
+                )
+            }
+            Self::Missing(name) => write!(f, "resource `{name}` does not exist in the world"),
+        }
+    }
+}
+
+impl std::error::Error for ResourceError {}
+
+/**
+ * A slot in the entity storage of a `World`.
+ *
+ * The generation outlives the entity so that the next
+ * entity spawned in this slot gets a fresh handle.
+ */
+struct EntitySlot {
+    generation: u32,
+    alive: bool,
+}
 
 pub struct World {
-    entities: Vec<Entity>,
-    resources: Vec<Option<Box<dyn Resource>>>,
-    mappings: Vec<Option<Box<dyn Mapping>>>,
-    systems: Vec<Box<dyn System>>,
+    entities: Vec<EntitySlot>,
+    free: Vec<u32>,
+    len: usize,
+    reserved: AtomicU32,
+    last_cleared: u32,
+    last_check: u32,
+    table: Table,
+    resources: Vec<Option<Box<UnsafeCell<dyn Resource>>>>,
+    schedule: Schedule,
+    observers: Observers,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self {
+            entities: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+            reserved: AtomicU32::new(0),
+            last_cleared: 0,
+            last_check: 0,
+            table: Table::new(),
+            resources: Vec::new(),
+            schedule: Schedule::new(),
+            observers: Observers::default(),
+        }
+    }
+
+    /**
+     * Spawns a new entity with no components.
+     *
+     * Slots of despawned entities are reused with a bumped generation.
+     */
+    pub fn spawn(&mut self) -> EntityId {
+        self.flush_entities();
+        let id = match self.free.pop() {
+            Some(index) => EntityId::new(index, self.entities[index as usize].generation),
+            None => {
+                let index = u32::try_from(self.entities.len()).expect("Too many entities");
+                self.entities.push(EntitySlot {
+                    generation: 0,
+                    alive: false,
+                });
+                EntityId::new(index, 0)
+            }
+        };
+
+        self.entities[id.index() as usize].alive = true;
+        self.table.insert_entity(id);
+        self.len += 1;
+        id
+    }
+
+    /**
+     * Reserves an id for an entity without spawning it.
+     *
+     * Only needs shared access, so systems can reserve ids while running.
+     * The entity comes alive, with no components, at the next `flush_entities`.
+     */
+    pub fn reserve_entity(&self) -> EntityId {
+        let offset = self.reserved.fetch_add(1, Ordering::Relaxed);
+        let index = u32::try_from(self.entities.len())
+            .ok()
+            .and_then(|len| len.checked_add(offset))
+            .expect("Too many entities");
+        EntityId::new(index, 0)
+    }
+
+    /**
+     * Spawns every entity reserved with `reserve_entity`.
+     */
+    pub fn flush_entities(&mut self) {
+        let reserved = std::mem::take(self.reserved.get_mut());
+        for _ in 0..reserved {
+            let id = EntityId::new(self.entities.len() as u32, 0);
+            self.entities.push(EntitySlot {
+                generation: 0,
+                alive: true,
+            });
+            self.table.insert_entity(id);
+        }
+        self.len += reserved as usize;
+    }
+
+    /**
+     * Despawns an entity, dropping all of its components.
+     *
+     * The entity is removed from its parent's `Children` and its own
+     * children lose their `Parent`, see `despawn_recursive` to despawn
+     * them too. If the handle is stale or the entity does not exist,
+     * it returns false.
+     */
+    pub fn despawn(&mut self, id: EntityId) -> bool {
+        if !self.contains(id) {
+            return false;
+        }
+
+        if let Ok(tasks) = self.resource::<Tasks>() {
+            tasks.cancel_owned(id);
+        }
+
+        for component in self.table.components_of(id) {
+            if !self.contains(id) {
+                return true;
+            }
+            self.trigger_removal(id, component);
+        }
+        if !self.contains(id) {
+            return true;
+        }
+
+        // The hooks and observers of the entity's own components already
+        // ran above, but its parent and children are told through theirs.
+        if let Some(parent) = self.table.remove::<Parent>(id) {
+            self.remove_child(parent.get(), id);
+        }
+        if let Some(children) = self.table.remove::<Children>(id) {
+            for child in children.into_vec() {
+                if let Some(mut child) = self.get_mut(child)
+                    && child.get_component::<Parent>().map(Parent::get) == Some(id)
+                {
+                    child.remove_component::<Parent>();
+                }
+            }
+        }
+
+        self.table.remove_entity(id);
+        let slot = &mut self.entities[id.index() as usize];
+        slot.alive = false;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index());
+        self.len -= 1;
+        true
+    }
+
+    /**
+     * Despawns an entity along with all of its descendants.
+     *
+     * If the handle is stale or the entity does not exist, it returns false.
+     */
+    pub fn despawn_recursive(&mut self, id: EntityId) -> bool {
+        if !self.contains(id) {
+            return false;
+        }
+
+        self.detach(id);
+        let mut stack = vec![id];
+        while let Some(entity) = stack.pop() {
+            let children = self
+                .get_mut(entity)
+                .and_then(|mut entity| entity.remove_component::<Children>());
+            if let Some(children) = children {
+                stack.extend(children.into_vec());
+            }
+            self.despawn(entity);
+        }
+        true
+    }
+
+    /**
+     * Makes `child` a child of `parent`, detaching it from its
+     * previous parent if it had one.
+     *
+     * Returns false if either entity does not exist or if `parent`
+     * is `child` itself or one of its descendants.
+     */
+    pub fn set_parent(&mut self, child: EntityId, parent: EntityId) -> bool {
+        if !self.contains(child) || !self.contains(parent) || self.is_ancestor(child, parent) {
+            return false;
+        }
+
+        self.detach(child);
+        // The removal hooks may have despawned either entity.
+        if !self.contains(child) || !self.contains(parent) {
+            return false;
+        }
+        self.get_mut(child)
+            .unwrap()
+            .set_component(Parent::new(parent));
+        let Some(mut entity) = self.get_mut(parent) else {
+            return false;
+        };
+        if let Some(mut children) = entity.get_component_mut::<Children>() {
+            children.push(child);
+        } else {
+            entity.set_component(Children::new(vec![child]));
+        }
+        true
+    }
+
+    /**
+     * Detaches an entity from its parent, making it a root.
+     *
+     * If the entity has no parent, it returns false.
+     */
+    pub fn remove_parent(&mut self, child: EntityId) -> bool {
+        self.contains(child) && self.detach(child)
+    }
+
+    /**
+     * Checks if `ancestor` is `id` or one of its ancestors.
+     */
+    fn is_ancestor(&self, ancestor: EntityId, id: EntityId) -> bool {
+        let mut current = Some(id);
+        while let Some(entity) = current {
+            if entity == ancestor {
+                return true;
+            }
+            current = self.table.get::<Parent>(entity).map(Parent::get);
+        }
+        false
+    }
+
+    fn detach(&mut self, child: EntityId) -> bool {
+        let parent = self
+            .get_mut(child)
+            .and_then(|mut entity| entity.remove_component::<Parent>());
+        let Some(parent) = parent else {
+            return false;
+        };
+        self.remove_child(parent.get(), child);
+        true
+    }
+
+    /**
+     * Removes `child` from the `Children` of `parent`, dropping the
+     * component once it is empty.
+     */
+    fn remove_child(&mut self, parent: EntityId, child: EntityId) {
+        let Some(mut entity) = self.get_mut(parent) else {
+            return;
+        };
+        let Some(mut children) = entity.get_component_mut::<Children>() else {
+            return;
+        };
+        children.remove(child);
+        if children.is_empty() {
+            entity.remove_component::<Children>();
+        }
+    }
+
+    /**
+     * Checks if the handle refers to a live entity.
+     */
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.entities
+            .get(id.index() as usize)
+            .is_some_and(|slot| slot.alive && slot.generation == id.generation())
+    }
+
+    /**
+     * Gets an entity from the world.
+     *
+     * If the handle is stale or the entity does not exist, it returns None.
+     */
+    pub fn get(&self, id: EntityId) -> Option<EntityRef<'_>> {
+        self.contains(id).then(|| EntityRef::new(id, &self.table))
+    }
+
+    /**
+     * Gets a mutable entity from the world.
+     *
+     * If the handle is stale or the entity does not exist, it returns None.
+     */
+    pub fn get_mut(&mut self, id: EntityId) -> Option<EntityMut<'_>> {
+        if !self.contains(id) {
+            return None;
+        }
+        Some(EntityMut::new(id, self))
+    }
+
+    /**
+     * Gets the archetype table holding every entity's components.
+     */
+    pub fn table(&self) -> &Table {
+        &self.table
+    }
+
+    pub(crate) fn table_mut(&mut self) -> &mut Table {
+        &mut self.table
+    }
+
+    pub(crate) fn observers_mut(&mut self) -> &mut Observers {
+        &mut self.observers
+    }
+
+    /**
+     * The tick changes made outside of systems are stamped with
+     */
+    pub fn change_tick(&self) -> u32 {
+        self.table.change_tick()
+    }
+
+    /**
+     * Claims the current change tick and advances the counter, see `Table`.
+     */
+    pub fn increment_change_tick(&self) -> u32 {
+        self.table.increment_change_tick()
+    }
+
+    /**
+     * Adds an `Events<T>` resource if the world does not have one yet.
+     *
+     * Fails if `T` was not registered with the `Event` derive.
+     * Generic events are only updated once they were added here.
+     */
+    pub fn add_event<T: Event>(&mut self) -> Result<&mut Events<T>, ResourceError> {
+        if let Some(registration) = T::generic_registration() {
+            register_generic_event(registration);
+        }
+        self.init_resource::<Events<T>>()
+    }
+
+    /**
+     * Sends an event without going through a system.
+     *
+     * If the world has no `Events<T>`, the event is handed back as an error.
+     */
+    pub fn send_event<T: Event>(&mut self, event: T) -> Result<(), T> {
+        match self.resource_mut::<Events<T>>() {
+            Ok(events) => {
+                events.send(event);
+                Ok(())
+            }
+            Err(_) => Err(event),
+        }
+    }
+
+    /**
+     * Swaps the buffers of every registered event type, see `Events`.
+     *
+     * Called once per frame by `run_systems`.
+     */
+    pub fn update_events(&mut self) {
+        let mut updates = Vec::new();
+        for_each_event(|registration| updates.push(registration.update));
+        for update in updates {
+            update(self);
+        }
+    }
+
+    /**
+     * Forgets component removals older than the previous call.
+     *
+     * Called once per frame, so every removal stays visible to
+     * `RemovedComponents` for two frames. Every `CHECK_TICK_THRESHOLD`
+     * ticks it also runs `check_change_ticks` and returns the tick it
+     * checked against, so schedules kept outside the world can be
+     * checked too.
+     */
+    pub fn clear_trackers(&mut self) -> Option<u32> {
+        let tick = self.change_tick();
+        self.table.clear_removed(self.last_cleared);
+        self.last_cleared = tick;
+        (tick.wrapping_sub(self.last_check) >= CHECK_TICK_THRESHOLD)
+            .then(|| self.check_change_ticks())
+    }
+
+    /**
+     * Clamps every change tick older than `MAX_CHANGE_AGE` in the world,
+     * in its schedule and in its observers.
+     *
+     * Keeps ticks from getting so old that `is_newer` would see them
+     * as new once the counter wraps around. Returns the tick the ages
+     * were measured from.
+     */
+    pub fn check_change_ticks(&mut self) -> u32 {
+        let tick = self.change_tick();
+        self.table.check_change_ticks(tick);
+        self.schedule.check_change_ticks(tick);
+        self.observers.check_change_ticks(tick);
+        self.last_check = tick;
+        tick
+    }
+
+    /**
+     * Moves the change tick ahead as if `ticks` systems had run.
+     */
+    pub fn advance_change_tick(&self, ticks: u32) {
+        self.table.advance_change_tick(ticks);
+    }
+
+    fn resource_id<T: Resource>() -> Result<usize, ResourceError> {
+        try_get_resource_id::<T>().ok_or(ResourceError::NotRegistered(std::any::type_name::<T>()))
+    }
+
+    /**
+     * Inserts a resource.
+     *
+     * If a resource of the same type existed, it is replaced and returned.
+     */
+    pub fn insert_resource<T: Resource>(
+        &mut self,
+        resource: T,
+    ) -> Result<Option<T>, ResourceError> {
+        let id = Self::resource_id::<T>()?;
+        if self.resources.len() <= id {
+            self.resources
+                .resize_with(resource_count().max(id + 1), || None);
+        }
+        let old = self.resources[id].replace(Box::new(UnsafeCell::new(resource)));
+        Ok(old.map(|old| unsafe { downcast_resource(old) }))
+    }
+
+    /**
+     * Inserts the default value of a resource if it does not exist yet.
+     *
+     * Returns the resource either way.
+     */
+    pub fn init_resource<T: Resource + Default>(&mut self) -> Result<&mut T, ResourceError> {
+        if !self.contains_resource::<T>() {
+            self.insert_resource(T::default())?;
+        }
+        self.resource_mut::<T>()
+    }
+
+    /**
+     * Removes a resource from the world and returns it.
+     */
+    pub fn remove_resource<T: Resource>(&mut self) -> Result<T, ResourceError> {
+        let id = Self::resource_id::<T>()?;
+        let cell = self
+            .resources
+            .get_mut(id)
+            .and_then(Option::take)
+            .ok_or(ResourceError::Missing(std::any::type_name::<T>()))?;
+        Ok(unsafe { downcast_resource(cell) })
+    }
+
+    pub fn contains_resource<T: Resource>(&self) -> bool {
+        self.resource::<T>().is_ok()
+    }
+
+    /**
+     * Gets a resource.
+     */
+    pub fn resource<T: Resource>(&self) -> Result<&T, ResourceError> {
+        let ptr = unsafe { Self::resource_ptr::<T>(self) }?;
+        Ok(unsafe { &*ptr })
+    }
+
+    /**
+     * Gets a resource mutably.
+     */
+    pub fn resource_mut<T: Resource>(&mut self) -> Result<&mut T, ResourceError> {
+        let ptr = unsafe { Self::resource_ptr::<T>(self) }?;
+        Ok(unsafe { &mut *ptr })
+    }
+
+    /**
+     * Gets a pointer to a resource through a raw world pointer.
+     *
+     * # Safety
+     *
+     * `world` must be valid and the caller must not create
+     * references that alias the resource mutably.
+     */
+    pub(crate) unsafe fn resource_ptr<T: Resource>(
+        world: *const World,
+    ) -> Result<*mut T, ResourceError> {
+        let resources = unsafe { &(*world).resources };
+        let cell = resources
+            .get(Self::resource_id::<T>()?)
+            .and_then(Option::as_ref)
+            .ok_or(ResourceError::Missing(std::any::type_name::<T>()))?;
+        let ptr = cell.get() as *mut T;
+        debug_assert!(cell_is::<T>(cell));
+        Ok(ptr)
+    }
+
+    /**
+     * Adds a system to be run by `run_systems`.
+     */
+    pub fn add_system<M>(&mut self, system: impl IntoSystem<M>) -> SystemConfig<'_> {
+        self.schedule.add_system(system)
+    }
+
+    /**
+     * The schedule `run_systems` runs.
+     */
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    /**
+     * Adds a sync point after every system added so far, see `Schedule`.
+     */
+    pub fn add_sync_point(&mut self) {
+        self.schedule.add_sync_point();
+    }
+
+    /**
+     * Runs every added system once, see `Schedule`, then applies the
+     * results of finished tasks.
+     */
+    pub fn run_systems(&mut self) {
+        let mut schedule = std::mem::take(&mut self.schedule);
+        schedule.run(self);
+        self.schedule = schedule;
+        Tasks::apply(self);
+        self.update_events();
+        self.clear_trackers();
+    }
+
+    /**
+     * Iterates over every entity matching a read-only query.
+     */
+    pub fn query<D: ReadOnlyQueryData, F: QueryFilter>(&self) -> QueryIter<'_, D, F> {
+        QueryState::<D, F>::new().iter(&self.table)
+    }
+
+    /**
+     * Iterates mutably over every entity matching a query.
+     */
+    pub fn query_mut<D: QueryData, F: QueryFilter>(&mut self) -> QueryIter<'_, D, F> {
+        QueryState::<D, F>::new().iter_mut(&mut self.table)
+    }
+
+    /**
+     * Returns the number of live entities.
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Iterates over all live entities.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = EntityRef<'_>> {
+        self.entities
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.alive)
+            .map(|(index, slot)| {
+                EntityRef::new(EntityId::new(index as u32, slot.generation), &self.table)
+            })
+    }
+}
+
+/**
+ * Unboxes a resource known to be a `T`.
+ *
+ * # Safety
+ *
+ * The resource must have been stored from a `T`.
+ */
+unsafe fn downcast_resource<T: Resource>(cell: Box<UnsafeCell<dyn Resource>>) -> T {
+    debug_assert!(cell_is::<T>(&cell));
+    let raw = Box::into_raw(cell) as *mut UnsafeCell<T>;
+    unsafe { Box::from_raw(raw) }.into_inner()
+}
+
+fn cell_is<T: Resource>(cell: &UnsafeCell<dyn Resource>) -> bool {
+    unsafe { (*cell.get()).as_any().is::<T>() }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
 }
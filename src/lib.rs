@@ -1,3 +1,4 @@
+pub mod app;
 pub mod audio;
 pub mod ecs;
 pub mod input;
@@ -0,0 +1,122 @@
+use std::ops::Mul;
+
+use serde::{Deserialize, Serialize};
+
+use super::matrix::{Mat3, Mat4};
+use super::quat::Quat;
+use super::vector::{Vec3, Vec4};
+
+/**
+ * A 3D affine transform: a linear part followed by a translation
+ *
+ * It is a `Mat4` without the last row, which is always `[0, 0, 0, 1]`,
+ * and is cheaper to combine and invert.
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct Affine3 {
+    pub matrix3: Mat3,
+    pub translation: Vec3,
+}
+
+impl Affine3 {
+    pub const IDENTITY: Self = Self::new(Mat3::IDENTITY, Vec3::ZERO);
+
+    pub const fn new(matrix3: Mat3, translation: Vec3) -> Self {
+        Self {
+            matrix3,
+            translation,
+        }
+    }
+
+    /**
+     * The transform that scales, then rotates, then translates.
+     */
+    pub fn from_scale_rotation_translation(scale: Vec3, rotation: Quat, translation: Vec3) -> Self {
+        let m = Mat3::from_quat(rotation);
+        Self::new(
+            Mat3::from_cols(m.x_axis * scale.x, m.y_axis * scale.y, m.z_axis * scale.z),
+            translation,
+        )
+    }
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self::new(Mat3::IDENTITY, translation)
+    }
+
+    pub fn from_quat(rotation: Quat) -> Self {
+        Self::new(Mat3::from_quat(rotation), Vec3::ZERO)
+    }
+
+    pub fn from_mat3(matrix3: Mat3) -> Self {
+        Self::new(matrix3, Vec3::ZERO)
+    }
+
+    pub fn transform_point3(&self, p: Vec3) -> Vec3 {
+        self.matrix3 * p + self.translation
+    }
+
+    /**
+     * Transforms a direction, ignoring translation.
+     */
+    pub fn transform_vector3(&self, v: Vec3) -> Vec3 {
+        self.matrix3 * v
+    }
+
+    /**
+     * The inverse transform.
+     *
+     * A singular linear part gives a non-finite result.
+     */
+    pub fn inverse(&self) -> Self {
+        let matrix3 = self.matrix3.inverse();
+        Self::new(matrix3, -(matrix3 * self.translation))
+    }
+
+    pub fn abs_diff_eq(&self, rhs: &Self, max_abs_diff: f32) -> bool {
+        self.matrix3.abs_diff_eq(&rhs.matrix3, max_abs_diff)
+            && self.translation.abs_diff_eq(rhs.translation, max_abs_diff)
+    }
+}
+
+impl Default for Affine3 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Affine3 {
+    type Output = Self;
+
+    /**
+     * Combines two transforms, `rhs` is applied first.
+     */
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.matrix3 * rhs.matrix3,
+            self.transform_point3(rhs.translation),
+        )
+    }
+}
+
+impl From<Affine3> for Mat4 {
+    fn from(a: Affine3) -> Self {
+        let m = a.matrix3;
+        Mat4::from_cols(
+            m.x_axis.extend(0.0),
+            m.y_axis.extend(0.0),
+            m.z_axis.extend(0.0),
+            a.translation.extend(1.0),
+        )
+    }
+}
+
+impl From<Affine3> for [Vec4; 3] {
+    /**
+     * The rows of the affine matrix, the layout shaders usually take.
+     */
+    fn from(a: Affine3) -> Self {
+        let m = Mat4::from(a);
+        [m.row(0), m.row(1), m.row(2)]
+    }
+}
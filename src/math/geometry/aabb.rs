@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Bounded, Ray, RayCast, RayHit, Sphere, SupportMap};
+use crate::math::affine::Affine3;
+use crate::math::vector::Vec3;
+
+/**
+ * An axis-aligned bounding box
+ *
+ * `min` must not be greater than `max` on any axis. A box with
+ * no extent, like one made with `from_point`, is still valid.
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub const fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_center_half_extents(center: Vec3, half_extents: Vec3) -> Self {
+        Self::new(center - half_extents, center + half_extents)
+    }
+
+    /**
+     * Creates a box with no extent around a point.
+     */
+    pub const fn from_point(p: Vec3) -> Self {
+        Self::new(p, p)
+    }
+
+    /**
+     * The smallest box enclosing every point.
+     *
+     * If there are no points, it returns None.
+     */
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = Self::from_point(points.next()?);
+        Some(points.fold(first, |aabb, p| aabb.include(p)))
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.size().element_product()
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let s = self.size();
+        2.0 * (s.x * s.y + s.y * s.z + s.z * s.x)
+    }
+
+    /**
+     * The eight corners, ordered by the bits of their index:
+     * bit 0 picks `max.x`, bit 1 `max.y` and bit 2 `max.z`.
+     */
+    pub fn corners(&self) -> [Vec3; 8] {
+        std::array::from_fn(|i| {
+            Vec3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            )
+        })
+    }
+
+    /**
+     * The smallest box enclosing both boxes.
+     */
+    pub fn merge(&self, other: &Aabb) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /**
+     * The smallest box enclosing the box and a point.
+     */
+    pub fn include(&self, p: Vec3) -> Self {
+        Self::new(self.min.min(p), self.max.max(p))
+    }
+
+    /**
+     * The box grown by `amount` on every side.
+     */
+    pub fn grow(&self, amount: f32) -> Self {
+        Self::new(
+            self.min - Vec3::splat(amount),
+            self.max + Vec3::splat(amount),
+        )
+    }
+
+    /**
+     * The overlap of both boxes, or None if they do not overlap.
+     */
+    pub fn intersection(&self, other: &Aabb) -> Option<Self> {
+        self.intersects(other)
+            .then(|| Self::new(self.min.max(other.min), self.max.min(other.max)))
+    }
+
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        self.min.x <= p.x
+            && p.x <= self.max.x
+            && self.min.y <= p.y
+            && p.y <= self.max.y
+            && self.min.z <= p.z
+            && p.z <= self.max.z
+    }
+
+    /**
+     * Checks if the other box lies entirely inside this one.
+     */
+    pub fn contains(&self, other: &Aabb) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    /**
+     * Checks if both boxes overlap. Touching boxes overlap.
+     */
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+            && self.min.z <= other.max.z
+            && other.min.z <= self.max.z
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.distance_squared_to_point(sphere.center) <= sphere.radius * sphere.radius
+    }
+
+    /**
+     * The point of the box closest to `p`.
+     *
+     * Points inside the box are their own closest point.
+     */
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        p.clamp(self.min, self.max)
+    }
+
+    /**
+     * The squared distance from a point to the box.
+     *
+     * Points inside the box have a distance of zero.
+     */
+    pub fn distance_squared_to_point(&self, p: Vec3) -> f32 {
+        self.closest_point(p).distance_squared(p)
+    }
+
+    /**
+     * The box enclosing this one after an affine transform.
+     */
+    pub fn transformed(&self, transform: &Affine3) -> Self {
+        let center = transform.transform_point3(self.center());
+        let half_extents = transform.matrix3.abs() * self.half_extents();
+        Self::from_center_half_extents(center, half_extents)
+    }
+}
+
+impl RayCast for Aabb {
+    fn cast_ray(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        let mut enter = f32::NEG_INFINITY;
+        let mut exit = max_distance;
+        let mut normal = -ray.direction;
+
+        for axis in 0..3 {
+            let (o, d) = (ray.origin[axis], ray.direction[axis]);
+            let (min, max) = (self.min[axis], self.max[axis]);
+            if d == 0.0 {
+                if o < min || o > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let recip = 1.0 / d;
+            let (near, far) = if d > 0.0 {
+                ((min - o) * recip, (max - o) * recip)
+            } else {
+                ((max - o) * recip, (min - o) * recip)
+            };
+            if near > enter {
+                enter = near;
+                normal = Vec3::ZERO;
+                normal[axis] = -d.signum();
+            }
+            exit = exit.min(far);
+            if enter > exit {
+                return None;
+            }
+        }
+
+        if enter <= 0.0 {
+            // The ray starts inside.
+            return (exit >= 0.0).then(|| RayHit::new(ray, 0.0, -ray.direction));
+        }
+        Some(RayHit::new(ray, enter, normal))
+    }
+}
+
+impl Bounded for Aabb {
+    fn aabb(&self) -> Aabb {
+        *self
+    }
+
+    fn bounding_sphere(&self) -> Sphere {
+        Sphere::new(self.center(), self.half_extents().length())
+    }
+}
+
+impl SupportMap for Aabb {
+    fn support(&self, direction: Vec3) -> Vec3 {
+        Vec3::new(
+            if direction.x >= 0.0 {
+                self.max.x
+            } else {
+                self.min.x
+            },
+            if direction.y >= 0.0 {
+                self.max.y
+            } else {
+                self.min.y
+            },
+            if direction.z >= 0.0 {
+                self.max.z
+            } else {
+                self.min.z
+            },
+        )
+    }
+}
@@ -0,0 +1,145 @@
+use std::ops::Range;
+
+use super::Aabb;
+
+const LEAF_SIZE: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Node {
+    aabb: Aabb,
+    /**
+     * The first item of a leaf, or the left child of a branch,
+     * whose right child comes right after it.
+     */
+    first: u32,
+    /**
+     * The number of items of a leaf, zero for a branch.
+     */
+    count: u32,
+}
+
+/**
+ * A bounding volume hierarchy over a fixed set of boxes
+ *
+ * It is built once, top down, by splitting the boxes at the median
+ * of their centers along the longest axis. Boxes are referred to by
+ * their index in the slice the hierarchy was built from.
+ */
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    items: Vec<u32>,
+    aabbs: Vec<Aabb>,
+}
+
+impl Bvh {
+    pub fn new(aabbs: &[Aabb]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(aabbs.len().div_ceil(LEAF_SIZE) * 2),
+            items: (0..aabbs.len() as u32).collect(),
+            aabbs: aabbs.to_vec(),
+        };
+        if !aabbs.is_empty() {
+            bvh.nodes.push(Node {
+                aabb: aabbs[0],
+                first: 0,
+                count: 0,
+            });
+            bvh.split(0, 0..aabbs.len(), aabbs);
+        }
+        bvh
+    }
+
+    fn split(&mut self, node: usize, range: Range<usize>, aabbs: &[Aabb]) {
+        let items = &mut self.items[range.clone()];
+        let aabb = items
+            .iter()
+            .map(|&i| aabbs[i as usize])
+            .reduce(|a, b| a.merge(&b))
+            .unwrap();
+
+        if items.len() <= LEAF_SIZE {
+            self.nodes[node] = Node {
+                aabb,
+                first: range.start as u32,
+                count: items.len() as u32,
+            };
+            return;
+        }
+
+        let centers = items
+            .iter()
+            .map(|&i| aabbs[i as usize].center())
+            .fold(None, |bounds: Option<Aabb>, c| {
+                Some(bounds.map_or(Aabb::from_point(c), |b| b.include(c)))
+            })
+            .unwrap();
+        let size = centers.size();
+        let axis = if size.x >= size.y && size.x >= size.z {
+            0
+        } else if size.y >= size.z {
+            1
+        } else {
+            2
+        };
+        let middle = items.len() / 2;
+        items.select_nth_unstable_by(middle, |&a, &b| {
+            let a = aabbs[a as usize].center()[axis];
+            let b = aabbs[b as usize].center()[axis];
+            a.total_cmp(&b)
+        });
+
+        let left = self.nodes.len();
+        let placeholder = self.nodes[node];
+        self.nodes.extend([placeholder; 2]);
+        self.nodes[node] = Node {
+            aabb,
+            first: left as u32,
+            count: 0,
+        };
+        let middle = range.start + middle;
+        self.split(left, range.start..middle, aabbs);
+        self.split(left + 1, middle..range.end, aabbs);
+    }
+
+    pub fn len(&self) -> usize {
+        self.aabbs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aabbs.is_empty()
+    }
+
+    /**
+     * The box around every box of the hierarchy.
+     */
+    pub fn aabb(&self) -> Option<Aabb> {
+        self.nodes.first().map(|node| node.aabb)
+    }
+
+    /**
+     * Calls `visit` with the index of every box overlapping `aabb`.
+     */
+    pub fn query(&self, aabb: &Aabb, mut visit: impl FnMut(usize)) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.aabb.intersects(aabb) {
+                continue;
+            }
+            let first = node.first as usize;
+            if node.count == 0 {
+                stack.extend([first + 1, first]);
+            } else {
+                for &item in &self.items[first..first + node.count as usize] {
+                    if self.aabbs[item as usize].intersects(aabb) {
+                        visit(item as usize);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Aabb, Bounded, Ray, RayCast, RayHit, Segment, Sphere, SupportMap};
+use crate::math::scalar;
+use crate::math::vector::Vec3;
+
+/**
+ * All points within `radius` of the segment from `a` to `b`
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Capsule {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub radius: f32,
+}
+
+impl Capsule {
+    pub const fn new(a: Vec3, b: Vec3, radius: f32) -> Self {
+        Self { a, b, radius }
+    }
+
+    pub fn segment(&self) -> Segment {
+        Segment::new(self.a, self.b)
+    }
+
+    pub fn volume(&self) -> f32 {
+        let r = self.radius;
+        scalar::PI * r * r * (4.0 / 3.0 * r + self.segment().length())
+    }
+
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        self.segment().distance_squared_to_point(p) <= self.radius * self.radius
+    }
+
+    /**
+     * The point of the capsule closest to `p`.
+     *
+     * Points inside the capsule are their own closest point.
+     */
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        Sphere::new(self.segment().closest_point(p), self.radius).closest_point(p)
+    }
+
+    /**
+     * The distance from a point to the capsule.
+     *
+     * Points inside the capsule have a distance of zero.
+     */
+    pub fn distance_to_point(&self, p: Vec3) -> f32 {
+        (scalar::sqrt(self.segment().distance_squared_to_point(p)) - self.radius).max(0.0)
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        let radii = self.radius + sphere.radius;
+        self.segment().distance_squared_to_point(sphere.center) <= radii * radii
+    }
+
+    pub fn intersects(&self, other: &Capsule) -> bool {
+        let radii = self.radius + other.radius;
+        self.segment().distance_squared_to_segment(&other.segment()) <= radii * radii
+    }
+}
+
+impl RayCast for Capsule {
+    fn cast_ray(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        if self.contains_point(ray.origin) {
+            return Some(RayHit::new(ray, 0.0, -ray.direction));
+        }
+
+        // The origin is outside, so the first hit is the nearest of
+        // the two end spheres and the side of the cylinder.
+        let caps = [self.a, self.b]
+            .into_iter()
+            .filter_map(|c| Sphere::new(c, self.radius).cast_ray(ray, max_distance));
+
+        let axis = self.b - self.a;
+        let axis_squared = axis.length_squared();
+        let offset = ray.origin - self.a;
+        let along = axis.dot(ray.direction);
+        let a = axis_squared - along * along;
+        let b = axis_squared * offset.dot(ray.direction) - axis.dot(offset) * along;
+        let c = axis_squared * (offset.length_squared() - self.radius * self.radius)
+            - axis.dot(offset) * axis.dot(offset);
+        let discriminant = b * b - a * c;
+        let side = (a > f32::EPSILON * axis_squared && discriminant >= 0.0)
+            .then(|| (-b - scalar::sqrt(discriminant)) / a)
+            .filter(|&t| {
+                let y = axis.dot(offset) + t * along;
+                (0.0..=max_distance).contains(&t) && y > 0.0 && y < axis_squared
+            })
+            .map(|t| {
+                let point = ray.at(t);
+                let normal = point - self.segment().closest_point(point);
+                RayHit::new(ray, t, normal.try_normalize().unwrap_or(-ray.direction))
+            });
+
+        caps.chain(side)
+            .min_by(|x, y| x.distance.total_cmp(&y.distance))
+    }
+}
+
+impl Bounded for Capsule {
+    fn aabb(&self) -> Aabb {
+        self.segment().aabb().grow(self.radius)
+    }
+
+    fn bounding_sphere(&self) -> Sphere {
+        let segment = self.segment();
+        Sphere::new(segment.center(), segment.length() * 0.5 + self.radius)
+    }
+}
+
+impl SupportMap for Capsule {
+    fn support(&self, direction: Vec3) -> Vec3 {
+        self.segment().support(direction) + direction.normalize_or_zero() * self.radius
+    }
+}
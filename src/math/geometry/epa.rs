@@ -0,0 +1,251 @@
+use super::gjk::{self, Gjk, SupportPoint};
+use super::{SupportMap, Triangle};
+use crate::math::vector::Vec3;
+
+const MAX_ITERATIONS: usize = 64;
+
+/**
+ * How deep two overlapping convex shapes are inside each other
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Penetration {
+    /**
+     * The unit direction to move `b` in to separate the shapes,
+     * pointing from `a` towards `b`.
+     */
+    pub normal: Vec3,
+    pub depth: f32,
+    /**
+     * The point of `a` deepest inside `b`.
+     */
+    pub point_a: Vec3,
+    /**
+     * The point of `b` deepest inside `a`, `depth` behind `point_a`
+     * along the normal.
+     */
+    pub point_b: Vec3,
+}
+
+/**
+ * How deep two convex shapes overlap, the shortest way to pull
+ * them apart.
+ *
+ * It returns None if the shapes are separated, or if both are
+ * flat in the same plane so they can only touch.
+ */
+pub fn penetration(a: &impl SupportMap, b: &impl SupportMap) -> Option<Penetration> {
+    let Gjk::Overlapping(simplex) = gjk::run(a, b) else {
+        return None;
+    };
+    let tetrahedron = enclose(a, b, simplex)?;
+    Some(expand(a, b, tetrahedron))
+}
+
+/**
+ * Grows the simplex GJK ended with into a tetrahedron, as GJK
+ * stops early when the origin is on a vertex, edge or face.
+ */
+fn enclose(
+    a: &impl SupportMap,
+    b: &impl SupportMap,
+    mut simplex: Vec<SupportPoint>,
+) -> Option<Vec<SupportPoint>> {
+    const DIRECTIONS: [Vec3; 6] = [
+        Vec3::X,
+        Vec3::Y,
+        Vec3::Z,
+        Vec3::new(-1.0, 0.0, 0.0),
+        Vec3::new(0.0, -1.0, 0.0),
+        Vec3::new(0.0, 0.0, -1.0),
+    ];
+
+    let scale = |simplex: &[SupportPoint], p: Vec3| {
+        simplex
+            .iter()
+            .map(|s| s.w.length_squared())
+            .fold(p.length_squared().max(1.0), f32::max)
+    };
+
+    if simplex.len() == 1 {
+        let first = simplex[0].w;
+        let p = DIRECTIONS
+            .iter()
+            .map(|&d| SupportPoint::new(a, b, d))
+            .find(|p| (p.w - first).length_squared() > 1e-10 * scale(&simplex, p.w))?;
+        simplex.push(p);
+    }
+
+    if simplex.len() == 2 {
+        let line = (simplex[1].w - simplex[0].w).normalize();
+        let u = line.any_orthonormal_vector();
+        let v = line.cross(u);
+        let p = [u, v, -u, -v, u + v, -u - v]
+            .into_iter()
+            .map(|d| SupportPoint::new(a, b, d))
+            .find(|p| {
+                let offset = (p.w - simplex[0].w).reject_from(line);
+                offset.length_squared() > 1e-10 * scale(&simplex, p.w)
+            })?;
+        simplex.push(p);
+    }
+
+    if simplex.len() == 3 {
+        let w = [simplex[0].w, simplex[1].w, simplex[2].w];
+        let normal = (w[1] - w[0]).cross(w[2] - w[0]).normalize();
+        let height = |p: &SupportPoint| normal.dot(p.w - w[0]).abs();
+        let above = SupportPoint::new(a, b, normal);
+        let below = SupportPoint::new(a, b, -normal);
+        let p = if height(&above) >= height(&below) {
+            above
+        } else {
+            below
+        };
+        if height(&p) * height(&p) <= 1e-10 * scale(&simplex, p.w) {
+            return None;
+        }
+        simplex.push(p);
+    }
+
+    Some(simplex)
+}
+
+struct Face {
+    indices: [usize; 3],
+    normal: Vec3,
+    distance: f32,
+}
+
+impl Face {
+    /**
+     * The face with its normal pointing away from `inside`, or None
+     * if it has no area.
+     */
+    fn new(vertices: &[SupportPoint], [i, j, k]: [usize; 3], inside: Vec3) -> Option<Self> {
+        let (wi, wj, wk) = (vertices[i].w, vertices[j].w, vertices[k].w);
+        let normal = (wj - wi).cross(wk - wi).try_normalize()?;
+        let (indices, normal) = if normal.dot(wi - inside) < 0.0 {
+            ([i, k, j], -normal)
+        } else {
+            ([i, j, k], normal)
+        };
+        Some(Self {
+            indices,
+            normal,
+            distance: normal.dot(wi),
+        })
+    }
+}
+
+/**
+ * Runs the Expanding Polytope Algorithm.
+ *
+ * Starting from a tetrahedron inside the Minkowski difference that
+ * holds the origin, it keeps pushing out the face closest to the
+ * origin until it reaches the boundary of the difference. That face
+ * is the shortest way out.
+ */
+fn expand(
+    a: &impl SupportMap,
+    b: &impl SupportMap,
+    mut vertices: Vec<SupportPoint>,
+) -> Penetration {
+    let inside = vertices.iter().map(|v| v.w).sum::<Vec3>() / 4.0;
+    let mut faces: Vec<Face> = [[0, 1, 2], [0, 1, 3], [0, 2, 3], [1, 2, 3]]
+        .into_iter()
+        .filter_map(|indices| Face::new(&vertices, indices, inside))
+        .collect();
+    let size = vertices.iter().map(|v| v.w.length()).fold(0.0, f32::max);
+
+    let tolerance = 1e-4 * size;
+    // How far the difference reaches past the plane of a face.
+    let past =
+        |face: &Face| SupportPoint::new(a, b, face.normal).w.dot(face.normal) - face.distance;
+    let closest = |faces: &[Face]| {
+        (0..faces.len())
+            .min_by(|&i, &j| faces[i].distance.total_cmp(&faces[j].distance))
+            .unwrap()
+    };
+
+    for _ in 0..MAX_ITERATIONS {
+        let first = closest(&faces);
+        if past(&faces[first]) <= tolerance {
+            break;
+        }
+        let p = SupportPoint::new(a, b, faces[first].normal);
+
+        // The faces `p` can see are replaced by a fan of faces from
+        // the edges around them, the horizon, to `p`. They are found
+        // by walking out from the closest face, so rounding can not
+        // pick a face that would tear a hole elsewhere.
+        let sees = |face: &Face| face.normal.dot(p.w - vertices[face.indices[0]].w) > 0.0;
+        let mut visible = vec![false; faces.len()];
+        visible[first] = true;
+        let mut stack = vec![first];
+        let mut horizon: Vec<[usize; 2]> = Vec::new();
+        while let Some(f) = stack.pop() {
+            let [i, j, k] = faces[f].indices;
+            for [s, t] in [[i, j], [j, k], [k, i]] {
+                let neighbor = faces.iter().position(|face| {
+                    let [x, y, z] = face.indices;
+                    [[x, y], [y, z], [z, x]].contains(&[t, s])
+                });
+                match neighbor {
+                    Some(g) if visible[g] => {}
+                    Some(g) if sees(&faces[g]) => {
+                        visible[g] = true;
+                        stack.push(g);
+                    }
+                    _ => horizon.push([s, t]),
+                }
+            }
+        }
+
+        let index = vertices.len();
+        vertices.push(p);
+        let Some(fan) = horizon
+            .iter()
+            .map(|&[i, j]| Face::new(&vertices, [i, j, index], inside))
+            .collect::<Option<Vec<_>>>()
+        else {
+            // Rounding left a sliver, stop with what we have.
+            vertices.pop();
+            break;
+        };
+        let mut visible = visible.into_iter();
+        faces.retain(|_| !visible.next().unwrap());
+        faces.extend(fan);
+    }
+
+    // Flat parts of the difference are split into several faces at
+    // about the same distance, only one of them holds the point
+    // closest to the origin.
+    let first = closest(&faces);
+    let nearest = faces[first].distance;
+    let (face, weights) = faces
+        .iter()
+        .enumerate()
+        .filter(|&(index, face)| {
+            index == first || face.distance <= nearest + tolerance && past(face) <= tolerance
+        })
+        .map(|(_, face)| {
+            let [i, j, k] = face.indices.map(|i| vertices[i].w);
+            let foot = face.normal * face.distance;
+            let triangle = Triangle::new(i, j, k);
+            let weights = triangle.closest_weights(foot);
+            let miss = (i * weights[0] + j * weights[1] + k * weights[2]).distance_squared(foot);
+            (face, weights, miss)
+        })
+        .min_by(|x, y| x.2.total_cmp(&y.2))
+        .map(|(face, weights, _)| (face, weights))
+        .unwrap();
+    let [i, j, k] = face.indices.map(|i| vertices[i]);
+    let depth = face.distance.max(0.0);
+    let point_a = i.a * weights[0] + j.a * weights[1] + k.a * weights[2];
+    let point_b = i.b * weights[0] + j.b * weights[1] + k.b * weights[2];
+    Penetration {
+        normal: face.normal,
+        depth,
+        point_a,
+        point_b,
+    }
+}
@@ -0,0 +1,261 @@
+use super::{Segment, SupportMap, Triangle};
+use crate::math::vector::Vec3;
+
+const MAX_ITERATIONS: usize = 64;
+
+/**
+ * Checks if two convex shapes overlap.
+ */
+pub fn intersects(a: &impl SupportMap, b: &impl SupportMap) -> bool {
+    closest_points(a, b).is_none()
+}
+
+/**
+ * The closest pair of points of two convex shapes, the first on `a`.
+ *
+ * If the shapes overlap, it returns None.
+ */
+pub fn closest_points(a: &impl SupportMap, b: &impl SupportMap) -> Option<(Vec3, Vec3)> {
+    match run(a, b) {
+        Gjk::Separated(p, q) => Some((p, q)),
+        Gjk::Overlapping(_) => None,
+    }
+}
+
+/**
+ * The distance between two convex shapes, zero if they overlap.
+ */
+pub fn distance(a: &impl SupportMap, b: &impl SupportMap) -> f32 {
+    closest_points(a, b).map_or(0.0, |(p, q)| p.distance(q))
+}
+
+/**
+ * A point of the Minkowski difference, with the points of both
+ * shapes it came from.
+ */
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SupportPoint {
+    pub w: Vec3,
+    pub a: Vec3,
+    pub b: Vec3,
+}
+
+impl SupportPoint {
+    pub fn new(a: &impl SupportMap, b: &impl SupportMap, direction: Vec3) -> Self {
+        let p = a.support(direction);
+        let q = b.support(-direction);
+        Self {
+            w: p - q,
+            a: p,
+            b: q,
+        }
+    }
+}
+
+/**
+ * The outcome of `run`, overlapping shapes keep the last simplex
+ * as a starting point for EPA.
+ */
+pub(crate) enum Gjk {
+    Separated(Vec3, Vec3),
+    Overlapping(Vec<SupportPoint>),
+}
+
+/**
+ * Runs the Gilbert-Johnson-Keerthi distance algorithm.
+ *
+ * It searches the Minkowski difference `a - b` for the point closest
+ * to the origin, using only the support functions of the shapes.
+ * They overlap if the difference holds the origin.
+ */
+pub(crate) fn run(a: &impl SupportMap, b: &impl SupportMap) -> Gjk {
+    let first = SupportPoint::new(a, b, Vec3::X);
+    let mut simplex = vec![first];
+    let mut weights = vec![1.0];
+    let mut v = first.w;
+
+    for _ in 0..MAX_ITERATIONS {
+        let v_squared = v.length_squared();
+        if touches(v, &simplex) {
+            return Gjk::Overlapping(simplex);
+        }
+
+        let p = SupportPoint::new(a, b, -v);
+        // No point of the difference is much closer than `v`.
+        if v_squared - v.dot(p.w) <= 1e-5 * v_squared
+            || simplex.iter().any(|s| s.w.abs_diff_eq(p.w, 1e-7))
+        {
+            break;
+        }
+
+        simplex.push(p);
+        let Some((closest, reduced)) = solve(&simplex) else {
+            // `v` still separates the shapes if `p` did not cross to
+            // the far side of the origin, so only rounding made the
+            // sliver of a tetrahedron seem to hold it.
+            if v.dot(p.w) > 0.0 {
+                simplex.pop();
+                break;
+            }
+            return Gjk::Overlapping(simplex);
+        };
+        simplex = reduced.iter().map(|&(i, _)| simplex[i]).collect();
+        weights = reduced.iter().map(|&(_, weight)| weight).collect();
+        // Stop once the simplex no longer gets closer, rounding
+        // could otherwise keep it cycling. Precision is used up by
+        // then, so the origin only has to be roughly touched.
+        if closest.length_squared() >= v_squared {
+            if touches_roughly(v, &simplex) {
+                return Gjk::Overlapping(simplex);
+            }
+            break;
+        }
+        v = closest;
+    }
+
+    if touches(v, &simplex) {
+        return Gjk::Overlapping(simplex);
+    }
+    let (mut p, mut q) = (Vec3::ZERO, Vec3::ZERO);
+    for (s, weight) in simplex.iter().zip(&weights) {
+        p += s.a * *weight;
+        q += s.b * *weight;
+    }
+    Gjk::Separated(p, q)
+}
+
+/**
+ * Checks if `v` is as good as the origin, relative to the size of
+ * the simplex so it works at any scale.
+ */
+fn touches(v: Vec3, simplex: &[SupportPoint]) -> bool {
+    v.length_squared() <= 1e-10 * scale(simplex)
+}
+
+fn touches_roughly(v: Vec3, simplex: &[SupportPoint]) -> bool {
+    v.length_squared() <= 1e-8 * scale(simplex)
+}
+
+fn scale(simplex: &[SupportPoint]) -> f32 {
+    simplex
+        .iter()
+        .map(|s| s.w.length_squared())
+        .fold(1.0, f32::max)
+}
+
+/**
+ * Finds the point of the simplex closest to the origin, as the
+ * indices and weights of the fewest vertices that span it.
+ *
+ * If a tetrahedron holds the origin, it returns None.
+ */
+fn solve(simplex: &[SupportPoint]) -> Option<(Vec3, Vec<(usize, f32)>)> {
+    let w: Vec<Vec3> = simplex.iter().map(|s| s.w).collect();
+    let reduced = match w.len() {
+        1 => vec![(0, 1.0)],
+        2 => {
+            let t = Segment::new(w[0], w[1]).closest_parameter(Vec3::ZERO);
+            vec![(0, 1.0 - t), (1, t)]
+        }
+        3 => {
+            let weights = closest_weights([w[0], w[1], w[2]]);
+            weights.into_iter().enumerate().collect()
+        }
+        _ => {
+            const FACES: [[usize; 4]; 4] = [[0, 1, 2, 3], [0, 1, 3, 2], [0, 2, 3, 1], [1, 2, 3, 0]];
+            // A nearly flat tetrahedron has no reliable sides, so
+            // it is searched like a pair of triangles.
+            let edges = [w[1] - w[0], w[2] - w[0], w[3] - w[0]];
+            let volume = edges[0].dot(edges[1].cross(edges[2]));
+            let flat = volume.abs() <= 1e-4 * edges.iter().map(|e| e.length()).product::<f32>();
+
+            let mut best: Option<(f32, Vec<(usize, f32)>)> = None;
+            for [i, j, k, l] in FACES {
+                let normal = (w[j] - w[i]).cross(w[k] - w[i]);
+                // Only faces with the origin on the far side from the
+                // fourth vertex can hold the closest point.
+                if !flat && normal.dot(-w[i]) * normal.dot(w[l] - w[i]) > 0.0 {
+                    continue;
+                }
+                let weights = closest_weights([w[i], w[j], w[k]]);
+                let point = w[i] * weights[0] + w[j] * weights[1] + w[k] * weights[2];
+                let distance = point.length_squared();
+                if best.as_ref().is_none_or(|(d, _)| distance < *d) {
+                    best = Some((
+                        distance,
+                        vec![(i, weights[0]), (j, weights[1]), (k, weights[2])],
+                    ));
+                }
+            }
+            best?.1
+        }
+    };
+
+    let reduced: Vec<_> = reduced
+        .into_iter()
+        .filter(|&(_, weight)| weight > 0.0)
+        .collect();
+    let closest = reduced.iter().map(|&(i, weight)| w[i] * weight).sum();
+    Some((closest, reduced))
+}
+
+/**
+ * The weights of the point of a triangle closest to the origin.
+ *
+ * It is `Triangle::closest_weights` in `f64`. Near the origin the
+ * simplex is small next to its vertices, and for long and thin
+ * triangles `f32` loses the weights to cancellation, so GJK would
+ * stall before reaching the origin.
+ */
+fn closest_weights(triangle: [Vec3; 3]) -> [f32; 3] {
+    let [a, b, c] = triangle.map(|p| p.to_array().map(f64::from));
+    let sub = |p: [f64; 3], q: [f64; 3]| [p[0] - q[0], p[1] - q[1], p[2] - q[2]];
+    let dot = |p: [f64; 3], q: [f64; 3]| p[0] * q[0] + p[1] * q[1] + p[2] * q[2];
+    let ab = sub(b, a);
+    let ac = sub(c, a);
+    let d1 = -dot(ab, a);
+    let d2 = -dot(ac, a);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return [1.0, 0.0, 0.0];
+    }
+
+    let d3 = -dot(ab, b);
+    let d4 = -dot(ac, b);
+    if d3 >= 0.0 && d4 <= d3 {
+        return [0.0, 1.0, 0.0];
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let v = d1 / (d1 - d3);
+        return [1.0 - v, v, 0.0].map(|x| x as f32);
+    }
+
+    let d5 = -dot(ab, c);
+    let d6 = -dot(ac, c);
+    if d6 >= 0.0 && d5 <= d6 {
+        return [0.0, 0.0, 1.0];
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let w = d2 / (d2 - d6);
+        return [1.0 - w, 0.0, w].map(|x| x as f32);
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return [0.0, 1.0 - w, w].map(|x| x as f32);
+    }
+
+    let sum = va + vb + vc;
+    if sum <= 0.0 {
+        // No area, the triangle is a segment.
+        let [a, b, c] = triangle;
+        return Triangle::new(a, b, c).closest_weights(Vec3::ZERO);
+    }
+    let v = vb / sum;
+    let w = vc / sum;
+    [1.0 - v - w, v, w].map(|x| x as f32)
+}
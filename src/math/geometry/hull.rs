@@ -0,0 +1,385 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Aabb, Bounded, Plane, Ray, RayCast, RayHit, Sphere, SupportMap, Triangle, gjk};
+use crate::math::affine::Affine3;
+use crate::math::vector::Vec3;
+
+/**
+ * The ways building a `ConvexHull` can fail
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HullError {
+    /**
+     * A hull needs at least four points
+     */
+    TooFewPoints,
+    /**
+     * The points lie on a plane, so the hull has no volume
+     */
+    Degenerate,
+}
+
+impl fmt::Display for HullError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooFewPoints => write!(f, "a convex hull needs at least four points"),
+            Self::Degenerate => write!(f, "the points of the convex hull lie on a plane"),
+        }
+    }
+}
+
+impl std::error::Error for HullError {}
+
+/**
+ * The smallest convex shape enclosing a set of points
+ *
+ * It keeps the points on the hull, the triangles of its surface
+ * wound counter-clockwise seen from outside, and their planes.
+ * It is serialized as its points and rebuilt when deserialized.
+ */
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(try_from = "Vec<Vec3>", into = "Vec<Vec3>")]
+pub struct ConvexHull {
+    points: Vec<Vec3>,
+    faces: Vec<[u32; 3]>,
+    planes: Vec<Plane>,
+}
+
+impl ConvexHull {
+    /**
+     * Builds the hull of a set of points.
+     *
+     * Points within a small tolerance of the hull are left out.
+     */
+    pub fn new(points: &[Vec3]) -> Result<Self, HullError> {
+        if points.len() < 4 {
+            return Err(HullError::TooFewPoints);
+        }
+        let aabb = Aabb::from_points(points.iter().copied()).unwrap();
+        let tolerance = aabb.size().max_element() * 1e-5;
+        let [i0, i1, i2, i3] = initial_tetrahedron(points, tolerance)?;
+
+        let mut faces: Vec<([usize; 3], Plane)> = Vec::new();
+        let center = (points[i0] + points[i1] + points[i2] + points[i3]) * 0.25;
+        for [a, b, c] in [[i0, i1, i2], [i0, i3, i1], [i1, i3, i2], [i2, i3, i0]] {
+            let plane = face_plane(points[a], points[b], points[c]).unwrap();
+            if plane.signed_distance(center) > 0.0 {
+                faces.push(([a, c, b], plane.flip()));
+            } else {
+                faces.push(([a, b, c], plane));
+            }
+        }
+
+        // Adding the farthest points first leaves fewer thin faces.
+        let mut order: Vec<_> = (0..points.len()).collect();
+        order.sort_by(|&a, &b| {
+            let distance = |i: usize| points[i].distance_squared(center);
+            distance(b).total_cmp(&distance(a))
+        });
+
+        for p in order {
+            let point = points[p];
+            if faces
+                .iter()
+                .all(|(_, plane)| plane.signed_distance(point) <= tolerance)
+            {
+                continue;
+            }
+            // Once the point is clearly outside, every face it is in front
+            // of has to go, or nearly flat faces would leave dents.
+            let (visible, kept): (Vec<_>, Vec<_>) = faces
+                .into_iter()
+                .partition(|(_, plane)| plane.signed_distance(point) > 0.0);
+            faces = kept;
+
+            // The horizon is made of the edges of visible faces
+            // whose other face stays, and is joined to the point.
+            let edges: HashSet<_> = visible
+                .iter()
+                .flat_map(|([a, b, c], _)| [(*a, *b), (*b, *c), (*c, *a)])
+                .collect();
+            for ([a, b, c], plane) in &visible {
+                for (from, to) in [(*a, *b), (*b, *c), (*c, *a)] {
+                    if edges.contains(&(to, from)) {
+                        continue;
+                    }
+                    let new_plane = face_plane(points[from], points[to], point).unwrap_or(*plane);
+                    faces.push(([from, to, p], new_plane));
+                }
+            }
+        }
+
+        // Keep only the points on the hull.
+        let mut remap = vec![u32::MAX; points.len()];
+        let mut hull_points = Vec::new();
+        let faces = faces
+            .into_iter()
+            .map(|(face, plane)| {
+                let face = face.map(|i| {
+                    if remap[i] == u32::MAX {
+                        remap[i] = hull_points.len() as u32;
+                        hull_points.push(points[i]);
+                    }
+                    remap[i]
+                });
+                (face, plane)
+            })
+            .collect::<Vec<_>>();
+
+        let (faces, planes) = faces.into_iter().unzip();
+        Ok(Self {
+            points: hull_points,
+            faces,
+            planes,
+        })
+    }
+
+    /**
+     * The points on the hull.
+     */
+    pub fn points(&self) -> &[Vec3] {
+        &self.points
+    }
+
+    /**
+     * The triangles of the surface, as indices into `points`.
+     */
+    pub fn faces(&self) -> &[[u32; 3]] {
+        &self.faces
+    }
+
+    /**
+     * The planes of the faces, facing out.
+     */
+    pub fn planes(&self) -> &[Plane] {
+        &self.planes
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = Triangle> + '_ {
+        self.faces.iter().map(|face| {
+            let [a, b, c] = face.map(|i| self.points[i as usize]);
+            Triangle::new(a, b, c)
+        })
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.tetrahedra().map(|(volume, _)| volume).sum()
+    }
+
+    /**
+     * The center of mass of the hull as a solid of uniform density.
+     */
+    pub fn centroid(&self) -> Vec3 {
+        let (volume, moment) = self
+            .tetrahedra()
+            .fold((0.0, Vec3::ZERO), |(v, m), (volume, centroid)| {
+                (v + volume, m + centroid * volume)
+            });
+        moment / volume
+    }
+
+    /**
+     * Splits the hull into tetrahedra fanning out from its first
+     * point, as pairs of volume and centroid.
+     */
+    fn tetrahedra(&self) -> impl Iterator<Item = (f32, Vec3)> + '_ {
+        let origin = self.points[0];
+        self.triangles().map(move |t| {
+            let volume = (t.a - origin).dot((t.b - origin).cross(t.c - origin)) / 6.0;
+            (volume, (origin + t.a + t.b + t.c) * 0.25)
+        })
+    }
+
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(p) <= 0.0)
+    }
+
+    /**
+     * The point of the hull closest to `p`.
+     *
+     * Points inside the hull are their own closest point.
+     */
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        if self.contains_point(p) {
+            return p;
+        }
+        self.triangles()
+            .map(|t| t.closest_point(p))
+            .min_by(|a, b| a.distance_squared(p).total_cmp(&b.distance_squared(p)))
+            .unwrap()
+    }
+
+    pub fn distance_squared_to_point(&self, p: Vec3) -> f32 {
+        self.closest_point(p).distance_squared(p)
+    }
+
+    /**
+     * Checks if the hull overlaps another convex shape, see `gjk`.
+     */
+    pub fn intersects(&self, other: &impl SupportMap) -> bool {
+        gjk::intersects(self, other)
+    }
+
+    /**
+     * The hull after an affine transform, which must be invertible.
+     */
+    pub fn transformed(&self, transform: &Affine3) -> Self {
+        let points: Vec<_> = self
+            .points
+            .iter()
+            .map(|&p| transform.transform_point3(p))
+            .collect();
+        let normal_matrix = transform.matrix3.inverse().transpose();
+        let mirrored = transform.matrix3.determinant() < 0.0;
+
+        let faces = self
+            .faces
+            .iter()
+            .map(|&[a, b, c]| if mirrored { [a, c, b] } else { [a, b, c] })
+            .collect();
+        let planes = self
+            .planes
+            .iter()
+            .zip(&self.faces)
+            .map(|(plane, face)| {
+                Plane::from_point_normal(points[face[0] as usize], normal_matrix * plane.normal)
+            })
+            .collect();
+        Self {
+            points,
+            faces,
+            planes,
+        }
+    }
+}
+
+/**
+ * Picks four points spanning a tetrahedron of more than `tolerance`
+ * in every direction.
+ */
+fn initial_tetrahedron(points: &[Vec3], tolerance: f32) -> Result<[usize; 4], HullError> {
+    let farthest = |distance: &dyn Fn(Vec3) -> f32| {
+        let (index, &point) = points
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| distance(**a).total_cmp(&distance(**b)))
+            .unwrap();
+        (index, distance(point))
+    };
+
+    let (i0, _) = farthest(&|p| -p.x);
+    let (i1, d1) = farthest(&|p| p.distance(points[i0]));
+    if d1 <= tolerance {
+        return Err(HullError::Degenerate);
+    }
+    let line = super::Segment::new(points[i0], points[i1]);
+    let axis = line.direction();
+    let (i2, d2) = farthest(&|p| (p - line.a).reject_from(axis).length());
+    if d2 <= tolerance {
+        return Err(HullError::Degenerate);
+    }
+    let plane = Plane::from_points(points[i0], points[i1], points[i2]).unwrap();
+    let (i3, d3) = farthest(&|p| plane.signed_distance(p).abs());
+    if d3 <= tolerance {
+        return Err(HullError::Degenerate);
+    }
+    Ok([i0, i1, i2, i3])
+}
+
+/**
+ * `Plane::from_points` in double precision. Faces of a hull can be
+ * long and thin, and their normals lose too much to rounding in `f32`.
+ */
+fn face_plane(a: Vec3, b: Vec3, c: Vec3) -> Option<Plane> {
+    let [a, b, c] = [a, b, c].map(|p| p.to_array().map(f64::from));
+    let u = [0, 1, 2].map(|i| b[i] - a[i]);
+    let v = [0, 1, 2].map(|i| c[i] - a[i]);
+    let n = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    if length == 0.0 {
+        return None;
+    }
+    let n = n.map(|x| x / length);
+    let distance = n[0] * a[0] + n[1] * a[1] + n[2] * a[2];
+    let normal = Vec3::from_array(n.map(|x| x as f32));
+    Some(Plane::new(normal, distance as f32))
+}
+
+impl TryFrom<Vec<Vec3>> for ConvexHull {
+    type Error = HullError;
+
+    fn try_from(points: Vec<Vec3>) -> Result<Self, HullError> {
+        Self::new(&points)
+    }
+}
+
+impl From<ConvexHull> for Vec<Vec3> {
+    fn from(hull: ConvexHull) -> Self {
+        hull.points
+    }
+}
+
+impl RayCast for ConvexHull {
+    fn cast_ray(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        let mut enter = f32::NEG_INFINITY;
+        let mut exit = max_distance;
+        let mut normal = -ray.direction;
+
+        for plane in &self.planes {
+            let denom = plane.normal.dot(ray.direction);
+            let distance = plane.signed_distance(ray.origin);
+            if denom == 0.0 {
+                if distance > 0.0 {
+                    return None;
+                }
+                continue;
+            }
+            let t = -distance / denom;
+            if denom < 0.0 {
+                if t > enter {
+                    enter = t;
+                    normal = plane.normal;
+                }
+            } else {
+                exit = exit.min(t);
+            }
+            if enter > exit {
+                return None;
+            }
+        }
+
+        if enter <= 0.0 {
+            return (exit >= 0.0).then(|| RayHit::new(ray, 0.0, -ray.direction));
+        }
+        Some(RayHit::new(ray, enter, normal))
+    }
+}
+
+impl Bounded for ConvexHull {
+    fn aabb(&self) -> Aabb {
+        Aabb::from_points(self.points.iter().copied()).unwrap()
+    }
+
+    fn bounding_sphere(&self) -> Sphere {
+        Sphere::from_points(&self.points).unwrap()
+    }
+}
+
+impl SupportMap for ConvexHull {
+    fn support(&self, direction: Vec3) -> Vec3 {
+        self.points
+            .iter()
+            .copied()
+            .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+            .unwrap()
+    }
+}
@@ -0,0 +1,59 @@
+pub mod aabb;
+pub mod bvh;
+pub mod capsule;
+pub mod epa;
+pub mod gjk;
+pub mod hull;
+pub mod obb;
+pub mod plane;
+pub mod ray;
+pub mod segment;
+pub mod sphere;
+pub mod triangle;
+
+pub use aabb::Aabb;
+pub use bvh::Bvh;
+pub use capsule::Capsule;
+pub use epa::Penetration;
+pub use hull::{ConvexHull, HullError};
+pub use obb::Obb;
+pub use plane::Plane;
+pub use ray::{Ray, RayHit};
+pub use segment::Segment;
+pub use sphere::Sphere;
+pub use triangle::Triangle;
+
+use super::vector::Vec3;
+
+/**
+ * This is a trait for shapes that can be hit by a `Ray`
+ */
+pub trait RayCast {
+    /**
+     * Finds where the ray first enters the shape, if it does so
+     * within `max_distance`.
+     *
+     * A ray starting inside a solid shape hits it at distance zero,
+     * with a normal pointing against the ray.
+     */
+    fn cast_ray(&self, ray: &Ray, max_distance: f32) -> Option<RayHit>;
+}
+
+/**
+ * This is a trait for shapes that can be enclosed in bounding volumes
+ */
+pub trait Bounded {
+    fn aabb(&self) -> Aabb;
+
+    fn bounding_sphere(&self) -> Sphere;
+}
+
+/**
+ * This is a trait for convex shapes described by their support function
+ *
+ * The support point is the point of the shape farthest along a direction.
+ * Any two such shapes can be tested against each other with GJK.
+ */
+pub trait SupportMap {
+    fn support(&self, direction: Vec3) -> Vec3;
+}
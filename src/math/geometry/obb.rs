@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Aabb, Bounded, Ray, RayCast, RayHit, Sphere, SupportMap};
+use crate::math::matrix::Mat3;
+use crate::math::quat::Quat;
+use crate::math::vector::Vec3;
+
+/**
+ * A box rotated around its center
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Obb {
+    pub center: Vec3,
+    pub half_extents: Vec3,
+    pub rotation: Quat,
+}
+
+impl Obb {
+    pub const fn new(center: Vec3, half_extents: Vec3, rotation: Quat) -> Self {
+        Self {
+            center,
+            half_extents,
+            rotation,
+        }
+    }
+
+    /**
+     * The unit axes of the box, its local X, Y and Z.
+     */
+    pub fn axes(&self) -> [Vec3; 3] {
+        let m = Mat3::from_quat(self.rotation);
+        [m.x_axis, m.y_axis, m.z_axis]
+    }
+
+    /**
+     * Turns a point into the box's space, where it is an `Aabb`
+     * centered on the origin.
+     */
+    pub fn to_local(&self, p: Vec3) -> Vec3 {
+        self.rotation.inverse() * (p - self.center)
+    }
+
+    pub fn from_local(&self, p: Vec3) -> Vec3 {
+        self.rotation * p + self.center
+    }
+
+    /**
+     * The box in its own space, see `to_local`.
+     */
+    pub fn local_aabb(&self) -> Aabb {
+        Aabb::from_center_half_extents(Vec3::ZERO, self.half_extents)
+    }
+
+    /**
+     * The eight corners, in the order of `Aabb::corners`.
+     */
+    pub fn corners(&self) -> [Vec3; 8] {
+        self.local_aabb().corners().map(|c| self.from_local(c))
+    }
+
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        self.local_aabb().contains_point(self.to_local(p))
+    }
+
+    /**
+     * The point of the box closest to `p`.
+     *
+     * Points inside the box are their own closest point.
+     */
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        let local = self.to_local(p);
+        if self.local_aabb().contains_point(local) {
+            return p;
+        }
+        self.from_local(self.local_aabb().closest_point(local))
+    }
+
+    pub fn distance_squared_to_point(&self, p: Vec3) -> f32 {
+        self.local_aabb()
+            .distance_squared_to_point(self.to_local(p))
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.distance_squared_to_point(sphere.center) <= sphere.radius * sphere.radius
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.intersects(&Obb::from(*aabb))
+    }
+
+    /**
+     * Checks if both boxes overlap, by the separating axis test.
+     */
+    pub fn intersects(&self, other: &Obb) -> bool {
+        let a = self.axes();
+        let b = other.axes();
+        let offset = other.center - self.center;
+        let separates = |axis: Vec3| {
+            let project = |axes: &[Vec3; 3], half_extents: Vec3| {
+                (0..3)
+                    .map(|i| half_extents[i] * axes[i].dot(axis).abs())
+                    .sum::<f32>()
+            };
+            let reach = project(&a, self.half_extents) + project(&b, other.half_extents);
+            // Scaled so that near-parallel edge axes cannot separate by rounding.
+            offset.dot(axis).abs() > reach + 1e-5 * axis.length() * reach.max(1.0)
+        };
+
+        if a.iter().chain(&b).any(|&axis| separates(axis)) {
+            return false;
+        }
+        !a.iter()
+            .flat_map(|&u| b.iter().map(move |&v| u.cross(v)))
+            .filter(|axis| axis.length_squared() > 1e-6)
+            .any(separates)
+    }
+}
+
+impl From<Aabb> for Obb {
+    fn from(aabb: Aabb) -> Self {
+        Self::new(aabb.center(), aabb.half_extents(), Quat::IDENTITY)
+    }
+}
+
+impl RayCast for Obb {
+    fn cast_ray(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        let local = Ray {
+            origin: self.to_local(ray.origin),
+            direction: self.rotation.inverse() * ray.direction,
+        };
+        let hit = self.local_aabb().cast_ray(&local, max_distance)?;
+        Some(RayHit::new(ray, hit.distance, self.rotation * hit.normal))
+    }
+}
+
+impl Bounded for Obb {
+    fn aabb(&self) -> Aabb {
+        let half_extents = Mat3::from_quat(self.rotation).abs() * self.half_extents;
+        Aabb::from_center_half_extents(self.center, half_extents)
+    }
+
+    fn bounding_sphere(&self) -> Sphere {
+        Sphere::new(self.center, self.half_extents.length())
+    }
+}
+
+impl SupportMap for Obb {
+    fn support(&self, direction: Vec3) -> Vec3 {
+        let local = self.rotation.inverse() * direction;
+        self.from_local(self.local_aabb().support(local))
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Aabb, Obb, Ray, RayCast, RayHit, Sphere};
+use crate::math::vector::Vec3;
+
+/**
+ * The points `p` with `normal.dot(p) == distance`
+ *
+ * The normal has unit length and points to the plane's front side.
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl Plane {
+    pub const fn new(normal: Vec3, distance: f32) -> Self {
+        Self { normal, distance }
+    }
+
+    /**
+     * The plane through a point, normalizing the normal.
+     */
+    pub fn from_point_normal(point: Vec3, normal: Vec3) -> Self {
+        let normal = normal.normalize();
+        Self::new(normal, normal.dot(point))
+    }
+
+    /**
+     * The plane through three points, whose front side sees
+     * them counter-clockwise.
+     *
+     * If the points lie on a line, it returns None.
+     */
+    pub fn from_points(a: Vec3, b: Vec3, c: Vec3) -> Option<Self> {
+        let normal = (b - a).cross(c - a).try_normalize()?;
+        Some(Self::new(normal, normal.dot(a)))
+    }
+
+    /**
+     * The distance from the plane to a point, negative behind it.
+     */
+    pub fn signed_distance(&self, p: Vec3) -> f32 {
+        self.normal.dot(p) - self.distance
+    }
+
+    /**
+     * The point of the plane closest to `p`.
+     */
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        p - self.normal * self.signed_distance(p)
+    }
+
+    /**
+     * The same plane facing the other way.
+     */
+    pub fn flip(&self) -> Self {
+        Self::new(-self.normal, -self.distance)
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.signed_distance(sphere.center).abs() <= sphere.radius
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        let reach = aabb.half_extents().dot(self.normal.abs());
+        self.signed_distance(aabb.center()).abs() <= reach
+    }
+
+    pub fn intersects_obb(&self, obb: &Obb) -> bool {
+        let reach: f32 = obb
+            .axes()
+            .iter()
+            .enumerate()
+            .map(|(i, axis)| obb.half_extents[i] * axis.dot(self.normal).abs())
+            .sum();
+        self.signed_distance(obb.center).abs() <= reach
+    }
+}
+
+impl RayCast for Plane {
+    /**
+     * A plane is a surface without inside, it can be hit from either side.
+     */
+    fn cast_ray(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        let denom = self.normal.dot(ray.direction);
+        if denom == 0.0 {
+            return None;
+        }
+        let distance = -self.signed_distance(ray.origin) / denom;
+        if !(0.0..=max_distance).contains(&distance) {
+            return None;
+        }
+        let normal = if denom < 0.0 {
+            self.normal
+        } else {
+            -self.normal
+        };
+        Some(RayHit::new(ray, distance, normal))
+    }
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+use crate::math::vector::Vec3;
+
+/**
+ * A half-line from an origin along a unit direction
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /**
+     * Creates a ray, normalizing its direction.
+     *
+     * The direction must not be zero.
+     */
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /**
+     * The point `distance` along the ray.
+     */
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /**
+     * The point of the ray closest to `p`.
+     */
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        self.at((p - self.origin).dot(self.direction).max(0.0))
+    }
+}
+
+/**
+ * Where a `Ray` hit a shape
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RayHit {
+    /**
+     * The distance along the ray
+     */
+    pub distance: f32,
+    pub point: Vec3,
+    /**
+     * The unit surface normal at the hit, facing the ray
+     */
+    pub normal: Vec3,
+}
+
+impl RayHit {
+    pub(crate) fn new(ray: &Ray, distance: f32, normal: Vec3) -> Self {
+        Self {
+            distance,
+            point: ray.at(distance),
+            normal,
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Aabb, Bounded, Sphere, SupportMap};
+use crate::math::vector::Vec3;
+
+/**
+ * The straight line between two points
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Segment {
+    pub a: Vec3,
+    pub b: Vec3,
+}
+
+impl Segment {
+    pub const fn new(a: Vec3, b: Vec3) -> Self {
+        Self { a, b }
+    }
+
+    /**
+     * The vector from `a` to `b`.
+     */
+    pub fn direction(&self) -> Vec3 {
+        self.b - self.a
+    }
+
+    pub fn length(&self) -> f32 {
+        self.direction().length()
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.a + self.b) * 0.5
+    }
+
+    /**
+     * The point at `t`, from `a` at 0 to `b` at 1.
+     */
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.a.lerp(self.b, t)
+    }
+
+    /**
+     * The `t` of the point of the segment closest to `p`, see `at`.
+     */
+    pub fn closest_parameter(&self, p: Vec3) -> f32 {
+        let d = self.direction();
+        let length_squared = d.length_squared();
+        if length_squared <= f32::EPSILON {
+            return 0.0;
+        }
+        ((p - self.a).dot(d) / length_squared).clamp(0.0, 1.0)
+    }
+
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        self.at(self.closest_parameter(p))
+    }
+
+    pub fn distance_squared_to_point(&self, p: Vec3) -> f32 {
+        self.closest_point(p).distance_squared(p)
+    }
+
+    /**
+     * The closest pair of points of two segments, the first on this one.
+     *
+     * Parallel segments have many such pairs, one of them is returned.
+     */
+    pub fn closest_points(&self, other: &Segment) -> (Vec3, Vec3) {
+        // Ericson, "Real-Time Collision Detection", 5.1.9
+        let d1 = self.direction();
+        let d2 = other.direction();
+        let r = self.a - other.a;
+        let a = d1.length_squared();
+        let e = d2.length_squared();
+        let f = d2.dot(r);
+
+        let (s, t) = if a <= f32::EPSILON && e <= f32::EPSILON {
+            (0.0, 0.0)
+        } else if a <= f32::EPSILON {
+            (0.0, (f / e).clamp(0.0, 1.0))
+        } else {
+            let c = d1.dot(r);
+            if e <= f32::EPSILON {
+                ((-c / a).clamp(0.0, 1.0), 0.0)
+            } else {
+                let b = d1.dot(d2);
+                let denom = a * e - b * b;
+                let s = if denom > f32::EPSILON * a * e {
+                    ((b * f - c * e) / denom).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let t = (b * s + f) / e;
+                if t < 0.0 {
+                    ((-c / a).clamp(0.0, 1.0), 0.0)
+                } else if t > 1.0 {
+                    (((b - c) / a).clamp(0.0, 1.0), 1.0)
+                } else {
+                    (s, t)
+                }
+            }
+        };
+        (self.at(s), other.at(t))
+    }
+
+    pub fn distance_squared_to_segment(&self, other: &Segment) -> f32 {
+        let (p, q) = self.closest_points(other);
+        p.distance_squared(q)
+    }
+}
+
+impl Bounded for Segment {
+    fn aabb(&self) -> Aabb {
+        Aabb::new(self.a.min(self.b), self.a.max(self.b))
+    }
+
+    fn bounding_sphere(&self) -> Sphere {
+        Sphere::new(self.center(), self.length() * 0.5)
+    }
+}
+
+impl SupportMap for Segment {
+    fn support(&self, direction: Vec3) -> Vec3 {
+        if self.a.dot(direction) >= self.b.dot(direction) {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Aabb, Bounded, Ray, RayCast, RayHit, SupportMap};
+use crate::math::scalar;
+use crate::math::vector::Vec3;
+
+/**
+ * A ball around a center point
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub const fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /**
+     * A sphere enclosing every point, at most a few percent
+     * larger than the smallest one.
+     *
+     * If there are no points, it returns None.
+     */
+    pub fn from_points(points: &[Vec3]) -> Option<Self> {
+        // Ritter, "An Efficient Bounding Sphere"
+        let first = *points.first()?;
+        let farthest = |from: Vec3| {
+            points
+                .iter()
+                .copied()
+                .max_by(|a, b| {
+                    a.distance_squared(from)
+                        .total_cmp(&b.distance_squared(from))
+                })
+                .unwrap()
+        };
+        let a = farthest(first);
+        let b = farthest(a);
+        let mut sphere = Self::new((a + b) * 0.5, a.distance(b) * 0.5);
+        for &p in points {
+            let d = p.distance(sphere.center);
+            if d > sphere.radius {
+                let radius = (sphere.radius + d) * 0.5;
+                sphere.center += (p - sphere.center) * ((radius - sphere.radius) / d);
+                sphere.radius = radius;
+            }
+        }
+        // Growing the sphere rounds, so make sure every point is inside,
+        // with a few ulps to spare for `contains_point`.
+        sphere.radius = points
+            .iter()
+            .map(|p| p.distance(sphere.center))
+            .fold(sphere.radius, f32::max)
+            * (1.0 + 4.0 * f32::EPSILON);
+        Some(sphere)
+    }
+
+    pub fn volume(&self) -> f32 {
+        4.0 / 3.0 * scalar::PI * self.radius * self.radius * self.radius
+    }
+
+    /**
+     * The smallest sphere enclosing both spheres.
+     */
+    pub fn merge(&self, other: &Sphere) -> Self {
+        let offset = other.center - self.center;
+        let distance = offset.length();
+        if distance + other.radius <= self.radius {
+            return *self;
+        }
+        if distance + self.radius <= other.radius {
+            return *other;
+        }
+        let radius = (distance + self.radius + other.radius) * 0.5;
+        let center = self.center + offset * ((radius - self.radius) / distance);
+        Self::new(center, radius)
+    }
+
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        p.distance_squared(self.center) <= self.radius * self.radius
+    }
+
+    /**
+     * Checks if the other sphere lies entirely inside this one.
+     */
+    pub fn contains(&self, other: &Sphere) -> bool {
+        self.center.distance(other.center) + other.radius <= self.radius
+    }
+
+    /**
+     * Checks if both spheres overlap. Touching spheres overlap.
+     */
+    pub fn intersects(&self, other: &Sphere) -> bool {
+        let radii = self.radius + other.radius;
+        self.center.distance_squared(other.center) <= radii * radii
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        aabb.intersects_sphere(self)
+    }
+
+    /**
+     * The point of the sphere closest to `p`.
+     *
+     * Points inside the sphere are their own closest point.
+     */
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        let offset = p - self.center;
+        let distance = offset.length();
+        if distance <= self.radius {
+            return p;
+        }
+        self.center + offset * (self.radius / distance)
+    }
+
+    /**
+     * The distance from a point to the sphere.
+     *
+     * Points inside the sphere have a distance of zero.
+     */
+    pub fn distance_to_point(&self, p: Vec3) -> f32 {
+        (p.distance(self.center) - self.radius).max(0.0)
+    }
+}
+
+impl RayCast for Sphere {
+    fn cast_ray(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        let m = ray.origin - self.center;
+        let b = m.dot(ray.direction);
+        let c = m.length_squared() - self.radius * self.radius;
+        if c <= 0.0 {
+            return Some(RayHit::new(ray, 0.0, -ray.direction));
+        }
+        if b > 0.0 {
+            return None;
+        }
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let distance = -b - scalar::sqrt(discriminant);
+        if distance > max_distance {
+            return None;
+        }
+        let point = ray.at(distance);
+        let normal = (point - self.center)
+            .try_normalize()
+            .unwrap_or(-ray.direction);
+        Some(RayHit {
+            distance,
+            point,
+            normal,
+        })
+    }
+}
+
+impl Bounded for Sphere {
+    fn aabb(&self) -> Aabb {
+        Aabb::from_center_half_extents(self.center, Vec3::splat(self.radius))
+    }
+
+    fn bounding_sphere(&self) -> Sphere {
+        *self
+    }
+}
+
+impl SupportMap for Sphere {
+    fn support(&self, direction: Vec3) -> Vec3 {
+        self.center + direction.normalize_or_zero() * self.radius
+    }
+}
@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Aabb, Bounded, Plane, Ray, RayCast, RayHit, Segment, Sphere, SupportMap};
+use crate::math::vector::Vec3;
+
+/**
+ * A triangle, whose front side sees `a`, `b` and `c` counter-clockwise
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Triangle {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub c: Vec3,
+}
+
+impl Triangle {
+    pub const fn new(a: Vec3, b: Vec3, c: Vec3) -> Self {
+        Self { a, b, c }
+    }
+
+    pub fn vertices(&self) -> [Vec3; 3] {
+        [self.a, self.b, self.c]
+    }
+
+    /**
+     * The unit normal of the front side, or zero if the triangle
+     * has no area.
+     */
+    pub fn normal(&self) -> Vec3 {
+        (self.b - self.a).cross(self.c - self.a).normalize_or_zero()
+    }
+
+    pub fn area(&self) -> f32 {
+        (self.b - self.a).cross(self.c - self.a).length() * 0.5
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        (self.a + self.b + self.c) / 3.0
+    }
+
+    /**
+     * The plane of the triangle, or None if it has no area.
+     */
+    pub fn plane(&self) -> Option<Plane> {
+        Plane::from_points(self.a, self.b, self.c)
+    }
+
+    /**
+     * The weights of `a`, `b` and `c` that give the point of the
+     * triangle closest to `p`. They are never negative and sum to one.
+     */
+    pub fn closest_weights(&self, p: Vec3) -> [f32; 3] {
+        // Ericson, "Real-Time Collision Detection", 5.1.5
+        let (a, b, c) = (self.a, self.b, self.c);
+        let ab = b - a;
+        let ac = c - a;
+        let ap = p - a;
+        let d1 = ab.dot(ap);
+        let d2 = ac.dot(ap);
+        if d1 <= 0.0 && d2 <= 0.0 {
+            return [1.0, 0.0, 0.0];
+        }
+
+        let bp = p - b;
+        let d3 = ab.dot(bp);
+        let d4 = ac.dot(bp);
+        if d3 >= 0.0 && d4 <= d3 {
+            return [0.0, 1.0, 0.0];
+        }
+
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+            let v = d1 / (d1 - d3);
+            return [1.0 - v, v, 0.0];
+        }
+
+        let cp = p - c;
+        let d5 = ab.dot(cp);
+        let d6 = ac.dot(cp);
+        if d6 >= 0.0 && d5 <= d6 {
+            return [0.0, 0.0, 1.0];
+        }
+
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+            let w = d2 / (d2 - d6);
+            return [1.0 - w, 0.0, w];
+        }
+
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+            let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+            return [0.0, 1.0 - w, w];
+        }
+
+        let sum = va + vb + vc;
+        if sum <= 0.0 {
+            return self.closest_edge_weights(p);
+        }
+        let v = vb / sum;
+        let w = vc / sum;
+        [1.0 - v - w, v, w]
+    }
+
+    /**
+     * `closest_weights` for triangles without area, which are segments.
+     */
+    fn closest_edge_weights(&self, p: Vec3) -> [f32; 3] {
+        let edges = [(0, 1), (1, 2), (2, 0)];
+        let vertices = self.vertices();
+        let (i, j, t) = edges
+            .into_iter()
+            .map(|(i, j)| {
+                let t = Segment::new(vertices[i], vertices[j]).closest_parameter(p);
+                (i, j, t)
+            })
+            .min_by(|x, y| {
+                let distance = |&(i, j, t): &(usize, usize, f32)| {
+                    vertices[i].lerp(vertices[j], t).distance_squared(p)
+                };
+                distance(x).total_cmp(&distance(y))
+            })
+            .unwrap();
+        let mut weights = [0.0; 3];
+        weights[i] = 1.0 - t;
+        weights[j] = t;
+        weights
+    }
+
+    /**
+     * The point of the triangle closest to `p`.
+     */
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        let [u, v, w] = self.closest_weights(p);
+        self.a * u + self.b * v + self.c * w
+    }
+
+    pub fn distance_squared_to_point(&self, p: Vec3) -> f32 {
+        self.closest_point(p).distance_squared(p)
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.distance_squared_to_point(sphere.center) <= sphere.radius * sphere.radius
+    }
+}
+
+impl RayCast for Triangle {
+    /**
+     * A triangle is a surface without inside, it can be hit from either side.
+     */
+    fn cast_ray(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        // Möller and Trumbore, "Fast, Minimum Storage Ray/Triangle Intersection"
+        let e1 = self.b - self.a;
+        let e2 = self.c - self.a;
+        let p = ray.direction.cross(e2);
+        let det = e1.dot(p);
+        if det.abs() <= f32::EPSILON * e1.length_squared().max(e2.length_squared()) {
+            return None;
+        }
+
+        let recip = 1.0 / det;
+        let t = ray.origin - self.a;
+        let u = t.dot(p) * recip;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = t.cross(e1);
+        let v = ray.direction.dot(q) * recip;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let distance = e2.dot(q) * recip;
+        if !(0.0..=max_distance).contains(&distance) {
+            return None;
+        }
+
+        let normal = self.normal();
+        let normal = if det > 0.0 { normal } else { -normal };
+        Some(RayHit::new(ray, distance, normal))
+    }
+}
+
+impl Bounded for Triangle {
+    fn aabb(&self) -> Aabb {
+        Aabb::new(
+            self.a.min(self.b).min(self.c),
+            self.a.max(self.b).max(self.c),
+        )
+    }
+
+    fn bounding_sphere(&self) -> Sphere {
+        Sphere::from_points(&self.vertices()).unwrap()
+    }
+}
+
+impl SupportMap for Triangle {
+    fn support(&self, direction: Vec3) -> Vec3 {
+        self.vertices()
+            .into_iter()
+            .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+            .unwrap()
+    }
+}
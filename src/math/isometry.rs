@@ -0,0 +1,88 @@
+use std::ops::Mul;
+
+use serde::{Deserialize, Serialize};
+
+use super::quat::Quat;
+use super::transform::Transform;
+use super::vector::Vec3;
+
+/**
+ * A rotation followed by a translation, a `Transform` without scale
+ *
+ * Used to place rigid shapes, which keep their size and angles.
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Isometry {
+    pub translation: Vec3,
+    pub rotation: Quat,
+}
+
+impl Isometry {
+    pub const IDENTITY: Self = Self::new(Vec3::ZERO, Quat::IDENTITY);
+
+    pub const fn new(translation: Vec3, rotation: Quat) -> Self {
+        Self {
+            translation,
+            rotation,
+        }
+    }
+
+    pub const fn from_translation(translation: Vec3) -> Self {
+        Self::new(translation, Quat::IDENTITY)
+    }
+
+    pub const fn from_rotation(rotation: Quat) -> Self {
+        Self::new(Vec3::ZERO, rotation)
+    }
+
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        self.rotation * p + self.translation
+    }
+
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        self.rotation * v
+    }
+
+    pub fn inverse_transform_point(&self, p: Vec3) -> Vec3 {
+        self.rotation.inverse() * (p - self.translation)
+    }
+
+    pub fn inverse_transform_vector(&self, v: Vec3) -> Vec3 {
+        self.rotation.inverse() * v
+    }
+
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.inverse();
+        Self::new(rotation * -self.translation, rotation)
+    }
+}
+
+impl Mul for Isometry {
+    type Output = Self;
+
+    /**
+     * Combines two isometries, `rhs` is applied first.
+     */
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.transform_point(rhs.translation),
+            self.rotation * rhs.rotation,
+        )
+    }
+}
+
+impl From<Transform> for Isometry {
+    /**
+     * Drops the scale of the transform.
+     */
+    fn from(transform: Transform) -> Self {
+        Self::new(transform.translation, transform.rotation)
+    }
+}
+
+impl From<Isometry> for Transform {
+    fn from(isometry: Isometry) -> Self {
+        Transform::from_translation(isometry.translation).with_rotation(isometry.rotation)
+    }
+}
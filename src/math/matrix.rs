@@ -0,0 +1,525 @@
+use std::ops::{Add, Mul, Sub};
+
+use serde::{Deserialize, Serialize};
+
+use super::quat::Quat;
+use super::scalar;
+use super::vector::{Vec3, Vec4};
+
+/**
+ * A 3x3 column-major matrix
+ *
+ * Used for rotations, scales and inertia tensors.
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(from = "[f32; 9]", into = "[f32; 9]")]
+#[repr(C)]
+pub struct Mat3 {
+    pub x_axis: Vec3,
+    pub y_axis: Vec3,
+    pub z_axis: Vec3,
+}
+
+impl Mat3 {
+    pub const ZERO: Self = Self::from_cols(Vec3::ZERO, Vec3::ZERO, Vec3::ZERO);
+    pub const IDENTITY: Self = Self::from_cols(Vec3::X, Vec3::Y, Vec3::Z);
+
+    pub const fn from_cols(x_axis: Vec3, y_axis: Vec3, z_axis: Vec3) -> Self {
+        Self {
+            x_axis,
+            y_axis,
+            z_axis,
+        }
+    }
+
+    pub const fn from_cols_array(m: [f32; 9]) -> Self {
+        Self::from_cols(
+            Vec3::new(m[0], m[1], m[2]),
+            Vec3::new(m[3], m[4], m[5]),
+            Vec3::new(m[6], m[7], m[8]),
+        )
+    }
+
+    pub const fn to_cols_array(&self) -> [f32; 9] {
+        let (x, y, z) = (self.x_axis, self.y_axis, self.z_axis);
+        [x.x, x.y, x.z, y.x, y.y, y.z, z.x, z.y, z.z]
+    }
+
+    pub const fn from_diagonal(diagonal: Vec3) -> Self {
+        Self::from_cols(
+            Vec3::new(diagonal.x, 0.0, 0.0),
+            Vec3::new(0.0, diagonal.y, 0.0),
+            Vec3::new(0.0, 0.0, diagonal.z),
+        )
+    }
+
+    /**
+     * The rotation matrix of a unit quaternion.
+     */
+    pub fn from_quat(q: Quat) -> Self {
+        let (x2, y2, z2) = (q.x + q.x, q.y + q.y, q.z + q.z);
+        let (xx, xy, xz) = (q.x * x2, q.x * y2, q.x * z2);
+        let (yy, yz, zz) = (q.y * y2, q.y * z2, q.z * z2);
+        let (wx, wy, wz) = (q.w * x2, q.w * y2, q.w * z2);
+        Self::from_cols(
+            Vec3::new(1.0 - (yy + zz), xy + wz, xz - wy),
+            Vec3::new(xy - wz, 1.0 - (xx + zz), yz + wx),
+            Vec3::new(xz + wy, yz - wx, 1.0 - (xx + yy)),
+        )
+    }
+
+    pub fn from_scale(scale: Vec3) -> Self {
+        Self::from_diagonal(scale)
+    }
+
+    /**
+     * The matrix of a cross product, so that `m * v == a.cross(v)`.
+     */
+    pub fn from_cross(a: Vec3) -> Self {
+        Self::from_cols(
+            Vec3::new(0.0, a.z, -a.y),
+            Vec3::new(-a.z, 0.0, a.x),
+            Vec3::new(a.y, -a.x, 0.0),
+        )
+    }
+
+    pub fn col(&self, index: usize) -> Vec3 {
+        match index {
+            0 => self.x_axis,
+            1 => self.y_axis,
+            2 => self.z_axis,
+            _ => panic!("Column index {index} out of bounds for Mat3"),
+        }
+    }
+
+    pub fn row(&self, index: usize) -> Vec3 {
+        Vec3::new(self.x_axis[index], self.y_axis[index], self.z_axis[index])
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self::from_cols(self.row(0), self.row(1), self.row(2))
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.z_axis.dot(self.x_axis.cross(self.y_axis))
+    }
+
+    /**
+     * The inverse of the matrix.
+     *
+     * A singular matrix gives a non-finite result.
+     */
+    pub fn inverse(&self) -> Self {
+        let c0 = self.y_axis.cross(self.z_axis);
+        let c1 = self.z_axis.cross(self.x_axis);
+        let c2 = self.x_axis.cross(self.y_axis);
+        let recip = 1.0 / self.z_axis.dot(c2);
+        Self::from_cols(c0 * recip, c1 * recip, c2 * recip).transpose()
+    }
+
+    pub fn abs(&self) -> Self {
+        Self::from_cols(self.x_axis.abs(), self.y_axis.abs(), self.z_axis.abs())
+    }
+
+    pub fn mul_vec3(&self, v: Vec3) -> Vec3 {
+        self.x_axis * v.x + self.y_axis * v.y + self.z_axis * v.z
+    }
+
+    pub fn mul_mat3(&self, rhs: &Self) -> Self {
+        Self::from_cols(
+            self.mul_vec3(rhs.x_axis),
+            self.mul_vec3(rhs.y_axis),
+            self.mul_vec3(rhs.z_axis),
+        )
+    }
+
+    pub fn abs_diff_eq(&self, rhs: &Self, max_abs_diff: f32) -> bool {
+        self.x_axis.abs_diff_eq(rhs.x_axis, max_abs_diff)
+            && self.y_axis.abs_diff_eq(rhs.y_axis, max_abs_diff)
+            && self.z_axis.abs_diff_eq(rhs.z_axis, max_abs_diff)
+    }
+}
+
+impl Default for Mat3 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Vec3) -> Vec3 {
+        self.mul_vec3(rhs)
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        self.mul_mat3(&rhs)
+    }
+}
+
+impl Mul<f32> for Mat3 {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self::from_cols(self.x_axis * rhs, self.y_axis * rhs, self.z_axis * rhs)
+    }
+}
+
+impl Add for Mat3 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::from_cols(
+            self.x_axis + rhs.x_axis,
+            self.y_axis + rhs.y_axis,
+            self.z_axis + rhs.z_axis,
+        )
+    }
+}
+
+impl Sub for Mat3 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::from_cols(
+            self.x_axis - rhs.x_axis,
+            self.y_axis - rhs.y_axis,
+            self.z_axis - rhs.z_axis,
+        )
+    }
+}
+
+impl From<[f32; 9]> for Mat3 {
+    fn from(m: [f32; 9]) -> Self {
+        Self::from_cols_array(m)
+    }
+}
+
+impl From<Mat3> for [f32; 9] {
+    fn from(m: Mat3) -> Self {
+        m.to_cols_array()
+    }
+}
+
+/**
+ * A 4x4 column-major matrix
+ *
+ * Used for projections and for handing transforms to the GPU.
+ * Its columns are 16-byte aligned `Vec4`s.
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(from = "[f32; 16]", into = "[f32; 16]")]
+#[repr(C)]
+pub struct Mat4 {
+    pub x_axis: Vec4,
+    pub y_axis: Vec4,
+    pub z_axis: Vec4,
+    pub w_axis: Vec4,
+}
+
+impl Mat4 {
+    pub const ZERO: Self = Self::from_cols(Vec4::ZERO, Vec4::ZERO, Vec4::ZERO, Vec4::ZERO);
+    pub const IDENTITY: Self = Self::from_cols(Vec4::X, Vec4::Y, Vec4::Z, Vec4::W);
+
+    pub const fn from_cols(x_axis: Vec4, y_axis: Vec4, z_axis: Vec4, w_axis: Vec4) -> Self {
+        Self {
+            x_axis,
+            y_axis,
+            z_axis,
+            w_axis,
+        }
+    }
+
+    pub const fn from_cols_array(m: [f32; 16]) -> Self {
+        Self::from_cols(
+            Vec4::new(m[0], m[1], m[2], m[3]),
+            Vec4::new(m[4], m[5], m[6], m[7]),
+            Vec4::new(m[8], m[9], m[10], m[11]),
+            Vec4::new(m[12], m[13], m[14], m[15]),
+        )
+    }
+
+    pub const fn to_cols_array(&self) -> [f32; 16] {
+        let (x, y, z, w) = (self.x_axis, self.y_axis, self.z_axis, self.w_axis);
+        [
+            x.x, x.y, x.z, x.w, y.x, y.y, y.z, y.w, z.x, z.y, z.z, z.w, w.x, w.y, w.z, w.w,
+        ]
+    }
+
+    /**
+     * The matrix that scales, then rotates, then translates.
+     */
+    pub fn from_scale_rotation_translation(scale: Vec3, rotation: Quat, translation: Vec3) -> Self {
+        let m = Mat3::from_quat(rotation);
+        Self::from_cols(
+            (m.x_axis * scale.x).extend(0.0),
+            (m.y_axis * scale.y).extend(0.0),
+            (m.z_axis * scale.z).extend(0.0),
+            translation.extend(1.0),
+        )
+    }
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self::from_scale_rotation_translation(Vec3::ONE, Quat::IDENTITY, translation)
+    }
+
+    pub fn from_quat(rotation: Quat) -> Self {
+        Self::from_scale_rotation_translation(Vec3::ONE, rotation, Vec3::ZERO)
+    }
+
+    pub fn from_scale(scale: Vec3) -> Self {
+        Self::from_scale_rotation_translation(scale, Quat::IDENTITY, Vec3::ZERO)
+    }
+
+    pub fn from_mat3(m: &Mat3) -> Self {
+        Self::from_cols(
+            m.x_axis.extend(0.0),
+            m.y_axis.extend(0.0),
+            m.z_axis.extend(0.0),
+            Vec4::W,
+        )
+    }
+
+    /**
+     * A right-handed perspective projection to a depth range of 0 to 1.
+     */
+    pub fn perspective_rh(fov_y: f32, aspect: f32, near: f32, far: f32) -> Self {
+        let f = 1.0 / scalar::tan(0.5 * fov_y);
+        let range = far / (near - far);
+        Self::from_cols(
+            Vec4::new(f / aspect, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, f, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, range, -1.0),
+            Vec4::new(0.0, 0.0, range * near, 0.0),
+        )
+    }
+
+    /**
+     * A right-handed orthographic projection to a depth range of 0 to 1.
+     */
+    pub fn orthographic_rh(
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        near: f32,
+        far: f32,
+    ) -> Self {
+        let rw = 1.0 / (right - left);
+        let rh = 1.0 / (top - bottom);
+        let r = 1.0 / (near - far);
+        Self::from_cols(
+            Vec4::new(rw + rw, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, rh + rh, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, r, 0.0),
+            Vec4::new(-(left + right) * rw, -(top + bottom) * rh, r * near, 1.0),
+        )
+    }
+
+    /**
+     * A right-handed view matrix looking from `eye` at `target`.
+     */
+    pub fn look_at_rh(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        let f = (target - eye).normalize();
+        let s = f.cross(up).normalize();
+        let u = s.cross(f);
+        Self::from_cols(
+            Vec4::new(s.x, u.x, -f.x, 0.0),
+            Vec4::new(s.y, u.y, -f.y, 0.0),
+            Vec4::new(s.z, u.z, -f.z, 0.0),
+            Vec4::new(-s.dot(eye), -u.dot(eye), f.dot(eye), 1.0),
+        )
+    }
+
+    pub fn col(&self, index: usize) -> Vec4 {
+        match index {
+            0 => self.x_axis,
+            1 => self.y_axis,
+            2 => self.z_axis,
+            3 => self.w_axis,
+            _ => panic!("Column index {index} out of bounds for Mat4"),
+        }
+    }
+
+    pub fn row(&self, index: usize) -> Vec4 {
+        Vec4::new(
+            self.x_axis[index],
+            self.y_axis[index],
+            self.z_axis[index],
+            self.w_axis[index],
+        )
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self::from_cols(self.row(0), self.row(1), self.row(2), self.row(3))
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let m = self.to_cols_array();
+        let inv = adjugate(&m);
+        m[0] * inv[0] + m[1] * inv[4] + m[2] * inv[8] + m[3] * inv[12]
+    }
+
+    /**
+     * The inverse of the matrix.
+     *
+     * A singular matrix gives a non-finite result.
+     */
+    pub fn inverse(&self) -> Self {
+        let m = self.to_cols_array();
+        let inv = adjugate(&m);
+        let det = m[0] * inv[0] + m[1] * inv[4] + m[2] * inv[8] + m[3] * inv[12];
+        let recip = 1.0 / det;
+        Self::from_cols_array(inv.map(|c| c * recip))
+    }
+
+    pub fn mul_vec4(&self, v: Vec4) -> Vec4 {
+        self.x_axis * v.x + self.y_axis * v.y + self.z_axis * v.z + self.w_axis * v.w
+    }
+
+    pub fn mul_mat4(&self, rhs: &Self) -> Self {
+        Self::from_cols(
+            self.mul_vec4(rhs.x_axis),
+            self.mul_vec4(rhs.y_axis),
+            self.mul_vec4(rhs.z_axis),
+            self.mul_vec4(rhs.w_axis),
+        )
+    }
+
+    /**
+     * Transforms a point, dividing by w.
+     */
+    pub fn project_point3(&self, p: Vec3) -> Vec3 {
+        let v = self.mul_vec4(p.extend(1.0));
+        v.truncate() / v.w
+    }
+
+    /**
+     * Transforms a point of an affine matrix, ignoring the last row.
+     */
+    pub fn transform_point3(&self, p: Vec3) -> Vec3 {
+        self.mul_vec4(p.extend(1.0)).truncate()
+    }
+
+    /**
+     * Transforms a direction, ignoring translation.
+     */
+    pub fn transform_vector3(&self, v: Vec3) -> Vec3 {
+        self.mul_vec4(v.extend(0.0)).truncate()
+    }
+
+    pub fn abs_diff_eq(&self, rhs: &Self, max_abs_diff: f32) -> bool {
+        (0..4).all(|i| self.col(i).abs_diff_eq(rhs.col(i), max_abs_diff))
+    }
+}
+
+/**
+ * The adjugate of a matrix, the inverse times the determinant.
+ */
+fn adjugate(m: &[f32; 16]) -> [f32; 16] {
+    let mut inv = [0.0; 16];
+    inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+        + m[9] * m[7] * m[14]
+        + m[13] * m[6] * m[11]
+        - m[13] * m[7] * m[10];
+    inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+        - m[8] * m[7] * m[14]
+        - m[12] * m[6] * m[11]
+        + m[12] * m[7] * m[10];
+    inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+        + m[8] * m[7] * m[13]
+        + m[12] * m[5] * m[11]
+        - m[12] * m[7] * m[9];
+    inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+        - m[8] * m[6] * m[13]
+        - m[12] * m[5] * m[10]
+        + m[12] * m[6] * m[9];
+    inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+        - m[9] * m[3] * m[14]
+        - m[13] * m[2] * m[11]
+        + m[13] * m[3] * m[10];
+    inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+        + m[8] * m[3] * m[14]
+        + m[12] * m[2] * m[11]
+        - m[12] * m[3] * m[10];
+    inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+        - m[8] * m[3] * m[13]
+        - m[12] * m[1] * m[11]
+        + m[12] * m[3] * m[9];
+    inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+        + m[8] * m[2] * m[13]
+        + m[12] * m[1] * m[10]
+        - m[12] * m[2] * m[9];
+    inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+        + m[5] * m[3] * m[14]
+        + m[13] * m[2] * m[7]
+        - m[13] * m[3] * m[6];
+    inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+        - m[4] * m[3] * m[14]
+        - m[12] * m[2] * m[7]
+        + m[12] * m[3] * m[6];
+    inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+        + m[4] * m[3] * m[13]
+        + m[12] * m[1] * m[7]
+        - m[12] * m[3] * m[5];
+    inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+        - m[4] * m[2] * m[13]
+        - m[12] * m[1] * m[6]
+        + m[12] * m[2] * m[5];
+    inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+        - m[5] * m[3] * m[10]
+        - m[9] * m[2] * m[7]
+        + m[9] * m[3] * m[6];
+    inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+        + m[4] * m[3] * m[10]
+        + m[8] * m[2] * m[7]
+        - m[8] * m[3] * m[6];
+    inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+        - m[4] * m[3] * m[9]
+        - m[8] * m[1] * m[7]
+        + m[8] * m[3] * m[5];
+    inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+        + m[4] * m[2] * m[9]
+        + m[8] * m[1] * m[6]
+        - m[8] * m[2] * m[5];
+    inv
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+
+    fn mul(self, rhs: Vec4) -> Vec4 {
+        self.mul_vec4(rhs)
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        self.mul_mat4(&rhs)
+    }
+}
+
+impl From<[f32; 16]> for Mat4 {
+    fn from(m: [f32; 16]) -> Self {
+        Self::from_cols_array(m)
+    }
+}
+
+impl From<Mat4> for [f32; 16] {
+    fn from(m: Mat4) -> Self {
+        m.to_cols_array()
+    }
+}
@@ -1 +1,15 @@
+pub mod affine;
+pub mod geometry;
+pub mod isometry;
+pub mod matrix;
+pub mod quat;
+pub mod scalar;
+pub mod transform;
+pub mod vector;
 
+pub use affine::Affine3;
+pub use isometry::Isometry;
+pub use matrix::{Mat3, Mat4};
+pub use quat::Quat;
+pub use transform::{GlobalTransform, Transform};
+pub use vector::{Vec2, Vec3, Vec4};
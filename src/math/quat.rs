@@ -0,0 +1,258 @@
+use std::ops::{Mul, MulAssign, Neg};
+
+use serde::{Deserialize, Serialize};
+
+use super::matrix::Mat3;
+use super::scalar;
+use super::vector::{Vec3, Vec4};
+
+/**
+ * A rotation stored as a unit quaternion
+ *
+ * Quaternions built by hand with `from_xyzw` have to be normalized
+ * to represent a rotation. It is laid out like a `Vec4`.
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(from = "[f32; 4]", into = "[f32; 4]")]
+#[repr(C, align(16))]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Quat {
+    pub const IDENTITY: Self = Self::from_xyzw(0.0, 0.0, 0.0, 1.0);
+
+    pub const fn from_xyzw(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Self { x, y, z, w }
+    }
+
+    pub const fn from_array([x, y, z, w]: [f32; 4]) -> Self {
+        Self { x, y, z, w }
+    }
+
+    pub const fn to_array(self) -> [f32; 4] {
+        [self.x, self.y, self.z, self.w]
+    }
+
+    pub fn from_vec4(v: Vec4) -> Self {
+        Self::from_xyzw(v.x, v.y, v.z, v.w)
+    }
+
+    pub fn to_vec4(self) -> Vec4 {
+        Vec4::new(self.x, self.y, self.z, self.w)
+    }
+
+    /**
+     * A rotation of `angle` radians around a unit axis.
+     */
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self {
+        let (sin, cos) = scalar::sin_cos(angle * 0.5);
+        let v = axis * sin;
+        Self::from_xyzw(v.x, v.y, v.z, cos)
+    }
+
+    pub fn from_rotation_x(angle: f32) -> Self {
+        Self::from_axis_angle(Vec3::X, angle)
+    }
+
+    pub fn from_rotation_y(angle: f32) -> Self {
+        Self::from_axis_angle(Vec3::Y, angle)
+    }
+
+    pub fn from_rotation_z(angle: f32) -> Self {
+        Self::from_axis_angle(Vec3::Z, angle)
+    }
+
+    /**
+     * A rotation from Euler angles, applied as yaw around Y,
+     * then pitch around X, then roll around Z.
+     */
+    pub fn from_euler_yxz(yaw: f32, pitch: f32, roll: f32) -> Self {
+        Self::from_rotation_y(yaw) * Self::from_rotation_x(pitch) * Self::from_rotation_z(roll)
+    }
+
+    /**
+     * The shortest rotation taking unit vector `from` to unit vector `to`.
+     */
+    pub fn from_rotation_arc(from: Vec3, to: Vec3) -> Self {
+        let dot = from.dot(to);
+        if dot < -1.0 + scalar::EPSILON {
+            // Opposite vectors, any perpendicular axis works.
+            return Self::from_axis_angle(from.any_orthonormal_vector(), scalar::PI);
+        }
+        let c = from.cross(to);
+        Self::from_xyzw(c.x, c.y, c.z, 1.0 + dot).normalize()
+    }
+
+    /**
+     * The rotation of an orthonormal rotation matrix.
+     */
+    pub fn from_mat3(m: &Mat3) -> Self {
+        let (x, y, z) = (m.x_axis, m.y_axis, m.z_axis);
+        let trace = x.x + y.y + z.z;
+        let q = if trace > 0.0 {
+            let s = scalar::sqrt(trace + 1.0) * 2.0;
+            Self::from_xyzw((y.z - z.y) / s, (z.x - x.z) / s, (x.y - y.x) / s, 0.25 * s)
+        } else if x.x > y.y && x.x > z.z {
+            let s = scalar::sqrt(1.0 + x.x - y.y - z.z) * 2.0;
+            Self::from_xyzw(0.25 * s, (y.x + x.y) / s, (z.x + x.z) / s, (y.z - z.y) / s)
+        } else if y.y > z.z {
+            let s = scalar::sqrt(1.0 + y.y - x.x - z.z) * 2.0;
+            Self::from_xyzw((y.x + x.y) / s, 0.25 * s, (z.y + y.z) / s, (z.x - x.z) / s)
+        } else {
+            let s = scalar::sqrt(1.0 + z.z - x.x - y.y) * 2.0;
+            Self::from_xyzw((z.x + x.z) / s, (z.y + y.z) / s, 0.25 * s, (x.y - y.x) / s)
+        };
+        q.normalize()
+    }
+
+    /**
+     * The unit axis and angle in radians of the rotation.
+     *
+     * The identity gives the X axis and an angle of zero.
+     */
+    pub fn to_axis_angle(self) -> (Vec3, f32) {
+        let q = if self.w < 0.0 { -self } else { self };
+        let v = Vec3::new(q.x, q.y, q.z);
+        let angle = 2.0 * scalar::acos(q.w);
+        (v.try_normalize().unwrap_or(Vec3::X), angle)
+    }
+
+    pub fn xyz(self) -> Vec3 {
+        Vec3::new(self.x, self.y, self.z)
+    }
+
+    pub fn dot(self, rhs: Self) -> f32 {
+        self.to_vec4().dot(rhs.to_vec4())
+    }
+
+    pub fn length(self) -> f32 {
+        self.to_vec4().length()
+    }
+
+    pub fn normalize(self) -> Self {
+        Self::from_vec4(self.to_vec4().normalize())
+    }
+
+    pub fn is_normalized(self) -> bool {
+        self.to_vec4().is_normalized()
+    }
+
+    /**
+     * The inverse of a unit quaternion.
+     */
+    pub fn conjugate(self) -> Self {
+        Self::from_xyzw(-self.x, -self.y, -self.z, self.w)
+    }
+
+    /**
+     * The inverse rotation, same as `conjugate` for unit quaternions.
+     */
+    pub fn inverse(self) -> Self {
+        self.conjugate()
+    }
+
+    /**
+     * Checks if both rotate the same way, allowing for `q` and `-q`.
+     */
+    pub fn abs_diff_eq(self, rhs: Self, max_abs_diff: f32) -> bool {
+        self.to_vec4().abs_diff_eq(rhs.to_vec4(), max_abs_diff)
+            || self.to_vec4().abs_diff_eq(-rhs.to_vec4(), max_abs_diff)
+    }
+
+    /**
+     * The angle in radians between two rotations.
+     */
+    pub fn angle_between(self, rhs: Self) -> f32 {
+        2.0 * scalar::acos(self.dot(rhs).abs())
+    }
+
+    /**
+     * Interpolates along the shortest arc between two rotations.
+     */
+    pub fn slerp(self, mut end: Self, t: f32) -> Self {
+        let mut dot = self.dot(end);
+        if dot < 0.0 {
+            end = -end;
+            dot = -dot;
+        }
+        if dot > 1.0 - scalar::EPSILON {
+            // Too close to divide by the sine, lerp instead.
+            return Self::from_vec4(self.to_vec4().lerp(end.to_vec4(), t)).normalize();
+        }
+        let theta = scalar::acos(dot);
+        let sin = scalar::sin(theta);
+        let a = scalar::sin((1.0 - t) * theta) / sin;
+        let b = scalar::sin(t * theta) / sin;
+        Self::from_vec4(self.to_vec4() * a + end.to_vec4() * b)
+    }
+
+    /**
+     * Rotates a vector.
+     */
+    pub fn mul_vec3(self, v: Vec3) -> Vec3 {
+        let q = self.xyz();
+        let t = q.cross(v) * 2.0;
+        v + t * self.w + q.cross(t)
+    }
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Quat {
+    type Output = Self;
+
+    /**
+     * Combines two rotations, `rhs` is applied first.
+     */
+    fn mul(self, rhs: Self) -> Self {
+        let (a, b) = (self, rhs);
+        Self::from_xyzw(
+            a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+            a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+        )
+    }
+}
+
+impl MulAssign for Quat {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl Mul<Vec3> for Quat {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Vec3) -> Vec3 {
+        self.mul_vec3(rhs)
+    }
+}
+
+impl Neg for Quat {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::from_xyzw(-self.x, -self.y, -self.z, -self.w)
+    }
+}
+
+impl From<[f32; 4]> for Quat {
+    fn from(array: [f32; 4]) -> Self {
+        Self::from_array(array)
+    }
+}
+
+impl From<Quat> for [f32; 4] {
+    fn from(q: Quat) -> Self {
+        q.to_array()
+    }
+}
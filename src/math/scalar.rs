@@ -0,0 +1,113 @@
+pub const PI: f32 = std::f32::consts::PI;
+pub const TAU: f32 = std::f32::consts::TAU;
+pub const FRAC_PI_2: f32 = std::f32::consts::FRAC_PI_2;
+
+/**
+ * The tolerance used by `is_normalized` and friends
+ */
+pub const EPSILON: f32 = 1e-5;
+
+/**
+ * Every transcendental function the math types use goes through here.
+ *
+ * The platform's versions can differ in the last bit between targets,
+ * so with the `deterministic` feature they are replaced by the portable
+ * software versions of `libm`. Arithmetic, `sqrt`, `abs`, `min` and
+ * friends are exactly rounded by IEEE 754 and deterministic already,
+ * as long as nothing is fused, so the math types never use `mul_add`.
+ */
+#[cfg(not(feature = "deterministic"))]
+mod backend {
+    pub fn sin_cos(x: f32) -> (f32, f32) {
+        x.sin_cos()
+    }
+
+    pub fn tan(x: f32) -> f32 {
+        x.tan()
+    }
+
+    pub fn acos(x: f32) -> f32 {
+        x.acos()
+    }
+
+    pub fn asin(x: f32) -> f32 {
+        x.asin()
+    }
+
+    pub fn atan2(y: f32, x: f32) -> f32 {
+        y.atan2(x)
+    }
+
+    pub fn exp(x: f32) -> f32 {
+        x.exp()
+    }
+
+    pub fn powf(x: f32, n: f32) -> f32 {
+        x.powf(n)
+    }
+}
+
+#[cfg(feature = "deterministic")]
+mod backend {
+    pub use libm::{
+        acosf as acos, asinf as asin, atan2f as atan2, expf as exp, powf, sincosf as sin_cos,
+        tanf as tan,
+    };
+}
+
+/**
+ * Checks if the math functions are the deterministic ones, see `backend`.
+ */
+pub const fn is_deterministic() -> bool {
+    cfg!(feature = "deterministic")
+}
+
+#[inline]
+pub fn sqrt(x: f32) -> f32 {
+    x.sqrt()
+}
+
+#[inline]
+pub fn sin(x: f32) -> f32 {
+    sin_cos(x).0
+}
+
+#[inline]
+pub fn cos(x: f32) -> f32 {
+    sin_cos(x).1
+}
+
+#[inline]
+pub fn sin_cos(x: f32) -> (f32, f32) {
+    backend::sin_cos(x)
+}
+
+#[inline]
+pub fn tan(x: f32) -> f32 {
+    backend::tan(x)
+}
+
+#[inline]
+pub fn acos(x: f32) -> f32 {
+    backend::acos(x.clamp(-1.0, 1.0))
+}
+
+#[inline]
+pub fn asin(x: f32) -> f32 {
+    backend::asin(x.clamp(-1.0, 1.0))
+}
+
+#[inline]
+pub fn atan2(y: f32, x: f32) -> f32 {
+    backend::atan2(y, x)
+}
+
+#[inline]
+pub fn exp(x: f32) -> f32 {
+    backend::exp(x)
+}
+
+#[inline]
+pub fn powf(x: f32, n: f32) -> f32 {
+    backend::powf(x, n)
+}
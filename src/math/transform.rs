@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+use super::affine::Affine3;
+use super::matrix::{Mat3, Mat4};
+use super::quat::Quat;
+use super::vector::Vec3;
+use crate::prelude::*;
+
+/**
+ * The position, rotation and scale of an entity relative to its parent
+ *
+ * Entities without a parent are placed relative to the world origin.
+ * Scale is applied first, then rotation, then translation.
+ */
+#[derive(Component, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    };
+
+    pub const fn from_xyz(x: f32, y: f32, z: f32) -> Self {
+        Self::from_translation(Vec3::new(x, y, z))
+    }
+
+    pub const fn from_translation(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub const fn from_rotation(rotation: Quat) -> Self {
+        Self {
+            rotation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub const fn from_scale(scale: Vec3) -> Self {
+        Self {
+            scale,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub const fn with_translation(mut self, translation: Vec3) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    pub const fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub const fn with_scale(mut self, scale: Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /**
+     * The local X axis, rotated into the parent's space.
+     */
+    pub fn right(&self) -> Vec3 {
+        self.rotation * Vec3::X
+    }
+
+    /**
+     * The local Y axis, rotated into the parent's space.
+     */
+    pub fn up(&self) -> Vec3 {
+        self.rotation * Vec3::Y
+    }
+
+    /**
+     * The local negative Z axis, rotated into the parent's space.
+     */
+    pub fn forward(&self) -> Vec3 {
+        self.rotation * -Vec3::Z
+    }
+
+    /**
+     * Rotates the transform in place around its own origin.
+     */
+    pub fn rotate(&mut self, rotation: Quat) {
+        self.rotation = (rotation * self.rotation).normalize();
+    }
+
+    /**
+     * Rotates the transform so that `forward` points at `target`.
+     *
+     * `up` is only used to fix the roll and must not be parallel
+     * to the direction of `target`.
+     */
+    pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+        let back = (self.translation - target).normalize();
+        let right = up.cross(back).normalize();
+        let up = back.cross(right);
+        self.rotation = Quat::from_mat3(&Mat3::from_cols(right, up, back));
+    }
+
+    /**
+     * Applies the transform to a point.
+     */
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        self.rotation * (point * self.scale) + self.translation
+    }
+
+    /**
+     * Combines the transform with one relative to it.
+     *
+     * Scale is combined per axis, so a non-uniform scale
+     * followed by a rotation is only approximated.
+     */
+    pub fn mul_transform(&self, child: &Transform) -> Transform {
+        Transform {
+            translation: self.transform_point(child.translation),
+            rotation: self.rotation * child.rotation,
+            scale: self.scale * child.scale,
+        }
+    }
+
+    pub fn compute_affine(&self) -> Affine3 {
+        Affine3::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+
+    pub fn compute_matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/**
+ * The transform of an entity relative to the world origin
+ *
+ * It is computed from the `Transform`s of the entity and its
+ * ancestors by `hierarchy::propagate_transforms` and should
+ * not be written to directly.
+ */
+#[derive(Component, Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct GlobalTransform(pub Transform);
+
+impl GlobalTransform {
+    pub fn transform(&self) -> &Transform {
+        &self.0
+    }
+
+    pub fn translation(&self) -> Vec3 {
+        self.0.translation
+    }
+
+    pub fn rotation(&self) -> Quat {
+        self.0.rotation
+    }
+
+    pub fn affine(&self) -> Affine3 {
+        self.0.compute_affine()
+    }
+}
+
+impl From<Transform> for GlobalTransform {
+    fn from(transform: Transform) -> Self {
+        Self(transform)
+    }
+}
@@ -0,0 +1,440 @@
+use std::iter::Sum;
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::scalar;
+
+/**
+ * A 2D vector
+ *
+ * Converts to and from egui's `Vec2` and `Pos2`.
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(from = "[f32; 2]", into = "[f32; 2]")]
+#[repr(C)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+/**
+ * A 3D vector
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(from = "[f32; 3]", into = "[f32; 3]")]
+#[repr(C)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+/**
+ * A 4D vector
+ *
+ * It is 16-byte aligned so it fits a SIMD register.
+ */
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(from = "[f32; 4]", into = "[f32; 4]")]
+#[repr(C, align(16))]
+pub struct Vec4 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+macro_rules! impl_vector {
+    ($name:ident, $n:literal, $($field:ident),+) => {
+        impl $name {
+            pub const ZERO: Self = Self::splat(0.0);
+            pub const ONE: Self = Self::splat(1.0);
+            pub const NAN: Self = Self::splat(f32::NAN);
+
+            pub const fn new($($field: f32),+) -> Self {
+                Self { $($field),+ }
+            }
+
+            pub const fn splat(value: f32) -> Self {
+                Self { $($field: value),+ }
+            }
+
+            pub const fn from_array(array: [f32; $n]) -> Self {
+                let [$($field),+] = array;
+                Self { $($field),+ }
+            }
+
+            pub const fn to_array(self) -> [f32; $n] {
+                [$(self.$field),+]
+            }
+
+            pub fn dot(self, rhs: Self) -> f32 {
+                0.0 $(+ self.$field * rhs.$field)+
+            }
+
+            pub fn length_squared(self) -> f32 {
+                self.dot(self)
+            }
+
+            pub fn length(self) -> f32 {
+                scalar::sqrt(self.length_squared())
+            }
+
+            pub fn distance(self, rhs: Self) -> f32 {
+                (self - rhs).length()
+            }
+
+            pub fn distance_squared(self, rhs: Self) -> f32 {
+                (self - rhs).length_squared()
+            }
+
+            /**
+             * Scales the vector to a length of one.
+             *
+             * A zero or non-finite vector gives a non-finite result,
+             * see `try_normalize` and `normalize_or_zero`.
+             */
+            pub fn normalize(self) -> Self {
+                self * (1.0 / self.length())
+            }
+
+            /**
+             * Scales the vector to a length of one.
+             *
+             * If the vector is zero or not finite, it returns None.
+             */
+            pub fn try_normalize(self) -> Option<Self> {
+                let recip = 1.0 / self.length();
+                (recip.is_finite() && recip > 0.0).then(|| self * recip)
+            }
+
+            pub fn normalize_or_zero(self) -> Self {
+                self.try_normalize().unwrap_or(Self::ZERO)
+            }
+
+            pub fn is_normalized(self) -> bool {
+                (self.length_squared() - 1.0).abs() <= 2.0 * scalar::EPSILON
+            }
+
+            /**
+             * Interpolates linearly, giving `self` at 0 and `rhs` at 1.
+             */
+            pub fn lerp(self, rhs: Self, t: f32) -> Self {
+                self + (rhs - self) * t
+            }
+
+            pub fn min(self, rhs: Self) -> Self {
+                Self { $($field: self.$field.min(rhs.$field)),+ }
+            }
+
+            pub fn max(self, rhs: Self) -> Self {
+                Self { $($field: self.$field.max(rhs.$field)),+ }
+            }
+
+            pub fn clamp(self, min: Self, max: Self) -> Self {
+                self.max(min).min(max)
+            }
+
+            pub fn abs(self) -> Self {
+                Self { $($field: self.$field.abs()),+ }
+            }
+
+            pub fn signum(self) -> Self {
+                Self { $($field: self.$field.signum()),+ }
+            }
+
+            pub fn recip(self) -> Self {
+                Self { $($field: 1.0 / self.$field),+ }
+            }
+
+            pub fn min_element(self) -> f32 {
+                self.to_array().into_iter().fold(f32::INFINITY, f32::min)
+            }
+
+            pub fn max_element(self) -> f32 {
+                self.to_array().into_iter().fold(f32::NEG_INFINITY, f32::max)
+            }
+
+            pub fn element_sum(self) -> f32 {
+                0.0 $(+ self.$field)+
+            }
+
+            pub fn element_product(self) -> f32 {
+                1.0 $(* self.$field)+
+            }
+
+            pub fn is_finite(self) -> bool {
+                true $(&& self.$field.is_finite())+
+            }
+
+            /**
+             * Checks if every element differs by at most `max_abs_diff`.
+             */
+            pub fn abs_diff_eq(self, rhs: Self, max_abs_diff: f32) -> bool {
+                true $(&& (self.$field - rhs.$field).abs() <= max_abs_diff)+
+            }
+
+            /**
+             * The part of the vector parallel to `rhs`.
+             *
+             * `rhs` must not be zero.
+             */
+            pub fn project_onto(self, rhs: Self) -> Self {
+                rhs * (self.dot(rhs) / rhs.length_squared())
+            }
+
+            /**
+             * The part of the vector perpendicular to `rhs`.
+             *
+             * `rhs` must not be zero.
+             */
+            pub fn reject_from(self, rhs: Self) -> Self {
+                self - self.project_onto(rhs)
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self { $($field: self.$field + rhs.$field),+ }
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self { $($field: self.$field - rhs.$field),+ }
+            }
+        }
+
+        impl Mul for $name {
+            type Output = Self;
+
+            fn mul(self, rhs: Self) -> Self {
+                Self { $($field: self.$field * rhs.$field),+ }
+            }
+        }
+
+        impl Div for $name {
+            type Output = Self;
+
+            fn div(self, rhs: Self) -> Self {
+                Self { $($field: self.$field / rhs.$field),+ }
+            }
+        }
+
+        impl Mul<f32> for $name {
+            type Output = Self;
+
+            fn mul(self, rhs: f32) -> Self {
+                Self { $($field: self.$field * rhs),+ }
+            }
+        }
+
+        impl Mul<$name> for f32 {
+            type Output = $name;
+
+            fn mul(self, rhs: $name) -> $name {
+                rhs * self
+            }
+        }
+
+        impl Div<f32> for $name {
+            type Output = Self;
+
+            fn div(self, rhs: f32) -> Self {
+                Self { $($field: self.$field / rhs),+ }
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self { $($field: -self.$field),+ }
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl MulAssign for $name {
+            fn mul_assign(&mut self, rhs: Self) {
+                *self = *self * rhs;
+            }
+        }
+
+        impl MulAssign<f32> for $name {
+            fn mul_assign(&mut self, rhs: f32) {
+                *self = *self * rhs;
+            }
+        }
+
+        impl DivAssign<f32> for $name {
+            fn div_assign(&mut self, rhs: f32) {
+                *self = *self / rhs;
+            }
+        }
+
+        impl Index<usize> for $name {
+            type Output = f32;
+
+            fn index(&self, index: usize) -> &f32 {
+                // The type is `repr(C)` with only `f32` fields.
+                let array = unsafe { &*(self as *const Self as *const [f32; $n]) };
+                &array[index]
+            }
+        }
+
+        impl IndexMut<usize> for $name {
+            fn index_mut(&mut self, index: usize) -> &mut f32 {
+                let array = unsafe { &mut *(self as *mut Self as *mut [f32; $n]) };
+                &mut array[index]
+            }
+        }
+
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                iter.fold(Self::ZERO, Add::add)
+            }
+        }
+
+        impl From<[f32; $n]> for $name {
+            fn from(array: [f32; $n]) -> Self {
+                Self::from_array(array)
+            }
+        }
+
+        impl From<$name> for [f32; $n] {
+            fn from(v: $name) -> Self {
+                v.to_array()
+            }
+        }
+    };
+}
+
+impl_vector!(Vec2, 2, x, y);
+impl_vector!(Vec3, 3, x, y, z);
+impl_vector!(Vec4, 4, x, y, z, w);
+
+impl Vec2 {
+    pub const X: Self = Self::new(1.0, 0.0);
+    pub const Y: Self = Self::new(0.0, 1.0);
+
+    /**
+     * The unit vector at `angle` radians counter-clockwise from `X`.
+     */
+    pub fn from_angle(angle: f32) -> Self {
+        let (sin, cos) = scalar::sin_cos(angle);
+        Self::new(cos, sin)
+    }
+
+    /**
+     * The vector rotated by 90 degrees counter-clockwise.
+     */
+    pub fn perp(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    /**
+     * The z component of the 3D cross product.
+     */
+    pub fn perp_dot(self, rhs: Self) -> f32 {
+        self.x * rhs.y - self.y * rhs.x
+    }
+
+    pub fn extend(self, z: f32) -> Vec3 {
+        Vec3::new(self.x, self.y, z)
+    }
+}
+
+impl From<egui::Vec2> for Vec2 {
+    fn from(v: egui::Vec2) -> Self {
+        Self::new(v.x, v.y)
+    }
+}
+
+impl From<Vec2> for egui::Vec2 {
+    fn from(v: Vec2) -> Self {
+        egui::vec2(v.x, v.y)
+    }
+}
+
+impl From<egui::Pos2> for Vec2 {
+    fn from(p: egui::Pos2) -> Self {
+        Self::new(p.x, p.y)
+    }
+}
+
+impl From<Vec2> for egui::Pos2 {
+    fn from(v: Vec2) -> Self {
+        egui::pos2(v.x, v.y)
+    }
+}
+
+impl Vec3 {
+    pub const X: Self = Self::new(1.0, 0.0, 0.0);
+    pub const Y: Self = Self::new(0.0, 1.0, 0.0);
+    pub const Z: Self = Self::new(0.0, 0.0, 1.0);
+
+    pub fn cross(self, rhs: Self) -> Self {
+        Self::new(
+            self.y * rhs.z - self.z * rhs.y,
+            self.z * rhs.x - self.x * rhs.z,
+            self.x * rhs.y - self.y * rhs.x,
+        )
+    }
+
+    pub fn extend(self, w: f32) -> Vec4 {
+        Vec4::new(self.x, self.y, self.z, w)
+    }
+
+    pub fn truncate(self) -> Vec2 {
+        Vec2::new(self.x, self.y)
+    }
+
+    /**
+     * The angle between two vectors in radians.
+     *
+     * Neither vector may be zero.
+     */
+    pub fn angle_between(self, rhs: Self) -> f32 {
+        scalar::acos(self.dot(rhs) / scalar::sqrt(self.length_squared() * rhs.length_squared()))
+    }
+
+    /**
+     * Some unit vector perpendicular to a unit vector.
+     */
+    pub fn any_orthonormal_vector(self) -> Self {
+        // Duff et al., "Building an Orthonormal Basis, Revisited"
+        let sign = 1.0f32.copysign(self.z);
+        let a = -1.0 / (sign + self.z);
+        let b = self.x * self.y * a;
+        Self::new(b, sign + self.y * self.y * a, -self.y)
+    }
+}
+
+impl Vec4 {
+    pub const X: Self = Self::new(1.0, 0.0, 0.0, 0.0);
+    pub const Y: Self = Self::new(0.0, 1.0, 0.0, 0.0);
+    pub const Z: Self = Self::new(0.0, 0.0, 1.0, 0.0);
+    pub const W: Self = Self::new(0.0, 0.0, 0.0, 1.0);
+
+    pub fn truncate(self) -> Vec3 {
+        Vec3::new(self.x, self.y, self.z)
+    }
+}
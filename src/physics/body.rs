@@ -0,0 +1,290 @@
+use serde::{Deserialize, Serialize};
+
+use super::collider::Collider;
+use super::shape::ColliderShape;
+use crate::math::scalar;
+use crate::prelude::*;
+
+/**
+ * Makes an entity move under the physics simulation
+ *
+ * Dynamic bodies are moved by gravity, forces and contacts. Kinematic
+ * bodies move only at the `Velocity` they are given, pushing dynamic
+ * bodies out of their way. Static bodies never move. Colliders
+ * without a rigid body act like static ones.
+ *
+ * Bodies are simulated on their `Transform`, so they should not have
+ * a parent.
+ */
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum RigidBody {
+    #[default]
+    Dynamic,
+    Kinematic,
+    Static,
+}
+
+/**
+ * The velocity of a rigid body, with the angular velocity as an axis
+ * scaled by the speed in radians per second, both in world space
+ */
+#[derive(Component, Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Velocity {
+    pub linear: Vec3,
+    pub angular: Vec3,
+}
+
+impl Velocity {
+    pub const ZERO: Self = Self::new(Vec3::ZERO, Vec3::ZERO);
+
+    pub const fn new(linear: Vec3, angular: Vec3) -> Self {
+        Self { linear, angular }
+    }
+
+    pub const fn linear(linear: Vec3) -> Self {
+        Self::new(linear, Vec3::ZERO)
+    }
+
+    /**
+     * The velocity of a point of the body, `offset` from its origin.
+     */
+    pub fn at(&self, offset: Vec3) -> Vec3 {
+        self.linear + self.angular.cross(offset)
+    }
+}
+
+/**
+ * The mass of a rigid body and its moments of inertia about its
+ * local axes
+ *
+ * The center of mass is taken to be the entity's origin. A mass of
+ * zero makes a body immovable. Bodies added with a collider but no
+ * mass get one from their shape at unit density.
+ */
+#[derive(Component, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Mass {
+    pub mass: f32,
+    pub inertia: Vec3,
+}
+
+impl Mass {
+    pub const fn new(mass: f32, inertia: Vec3) -> Self {
+        Self { mass, inertia }
+    }
+
+    /**
+     * The mass of a shape of uniform `density`, centered on the
+     * shape's origin.
+     *
+     * Triangle meshes and height fields are hollow, so they have no
+     * mass. The inertia of a convex hull is only taken about its
+     * local axes, ignoring the products of inertia.
+     */
+    pub fn from_shape(shape: &ColliderShape, density: f32) -> Self {
+        match shape {
+            ColliderShape::Sphere { radius } => {
+                let mass = density * 4.0 / 3.0 * scalar::PI * radius * radius * radius;
+                Self::new(mass, Vec3::splat(0.4 * mass * radius * radius))
+            }
+            ColliderShape::Cuboid { half_extents } => {
+                let mass = density * 8.0 * half_extents.x * half_extents.y * half_extents.z;
+                let squared = *half_extents * *half_extents;
+                Self::new(
+                    mass,
+                    Vec3::new(
+                        squared.y + squared.z,
+                        squared.x + squared.z,
+                        squared.x + squared.y,
+                    ) * (mass / 3.0),
+                )
+            }
+            ColliderShape::Capsule {
+                half_height: h,
+                radius: r,
+            } => {
+                let cylinder = density * scalar::PI * r * r * 2.0 * h;
+                let caps = density * 4.0 / 3.0 * scalar::PI * r * r * r;
+                // The caps are two halves of a sphere, pulled apart
+                // by the length of the cylinder.
+                let axial = cylinder * r * r * 0.5 + caps * 0.4 * r * r;
+                let across = cylinder * (r * r / 4.0 + h * h / 3.0)
+                    + caps * (0.4 * r * r + h * h + 0.75 * h * r);
+                Self::new(cylinder + caps, Vec3::new(across, axial, across))
+            }
+            ColliderShape::ConvexHull(hull) => {
+                // Sums the tetrahedra between the origin and each face.
+                let mut volume = 0.0;
+                let mut moments = Vec3::ZERO;
+                for triangle in hull.triangles() {
+                    let [a, b, c] = triangle.vertices();
+                    let tetrahedron = a.dot(b.cross(c)) / 6.0;
+                    volume += tetrahedron;
+                    moments +=
+                        (a * a + b * b + c * c + a * b + a * c + b * c) * (tetrahedron / 10.0);
+                }
+                let mass = density * volume;
+                let moments = moments * density;
+                Self::new(
+                    mass,
+                    Vec3::new(
+                        moments.y + moments.z,
+                        moments.x + moments.z,
+                        moments.x + moments.y,
+                    ),
+                )
+            }
+            ColliderShape::TriMesh(_) | ColliderShape::HeightField(_) => Self::new(0.0, Vec3::ZERO),
+        }
+    }
+
+    /**
+     * The inverse of the mass, zero for an immovable body.
+     */
+    pub fn inverse_mass(&self) -> f32 {
+        if self.mass > 0.0 {
+            1.0 / self.mass
+        } else {
+            0.0
+        }
+    }
+
+    /**
+     * The inverse of each moment of inertia, zero where the body
+     * cannot turn.
+     */
+    pub fn inverse_inertia(&self) -> Vec3 {
+        let inverse = |i: f32| if i > 0.0 { 1.0 / i } else { 0.0 };
+        Vec3::new(
+            inverse(self.inertia.x),
+            inverse(self.inertia.y),
+            inverse(self.inertia.z),
+        )
+    }
+}
+
+/**
+ * The mass of a unit cube of unit density.
+ */
+impl Default for Mass {
+    fn default() -> Self {
+        Self::new(1.0, Vec3::splat(1.0 / 6.0))
+    }
+}
+
+/**
+ * The forces on a rigid body, in world space
+ *
+ * They add up over a fixed step and are cleared once they have been
+ * applied, so continuous forces have to be applied every step.
+ */
+#[derive(Component, Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Forces {
+    pub force: Vec3,
+    pub torque: Vec3,
+}
+
+impl Forces {
+    pub fn apply_force(&mut self, force: Vec3) {
+        self.force += force;
+    }
+
+    pub fn apply_torque(&mut self, torque: Vec3) {
+        self.torque += torque;
+    }
+
+    /**
+     * Applies a force at a point in world space, which also turns a
+     * body whose origin is at `origin`.
+     */
+    pub fn apply_force_at(&mut self, force: Vec3, point: Vec3, origin: Vec3) {
+        self.force += force;
+        self.torque += (point - origin).cross(force);
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/**
+ * How a collider's surface behaves in contacts
+ *
+ * Where two colliders touch, their friction coefficients are combined
+ * by their geometric mean and the larger restitution is used.
+ */
+#[derive(Component, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhysicsMaterial {
+    pub friction: f32,
+    /**
+     * How much of the speed a body hits with is kept bouncing off,
+     * from 0 to 1.
+     */
+    pub restitution: f32,
+}
+
+impl PhysicsMaterial {
+    pub const fn new(friction: f32, restitution: f32) -> Self {
+        Self {
+            friction,
+            restitution,
+        }
+    }
+
+    pub fn combine(&self, other: &PhysicsMaterial) -> PhysicsMaterial {
+        Self::new(
+            scalar::sqrt(self.friction * other.friction),
+            self.restitution.max(other.restitution),
+        )
+    }
+}
+
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        Self::new(0.5, 0.0)
+    }
+}
+
+/**
+ * Gives a new rigid body the `Velocity` and `Forces` it is missing
+ */
+pub fn add_body_components(trigger: Trigger<OnAdd, RigidBody>, mut commands: Commands) {
+    let entity = trigger.entity();
+    commands.add(move |world| {
+        let Some(mut body) = world.get_mut(entity) else {
+            return;
+        };
+        if body.get_component::<Velocity>().is_none() {
+            body.set_component(Velocity::ZERO);
+        }
+        if body.get_component::<Forces>().is_none() {
+            body.set_component(Forces::default());
+        }
+        add_mass(&mut body);
+    });
+}
+
+/**
+ * Gives a rigid body that gets a collider the mass of its shape, if
+ * it has no `Mass` yet
+ */
+pub fn add_collider_mass(trigger: Trigger<OnAdd, Collider>, mut commands: Commands) {
+    let entity = trigger.entity();
+    commands.add(move |world| {
+        if let Some(mut body) = world.get_mut(entity) {
+            add_mass(&mut body);
+        }
+    });
+}
+
+fn add_mass(body: &mut EntityMut) {
+    if body.get_component::<RigidBody>().is_none() || body.get_component::<Mass>().is_some() {
+        return;
+    }
+    if let Some(collider) = body.get_component::<Collider>() {
+        let mass = Mass::from_shape(&collider.shape, 1.0);
+        body.set_component(mass);
+    }
+}
@@ -0,0 +1,103 @@
+use super::collider::{Collider, CollisionLayers};
+use crate::ecs::mappings::Query as _;
+use crate::ecs::mappings::spatial::{Overlapping, SpatialGrid};
+use crate::math::geometry::Aabb;
+use crate::prelude::*;
+
+/**
+ * The pairs of colliders whose bounds overlap
+ *
+ * Bounds are indexed in a `SpatialGrid`, so each collider is only
+ * compared with those in the cells it touches. Pairs are sorted by
+ * entity, with the lower entity first, so they come out in the same
+ * order on every run.
+ */
+#[derive(Resource)]
+pub struct Broadphase {
+    grid: SpatialGrid,
+    indexed: Vec<EntityId>,
+    pairs: Vec<(EntityId, EntityId)>,
+}
+
+impl Broadphase {
+    /**
+     * The grid cell size used by `Default`, a few times the size of
+     * a typical collider.
+     */
+    pub const DEFAULT_CELL_SIZE: f32 = 4.0;
+
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            grid: SpatialGrid::new(cell_size),
+            indexed: Vec::new(),
+            pairs: Vec::new(),
+        }
+    }
+
+    pub fn grid(&self) -> &SpatialGrid {
+        &self.grid
+    }
+
+    pub fn pairs(&self) -> &[(EntityId, EntityId)] {
+        &self.pairs
+    }
+
+    /**
+     * Replaces the indexed colliders by `colliders` and finds the
+     * pairs that overlap and whose layers interact.
+     */
+    pub fn update(
+        &mut self,
+        colliders: impl IntoIterator<Item = (EntityId, Aabb, CollisionLayers)>,
+    ) {
+        let mut colliders: Vec<_> = colliders.into_iter().collect();
+        colliders.sort_by_key(|&(id, ..)| id);
+
+        let find = |id: &EntityId| colliders.binary_search_by_key(id, |&(id, ..)| id);
+
+        for id in &self.indexed {
+            if find(id).is_err() {
+                self.grid.remove(*id);
+            }
+        }
+        self.indexed.clear();
+        for &(id, aabb, _) in &colliders {
+            self.grid.update(id, aabb);
+            self.indexed.push(id);
+        }
+
+        self.pairs.clear();
+        for &(a, aabb, layers) in &colliders {
+            for b in Overlapping(aabb).query(&self.grid) {
+                if b <= a {
+                    continue;
+                }
+                let other = &colliders[find(&b).unwrap()];
+                if layers.interacts_with(&other.2) {
+                    self.pairs.push((a, b));
+                }
+            }
+        }
+        self.pairs.sort_unstable();
+    }
+}
+
+impl Default for Broadphase {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CELL_SIZE)
+    }
+}
+
+/**
+ * Indexes the bounds of every collider and finds the overlapping pairs
+ */
+pub fn update_broadphase(
+    colliders: Query<(EntityId, &Collider, &Transform)>,
+    mut broadphase: ResMut<Broadphase>,
+) {
+    broadphase.update(
+        colliders
+            .iter()
+            .map(|(id, collider, transform)| (id, collider.aabb(transform), collider.layers)),
+    );
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+use super::shape::{ColliderShape, HeightField, ShapeError, TriMesh};
+use crate::math::Isometry;
+use crate::math::geometry::{Aabb, ConvexHull, HullError};
+use crate::prelude::*;
+
+/**
+ * Which groups a collider belongs to and which it collides with
+ *
+ * Two colliders only interact if each is in a group the other
+ * collides with. By default colliders are in every group and
+ * collide with every group.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct CollisionLayers {
+    pub memberships: u32,
+    pub filters: u32,
+}
+
+impl CollisionLayers {
+    pub const ALL: Self = Self::new(u32::MAX, u32::MAX);
+    pub const NONE: Self = Self::new(0, 0);
+
+    pub const fn new(memberships: u32, filters: u32) -> Self {
+        Self {
+            memberships,
+            filters,
+        }
+    }
+
+    pub fn interacts_with(&self, other: &CollisionLayers) -> bool {
+        self.memberships & other.filters != 0 && other.memberships & self.filters != 0
+    }
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/**
+ * Gives an entity a shape that collides with other colliders
+ *
+ * The shape is placed by the entity's `Transform`, moved by `offset`.
+ * Colliders are rigid, so the scale of the transform is ignored.
+ */
+#[derive(Component, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Collider {
+    pub shape: ColliderShape,
+    #[serde(default)]
+    pub offset: Isometry,
+    #[serde(default)]
+    pub layers: CollisionLayers,
+}
+
+impl Collider {
+    pub fn new(shape: ColliderShape) -> Self {
+        Self {
+            shape,
+            offset: Isometry::IDENTITY,
+            layers: CollisionLayers::ALL,
+        }
+    }
+
+    pub fn sphere(radius: f32) -> Self {
+        Self::new(ColliderShape::Sphere { radius })
+    }
+
+    pub fn cuboid(half_extents: Vec3) -> Self {
+        Self::new(ColliderShape::Cuboid { half_extents })
+    }
+
+    /**
+     * A capsule along the Y axis, see `ColliderShape::Capsule`.
+     */
+    pub fn capsule(half_height: f32, radius: f32) -> Self {
+        Self::new(ColliderShape::Capsule {
+            half_height,
+            radius,
+        })
+    }
+
+    pub fn convex_hull(points: &[Vec3]) -> Result<Self, HullError> {
+        ConvexHull::new(points).map(|hull| Self::new(ColliderShape::ConvexHull(hull)))
+    }
+
+    pub fn trimesh(vertices: Vec<Vec3>, indices: Vec<[u32; 3]>) -> Result<Self, ShapeError> {
+        TriMesh::new(vertices, indices).map(|mesh| Self::new(ColliderShape::TriMesh(mesh)))
+    }
+
+    pub fn heightfield(heights: Vec<f32>, columns: usize, scale: Vec3) -> Result<Self, ShapeError> {
+        HeightField::new(heights, columns, scale)
+            .map(|field| Self::new(ColliderShape::HeightField(field)))
+    }
+
+    pub fn with_offset(mut self, offset: Isometry) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_layers(mut self, layers: CollisionLayers) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /**
+     * Where the shape is in the world, for an entity at `transform`.
+     */
+    pub fn pose(&self, transform: &Transform) -> Isometry {
+        Isometry::from(*transform) * self.offset
+    }
+
+    /**
+     * The world bounds of the shape, for an entity at `transform`.
+     */
+    pub fn aabb(&self, transform: &Transform) -> Aabb {
+        self.shape.aabb(&self.pose(transform))
+    }
+}
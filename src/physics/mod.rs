@@ -1,4 +1,73 @@
+pub mod body;
+pub mod broadphase;
+pub mod collider;
+pub mod narrowphase;
+pub mod shape;
+pub mod solver;
+
+pub use body::{Forces, Mass, PhysicsMaterial, RigidBody, Velocity};
+pub use broadphase::{Broadphase, update_broadphase};
+pub use collider::{Collider, CollisionLayers};
+pub use narrowphase::{
+    ContactManifold, ContactPair, ContactPoint, Contacts, collide, update_contacts,
+};
+pub use shape::{ColliderShape, HeightField, ShapeError, TriMesh};
+pub use solver::{
+    ContactCache, Gravity, SolverSettings, integrate_positions, integrate_velocities,
+    solve_contacts,
+};
+
 use crate::prelude::*;
 
-#[derive(Component)]
-pub struct Collider {}
+/**
+ * Adds the `Broadphase` and `Contacts` resources, and the
+ * `update_broadphase` and `update_contacts` systems to `FixedUpdate`,
+ * labeled with their names so other systems can order against them
+ */
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Broadphase>()
+            .init_resource::<Contacts>();
+        app.add_system(ScheduleLabel::FixedUpdate, update_broadphase)
+            .label("update_broadphase");
+        app.add_system(ScheduleLabel::FixedUpdate, update_contacts)
+            .label("update_contacts")
+            .after("update_broadphase");
+    }
+}
+
+/**
+ * Simulates rigid bodies on the fixed timestep
+ *
+ * Adds `CollisionPlugin` if it is not there yet, the `Gravity`,
+ * `SolverSettings` and `ContactCache` resources, and the observers
+ * completing new bodies. Each `FixedUpdate` then runs `integrate_velocities`, the
+ * collision systems, `solve_contacts` and `integrate_positions`, in
+ * that order and labeled with their names.
+ */
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<CollisionPlugin>() {
+            app.add_plugin(CollisionPlugin);
+        }
+        app.init_resource::<Gravity>()
+            .init_resource::<SolverSettings>()
+            .init_resource::<ContactCache>()
+            .observe(body::add_body_components)
+            .observe(body::add_collider_mass);
+        app.add_system(ScheduleLabel::FixedUpdate, integrate_velocities)
+            .label("integrate_velocities")
+            .before("update_broadphase");
+        app.add_system(ScheduleLabel::FixedUpdate, solve_contacts)
+            .label("solve_contacts")
+            .after("update_contacts")
+            .after("integrate_velocities");
+        app.add_system(ScheduleLabel::FixedUpdate, integrate_positions)
+            .label("integrate_positions")
+            .after("solve_contacts");
+    }
+}
@@ -0,0 +1,652 @@
+use rayon::prelude::*;
+
+use super::broadphase::Broadphase;
+use super::collider::Collider;
+use super::shape::ColliderShape;
+use crate::math::Isometry;
+use crate::math::geometry::{
+    Aabb, Bounded, Capsule, ConvexHull, Penetration, Sphere, SupportMap, Triangle, epa, gjk,
+};
+use crate::math::scalar;
+use crate::prelude::*;
+
+/**
+ * The most points a manifold keeps, enough to hold a face steady.
+ */
+const MAX_POINTS: usize = 4;
+
+/**
+ * How far below the farthest vertex along the normal a vertex may
+ * be and still belong to the touching face, relative to the size
+ * of the shape. It lets faces tilted by about a degree lie flat.
+ */
+const FEATURE_TOLERANCE: f32 = 0.02;
+
+/**
+ * Manifolds of one mesh whose normals are closer than this cosine
+ * are merged into one.
+ */
+const MERGE_COSINE: f32 = 0.999;
+
+/**
+ * A point where two shapes touch, in world space
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ContactPoint {
+    /**
+     * The point of the first shape deepest inside the second.
+     */
+    pub point_a: Vec3,
+    /**
+     * The point of the second shape deepest inside the first.
+     */
+    pub point_b: Vec3,
+    pub depth: f32,
+}
+
+/**
+ * Where two shapes touch, as a few points sharing one normal
+ *
+ * The normal points from the first shape towards the second, moving
+ * the second by `depth` along it separates a point.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct ContactManifold {
+    pub normal: Vec3,
+    pub points: Vec<ContactPoint>,
+}
+
+impl ContactManifold {
+    /**
+     * The same contact seen from the second shape.
+     */
+    pub fn flipped(self) -> Self {
+        Self {
+            normal: -self.normal,
+            points: self
+                .points
+                .into_iter()
+                .map(|p| ContactPoint {
+                    point_a: p.point_b,
+                    point_b: p.point_a,
+                    depth: p.depth,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn deepest(&self) -> Option<&ContactPoint> {
+        self.points
+            .iter()
+            .max_by(|a, b| a.depth.total_cmp(&b.depth))
+    }
+}
+
+/**
+ * The contact manifolds of two colliding entities
+ *
+ * Convex shapes touch in one manifold. Against a triangle mesh or a
+ * height field there is one for each direction the surface pushes.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct ContactPair {
+    pub entity_a: EntityId,
+    pub entity_b: EntityId,
+    pub manifolds: Vec<ContactManifold>,
+}
+
+/**
+ * The colliders found touching in the last fixed step
+ *
+ * Pairs come in the order of `Broadphase::pairs`, with the lower
+ * entity first.
+ */
+#[derive(Resource, Default)]
+pub struct Contacts {
+    pairs: Vec<ContactPair>,
+}
+
+impl Contacts {
+    pub fn pairs(&self) -> &[ContactPair] {
+        &self.pairs
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /**
+     * The contact between two entities, given in either order.
+     */
+    pub fn get(&self, a: EntityId, b: EntityId) -> Option<&ContactPair> {
+        let key = (a.min(b), a.max(b));
+        self.pairs
+            .binary_search_by_key(&key, |pair| (pair.entity_a, pair.entity_b))
+            .ok()
+            .map(|index| &self.pairs[index])
+    }
+
+    /**
+     * Every contact an entity takes part in.
+     */
+    pub fn involving(&self, entity: EntityId) -> impl Iterator<Item = &ContactPair> {
+        self.pairs
+            .iter()
+            .filter(move |pair| pair.entity_a == entity || pair.entity_b == entity)
+    }
+}
+
+/**
+ * Computes the contact manifolds of every pair from the broadphase
+ *
+ * Pairs are handled in parallel on the rayon pool.
+ */
+pub fn update_contacts(
+    broadphase: Res<Broadphase>,
+    colliders: Query<(&Collider, &Transform)>,
+    mut contacts: ResMut<Contacts>,
+) {
+    let pairs: Vec<_> = broadphase
+        .pairs()
+        .iter()
+        .filter_map(|&(a, b)| {
+            let (collider_a, transform_a) = colliders.get(a)?;
+            let (collider_b, transform_b) = colliders.get(b)?;
+            Some((
+                (a, &collider_a.shape, collider_a.pose(transform_a)),
+                (b, &collider_b.shape, collider_b.pose(transform_b)),
+            ))
+        })
+        .collect();
+
+    contacts.pairs = pairs
+        .into_par_iter()
+        .filter_map(|((a, shape_a, pose_a), (b, shape_b, pose_b))| {
+            let manifolds = collide(shape_a, &pose_a, shape_b, &pose_b);
+            (!manifolds.is_empty()).then_some(ContactPair {
+                entity_a: a,
+                entity_b: b,
+                manifolds,
+            })
+        })
+        .collect();
+}
+
+/**
+ * Finds where two shapes touch, in world space.
+ *
+ * Convex shapes are tested with GJK, and when they overlap EPA gives
+ * the normal. The faces of both shapes facing each other along it are
+ * then clipped against each other for up to four points. Meshes are
+ * tested one triangle at a time, and two meshes never collide.
+ */
+pub fn collide(
+    a: &ColliderShape,
+    pose_a: &Isometry,
+    b: &ColliderShape,
+    pose_b: &Isometry,
+) -> Vec<ContactManifold> {
+    match (Convex::of(a), Convex::of(b)) {
+        (Some(a), Some(b)) => convex_manifold(a, pose_a, b, pose_b).into_iter().collect(),
+        (None, Some(b)) => mesh_manifolds(a, pose_a, b, pose_b),
+        (Some(a), None) => mesh_manifolds(b, pose_b, a, pose_a)
+            .into_iter()
+            .map(ContactManifold::flipped)
+            .collect(),
+        (None, None) => Vec::new(),
+    }
+}
+
+/**
+ * A solid convex shape in its own space
+ */
+#[derive(Clone, Copy)]
+enum Convex<'a> {
+    Sphere(f32),
+    Cuboid(Vec3),
+    Capsule(f32, f32),
+    Hull(&'a ConvexHull),
+    Triangle(Triangle),
+}
+
+impl<'a> Convex<'a> {
+    fn of(shape: &'a ColliderShape) -> Option<Self> {
+        match shape {
+            ColliderShape::Sphere { radius } => Some(Self::Sphere(*radius)),
+            ColliderShape::Cuboid { half_extents } => Some(Self::Cuboid(*half_extents)),
+            ColliderShape::Capsule {
+                half_height,
+                radius,
+            } => Some(Self::Capsule(*half_height, *radius)),
+            ColliderShape::ConvexHull(hull) => Some(Self::Hull(hull)),
+            ColliderShape::TriMesh(_) | ColliderShape::HeightField(_) => None,
+        }
+    }
+
+    /**
+     * The shape without its rounding and the radius of the rounding,
+     * a point for a sphere and a segment for a capsule.
+     */
+    fn core(&self) -> (Self, f32) {
+        match *self {
+            Self::Sphere(radius) => (Self::Sphere(0.0), radius),
+            Self::Capsule(half_height, radius) => (Self::Capsule(half_height, 0.0), radius),
+            _ => (*self, 0.0),
+        }
+    }
+
+    fn size(&self) -> f32 {
+        match self {
+            Self::Sphere(radius) => radius * 2.0,
+            Self::Cuboid(half_extents) => half_extents.max_element() * 2.0,
+            Self::Capsule(half_height, radius) => (half_height + radius) * 2.0,
+            Self::Hull(hull) => hull.aabb().size().max_element(),
+            Self::Triangle(triangle) => triangle.aabb().size().max_element(),
+        }
+    }
+
+    /**
+     * The face, edge or vertex the shape leads with along a unit
+     * direction, as a convex polygon. Round shapes give one point,
+     * or two for the side of a capsule.
+     */
+    fn feature(&self, direction: Vec3) -> Vec<Vec3> {
+        let tolerance = FEATURE_TOLERANCE * self.size();
+        let vertices = match *self {
+            Self::Sphere(radius) => return vec![direction * radius],
+            Self::Capsule(half_height, radius) => {
+                let ends = [Vec3::Y * half_height, Vec3::Y * -half_height];
+                return leading(&ends, direction, tolerance)
+                    .into_iter()
+                    .map(|p| p + direction * radius)
+                    .collect();
+            }
+            Self::Cuboid(half_extents) => Aabb::from_center_half_extents(Vec3::ZERO, half_extents)
+                .corners()
+                .to_vec(),
+            Self::Hull(hull) => hull.points().to_vec(),
+            Self::Triangle(triangle) => triangle.vertices().to_vec(),
+        };
+
+        let mut polygon = leading(&vertices, direction, tolerance);
+        if polygon.len() > 2 {
+            // Wind the points around their center.
+            let center = polygon.iter().copied().sum::<Vec3>() / polygon.len() as f32;
+            let u = direction.any_orthonormal_vector();
+            let v = direction.cross(u);
+            let angle = |p: &Vec3| scalar::atan2((*p - center).dot(v), (*p - center).dot(u));
+            polygon.sort_by(|p, q| angle(p).total_cmp(&angle(q)));
+        }
+        polygon
+    }
+}
+
+impl SupportMap for Convex<'_> {
+    fn support(&self, direction: Vec3) -> Vec3 {
+        match *self {
+            Self::Sphere(radius) => Sphere::new(Vec3::ZERO, radius).support(direction),
+            Self::Cuboid(half_extents) => {
+                Aabb::from_center_half_extents(Vec3::ZERO, half_extents).support(direction)
+            }
+            Self::Capsule(half_height, radius) => {
+                let end = Vec3::Y * half_height;
+                Capsule::new(-end, end, radius).support(direction)
+            }
+            Self::Hull(hull) => hull.support(direction),
+            Self::Triangle(triangle) => triangle.support(direction),
+        }
+    }
+}
+
+/**
+ * A convex shape placed in the world
+ */
+struct Posed<'a> {
+    shape: Convex<'a>,
+    pose: Isometry,
+}
+
+impl Posed<'_> {
+    fn feature(&self, direction: Vec3) -> Vec<Vec3> {
+        let local = self.pose.inverse_transform_vector(direction);
+        self.shape
+            .feature(local)
+            .into_iter()
+            .map(|p| self.pose.transform_point(p))
+            .collect()
+    }
+
+    fn aabb(&self) -> Aabb {
+        let min = Vec3::new(
+            self.support(-Vec3::X).x,
+            self.support(-Vec3::Y).y,
+            self.support(-Vec3::Z).z,
+        );
+        let max = Vec3::new(
+            self.support(Vec3::X).x,
+            self.support(Vec3::Y).y,
+            self.support(Vec3::Z).z,
+        );
+        Aabb::new(min, max)
+    }
+}
+
+impl SupportMap for Posed<'_> {
+    fn support(&self, direction: Vec3) -> Vec3 {
+        let local = self.pose.inverse_transform_vector(direction);
+        self.pose.transform_point(self.shape.support(local))
+    }
+}
+
+/**
+ * The points at most `tolerance` behind the one farthest along
+ * `direction`.
+ */
+fn leading(points: &[Vec3], direction: Vec3, tolerance: f32) -> Vec<Vec3> {
+    let farthest = points
+        .iter()
+        .map(|p| p.dot(direction))
+        .fold(f32::NEG_INFINITY, f32::max);
+    points
+        .iter()
+        .copied()
+        .filter(|p| p.dot(direction) >= farthest - tolerance)
+        .collect()
+}
+
+fn convex_manifold(
+    a: Convex,
+    pose_a: &Isometry,
+    b: Convex,
+    pose_b: &Isometry,
+) -> Option<ContactManifold> {
+    let a = Posed {
+        shape: a,
+        pose: *pose_a,
+    };
+    let b = Posed {
+        shape: b,
+        pose: *pose_b,
+    };
+    let penetration = match rounded_penetration(&a, &b) {
+        Some(penetration) => penetration?,
+        None => epa::penetration(&a, &b)?,
+    };
+    let normal = penetration.normal;
+
+    // The shape with the larger face is the reference the other's
+    // face is clipped against.
+    let face_a = a.feature(normal);
+    let face_b = b.feature(-normal);
+    let mut points = if face_a.len() < 2 || face_b.len() < 2 {
+        Vec::new()
+    } else if face_a.len() >= face_b.len() {
+        clip(&face_a, normal, penetration.point_a, &face_b)
+            .into_iter()
+            .map(|(point_a, point_b, depth)| ContactPoint {
+                point_a,
+                point_b,
+                depth,
+            })
+            .collect()
+    } else {
+        clip(&face_b, -normal, penetration.point_b, &face_a)
+            .into_iter()
+            .map(|(point_b, point_a, depth)| ContactPoint {
+                point_a,
+                point_b,
+                depth,
+            })
+            .collect()
+    };
+    if points.is_empty() {
+        points.push(ContactPoint {
+            point_a: penetration.point_a,
+            point_b: penetration.point_b,
+            depth: penetration.depth,
+        });
+    }
+
+    Some(ContactManifold {
+        normal,
+        points: reduce(points, normal),
+    })
+}
+
+/**
+ * The penetration of shapes with a rounded side, found from the
+ * closest points of their cores. EPA only creeps up on curved
+ * surfaces, this is exact.
+ *
+ * It returns None when neither shape is round or the cores
+ * themselves touch, and EPA is needed.
+ */
+fn rounded_penetration(a: &Posed, b: &Posed) -> Option<Option<Penetration>> {
+    let (core_a, radius_a) = a.shape.core();
+    let (core_b, radius_b) = b.shape.core();
+    if radius_a + radius_b <= 0.0 {
+        return None;
+    }
+    let (p, q) = gjk::closest_points(
+        &Posed {
+            shape: core_a,
+            pose: a.pose,
+        },
+        &Posed {
+            shape: core_b,
+            pose: b.pose,
+        },
+    )?;
+    let distance = p.distance(q);
+    if distance <= f32::EPSILON {
+        return None;
+    }
+    let depth = radius_a + radius_b - distance;
+    let normal = (q - p) / distance;
+    Some((depth >= 0.0).then_some(Penetration {
+        normal,
+        depth,
+        point_a: p + normal * radius_a,
+        point_b: q - normal * radius_b,
+    }))
+}
+
+/**
+ * Clips the incident face to the sides of the reference face, whose
+ * outward normal is `normal` and which passes through `surface`.
+ *
+ * It keeps the points below the reference face, as the point moved
+ * up onto it, the point itself and how deep it is.
+ */
+fn clip(
+    reference: &[Vec3],
+    normal: Vec3,
+    surface: Vec3,
+    incident: &[Vec3],
+) -> Vec<(Vec3, Vec3, f32)> {
+    let sides: Vec<(Vec3, Vec3)> = if reference.len() == 2 {
+        let axis = reference[1] - reference[0];
+        vec![(reference[0], -axis), (reference[1], axis)]
+    } else {
+        let center = reference.iter().copied().sum::<Vec3>() / reference.len() as f32;
+        (0..reference.len())
+            .map(|i| {
+                let p = reference[i];
+                let q = reference[(i + 1) % reference.len()];
+                let out = (q - p).cross(normal);
+                (p, if out.dot(center - p) > 0.0 { -out } else { out })
+            })
+            .collect()
+    };
+
+    let mut polygon = incident.to_vec();
+    for (point, out) in sides {
+        polygon = clip_polygon(&polygon, point, out);
+    }
+    polygon
+        .into_iter()
+        .filter_map(|p| {
+            let depth = (surface - p).dot(normal);
+            (depth >= 0.0).then_some((p + normal * depth, p, depth))
+        })
+        .collect()
+}
+
+/**
+ * The part of a polygon, segment or point behind the plane through
+ * `point` facing `out`.
+ */
+fn clip_polygon(polygon: &[Vec3], point: Vec3, out: Vec3) -> Vec<Vec3> {
+    let distance = |p: Vec3| (p - point).dot(out);
+    let cut = |p: Vec3, q: Vec3| {
+        let (dp, dq) = (distance(p), distance(q));
+        p.lerp(q, dp / (dp - dq))
+    };
+
+    if polygon.len() <= 2 {
+        return match polygon {
+            &[p, q] => match (distance(p) <= 0.0, distance(q) <= 0.0) {
+                (true, true) => vec![p, q],
+                (true, false) => vec![p, cut(p, q)],
+                (false, true) => vec![cut(p, q), q],
+                (false, false) => Vec::new(),
+            },
+            _ => polygon
+                .iter()
+                .copied()
+                .filter(|&p| distance(p) <= 0.0)
+                .collect(),
+        };
+    }
+
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for (i, &p) in polygon.iter().enumerate() {
+        let q = polygon[(i + 1) % polygon.len()];
+        let (inside_p, inside_q) = (distance(p) <= 0.0, distance(q) <= 0.0);
+        if inside_p {
+            clipped.push(p);
+        }
+        if inside_p != inside_q {
+            clipped.push(cut(p, q));
+        }
+    }
+    clipped
+}
+
+/**
+ * Keeps the deepest point and the three that span the largest area
+ * with it.
+ */
+fn reduce(points: Vec<ContactPoint>, normal: Vec3) -> Vec<ContactPoint> {
+    if points.len() <= MAX_POINTS {
+        return points;
+    }
+    let position = |i: usize| points[i].point_b;
+    let best = |score: &dyn Fn(usize) -> f32| {
+        (0..points.len())
+            .max_by(|&i, &j| score(i).total_cmp(&score(j)))
+            .unwrap()
+    };
+
+    let first = best(&|i| points[i].depth);
+    let second = best(&|i| position(i).distance_squared(position(first)));
+    let area = |i: usize| {
+        (position(second) - position(first))
+            .cross(position(i) - position(first))
+            .dot(normal)
+    };
+    let third = best(&|i| area(i).abs());
+    // Points closer than this to the line through the first two are
+    // taken to be on it.
+    let flat = 1e-3 * position(second).distance_squared(position(first));
+
+    let mut chosen = vec![first];
+    if second != first {
+        chosen.push(second);
+    }
+    // Points all on one line span no area, the two ends hold them.
+    if area(third).abs() > flat {
+        chosen.push(third);
+        // The last point goes on the other side of the first two.
+        let side = area(third).signum();
+        let fourth = best(&|i| -side * area(i));
+        if -side * area(fourth) > flat {
+            chosen.push(fourth);
+        }
+    }
+    chosen.into_iter().map(|i| points[i]).collect()
+}
+
+/**
+ * The manifolds of a convex shape against each triangle of a mesh
+ * near it, with the mesh as the first shape.
+ */
+fn mesh_manifolds(
+    mesh: &ColliderShape,
+    pose_mesh: &Isometry,
+    shape: Convex,
+    pose: &Isometry,
+) -> Vec<ContactManifold> {
+    // The triangles are tested in the mesh's space and the results
+    // moved to the world.
+    let relative = pose_mesh.inverse() * *pose;
+    let aabb = Posed {
+        shape,
+        pose: relative,
+    }
+    .aabb();
+
+    let mut manifolds: Vec<ContactManifold> = Vec::new();
+    let mut visit = |triangle: Triangle| {
+        let Some(manifold) = convex_manifold(
+            Convex::Triangle(triangle),
+            &Isometry::IDENTITY,
+            shape,
+            &relative,
+        ) else {
+            return;
+        };
+        let normal = pose_mesh.transform_vector(manifold.normal);
+        let points = manifold.points.into_iter().map(|p| ContactPoint {
+            point_a: pose_mesh.transform_point(p.point_a),
+            point_b: pose_mesh.transform_point(p.point_b),
+            depth: p.depth,
+        });
+        match manifolds
+            .iter_mut()
+            .find(|m| m.normal.dot(normal) > MERGE_COSINE)
+        {
+            Some(merged) => {
+                // Triangles sharing an edge both find the points on it.
+                for point in points {
+                    let tolerance = 1e-6 * (1.0 + point.point_b.length_squared());
+                    if merged
+                        .points
+                        .iter()
+                        .all(|p| p.point_b.distance_squared(point.point_b) > tolerance)
+                    {
+                        merged.points.push(point);
+                    }
+                }
+            }
+            None => manifolds.push(ContactManifold {
+                normal,
+                points: points.collect(),
+            }),
+        }
+    };
+    match mesh {
+        ColliderShape::TriMesh(mesh) => mesh.visit_triangles(&aabb, &mut visit),
+        ColliderShape::HeightField(field) => field.visit_triangles(&aabb, &mut visit),
+        _ => unreachable!("only meshes are not convex"),
+    }
+
+    for manifold in &mut manifolds {
+        manifold.points = reduce(std::mem::take(&mut manifold.points), manifold.normal);
+    }
+    manifolds
+}
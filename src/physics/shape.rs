@@ -0,0 +1,373 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::math::Isometry;
+use crate::math::geometry::{Aabb, Bounded, Bvh, Capsule, ConvexHull, Sphere, Triangle};
+use crate::math::vector::Vec3;
+
+/**
+ * The ways building a `TriMesh` or `HeightField` can fail
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShapeError {
+    /**
+     * The shape has no triangles
+     */
+    Empty,
+    /**
+     * A triangle refers to a vertex the mesh does not have
+     */
+    IndexOutOfBounds,
+    /**
+     * The heights do not fill a grid of at least two by two, or
+     * the scale is not positive
+     */
+    BadGrid,
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "the shape has no triangles"),
+            Self::IndexOutOfBounds => write!(f, "a triangle refers to a missing vertex"),
+            Self::BadGrid => write!(
+                f,
+                "the heights do not fill a 2x2 grid with a positive scale"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ShapeError {}
+
+/**
+ * The shape of a `Collider`, in the collider's own space
+ *
+ * Spheres, cuboids, capsules and convex hulls are solid. Triangle
+ * meshes and height fields are hollow surfaces, meant for static
+ * level geometry, and only collide with the solid shapes.
+ */
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ColliderShape {
+    Sphere {
+        radius: f32,
+    },
+    Cuboid {
+        half_extents: Vec3,
+    },
+    /**
+     * A capsule along the Y axis, `half_height` is half the length
+     * of its inner segment.
+     */
+    Capsule {
+        half_height: f32,
+        radius: f32,
+    },
+    ConvexHull(ConvexHull),
+    TriMesh(TriMesh),
+    HeightField(HeightField),
+}
+
+impl ColliderShape {
+    /**
+     * Checks if the shape is solid and convex, so GJK can handle it.
+     */
+    pub fn is_convex(&self) -> bool {
+        !matches!(self, Self::TriMesh(_) | Self::HeightField(_))
+    }
+
+    pub fn local_aabb(&self) -> Aabb {
+        match self {
+            Self::Sphere { radius } => {
+                Aabb::from_center_half_extents(Vec3::ZERO, Vec3::splat(*radius))
+            }
+            Self::Cuboid { half_extents } => {
+                Aabb::from_center_half_extents(Vec3::ZERO, *half_extents)
+            }
+            Self::Capsule {
+                half_height,
+                radius,
+            } => Aabb::from_center_half_extents(
+                Vec3::ZERO,
+                Vec3::new(*radius, half_height + radius, *radius),
+            ),
+            Self::ConvexHull(hull) => hull.aabb(),
+            Self::TriMesh(mesh) => mesh.aabb(),
+            Self::HeightField(field) => field.aabb(),
+        }
+    }
+
+    /**
+     * The bounds of the shape placed at `pose`.
+     */
+    pub fn aabb(&self, pose: &Isometry) -> Aabb {
+        match self {
+            Self::Sphere { radius } => {
+                Aabb::from_center_half_extents(pose.translation, Vec3::splat(*radius))
+            }
+            Self::Capsule {
+                half_height,
+                radius,
+            } => {
+                let axis = pose.transform_vector(Vec3::Y * *half_height);
+                Capsule::new(pose.translation - axis, pose.translation + axis, *radius).aabb()
+            }
+            Self::ConvexHull(hull) => {
+                Aabb::from_points(hull.points().iter().map(|&p| pose.transform_point(p))).unwrap()
+            }
+            _ => {
+                let local = self.local_aabb();
+                let center = pose.transform_point(local.center());
+                let x = pose
+                    .transform_vector(Vec3::X * local.half_extents().x)
+                    .abs();
+                let y = pose
+                    .transform_vector(Vec3::Y * local.half_extents().y)
+                    .abs();
+                let z = pose
+                    .transform_vector(Vec3::Z * local.half_extents().z)
+                    .abs();
+                Aabb::from_center_half_extents(center, x + y + z)
+            }
+        }
+    }
+}
+
+/**
+ * A surface made of triangles
+ *
+ * The triangles are kept in a `Bvh`, so only the few near another
+ * collider are tested against it. It is serialized as its vertices
+ * and indices and rebuilt when deserialized.
+ */
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(try_from = "TriMeshData", into = "TriMeshData")]
+pub struct TriMesh {
+    vertices: Vec<Vec3>,
+    indices: Vec<[u32; 3]>,
+    bvh: Bvh,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TriMeshData {
+    vertices: Vec<Vec3>,
+    indices: Vec<[u32; 3]>,
+}
+
+impl TriMesh {
+    pub fn new(vertices: Vec<Vec3>, indices: Vec<[u32; 3]>) -> Result<Self, ShapeError> {
+        if indices.is_empty() {
+            return Err(ShapeError::Empty);
+        }
+        if indices
+            .iter()
+            .flatten()
+            .any(|&i| i as usize >= vertices.len())
+        {
+            return Err(ShapeError::IndexOutOfBounds);
+        }
+        let aabbs: Vec<Aabb> = indices
+            .iter()
+            .map(|triangle| Aabb::from_points(triangle.map(|i| vertices[i as usize])).unwrap())
+            .collect();
+        Ok(Self {
+            bvh: Bvh::new(&aabbs),
+            vertices,
+            indices,
+        })
+    }
+
+    pub fn vertices(&self) -> &[Vec3] {
+        &self.vertices
+    }
+
+    pub fn indices(&self) -> &[[u32; 3]] {
+        &self.indices
+    }
+
+    pub fn triangle(&self, index: usize) -> Triangle {
+        let [a, b, c] = self.indices[index].map(|i| self.vertices[i as usize]);
+        Triangle::new(a, b, c)
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = Triangle> + '_ {
+        (0..self.indices.len()).map(|i| self.triangle(i))
+    }
+
+    /**
+     * Calls `visit` with every triangle whose bounds overlap `aabb`.
+     */
+    pub fn visit_triangles(&self, aabb: &Aabb, mut visit: impl FnMut(Triangle)) {
+        self.bvh.query(aabb, |i| visit(self.triangle(i)));
+    }
+}
+
+impl TryFrom<TriMeshData> for TriMesh {
+    type Error = ShapeError;
+
+    fn try_from(data: TriMeshData) -> Result<Self, ShapeError> {
+        Self::new(data.vertices, data.indices)
+    }
+}
+
+impl From<TriMesh> for TriMeshData {
+    fn from(mesh: TriMesh) -> Self {
+        Self {
+            vertices: mesh.vertices,
+            indices: mesh.indices,
+        }
+    }
+}
+
+impl Bounded for TriMesh {
+    fn aabb(&self) -> Aabb {
+        self.bvh.aabb().unwrap()
+    }
+
+    fn bounding_sphere(&self) -> Sphere {
+        Sphere::from_points(&self.vertices).unwrap()
+    }
+}
+
+/**
+ * A terrain surface given by heights on a regular grid
+ *
+ * Heights are stored row by row, rows run along Z and columns along
+ * X. The grid is centered on the origin in X and Z, with samples
+ * `scale.x` and `scale.z` apart, and heights are multiplied by
+ * `scale.y`. Each cell is split into two triangles.
+ */
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(try_from = "HeightFieldData", into = "HeightFieldData")]
+pub struct HeightField {
+    heights: Vec<f32>,
+    columns: usize,
+    scale: Vec3,
+    range: (f32, f32),
+}
+
+#[derive(Serialize, Deserialize)]
+struct HeightFieldData {
+    heights: Vec<f32>,
+    columns: usize,
+    scale: Vec3,
+}
+
+impl HeightField {
+    pub fn new(heights: Vec<f32>, columns: usize, scale: Vec3) -> Result<Self, ShapeError> {
+        if columns < 2
+            || heights.len() < columns * 2
+            || !heights.len().is_multiple_of(columns)
+            || scale.min_element() <= 0.0
+        {
+            return Err(ShapeError::BadGrid);
+        }
+        let range = heights
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &h| {
+                (min.min(h), max.max(h))
+            });
+        Ok(Self {
+            heights,
+            columns,
+            scale,
+            range,
+        })
+    }
+
+    pub fn heights(&self) -> &[f32] {
+        &self.heights
+    }
+
+    pub fn rows(&self) -> usize {
+        self.heights.len() / self.columns
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn scale(&self) -> Vec3 {
+        self.scale
+    }
+
+    fn origin(&self) -> Vec3 {
+        let x = (self.columns - 1) as f32 * self.scale.x * 0.5;
+        let z = (self.rows() - 1) as f32 * self.scale.z * 0.5;
+        Vec3::new(-x, 0.0, -z)
+    }
+
+    /**
+     * The sample at a row and column, in the field's space.
+     */
+    pub fn point(&self, row: usize, column: usize) -> Vec3 {
+        let height = self.heights[row * self.columns + column];
+        self.origin() + Vec3::new(column as f32, height, row as f32) * self.scale
+    }
+
+    /**
+     * Calls `visit` with every triangle whose bounds overlap `aabb`.
+     */
+    pub fn visit_triangles(&self, aabb: &Aabb, mut visit: impl FnMut(Triangle)) {
+        let origin = self.origin();
+        let cells = |min: f32, max: f32, spacing: f32, count: usize| {
+            let last = count as f32 - 2.0;
+            let first = (min / spacing).floor().clamp(0.0, last) as usize;
+            let end = (max / spacing).floor().clamp(0.0, last) as usize;
+            first..=end
+        };
+        let (min, max) = (aabb.min - origin, aabb.max - origin);
+        if !self.aabb().intersects(aabb) {
+            return;
+        }
+
+        for row in cells(min.z, max.z, self.scale.z, self.rows()) {
+            for column in cells(min.x, max.x, self.scale.x, self.columns) {
+                let p00 = self.point(row, column);
+                let p01 = self.point(row, column + 1);
+                let p10 = self.point(row + 1, column);
+                let p11 = self.point(row + 1, column + 1);
+                for triangle in [Triangle::new(p00, p10, p01), Triangle::new(p01, p10, p11)] {
+                    if triangle.aabb().intersects(aabb) {
+                        visit(triangle);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<HeightFieldData> for HeightField {
+    type Error = ShapeError;
+
+    fn try_from(data: HeightFieldData) -> Result<Self, ShapeError> {
+        Self::new(data.heights, data.columns, data.scale)
+    }
+}
+
+impl From<HeightField> for HeightFieldData {
+    fn from(field: HeightField) -> Self {
+        Self {
+            heights: field.heights,
+            columns: field.columns,
+            scale: field.scale,
+        }
+    }
+}
+
+impl Bounded for HeightField {
+    fn aabb(&self) -> Aabb {
+        let origin = self.origin();
+        let (low, high) = self.range;
+        Aabb::new(
+            Vec3::new(origin.x, low * self.scale.y, origin.z),
+            Vec3::new(-origin.x, high * self.scale.y, -origin.z),
+        )
+    }
+
+    fn bounding_sphere(&self) -> Sphere {
+        let aabb = self.aabb();
+        Sphere::new(aabb.center(), aabb.half_extents().length())
+    }
+}
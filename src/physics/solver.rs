@@ -0,0 +1,453 @@
+use std::collections::HashMap;
+
+use super::body::{Forces, Mass, PhysicsMaterial, RigidBody, Velocity};
+use super::narrowphase::Contacts;
+use crate::prelude::*;
+
+/**
+ * The acceleration of every dynamic body with a mass
+ */
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct Gravity(pub Vec3);
+
+impl Default for Gravity {
+    fn default() -> Self {
+        Self(Vec3::new(0.0, -9.81, 0.0))
+    }
+}
+
+/**
+ * How the contact solver trades accuracy for speed
+ */
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct SolverSettings {
+    /**
+     * How many times every contact is solved each step. More make
+     * stacks steadier.
+     */
+    pub iterations: u32,
+    /**
+     * The part of the overlap pushed apart each step, from 0 to 1.
+     */
+    pub baumgarte: f32,
+    /**
+     * How deep shapes may overlap before they are pushed apart, which
+     * keeps resting contacts from jittering.
+     */
+    pub slop: f32,
+    /**
+     * The speed below which bodies stop bouncing.
+     */
+    pub restitution_threshold: f32,
+}
+
+impl Default for SolverSettings {
+    fn default() -> Self {
+        Self {
+            iterations: 8,
+            baumgarte: 0.2,
+            slop: 0.005,
+            restitution_threshold: 1.0,
+        }
+    }
+}
+
+type MovingBody = (
+    &'static RigidBody,
+    &'static Transform,
+    &'static mut Velocity,
+    Option<&'static Mass>,
+    Option<&'static mut Forces>,
+);
+
+/**
+ * Anything a collider can be on, colliders without a `RigidBody`
+ * are static.
+ */
+type ContactBody = (
+    Option<&'static RigidBody>,
+    Option<&'static mut Velocity>,
+    Option<&'static Mass>,
+    Option<&'static PhysicsMaterial>,
+    &'static Transform,
+);
+
+/**
+ * How close a contact point has to stay to one of the last step, in
+ * the space of the first body, to start from its impulses.
+ */
+const MATCH_DISTANCE: f32 = 0.05;
+
+/**
+ * The impulses the contact solver applied in the last step
+ *
+ * Contacts that persist start from them, so resting bodies settle in
+ * a few iterations instead of sinking and drifting.
+ */
+#[derive(Resource, Default)]
+pub struct ContactCache {
+    pairs: HashMap<(EntityId, EntityId), Vec<CachedManifold>>,
+}
+
+struct CachedManifold {
+    normal: Vec3,
+    points: Vec<CachedPoint>,
+}
+
+struct CachedPoint {
+    anchor: Vec3,
+    normal_impulse: f32,
+    friction_impulse: Vec3,
+}
+
+impl ContactCache {
+    pub fn clear(&mut self) {
+        self.pairs.clear();
+    }
+
+    /**
+     * The normal impulse the last step applied between two entities,
+     * given in either order, summed over their contact points.
+     */
+    pub fn normal_impulse(&self, a: EntityId, b: EntityId) -> f32 {
+        self.pairs
+            .get(&(a.min(b), a.max(b)))
+            .map_or(0.0, |manifolds| {
+                manifolds
+                    .iter()
+                    .flat_map(|manifold| &manifold.points)
+                    .map(|point| point.normal_impulse)
+                    .sum()
+            })
+    }
+
+    fn find(&self, pair: (EntityId, EntityId), normal: Vec3, anchor: Vec3) -> Option<&CachedPoint> {
+        self.pairs
+            .get(&pair)?
+            .iter()
+            .filter(|manifold| manifold.normal.dot(normal) > 0.99)
+            .flat_map(|manifold| &manifold.points)
+            .find(|point| point.anchor.distance_squared(anchor) < MATCH_DISTANCE * MATCH_DISTANCE)
+    }
+}
+
+/**
+ * Applies gravity and the accumulated `Forces` to the velocity of
+ * dynamic bodies, and clears the forces of every body
+ */
+pub fn integrate_velocities(
+    gravity: Res<Gravity>,
+    time: Res<FixedTime>,
+    mut bodies: Query<MovingBody>,
+) {
+    let step = time.step_secs();
+    for (body, transform, mut velocity, mass, forces) in bodies.iter_mut() {
+        let forces = forces.map_or_else(Forces::default, |mut forces| {
+            let applied = *forces;
+            forces.clear();
+            applied
+        });
+        let mass = mass.copied().unwrap_or_default();
+        if *body != RigidBody::Dynamic || mass.inverse_mass() == 0.0 {
+            continue;
+        }
+
+        velocity.linear += (gravity.0 + forces.force * mass.inverse_mass()) * step;
+        let rotation = transform.rotation;
+        let torque = rotation.inverse() * forces.torque;
+        velocity.angular += rotation * (torque * mass.inverse_inertia()) * step;
+    }
+}
+
+/**
+ * Moves kinematic and dynamic bodies by their velocity
+ */
+pub fn integrate_positions(
+    time: Res<FixedTime>,
+    mut bodies: Query<(&RigidBody, &Velocity, &mut Transform)>,
+) {
+    let step = time.step_secs();
+    for (body, velocity, mut transform) in bodies.iter_mut() {
+        if *body == RigidBody::Static {
+            continue;
+        }
+        transform.translation += velocity.linear * step;
+        let speed = velocity.angular.length();
+        if speed > 0.0 {
+            let turn = Quat::from_axis_angle(velocity.angular / speed, speed * step);
+            transform.rotation = (turn * transform.rotation).normalize();
+        }
+    }
+}
+
+/**
+ * A body taking part in the contacts of a step
+ */
+struct SolverBody {
+    entity: EntityId,
+    velocity: Velocity,
+    origin: Vec3,
+    rotation: Quat,
+    inverse_mass: f32,
+    inverse_inertia: Vec3,
+    material: PhysicsMaterial,
+    dynamic: bool,
+}
+
+impl SolverBody {
+    /**
+     * Multiplies a world space vector by the inverse inertia tensor.
+     */
+    fn turn(&self, v: Vec3) -> Vec3 {
+        self.rotation * (self.inverse_inertia * (self.rotation.inverse() * v))
+    }
+
+    fn apply_impulse(&mut self, impulse: Vec3, offset: Vec3) {
+        self.velocity.linear += impulse * self.inverse_mass;
+        self.velocity.angular += self.turn(offset.cross(impulse));
+    }
+
+    /**
+     * How hard the body resists an impulse along `direction` at
+     * `offset`, without the mass.
+     */
+    fn angular_resistance(&self, offset: Vec3, direction: Vec3) -> f32 {
+        self.turn(offset.cross(direction))
+            .cross(offset)
+            .dot(direction)
+    }
+}
+
+struct PointConstraint {
+    /**
+     * Where the point is in the space of the first body, to find it
+     * again next step.
+     */
+    anchor: Vec3,
+    offset_a: Vec3,
+    offset_b: Vec3,
+    normal_mass: f32,
+    tangent_mass: [f32; 2],
+    /**
+     * The normal speed the bodies should separate at, to bounce or
+     * to push out the overlap.
+     */
+    bias: f32,
+    normal_impulse: f32,
+    tangent_impulse: [f32; 2],
+}
+
+struct ContactConstraint {
+    a: usize,
+    b: usize,
+    normal: Vec3,
+    tangents: [Vec3; 2],
+    friction: f32,
+    points: Vec<PointConstraint>,
+}
+
+/**
+ * Resolves the `Contacts` of the step with sequential impulses
+ *
+ * Each iteration goes over the contact points in order, applying the
+ * friction and normal impulses that correct the relative velocity at
+ * that point. Contacts are visited in the order of the pairs, so a
+ * run gives the same result every time. Points found in the
+ * `ContactCache` start with the impulses of the last step.
+ */
+pub fn solve_contacts(
+    contacts: Res<Contacts>,
+    settings: Res<SolverSettings>,
+    mut cache: ResMut<ContactCache>,
+    time: Res<FixedTime>,
+    mut bodies: Query<ContactBody>,
+) {
+    let step = time.step_secs();
+    let mut solver_bodies: Vec<SolverBody> = Vec::new();
+    let mut indices: HashMap<EntityId, usize> = HashMap::new();
+    let mut index_of = |entity: EntityId, solver_bodies: &mut Vec<SolverBody>| {
+        if let Some(&index) = indices.get(&entity) {
+            return Some(index);
+        }
+        let (body, velocity, mass, material, transform) = bodies.get_mut(entity)?;
+        let body = body.copied().unwrap_or(RigidBody::Static);
+        let dynamic = body == RigidBody::Dynamic;
+        let mass = if dynamic {
+            mass.copied().unwrap_or_default()
+        } else {
+            Mass::new(0.0, Vec3::ZERO)
+        };
+        let velocity = match (body, velocity) {
+            (RigidBody::Static, _) | (_, None) => Velocity::ZERO,
+            (_, Some(velocity)) => *velocity,
+        };
+        solver_bodies.push(SolverBody {
+            entity,
+            velocity,
+            origin: transform.translation,
+            rotation: transform.rotation,
+            inverse_mass: mass.inverse_mass(),
+            inverse_inertia: mass.inverse_inertia(),
+            material: material.copied().unwrap_or_default(),
+            dynamic,
+        });
+        indices.insert(entity, solver_bodies.len() - 1);
+        Some(solver_bodies.len() - 1)
+    };
+
+    let mut constraints = Vec::new();
+    for pair in contacts.pairs() {
+        let (Some(a), Some(b)) = (
+            index_of(pair.entity_a, &mut solver_bodies),
+            index_of(pair.entity_b, &mut solver_bodies),
+        ) else {
+            continue;
+        };
+        let (body_a, body_b) = (&solver_bodies[a], &solver_bodies[b]);
+        if !body_a.dynamic && !body_b.dynamic {
+            continue;
+        }
+        let material = body_a.material.combine(&body_b.material);
+        let key = (pair.entity_a, pair.entity_b);
+
+        for manifold in &pair.manifolds {
+            let normal = manifold.normal;
+            let tangent = normal.any_orthonormal_vector();
+            let tangents = [tangent, normal.cross(tangent)];
+            let inverse_mass = body_a.inverse_mass + body_b.inverse_mass;
+            let effective_mass = |offset_a: Vec3, offset_b: Vec3, direction: Vec3| {
+                let k = inverse_mass
+                    + body_a.angular_resistance(offset_a, direction)
+                    + body_b.angular_resistance(offset_b, direction);
+                if k > 0.0 { 1.0 / k } else { 0.0 }
+            };
+
+            let points = manifold
+                .points
+                .iter()
+                .map(|point| {
+                    let middle = (point.point_a + point.point_b) * 0.5;
+                    let offset_a = middle - body_a.origin;
+                    let offset_b = middle - body_b.origin;
+                    let speed =
+                        (body_b.velocity.at(offset_b) - body_a.velocity.at(offset_a)).dot(normal);
+                    let bounce = if speed < -settings.restitution_threshold {
+                        -speed * material.restitution
+                    } else {
+                        0.0
+                    };
+                    let push = settings.baumgarte / step * (point.depth - settings.slop).max(0.0);
+                    let anchor = body_a.rotation.inverse() * offset_a;
+                    let (normal_impulse, tangent_impulse) =
+                        cache
+                            .find(key, normal, anchor)
+                            .map_or((0.0, [0.0; 2]), |cached| {
+                                let friction = cached.friction_impulse;
+                                (cached.normal_impulse, tangents.map(|t| friction.dot(t)))
+                            });
+                    PointConstraint {
+                        anchor,
+                        offset_a,
+                        offset_b,
+                        normal_mass: effective_mass(offset_a, offset_b, normal),
+                        tangent_mass: tangents.map(|t| effective_mass(offset_a, offset_b, t)),
+                        bias: bounce.max(push),
+                        normal_impulse,
+                        tangent_impulse,
+                    }
+                })
+                .collect();
+            constraints.push(ContactConstraint {
+                a,
+                b,
+                normal,
+                tangents,
+                friction: material.friction,
+                points,
+            });
+        }
+    }
+
+    for constraint in &constraints {
+        let (a, b) = pair_mut(&mut solver_bodies, constraint.a, constraint.b);
+        for point in &constraint.points {
+            let impulse = constraint.normal * point.normal_impulse
+                + constraint.tangents[0] * point.tangent_impulse[0]
+                + constraint.tangents[1] * point.tangent_impulse[1];
+            a.apply_impulse(-impulse, point.offset_a);
+            b.apply_impulse(impulse, point.offset_b);
+        }
+    }
+    for _ in 0..settings.iterations {
+        for constraint in &mut constraints {
+            solve(constraint, &mut solver_bodies);
+        }
+    }
+
+    cache.clear();
+    for constraint in &constraints {
+        let key = (
+            solver_bodies[constraint.a].entity,
+            solver_bodies[constraint.b].entity,
+        );
+        let points = constraint
+            .points
+            .iter()
+            .map(|point| CachedPoint {
+                anchor: point.anchor,
+                normal_impulse: point.normal_impulse,
+                friction_impulse: constraint.tangents[0] * point.tangent_impulse[0]
+                    + constraint.tangents[1] * point.tangent_impulse[1],
+            })
+            .collect();
+        cache.pairs.entry(key).or_default().push(CachedManifold {
+            normal: constraint.normal,
+            points,
+        });
+    }
+
+    for body in solver_bodies.iter().filter(|body| body.dynamic) {
+        if let Some((_, Some(mut velocity), ..)) = bodies.get_mut(body.entity) {
+            *velocity = body.velocity;
+        }
+    }
+}
+
+fn pair_mut(bodies: &mut [SolverBody], a: usize, b: usize) -> (&mut SolverBody, &mut SolverBody) {
+    if a < b {
+        let (left, right) = bodies.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = bodies.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
+fn solve(constraint: &mut ContactConstraint, bodies: &mut [SolverBody]) {
+    let (a, b) = pair_mut(bodies, constraint.a, constraint.b);
+    let relative = |a: &SolverBody, b: &SolverBody, point: &PointConstraint| {
+        b.velocity.at(point.offset_b) - a.velocity.at(point.offset_a)
+    };
+
+    for point in &mut constraint.points {
+        // Friction first, held within the cone of the normal impulse
+        // found so far.
+        let limit = constraint.friction * point.normal_impulse;
+        for (i, tangent) in constraint.tangents.iter().enumerate() {
+            let speed = relative(a, b, point).dot(*tangent);
+            let total =
+                (point.tangent_impulse[i] - speed * point.tangent_mass[i]).clamp(-limit, limit);
+            let impulse = *tangent * (total - point.tangent_impulse[i]);
+            point.tangent_impulse[i] = total;
+            a.apply_impulse(-impulse, point.offset_a);
+            b.apply_impulse(impulse, point.offset_b);
+        }
+
+        let speed = relative(a, b, point).dot(constraint.normal);
+        let total = (point.normal_impulse + (point.bias - speed) * point.normal_mass).max(0.0);
+        let impulse = constraint.normal * (total - point.normal_impulse);
+        point.normal_impulse = total;
+        a.apply_impulse(-impulse, point.offset_a);
+        b.apply_impulse(impulse, point.offset_b);
+    }
+}
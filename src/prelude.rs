@@ -1 +1,17 @@
+pub use crate::app::{App, AppExit, FixedTime, HeadlessRunner, Plugin, ScheduleLabel, Time};
+pub use crate::ecs::change_detection::{Mut, RemovedComponents};
+pub use crate::ecs::commands::{Commands, EntityCommands};
+pub use crate::ecs::event::{EventReader, EventWriter, Events};
+pub use crate::ecs::hierarchy::{Children, Parent, TransformPlugin};
+pub use crate::ecs::observer::{OnAdd, OnInsert, OnRemove, OnReplace, Trigger};
+pub use crate::ecs::query::{Added, Changed, Many, With, Without};
+pub use crate::ecs::scene::{BinaryScene, MapEntities, Scene, SceneRegistration};
+pub use crate::ecs::system::{IntoSystem, Query, Res, ResMut, System};
+pub use crate::ecs::world::World;
 pub use crate::ecs::*;
+pub use crate::math::{
+    Affine3, GlobalTransform, Isometry, Mat3, Mat4, Quat, Transform, Vec2, Vec3, Vec4,
+};
+pub use crate::physics::{
+    Collider, CollisionLayers, CollisionPlugin, Forces, Mass, PhysicsPlugin, RigidBody, Velocity,
+};
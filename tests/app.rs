@@ -0,0 +1,173 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use peano_engine::prelude::*;
+
+#[derive(Resource, Default)]
+struct Log(Vec<&'static str>);
+
+#[derive(Resource, Default)]
+struct Steps(u32);
+
+fn log(label: &'static str) -> impl Fn(ResMut<Log>) + Send + Sync + 'static {
+    move |mut log: ResMut<Log>| log.0.push(label)
+}
+
+fn count_step(mut steps: ResMut<Steps>) {
+    steps.0 += 1;
+}
+
+struct LogPlugin;
+
+impl Plugin for LogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Log>();
+    }
+}
+
+fn take_log(app: &mut App) -> Vec<&'static str> {
+    std::mem::take(&mut app.world_mut().resource_mut::<Log>().unwrap().0)
+}
+
+#[test]
+fn schedules_run_in_order() {
+    let mut app = App::new();
+    app.add_plugin(LogPlugin);
+    for (label, name) in [
+        (ScheduleLabel::Render, "render"),
+        (ScheduleLabel::PostUpdate, "post update"),
+        (ScheduleLabel::Update, "update"),
+        (ScheduleLabel::FixedUpdate, "fixed update"),
+        (ScheduleLabel::PreUpdate, "pre update"),
+        (ScheduleLabel::Startup, "startup"),
+    ] {
+        app.add_system(label, log(name));
+    }
+
+    app.update_by(FixedTime::DEFAULT_STEP);
+    assert_eq!(
+        take_log(&mut app),
+        [
+            "startup",
+            "pre update",
+            "fixed update",
+            "update",
+            "post update",
+            "render"
+        ]
+    );
+    // Startup only runs once, and no fixed step is due.
+    app.update_by(Duration::ZERO);
+    assert_eq!(
+        take_log(&mut app),
+        ["pre update", "update", "post update", "render"]
+    );
+}
+
+#[test]
+fn fixed_update_runs_per_whole_step() {
+    let mut app = App::new();
+    app.insert_resource(FixedTime::new(Duration::from_millis(10)));
+    app.init_resource::<Steps>();
+    app.add_system(ScheduleLabel::FixedUpdate, count_step);
+
+    app.update_by(Duration::from_millis(25));
+    assert_eq!(app.world().resource::<Steps>().unwrap().0, 2);
+    let overstep = app.world().resource::<FixedTime>().unwrap().overstep();
+    assert!((overstep - 0.5).abs() < 1e-4);
+
+    // The leftover time counts toward the next frame.
+    app.update_by(Duration::from_millis(5));
+    assert_eq!(app.world().resource::<Steps>().unwrap().0, 3);
+
+    // Long frames are capped at `max_steps`.
+    app.world_mut()
+        .resource_mut::<FixedTime>()
+        .unwrap()
+        .set_max_steps(4);
+    app.update_by(Duration::from_millis(1005));
+    assert_eq!(app.world().resource::<Steps>().unwrap().0, 7);
+    // Only the time into the next step is kept.
+    let overstep = app.world().resource::<FixedTime>().unwrap().overstep();
+    assert!((overstep - 0.5).abs() < 1e-4);
+}
+
+#[test]
+#[should_panic(expected = "At least one fixed step")]
+fn max_steps_cannot_be_zero() {
+    FixedTime::default().set_max_steps(0);
+}
+
+#[test]
+fn time_tracks_frames() {
+    let mut app = App::new();
+    app.update_by(Duration::from_millis(20));
+    app.update_by(Duration::from_millis(30));
+    let time = app.world().resource::<Time>().unwrap();
+    assert_eq!(time.frame(), 2);
+    assert_eq!(time.delta(), Duration::from_millis(30));
+    assert_eq!(time.elapsed(), Duration::from_millis(50));
+}
+
+#[test]
+fn plugins_can_be_functions() {
+    fn setup(app: &mut App) {
+        app.init_resource::<Steps>();
+    }
+    let mut app = App::new();
+    app.add_plugin(setup).add_plugin(LogPlugin);
+    assert!(app.is_plugin_added::<LogPlugin>());
+    assert!(app.world().contains_resource::<Steps>());
+    assert!(app.world().contains_resource::<Log>());
+}
+
+#[test]
+#[should_panic(expected = "was added twice")]
+fn plugins_cannot_be_added_twice() {
+    App::new().add_plugin(LogPlugin).add_plugin(LogPlugin);
+}
+
+#[test]
+fn headless_runner_stops_on_exit() {
+    let mut app = App::new();
+    app.init_resource::<Steps>();
+    app.add_system(
+        ScheduleLabel::Update,
+        |mut steps: ResMut<Steps>, mut exit: EventWriter<AppExit>| {
+            steps.0 += 1;
+            if steps.0 == 3 {
+                exit.send(AppExit);
+            }
+        },
+    );
+    HeadlessRunner::new()
+        .fixed_delta(Duration::from_millis(16))
+        .run(&mut app);
+    assert_eq!(app.world().resource::<Steps>().unwrap().0, 3);
+    assert!(app.should_exit());
+}
+
+#[test]
+fn headless_runner_stops_after_frames() {
+    let mut app = App::new();
+    HeadlessRunner::new()
+        .frames(4)
+        .fixed_delta(Duration::ZERO)
+        .run(&mut app);
+    assert_eq!(app.world().resource::<Time>().unwrap().frame(), 4);
+    assert!(!app.should_exit());
+}
+
+#[test]
+fn run_hands_the_app_to_the_runner() {
+    let frames = Rc::new(RefCell::new(0));
+    let seen = frames.clone();
+    let mut app = App::new();
+    app.set_runner(move |mut app| {
+        app.update_by(Duration::ZERO);
+        *seen.borrow_mut() = app.world().resource::<Time>().unwrap().frame();
+    });
+    app.run();
+    assert_eq!(*frames.borrow(), 1);
+}
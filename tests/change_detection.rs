@@ -0,0 +1,197 @@
+use peano_engine::ecs::change_detection::CHECK_TICK_THRESHOLD;
+use peano_engine::prelude::*;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Health(u32);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Poisoned;
+
+#[derive(Resource, Default)]
+struct Seen {
+    added: Vec<EntityId>,
+    changed: Vec<EntityId>,
+    removed: Vec<EntityId>,
+}
+
+fn record_added(query: Query<(EntityId,), Added<Health>>, mut seen: ResMut<Seen>) {
+    seen.added.extend(query.iter().map(|(id,)| id));
+}
+
+fn record_changed(query: Query<(EntityId,), Changed<Health>>, mut seen: ResMut<Seen>) {
+    seen.changed.extend(query.iter().map(|(id,)| id));
+}
+
+fn record_removed(removed: RemovedComponents<Health>, mut seen: ResMut<Seen>) {
+    seen.removed.extend(removed.iter());
+}
+
+fn poison(mut query: Query<(&mut Health,), With<Poisoned>>) {
+    for (mut health,) in query.iter_mut() {
+        health.0 -= 1;
+    }
+}
+
+fn world() -> World {
+    let mut world = World::new();
+    world.init_resource::<Seen>().unwrap();
+    world.add_system(record_added);
+    world.add_system(record_changed);
+    world.add_system(record_removed);
+    world
+}
+
+fn spawn(world: &mut World, health: u32) -> EntityId {
+    let id = world.spawn();
+    world.get_mut(id).unwrap().set_component(Health(health));
+    id
+}
+
+/**
+ * Runs one frame and takes what the recording systems saw in it.
+ */
+fn frame(world: &mut World) -> Seen {
+    world.run_systems();
+    std::mem::take(world.resource_mut::<Seen>().unwrap())
+}
+
+#[test]
+fn added_is_seen_once() {
+    let mut world = world();
+    let first = spawn(&mut world, 1);
+    assert_eq!(frame(&mut world).added, [first]);
+    assert!(frame(&mut world).added.is_empty());
+
+    let second = spawn(&mut world, 1);
+    let seen = frame(&mut world);
+    assert_eq!(seen.added, [second]);
+    // Adding counts as a change too.
+    assert_eq!(seen.changed, [second]);
+
+    // Replacing is a change, not an addition.
+    world.get_mut(first).unwrap().set_component(Health(5));
+    let seen = frame(&mut world);
+    assert!(seen.added.is_empty());
+    assert_eq!(seen.changed, [first]);
+}
+
+#[test]
+fn only_written_components_are_changed() {
+    let mut world = world();
+    world.add_system(poison);
+    let healthy = spawn(&mut world, 3);
+    let poisoned = spawn(&mut world, 4);
+    world.get_mut(poisoned).unwrap().set_component(Poisoned);
+    frame(&mut world);
+
+    // `poison` runs after the recorders, so each write is seen the
+    // frame after it happens.
+    for _ in 0..3 {
+        assert_eq!(frame(&mut world).changed, [poisoned]);
+    }
+    assert_eq!(
+        world.get(poisoned).unwrap().get_component::<Health>(),
+        Some(&Health(0))
+    );
+    assert_eq!(
+        world.get(healthy).unwrap().get_component::<Health>(),
+        Some(&Health(3))
+    );
+}
+
+#[test]
+fn reads_through_mut_are_not_changes() {
+    let mut world = world();
+    let id = spawn(&mut world, 3);
+    frame(&mut world);
+
+    let mut entity = world.get_mut(id).unwrap();
+    let mut health = entity.get_component_mut::<Health>().unwrap();
+    assert_eq!(health.0, 3);
+    health.bypass_change_detection().0 = 4;
+    assert!(frame(&mut world).changed.is_empty());
+
+    world
+        .get_mut(id)
+        .unwrap()
+        .get_component_mut::<Health>()
+        .unwrap()
+        .0 = 5;
+    assert_eq!(frame(&mut world).changed, [id]);
+}
+
+#[test]
+fn removals_are_seen_once() {
+    let mut world = world();
+    let removed = spawn(&mut world, 1);
+    let despawned = spawn(&mut world, 1);
+    frame(&mut world);
+
+    world.get_mut(removed).unwrap().remove_component::<Health>();
+    world.despawn(despawned);
+    assert_eq!(frame(&mut world).removed, [removed, despawned]);
+    assert!(frame(&mut world).removed.is_empty());
+}
+
+#[test]
+fn removals_outlive_one_clear_trackers() {
+    let mut world = World::new();
+    let id = spawn(&mut world, 1);
+    let health = get_component_id::<Health>();
+    world.get_mut(id).unwrap().remove_component::<Health>();
+    world.increment_change_tick();
+
+    // A system running every frame sees each removal before the
+    // second clear after it.
+    world.clear_trackers();
+    assert_eq!(world.table().removed(health).len(), 1);
+    world.increment_change_tick();
+    world.clear_trackers();
+    assert!(world.table().removed(health).is_empty());
+}
+
+#[test]
+fn systems_running_late_still_see_removals() {
+    let mut world = world();
+    let id = spawn(&mut world, 1);
+    frame(&mut world);
+
+    // Removed between the recorder's run and the end of the frame.
+    world.add_system(move |mut commands: Commands| {
+        commands.remove::<Health>(id);
+    });
+    let seen = frame(&mut world);
+    assert!(seen.removed.is_empty());
+    assert_eq!(frame(&mut world).removed, [id]);
+}
+
+#[test]
+fn old_ticks_survive_the_counter_wrapping() {
+    let mut world = world();
+    let id = spawn(&mut world, 3);
+    let added = world
+        .table()
+        .ticks(id, get_component_id::<Health>())
+        .unwrap()
+        .added;
+    // Other systems ran since, so the recording systems last ran
+    // well after `Health` was added.
+    world.advance_change_tick(100);
+    assert_eq!(frame(&mut world).added, [id]);
+
+    // Go all the way around, clearing trackers like frames would,
+    // until the next frame runs at the tick `Health` was added at.
+    let mut remaining = added.wrapping_sub(world.change_tick());
+    while remaining > 0 {
+        let step = remaining.min(CHECK_TICK_THRESHOLD);
+        world.advance_change_tick(step);
+        world.clear_trackers();
+        remaining -= step;
+    }
+    assert_eq!(world.change_tick(), added);
+    let seen = frame(&mut world);
+    assert!(seen.added.is_empty() && seen.changed.is_empty());
+
+    world.get_mut(id).unwrap().set_component(Health(2));
+    assert_eq!(frame(&mut world).changed, [id]);
+}
@@ -0,0 +1,249 @@
+use std::f32::consts::FRAC_PI_2;
+
+use peano_engine::math::Isometry;
+use peano_engine::physics::*;
+use peano_engine::prelude::*;
+
+const TOLERANCE: f32 = 1e-3;
+
+fn spawn(app: &mut App, collider: Collider, transform: Transform) -> EntityId {
+    let world = app.world_mut();
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    entity.set_component(transform);
+    entity.set_component(collider);
+    id
+}
+
+fn step(app: &mut App) {
+    app.update_by(FixedTime::DEFAULT_STEP);
+}
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!(a.distance(b) < TOLERANCE, "{a:?} != {b:?}");
+}
+
+#[test]
+fn layers_filter_pairs() {
+    let mut app = App::new();
+    app.add_plugin(CollisionPlugin);
+    let a = spawn(
+        &mut app,
+        Collider::sphere(1.0).with_layers(CollisionLayers::new(0b01, 0b10)),
+        Transform::default(),
+    );
+    let b = spawn(
+        &mut app,
+        Collider::sphere(1.0).with_layers(CollisionLayers::new(0b10, 0b01)),
+        Transform::from_xyz(0.5, 0.0, 0.0),
+    );
+    // Member of a layer `a` looks at, but not looking back at it.
+    let c = spawn(
+        &mut app,
+        Collider::sphere(1.0).with_layers(CollisionLayers::new(0b10, 0b10)),
+        Transform::from_xyz(-0.5, 0.0, 0.0),
+    );
+    let d = spawn(
+        &mut app,
+        Collider::sphere(1.0).with_layers(CollisionLayers::NONE),
+        Transform::from_xyz(0.0, 0.5, 0.0),
+    );
+    step(&mut app);
+
+    let contacts = app.world().resource::<Contacts>().unwrap();
+    assert!(contacts.get(a, b).is_some());
+    assert!(contacts.get(b, a).is_some());
+    assert!(contacts.get(a, c).is_none());
+    assert_eq!(contacts.involving(c).count(), 0);
+    assert_eq!(contacts.involving(d).count(), 0);
+    assert_eq!(contacts.len(), 1);
+}
+
+#[test]
+fn layers_interact_both_ways() {
+    let a = CollisionLayers::new(0b01, 0b10);
+    let b = CollisionLayers::new(0b10, 0b01);
+    assert!(a.interacts_with(&b) && b.interacts_with(&a));
+    assert!(!a.interacts_with(&a));
+    assert!(!CollisionLayers::NONE.interacts_with(&CollisionLayers::ALL));
+    assert!(CollisionLayers::ALL.interacts_with(&CollisionLayers::default()));
+}
+
+#[test]
+fn offset_moves_collider() {
+    let collider =
+        Collider::sphere(0.5).with_offset(Isometry::from_translation(Vec3::new(2.0, 0.0, 0.0)));
+    let transform =
+        Transform::from_xyz(0.0, 1.0, 0.0).with_rotation(Quat::from_rotation_y(FRAC_PI_2));
+    let pose = collider.pose(&transform);
+    // A quarter turn about Y takes +X to -Z.
+    assert_close(pose.translation, Vec3::new(0.0, 1.0, -2.0));
+    let aabb = collider.aabb(&transform);
+    assert_close(aabb.center(), Vec3::new(0.0, 1.0, -2.0));
+    assert_close(aabb.max - aabb.min, Vec3::splat(1.0));
+
+    let mut app = App::new();
+    app.add_plugin(CollisionPlugin);
+    let offset = spawn(&mut app, collider, transform);
+    let near = spawn(
+        &mut app,
+        Collider::sphere(0.5),
+        Transform::from_xyz(0.0, 1.0, -2.9),
+    );
+    // Overlaps the entity's origin but not its offset collider.
+    let far = spawn(
+        &mut app,
+        Collider::sphere(0.5),
+        Transform::from_xyz(0.0, 1.0, 0.0),
+    );
+    step(&mut app);
+
+    let contacts = app.world().resource::<Contacts>().unwrap();
+    let pair = contacts.get(offset, near).unwrap();
+    let manifold = &pair.manifolds[0];
+    let sign = if pair.entity_a == offset { 1.0 } else { -1.0 };
+    assert_close(manifold.normal * sign, Vec3::new(0.0, 0.0, -1.0));
+    assert!((manifold.points[0].depth - 0.1).abs() < TOLERANCE);
+    assert!(contacts.get(offset, far).is_none());
+}
+
+#[test]
+fn sphere_sphere_manifold() {
+    let sphere = ColliderShape::Sphere { radius: 1.0 };
+    let manifolds = collide(
+        &sphere,
+        &Isometry::IDENTITY,
+        &sphere,
+        &Isometry::from_translation(Vec3::new(1.9, 0.0, 0.0)),
+    );
+    assert_eq!(manifolds.len(), 1);
+    let manifold = &manifolds[0];
+    assert_close(manifold.normal, Vec3::X);
+    assert_eq!(manifold.points.len(), 1);
+    let point = manifold.points[0];
+    assert!((point.depth - 0.1).abs() < TOLERANCE);
+    assert_close(point.point_a, Vec3::new(1.0, 0.0, 0.0));
+    assert_close(point.point_b, Vec3::new(0.9, 0.0, 0.0));
+
+    let apart = collide(
+        &sphere,
+        &Isometry::IDENTITY,
+        &sphere,
+        &Isometry::from_translation(Vec3::new(2.1, 0.0, 0.0)),
+    );
+    assert!(apart.is_empty());
+}
+
+#[test]
+fn box_box_face_manifold() {
+    let cube = ColliderShape::Cuboid {
+        half_extents: Vec3::splat(0.5),
+    };
+    let manifolds = collide(
+        &cube,
+        &Isometry::IDENTITY,
+        &cube,
+        &Isometry::from_translation(Vec3::new(0.2, 0.99, 0.1)),
+    );
+    assert_eq!(manifolds.len(), 1);
+    let manifold = &manifolds[0];
+    assert_close(manifold.normal, Vec3::Y);
+    assert_eq!(manifold.points.len(), 4);
+    for point in &manifold.points {
+        assert!((point.depth - 0.01).abs() < TOLERANCE, "{point:?}");
+        // The points lie where the faces overlap.
+        assert!(point.point_b.x > -0.3 - TOLERANCE && point.point_b.x < 0.5 + TOLERANCE);
+        assert!(point.point_b.z > -0.4 - TOLERANCE && point.point_b.z < 0.5 + TOLERANCE);
+    }
+
+    // Seen from the other box, the normal turns around.
+    let flipped = collide(
+        &cube,
+        &Isometry::from_translation(Vec3::new(0.2, 0.99, 0.1)),
+        &cube,
+        &Isometry::IDENTITY,
+    );
+    assert_close(flipped[0].normal, -Vec3::Y);
+    assert_eq!(flipped[0].points.len(), 4);
+}
+
+#[test]
+fn capsule_box_manifold() {
+    let cube = ColliderShape::Cuboid {
+        half_extents: Vec3::new(2.0, 0.5, 2.0),
+    };
+    let capsule = ColliderShape::Capsule {
+        half_height: 1.0,
+        radius: 0.5,
+    };
+
+    // Lying on its side, the capsule touches along a line.
+    let lying = collide(
+        &cube,
+        &Isometry::IDENTITY,
+        &capsule,
+        &Isometry::new(Vec3::new(0.0, 0.99, 0.0), Quat::from_rotation_z(FRAC_PI_2)),
+    );
+    assert_eq!(lying.len(), 1);
+    assert_close(lying[0].normal, Vec3::Y);
+    assert_eq!(lying[0].points.len(), 2);
+    for point in &lying[0].points {
+        assert!((point.depth - 0.01).abs() < TOLERANCE, "{point:?}");
+        assert!((point.point_a.x.abs() - 1.0).abs() < TOLERANCE, "{point:?}");
+    }
+
+    // Standing up, only the end cap touches.
+    let standing = collide(
+        &cube,
+        &Isometry::IDENTITY,
+        &capsule,
+        &Isometry::from_translation(Vec3::new(0.0, 1.99, 0.0)),
+    );
+    assert_eq!(standing.len(), 1);
+    assert_close(standing[0].normal, Vec3::Y);
+    assert_eq!(standing[0].points.len(), 1);
+    assert!((standing[0].points[0].depth - 0.01).abs() < TOLERANCE);
+}
+
+#[test]
+fn collinear_points_reduce_to_ends() {
+    // A strip of thin triangles, each touching the capsule along a
+    // piece of the same line, gives more points than a manifold keeps
+    // with no area between them.
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for i in 0..=16 {
+        let x = i as f32 * 0.25 - 2.0;
+        vertices.push(Vec3::new(x, 0.0, -1.0));
+        vertices.push(Vec3::new(x, 0.0, 1.0));
+    }
+    for i in 0..16u32 {
+        let [a, b, c, d] = [2 * i, 2 * i + 1, 2 * i + 2, 2 * i + 3];
+        indices.push([a, b, c]);
+        indices.push([c, b, d]);
+    }
+    let mesh = Collider::trimesh(vertices, indices).unwrap().shape;
+    let capsule = ColliderShape::Capsule {
+        half_height: 1.5,
+        radius: 0.5,
+    };
+
+    let manifolds = collide(
+        &mesh,
+        &Isometry::IDENTITY,
+        &capsule,
+        &Isometry::new(Vec3::new(0.0, 0.49, 0.0), Quat::from_rotation_z(FRAC_PI_2)),
+    );
+    assert_eq!(manifolds.len(), 1);
+    let manifold = &manifolds[0];
+    assert_close(manifold.normal, Vec3::Y);
+    assert_eq!(manifold.points.len(), 2, "{:?}", manifold.points);
+    for point in &manifold.points {
+        assert!((point.depth - 0.01).abs() < TOLERANCE, "{point:?}");
+    }
+    // The ends of the line are kept.
+    let xs = manifold.points.iter().map(|p| p.point_b.x);
+    let min = xs.clone().fold(f32::INFINITY, f32::min);
+    let max = xs.fold(f32::NEG_INFINITY, f32::max);
+    assert!((min + 1.5).abs() < TOLERANCE && (max - 1.5).abs() < TOLERANCE);
+}
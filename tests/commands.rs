@@ -0,0 +1,146 @@
+use std::any::Any;
+
+use peano_engine::ecs::commands::CommandQueue;
+use peano_engine::ecs::world::ResourceError;
+use peano_engine::prelude::*;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Health(u32);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Enemy;
+
+#[derive(Resource, PartialEq, Debug)]
+struct Wave(u32);
+
+#[derive(Resource, Default)]
+struct Spawned(Vec<EntityId>);
+
+/**
+ * A resource implemented by hand, so it never gets an id
+ */
+struct Unregistered;
+
+impl Resource for Unregistered {
+    fn get_type_id(&self) -> usize {
+        usize::MAX
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[test]
+fn commands_wait_for_apply() {
+    let mut world = World::new();
+    let target = world.spawn();
+    let mut queue = CommandQueue::default();
+
+    let spawned = {
+        let mut commands = Commands::new(&mut queue, &world);
+        let spawned = commands.spawn().insert(Health(3)).insert(Enemy).id();
+        commands.insert(target, Health(1));
+        commands.insert_resource(Wave(2)).unwrap();
+        spawned
+    };
+    assert_eq!(queue.len(), 4);
+    assert!(!world.contains(spawned));
+    assert!(!world.contains_resource::<Wave>());
+
+    queue.apply(&mut world);
+    assert!(queue.is_empty());
+    let entity = world.get(spawned).unwrap();
+    assert_eq!(entity.get_component::<Health>(), Some(&Health(3)));
+    assert!(entity.has_component::<Enemy>());
+    assert_eq!(
+        world.get(target).unwrap().get_component::<Health>(),
+        Some(&Health(1))
+    );
+    assert_eq!(world.resource::<Wave>(), Ok(&Wave(2)));
+}
+
+#[test]
+fn commands_apply_in_order() {
+    let mut world = World::new();
+    let id = world.spawn();
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, &world);
+    commands.insert(id, Health(1));
+    commands.remove::<Health>(id);
+    commands.insert(id, Health(2));
+    commands.insert_resource(Wave(1)).unwrap();
+    commands.remove_resource::<Wave>();
+    queue.apply(&mut world);
+
+    assert_eq!(
+        world.get(id).unwrap().get_component::<Health>(),
+        Some(&Health(2))
+    );
+    assert!(!world.contains_resource::<Wave>());
+}
+
+#[test]
+fn commands_for_despawned_entities_are_ignored() {
+    let mut world = World::new();
+    let stale = world.spawn();
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, &world);
+    commands.despawn(stale);
+    queue.apply(&mut world);
+    assert!(!world.contains(stale));
+
+    // The recycled slot does not receive commands for its old owner.
+    let reused = world.spawn();
+    assert_eq!(reused.index(), stale.index());
+    let mut commands = Commands::new(&mut queue, &world);
+    commands.entity(stale).insert(Health(1)).insert(Enemy);
+    commands.despawn(stale);
+    queue.apply(&mut world);
+    let entity = world.get(reused).unwrap();
+    assert!(entity.get_component::<Health>().is_none());
+    assert!(!entity.has_component::<Enemy>());
+}
+
+#[test]
+fn systems_reserve_distinct_ids() {
+    fn spawn_enemy(mut commands: Commands, mut spawned: ResMut<Spawned>) {
+        spawned.0.push(commands.spawn().insert(Enemy).id());
+    }
+    fn spawn_healthy(mut commands: Commands) {
+        commands.spawn().insert(Health(5));
+    }
+
+    let mut world = World::new();
+    world.init_resource::<Spawned>().unwrap();
+    let despawned = world.spawn();
+    world.despawn(despawned);
+    world.add_system(spawn_enemy);
+    world.add_system(spawn_healthy);
+    world.run_systems();
+    world.run_systems();
+
+    assert_eq!(world.len(), 4);
+    assert_eq!(world.query::<(&Enemy,), ()>().count(), 2);
+    assert_eq!(world.query::<(&Health,), ()>().count(), 2);
+    let spawned = &world.resource::<Spawned>().unwrap().0;
+    assert_ne!(spawned[0], spawned[1]);
+    assert!(spawned.iter().all(|&id| world.contains(id)));
+}
+
+#[test]
+fn unregistered_resources_fail_when_queued() {
+    let mut world = World::new();
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, &world);
+    assert!(matches!(
+        commands.insert_resource(Unregistered),
+        Err(ResourceError::NotRegistered(_))
+    ));
+    assert!(queue.is_empty());
+    queue.apply(&mut world);
+}
@@ -0,0 +1,71 @@
+use peano_engine::ecs::{
+    component_id_by_key, component_id_by_stable_id, component_key, component_name,
+    component_registration, stable_id,
+};
+use peano_engine::prelude::*;
+
+mod physics {
+    use peano_engine::prelude::*;
+
+    #[derive(Component, Clone, Copy, Debug)]
+    pub struct Position(pub f32);
+}
+
+mod ui {
+    use peano_engine::prelude::*;
+
+    #[derive(Component, Clone, Copy, Debug)]
+    pub struct Position(pub f32);
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+#[component(id = "game.velocity")]
+struct Velocity;
+
+#[test]
+fn keys_are_module_paths() {
+    let physics = get_component_id::<physics::Position>();
+    let ui = get_component_id::<ui::Position>();
+    assert_ne!(physics, ui);
+    assert_eq!(component_key(physics), "component_ids::physics::Position");
+    assert_eq!(component_key(ui), "component_ids::ui::Position");
+    assert_eq!(component_name(physics), "Position");
+    assert_eq!(component_id_by_key("component_ids::ui::Position"), Some(ui));
+    assert_eq!(component_id_by_key("Position"), None);
+}
+
+#[test]
+fn explicit_ids_replace_the_path() {
+    let velocity = get_component_id::<Velocity>();
+    assert_eq!(component_key(velocity), "game.velocity");
+    assert_eq!(component_id_by_key("game.velocity"), Some(velocity));
+    assert_eq!(component_id_by_key("component_ids::Velocity"), None);
+}
+
+#[test]
+fn stable_ids_hash_the_key() {
+    let velocity = get_component_id::<Velocity>();
+    let registration = component_registration(velocity).unwrap();
+    assert_eq!(registration.stable_id(), stable_id("game.velocity"));
+    assert_eq!(
+        component_id_by_stable_id(stable_id("game.velocity")),
+        Some(velocity)
+    );
+    assert_eq!(component_id_by_stable_id(stable_id("game.missing")), None);
+    // FNV-1a of the empty string, so the hash never changes between builds.
+    assert_eq!(stable_id(""), 0xcbf29ce484222325);
+}
+
+#[test]
+fn ids_are_ordered_by_key() {
+    let mut ids = [
+        get_component_id::<physics::Position>(),
+        get_component_id::<ui::Position>(),
+        get_component_id::<Velocity>(),
+    ];
+    ids.sort();
+    let keys = ids.map(component_key);
+    let mut sorted = keys;
+    sorted.sort();
+    assert_eq!(keys, sorted);
+}
@@ -0,0 +1,146 @@
+use peano_engine::ecs::{StorageType, component_key, component_storage};
+use peano_engine::prelude::*;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Wrapper<T>(T);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+#[component(storage = "sparse")]
+struct Tagged<T: Copy>(T);
+
+#[derive(Resource, PartialEq, Debug)]
+struct Setting<T>(T);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+#[component(storage = "sparse")]
+struct Socket(u32);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Dense;
+
+#[derive(Resource, Default)]
+struct Log(Vec<String>);
+
+/**
+ * Logs its value when added and removed.
+ */
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+#[component(on_add = log_add, on_remove = log_remove)]
+struct Watched(u32);
+
+fn log_add(world: &mut World, entity: EntityId) {
+    record(world, entity, "add");
+}
+
+fn log_remove(world: &mut World, entity: EntityId) {
+    record(world, entity, "remove");
+}
+
+fn record(world: &mut World, entity: EntityId, event: &str) {
+    let entity = world.get(entity).unwrap();
+    let entry = format!("{event} {}", entity.get_component::<Watched>().unwrap().0);
+    world.resource_mut::<Log>().unwrap().0.push(entry);
+}
+
+#[test]
+fn generic_components_are_distinct() {
+    let small = get_component_id::<Wrapper<u8>>();
+    let large = get_component_id::<Wrapper<u64>>();
+    assert_ne!(small, large);
+    assert_eq!(component_key(small), std::any::type_name::<Wrapper<u8>>());
+
+    let mut world = World::new();
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    entity.set_component(Wrapper(1u8));
+    entity.set_component(Wrapper(2u64));
+    assert_eq!(entity.get_component::<Wrapper<u8>>(), Some(&Wrapper(1)));
+    assert_eq!(entity.get_component::<Wrapper<u64>>(), Some(&Wrapper(2)));
+    assert!(entity.get_component::<Wrapper<u16>>().is_none());
+}
+
+#[test]
+fn generic_ids_agree_across_threads() {
+    let here = get_component_id::<Wrapper<i8>>();
+    let there = std::thread::spawn(get_component_id::<Wrapper<i8>>)
+        .join()
+        .unwrap();
+    assert_eq!(here, there);
+    assert_eq!(get_component_id::<Wrapper<i8>>(), here);
+}
+
+#[test]
+fn generic_resources_are_distinct() {
+    let mut world = World::new();
+    world.insert_resource(Setting(1u8)).unwrap();
+    world.insert_resource(Setting("one")).unwrap();
+    assert_eq!(world.resource::<Setting<u8>>(), Ok(&Setting(1)));
+    assert_eq!(world.resource::<Setting<&str>>(), Ok(&Setting("one")));
+    assert!(world.resource::<Setting<u16>>().is_err());
+}
+
+#[test]
+fn storage_is_chosen_by_attribute() {
+    assert_eq!(
+        component_storage(get_component_id::<Socket>()),
+        StorageType::Sparse
+    );
+    assert_eq!(
+        component_storage(get_component_id::<Tagged<i8>>()),
+        StorageType::Sparse
+    );
+    assert_eq!(
+        component_storage(get_component_id::<Dense>()),
+        StorageType::Table
+    );
+}
+
+#[test]
+fn sparse_components_do_not_move_entities() {
+    let mut world = World::new();
+    let id = world.spawn();
+    world.get_mut(id).unwrap().set_component(Dense);
+    let before = world.table().location(id).unwrap();
+
+    let mut entity = world.get_mut(id).unwrap();
+    entity.add_component(Socket(1));
+    entity.add_component(Socket(2));
+    assert_eq!(world.table().location(id), Some(before));
+    let sockets: Vec<_> = world
+        .get(id)
+        .unwrap()
+        .get_components::<Socket>()
+        .copied()
+        .collect();
+    assert_eq!(sockets, [Socket(1), Socket(2)]);
+
+    let found: Vec<_> = world
+        .query::<(EntityId, &Socket), With<Dense>>()
+        .map(|(id, socket)| (id, *socket))
+        .collect();
+    assert_eq!(found, [(id, Socket(1))]);
+
+    world.get_mut(id).unwrap().remove_component::<Socket>();
+    assert_eq!(world.table().location(id), Some(before));
+    assert_eq!(world.query::<(&Socket,), ()>().count(), 0);
+}
+
+#[test]
+fn add_and_remove_hooks_see_the_component() {
+    let mut world = World::new();
+    world.init_resource::<Log>().unwrap();
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    entity.set_component(Watched(1));
+    // Replacing and adding more instances is not an addition.
+    entity.set_component(Watched(2));
+    entity.add_component(Watched(3));
+    entity.remove_component::<Watched>();
+    entity.set_component(Watched(4));
+    world.despawn(id);
+
+    assert_eq!(
+        world.resource::<Log>().unwrap().0,
+        ["add 1", "remove 2", "add 4", "remove 4"]
+    );
+}
@@ -0,0 +1,410 @@
+// Long simulation runs whose results must be bit-identical. With the
+// `deterministic` feature they are also checked against recorded
+// checksums.
+
+use std::time::Duration;
+
+use peano_engine::math::geometry::{ConvexHull, Plane, Sphere, gjk};
+use peano_engine::math::scalar;
+use peano_engine::physics::{Broadphase, ContactCache, Contacts};
+use peano_engine::prelude::*;
+
+const STEPS: u64 = 3000;
+const BODIES: usize = 48;
+const PHYSICS_STEPS: u64 = 600;
+
+#[derive(Component, Clone, Copy, Debug)]
+struct Body {
+    velocity: Vec3,
+    spin: Vec3,
+    radius: f32,
+}
+
+#[derive(Resource)]
+struct Obstacle(ConvexHull);
+
+/**
+ * FNV-1a over the bits of every value fed to it.
+ */
+struct Checksum(u64);
+
+impl Checksum {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, values: impl IntoIterator<Item = f32>) {
+        for value in values {
+            for byte in value.to_bits().to_le_bytes() {
+                self.0 ^= u64::from(byte);
+                self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+    }
+}
+
+/**
+ * A small xorshift generator, so the scene does not depend on
+ * anything but its seed.
+ */
+struct Rng(u32);
+
+impl Rng {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn vec3(&mut self, scale: f32) -> Vec3 {
+        Vec3::new(self.next() - 0.5, self.next() - 0.5, self.next() - 0.5) * (2.0 * scale)
+    }
+}
+
+fn integrate(time: Res<FixedTime>, mut bodies: Query<(&mut Transform, &mut Body)>) {
+    let dt = time.step_secs();
+    let ground = Plane::new(Vec3::Y, 0.0);
+    for (mut transform, mut body) in bodies.iter_mut() {
+        body.velocity += Vec3::new(0.0, -9.81, 0.0) * dt;
+        body.velocity *= scalar::powf(0.98, dt);
+        transform.translation += body.velocity * dt;
+
+        if let Some(axis) = body.spin.try_normalize() {
+            let angle = body.spin.length() * dt;
+            transform.rotate(Quat::from_axis_angle(axis, angle));
+        }
+
+        let depth = body.radius - ground.signed_distance(transform.translation);
+        if depth > 0.0 {
+            transform.translation += ground.normal * depth;
+            let normal_speed = body.velocity.dot(ground.normal);
+            if normal_speed < 0.0 {
+                body.velocity -= ground.normal * (1.7 * normal_speed);
+                let roll = ground.normal.cross(body.velocity) / body.radius;
+                body.spin = body.spin.lerp(roll, 0.5);
+            }
+        }
+    }
+}
+
+fn collide(obstacle: Res<Obstacle>, mut bodies: Query<(EntityId, &mut Transform, &mut Body)>) {
+    let mut state: Vec<_> = bodies
+        .iter_mut()
+        .map(|(id, transform, body)| (id, transform.translation, *body))
+        .collect();
+    state.sort_by_key(|(id, ..)| id.to_bits());
+
+    for i in 0..state.len() {
+        for j in i + 1..state.len() {
+            let (_, a, body_a) = state[i];
+            let (_, b, body_b) = state[j];
+            let offset = b - a;
+            let radii = body_a.radius + body_b.radius;
+            if offset.length_squared() >= radii * radii {
+                continue;
+            }
+            let Some(normal) = offset.try_normalize() else {
+                continue;
+            };
+            let push = normal * ((radii - offset.length()) * 0.5);
+            state[i].1 -= push;
+            state[j].1 += push;
+            let closing = (body_b.velocity - body_a.velocity).dot(normal);
+            if closing < 0.0 {
+                state[i].2.velocity += normal * (closing * 0.9);
+                state[j].2.velocity -= normal * (closing * 0.9);
+            }
+        }
+
+        // Bodies touching the obstacle are pushed out along the
+        // shortest way between them.
+        let (_, center, body) = state[i];
+        let sphere = Sphere::new(center, body.radius);
+        if let Some((p, q)) = gjk::closest_points(&sphere, &obstacle.0) {
+            let gap = p.distance(q);
+            if gap < 0.05 {
+                let normal = (p - q).normalize_or_zero();
+                state[i].1 += normal * (0.05 - gap);
+                let speed = state[i].2.velocity.dot(normal);
+                if speed < 0.0 {
+                    state[i].2.velocity -= normal * (1.5 * speed);
+                }
+            }
+        }
+    }
+
+    for (id, translation, body) in state {
+        let (_, mut transform, mut b) = bodies.get_mut(id).unwrap();
+        transform.translation = translation;
+        *b = body;
+    }
+}
+
+fn build_app(seed: u32) -> App {
+    let mut app = App::new();
+    let mut rng = Rng(seed);
+
+    let obstacle_points: Vec<_> = (0..24)
+        .map(|_| rng.vec3(2.0) + Vec3::new(0.0, 2.0, 0.0))
+        .collect();
+    app.insert_resource(Obstacle(ConvexHull::new(&obstacle_points).unwrap()));
+
+    for _ in 0..BODIES {
+        let transform =
+            Transform::from_translation(rng.vec3(6.0) + Vec3::new(0.0, 8.0, 0.0)).with_rotation(
+                Quat::from_euler_yxz(rng.next() * 6.0, rng.next() * 6.0, 0.0),
+            );
+        let body = Body {
+            velocity: rng.vec3(3.0),
+            spin: rng.vec3(4.0),
+            radius: 0.2 + rng.next() * 0.4,
+        };
+        let world = app.world_mut();
+        let id = world.spawn();
+        let mut entity = world.get_mut(id).unwrap();
+        entity.set_component(transform);
+        entity.set_component(body);
+    }
+
+    app.add_system(ScheduleLabel::FixedUpdate, integrate)
+        .label("integrate");
+    app.add_system(ScheduleLabel::FixedUpdate, collide)
+        .after("integrate");
+    app
+}
+
+/**
+ * Runs the scene and returns a checksum of its state every 500 steps.
+ */
+fn run(seed: u32) -> Vec<u64> {
+    let mut app = build_app(seed);
+    let mut checksums = Vec::new();
+    for step in 1..=STEPS {
+        app.update_by(FixedTime::DEFAULT_STEP);
+        if step % 500 == 0 {
+            let mut checksum = Checksum::new();
+            let mut bodies: Vec<_> = app
+                .world()
+                .query::<(EntityId, &Transform, &Body), ()>()
+                .collect();
+            bodies.sort_by_key(|(id, ..)| id.to_bits());
+            for (_, transform, body) in bodies {
+                assert!(transform.translation.is_finite() && transform.translation.y > -1.0);
+                checksum.write(transform.translation.to_array());
+                checksum.write(transform.rotation.to_array());
+                checksum.write(body.velocity.to_array());
+                checksum.write(body.spin.to_array());
+            }
+            checksums.push(checksum.0);
+        }
+    }
+    checksums
+}
+
+/**
+ * A pile of mixed shapes dropped with `PhysicsPlugin` onto a floor
+ * and a static hull, so the broadphase, the narrowphase and the
+ * warm-started solver all take part.
+ */
+fn build_physics_app(seed: u32) -> App {
+    let mut app = App::new();
+    app.add_plugin(PhysicsPlugin);
+    let mut rng = Rng(seed);
+
+    let hull_points: Vec<_> = (0..16).map(|_| rng.vec3(1.5)).collect();
+    let statics = [
+        (
+            Collider::cuboid(Vec3::new(40.0, 0.5, 40.0)),
+            Transform::from_xyz(0.0, -0.5, 0.0),
+        ),
+        (
+            Collider::convex_hull(&hull_points).unwrap(),
+            Transform::from_xyz(0.0, 1.0, 0.0),
+        ),
+    ];
+    let dynamics = (0..BODIES / 2).map(|i| {
+        let collider = match i % 4 {
+            0 => Collider::sphere(0.3 + rng.next() * 0.3),
+            1 => Collider::cuboid(Vec3::new(0.3, 0.4, 0.5) * (0.5 + rng.next())),
+            2 => Collider::capsule(0.2 + rng.next() * 0.3, 0.25),
+            _ => {
+                let points: Vec<_> = (0..10).map(|_| rng.vec3(0.5)).collect();
+                Collider::convex_hull(&points).unwrap()
+            }
+        };
+        let transform =
+            Transform::from_translation(rng.vec3(4.0) + Vec3::new(0.0, 7.0, 0.0)).with_rotation(
+                Quat::from_euler_yxz(rng.next() * 6.0, rng.next() * 6.0, 0.0),
+            );
+        (collider, transform)
+    });
+
+    let bodies = statics
+        .map(|(collider, transform)| (collider, transform, RigidBody::Static))
+        .into_iter()
+        .chain(dynamics.map(|(c, t)| (c, t, RigidBody::Dynamic)))
+        .collect::<Vec<_>>();
+    for (collider, transform, body) in bodies {
+        let velocity = Velocity::new(rng.vec3(2.0), rng.vec3(1.0));
+        let world = app.world_mut();
+        let id = world.spawn();
+        let mut entity = world.get_mut(id).unwrap();
+        entity.set_component(transform);
+        entity.set_component(body);
+        entity.set_component(collider);
+        if body == RigidBody::Dynamic {
+            entity.set_component(velocity);
+        }
+    }
+    app
+}
+
+/**
+ * Runs the pile and returns a checksum of the bodies, the contacts
+ * and the cached impulses every 100 steps.
+ */
+fn run_physics(seed: u32) -> Vec<u64> {
+    let mut app = build_physics_app(seed);
+    let mut checksums = Vec::new();
+    for step in 1..=PHYSICS_STEPS {
+        app.update_by(FixedTime::DEFAULT_STEP);
+        if step % 100 != 0 {
+            continue;
+        }
+        let world = app.world();
+        let mut checksum = Checksum::new();
+        let mut bodies: Vec<_> = world
+            .query::<(EntityId, &Transform, &Velocity), ()>()
+            .collect();
+        bodies.sort_by_key(|(id, ..)| id.to_bits());
+        for (_, transform, velocity) in bodies {
+            assert!(transform.translation.is_finite() && transform.translation.y > -1.0);
+            checksum.write(transform.translation.to_array());
+            checksum.write(transform.rotation.to_array());
+            checksum.write(velocity.linear.to_array());
+            checksum.write(velocity.angular.to_array());
+        }
+
+        let broadphase = world.resource::<Broadphase>().unwrap();
+        checksum.write([broadphase.pairs().len() as f32]);
+        let cache = world.resource::<ContactCache>().unwrap();
+        let contacts = world.resource::<Contacts>().unwrap();
+        let mut resting = false;
+        for pair in contacts.pairs() {
+            let impulse = cache.normal_impulse(pair.entity_a, pair.entity_b);
+            resting |= impulse > 0.0;
+            checksum.write([impulse]);
+            for manifold in &pair.manifolds {
+                checksum.write(manifold.normal.to_array());
+                for point in &manifold.points {
+                    checksum.write(point.point_a.to_array());
+                    checksum.write([point.depth]);
+                }
+            }
+        }
+        // The next step starts from the cached impulses.
+        assert!(resting);
+        checksums.push(checksum.0);
+    }
+    checksums
+}
+
+/**
+ * A checksum of the math functions over a sweep of inputs.
+ */
+fn math_checksum() -> u64 {
+    let mut checksum = Checksum::new();
+    let mut rng = Rng(7);
+    for i in 0..4096 {
+        let x = (i as f32 - 2048.0) * 0.013;
+        checksum.write([scalar::sin(x), scalar::cos(x), scalar::tan(x)]);
+        checksum.write([scalar::acos(x * 0.05), scalar::asin(x * 0.05)]);
+        checksum.write([
+            scalar::atan2(x, 1.5),
+            scalar::exp(x * 0.1),
+            scalar::powf(1.5, x),
+        ]);
+
+        let axis = rng.vec3(1.0).normalize_or_zero();
+        let q = Quat::from_axis_angle(axis, x);
+        let r = Quat::from_euler_yxz(x, x * 0.5, -x);
+        checksum.write(q.slerp(r, rng.next()).to_array());
+        checksum.write(q.to_axis_angle().0.to_array());
+        let m = Transform::from_rotation(q)
+            .with_translation(rng.vec3(10.0))
+            .with_scale(Vec3::splat(1.0 + rng.next()))
+            .compute_matrix();
+        checksum.write(m.inverse().to_cols_array());
+        checksum.write(Mat4::perspective_rh(1.0 + rng.next(), 1.5, 0.1, 100.0).to_cols_array());
+    }
+    checksum.0
+}
+
+#[test]
+fn replays_are_identical() {
+    let runs = [run(1), run(1)];
+    assert_eq!(runs[0], runs[1]);
+    assert_ne!(run(2), runs[0]);
+    assert_eq!(math_checksum(), math_checksum());
+}
+
+#[test]
+fn physics_replays_are_identical() {
+    let runs = [run_physics(1), run_physics(1)];
+    assert_eq!(runs[0], runs[1]);
+    assert_ne!(run_physics(2), runs[0]);
+}
+
+#[test]
+fn fixed_steps_do_not_depend_on_frame_rate() {
+    let mut a = build_app(3);
+    let mut b = build_app(3);
+    for _ in 0..240 {
+        a.update_by(FixedTime::DEFAULT_STEP);
+    }
+    // The same time in uneven frames runs the same fixed steps.
+    let mut remaining = FixedTime::DEFAULT_STEP * 240;
+    let mut frame = Duration::from_millis(3);
+    while !remaining.is_zero() {
+        let delta = frame.min(remaining);
+        b.update_by(delta);
+        remaining -= delta;
+        frame = Duration::from_millis(3 + frame.as_millis() as u64 % 29);
+    }
+    let positions = |app: &App| {
+        let mut bodies: Vec<_> = app.world().query::<(EntityId, &Transform), ()>().collect();
+        bodies.sort_by_key(|(id, _)| id.to_bits());
+        bodies.into_iter().map(|(_, t)| *t).collect::<Vec<_>>()
+    };
+    assert_eq!(positions(&a), positions(&b));
+}
+
+#[cfg(feature = "deterministic")]
+#[test]
+fn runs_match_recorded_checksums() {
+    assert!(scalar::is_deterministic());
+    assert_eq!(math_checksum(), 0x36fe_a04f_a2e3_f9f0);
+    assert_eq!(
+        run(1),
+        [
+            0xd262_a9ed_38d7_2325,
+            0x777e_3d19_92a2_f4aa,
+            0x1276_32ae_8247_6f2e,
+            0x8f5b_b8da_8c3a_3f6c,
+            0xb30e_c9c9_30d1_b433,
+            0xaaa0_ccb2_63f4_2030,
+        ]
+    );
+    assert_eq!(
+        run_physics(1),
+        [
+            0xcfde_d262_67d6_93de,
+            0x8dc0_88a5_b248_4585,
+            0x126b_dff4_362f_1b62,
+            0xeb97_f829_b565_200e,
+            0x5cd3_cad7_f2ab_fd54,
+            0xb700_81d6_ac69_8b57,
+        ]
+    );
+}
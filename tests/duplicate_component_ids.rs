@@ -0,0 +1,16 @@
+use peano_engine::prelude::*;
+
+#[derive(Component, Clone, Copy, Debug)]
+#[component(id = "game.health")]
+struct Health;
+
+#[derive(Component, Clone, Copy, Debug)]
+#[component(id = "game.health")]
+#[allow(dead_code)]
+struct OtherHealth;
+
+#[test]
+#[should_panic(expected = "two components are registered as `game.health`")]
+fn duplicate_ids_are_reported() {
+    get_component_id::<Health>();
+}
@@ -0,0 +1,82 @@
+use std::alloc::Layout;
+
+use peano_engine::ecs::dynamic::{ComponentDescriptor, register_component};
+use peano_engine::ecs::{StorageType, get_component_id};
+use peano_engine::prelude::*;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+#[component(storage = "sparse")]
+struct Sparse(u32);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Dense(u32);
+
+fn sorted(ids: impl Iterator<Item = EntityId>) -> Vec<EntityId> {
+    let mut ids: Vec<_> = ids.collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn bytes_round_trip() {
+    let health = register_component(
+        "test.dynamic.health",
+        ComponentDescriptor::data(Layout::new::<u32>()),
+    )
+    .unwrap();
+    let mut world = World::new();
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    assert!(entity.insert_bytes(health, &7u32.to_ne_bytes()));
+    assert!(!entity.insert_bytes(health, &[0; 2]));
+    entity.get_bytes_mut(health).unwrap()[..4].copy_from_slice(&9u32.to_ne_bytes());
+
+    let bytes = world.get(id).unwrap().get_bytes(health).unwrap();
+    assert_eq!(u32::from_ne_bytes(bytes.try_into().unwrap()), 9);
+    assert!(world.get_mut(id).unwrap().remove_by_id(health));
+    assert!(world.get(id).unwrap().get_bytes(health).is_none());
+}
+
+#[test]
+fn duplicate_keys_are_rejected() {
+    let layout = Layout::new::<u8>();
+    register_component("test.dynamic.unique", ComponentDescriptor::data(layout)).unwrap();
+    assert!(register_component("test.dynamic.unique", ComponentDescriptor::data(layout)).is_err());
+}
+
+#[test]
+fn query_by_id_matches_sparse_components() {
+    let shield = register_component(
+        "test.dynamic.shield",
+        ComponentDescriptor {
+            storage: StorageType::Sparse,
+            ..ComponentDescriptor::data(Layout::new::<u32>())
+        },
+    )
+    .unwrap();
+    let sparse = get_component_id::<Sparse>();
+    let dense = get_component_id::<Dense>();
+
+    let mut world = World::new();
+    let ids: Vec<_> = (0..4).map(|_| world.spawn()).collect();
+    for (i, &id) in ids.iter().enumerate() {
+        let mut entity = world.get_mut(id).unwrap();
+        if i % 2 == 0 {
+            assert!(entity.insert_bytes(shield, &(i as u32).to_ne_bytes()));
+            entity.set_component(Sparse(i as u32));
+        }
+        if i < 3 {
+            entity.set_component(Dense(i as u32));
+        }
+    }
+
+    let query = |components: &[usize]| sorted(world.query_by_id(components).map(|e| e.id()));
+    assert_eq!(query(&[shield]), [ids[0], ids[2]]);
+    assert_eq!(query(&[sparse]), [ids[0], ids[2]]);
+    assert_eq!(query(&[shield, dense]), [ids[0], ids[2]]);
+    assert_eq!(query(&[dense]), [ids[0], ids[1], ids[2]]);
+
+    world.get_mut(ids[2]).unwrap().remove_by_id(shield);
+    let query = |components: &[usize]| sorted(world.query_by_id(components).map(|e| e.id()));
+    assert_eq!(query(&[shield, sparse]), [ids[0]]);
+}
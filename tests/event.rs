@@ -0,0 +1,144 @@
+use peano_engine::prelude::*;
+
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+struct Hit(u32);
+
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+struct Unused;
+
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+struct Moved<T>(T);
+
+#[derive(Resource, Default)]
+struct Received(Vec<u32>);
+
+#[derive(Resource, Default)]
+struct Outbox(Vec<u32>);
+
+fn write(mut outbox: ResMut<Outbox>, mut writer: EventWriter<Hit>) {
+    writer.send_batch(outbox.0.drain(..).map(Hit));
+}
+
+fn read(mut reader: EventReader<Hit>, mut received: ResMut<Received>) {
+    received.0.extend(reader.read().map(|hit| hit.0));
+}
+
+fn world() -> World {
+    let mut world = World::new();
+    world.add_event::<Hit>().unwrap();
+    world.init_resource::<Received>().unwrap();
+    world.init_resource::<Outbox>().unwrap();
+    world
+}
+
+/**
+ * Queues events for `write`, runs one frame and takes what `read` got.
+ */
+fn frame(world: &mut World, sent: &[u32]) -> Vec<u32> {
+    world.resource_mut::<Outbox>().unwrap().0.extend(sent);
+    world.run_systems();
+    std::mem::take(&mut world.resource_mut::<Received>().unwrap().0)
+}
+
+#[test]
+fn events_live_for_two_updates() {
+    let mut events = Events::<Hit>::new();
+    events.send(Hit(1));
+    events.update();
+    events.send(Hit(2));
+    assert_eq!(events.len(), 2);
+    assert_eq!(events.count(), 2);
+
+    events.update();
+    assert_eq!(events.iter_since(0).copied().collect::<Vec<_>>(), [Hit(2)]);
+    events.update();
+    assert!(events.is_empty());
+    assert_eq!(events.count(), 2);
+}
+
+#[test]
+fn iter_since_skips_seen_events() {
+    let mut events = Events::<Hit>::new();
+    events.send_batch([Hit(1), Hit(2)]);
+    events.update();
+    events.send(Hit(3));
+    let since = |n| events.iter_since(n).map(|h| h.0).collect::<Vec<_>>();
+    assert_eq!(since(0), [1, 2, 3]);
+    assert_eq!(since(1), [2, 3]);
+    assert_eq!(since(2), [3]);
+    assert_eq!(since(3), []);
+}
+
+#[test]
+fn clear_drops_everything() {
+    let mut events = Events::<Hit>::new();
+    events.send(Hit(1));
+    events.update();
+    events.send(Hit(2));
+    events.clear();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn readers_after_the_writer_see_events_once() {
+    let mut world = world();
+    world.add_system(write).label("write");
+    world.add_system(read).after("write");
+    assert_eq!(frame(&mut world, &[1, 2]), [1, 2]);
+    assert_eq!(frame(&mut world, &[3]), [3]);
+    assert_eq!(frame(&mut world, &[]), []);
+}
+
+#[test]
+fn readers_before_the_writer_see_events_once() {
+    let mut world = world();
+    world.add_system(read).label("read");
+    world.add_system(write).after("read");
+    assert_eq!(frame(&mut world, &[1, 2]), []);
+    assert_eq!(frame(&mut world, &[3]), [1, 2]);
+    assert_eq!(frame(&mut world, &[]), [3]);
+    assert_eq!(frame(&mut world, &[]), []);
+}
+
+#[test]
+fn events_sent_outside_systems_are_read() {
+    let mut world = world();
+    world.add_system(read);
+    world.send_event(Hit(7)).unwrap();
+    assert_eq!(frame(&mut world, &[]), [7]);
+    assert_eq!(frame(&mut world, &[]), []);
+    assert_eq!(world.send_event(Unused), Err(Unused));
+}
+
+#[test]
+fn readers_have_their_own_cursor() {
+    let mut world = world();
+    world.add_system(read);
+    world.add_system(|mut reader: EventReader<Hit>, mut outbox: ResMut<Outbox>| {
+        // Skips the events instead of reading them.
+        if !reader.is_empty() {
+            outbox.0.push(reader.len() as u32 * 100);
+            reader.clear();
+        }
+    });
+    world.send_event(Hit(1)).unwrap();
+    world.send_event(Hit(2)).unwrap();
+    assert_eq!(frame(&mut world, &[]), [1, 2]);
+    assert_eq!(world.resource::<Outbox>().unwrap().0, [200]);
+}
+
+#[test]
+fn generic_events_are_updated() {
+    let mut world = World::new();
+    world.add_event::<Moved<u8>>().unwrap();
+    world.add_event::<Moved<i64>>().unwrap();
+    world.send_event(Moved(1u8)).unwrap();
+    world.send_event(Moved(-1i64)).unwrap();
+    assert_eq!(world.send_event(Moved(1u16)), Err(Moved(1)));
+
+    world.update_events();
+    assert_eq!(world.resource::<Events<Moved<u8>>>().unwrap().len(), 1);
+    world.update_events();
+    assert!(world.resource::<Events<Moved<u8>>>().unwrap().is_empty());
+    assert!(world.resource::<Events<Moved<i64>>>().unwrap().is_empty());
+}
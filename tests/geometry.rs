@@ -0,0 +1,361 @@
+use peano_engine::math::geometry::{
+    Aabb, Bounded, Capsule, ConvexHull, Obb, Plane, Ray, RayCast, Segment, Sphere, SupportMap,
+    Triangle, gjk,
+};
+use peano_engine::math::{Quat, Transform, Vec3};
+use proptest::prelude::*;
+
+const TOLERANCE: f32 = 1e-3;
+
+fn vec3(range: f32) -> impl Strategy<Value = Vec3> {
+    (-range..range, -range..range, -range..range).prop_map(|(x, y, z)| Vec3::new(x, y, z))
+}
+
+fn unit() -> impl Strategy<Value = f32> {
+    0.0f32..=1.0
+}
+
+fn quat() -> impl Strategy<Value = Quat> {
+    (vec3(1.0), -1.0f32..1.0)
+        .prop_filter("too short to normalize", |(v, w)| {
+            v.length_squared() + w * w > 0.01
+        })
+        .prop_map(|(v, w)| Quat::from_xyzw(v.x, v.y, v.z, w).normalize())
+}
+
+fn aabb() -> impl Strategy<Value = Aabb> {
+    (vec3(10.0), vec3(3.0)).prop_map(|(center, extents)| {
+        Aabb::from_center_half_extents(center, extents.abs() + Vec3::splat(0.1))
+    })
+}
+
+fn sphere() -> impl Strategy<Value = Sphere> {
+    (vec3(10.0), 0.1f32..5.0).prop_map(|(center, radius)| Sphere::new(center, radius))
+}
+
+fn obb() -> impl Strategy<Value = Obb> {
+    (aabb(), quat())
+        .prop_map(|(aabb, rotation)| Obb::new(aabb.center(), aabb.half_extents(), rotation))
+}
+
+fn capsule() -> impl Strategy<Value = Capsule> {
+    (vec3(10.0), vec3(3.0), 0.1f32..3.0)
+        .prop_map(|(a, offset, radius)| Capsule::new(a, a + offset, radius))
+}
+
+fn triangle() -> impl Strategy<Value = Triangle> {
+    (vec3(10.0), vec3(10.0), vec3(10.0)).prop_map(|(a, b, c)| Triangle::new(a, b, c))
+}
+
+fn points() -> impl Strategy<Value = Vec<Vec3>> {
+    (vec3(5.0), prop::collection::vec(vec3(3.0), 4..32))
+        .prop_map(|(center, points)| points.into_iter().map(|p| p + center).collect())
+}
+
+fn hull() -> impl Strategy<Value = ConvexHull> {
+    points().prop_filter_map("degenerate hull", |points| ConvexHull::new(&points).ok())
+}
+
+fn ray() -> impl Strategy<Value = Ray> {
+    (vec3(20.0), vec3(1.0))
+        .prop_filter("zero direction", |(_, d)| d.length_squared() > 0.01)
+        .prop_map(|(origin, direction)| Ray::new(origin, direction))
+}
+
+/**
+ * A ray from `origin` aimed at `target`, and the distance between them.
+ */
+fn aimed(origin: Vec3, target: Vec3) -> Option<(Ray, f32)> {
+    let distance = origin.distance(target);
+    (distance > 0.1).then(|| (Ray::new(origin, target - origin), distance))
+}
+
+proptest! {
+    #[test]
+    fn aabb_merge_contains_both(a in aabb(), b in aabb()) {
+        let merged = a.merge(&b);
+        prop_assert!(merged.contains(&a) && merged.contains(&b));
+        prop_assert!(merged.intersects(&a) && merged.intersects(&b));
+    }
+
+    #[test]
+    fn aabb_intersection_is_inside_both(a in aabb(), b in aabb()) {
+        match a.intersection(&b) {
+            Some(overlap) => prop_assert!(a.contains(&overlap) && b.contains(&overlap)),
+            None => prop_assert!(!a.intersects(&b) && !a.contains_point(b.center())),
+        }
+    }
+
+    #[test]
+    fn aabb_transformed_contains_corners(
+        a in aabb(),
+        translation in vec3(10.0),
+        rotation in quat(),
+        scale in vec3(3.0),
+    ) {
+        let transform = Transform { translation, rotation, scale }.compute_affine();
+        let transformed = a.transformed(&transform).grow(TOLERANCE);
+        for corner in a.corners() {
+            prop_assert!(transformed.contains_point(transform.transform_point3(corner)));
+        }
+    }
+
+    #[test]
+    fn aabb_closest_point_is_nearest(a in aabb(), p in vec3(20.0), t in (unit(), unit(), unit())) {
+        let closest = a.closest_point(p);
+        prop_assert!(a.contains_point(closest));
+        let sample = a.min + a.size() * Vec3::new(t.0, t.1, t.2);
+        prop_assert!(closest.distance(p) <= sample.distance(p) + TOLERANCE);
+        prop_assert!((a.distance_squared_to_point(p) - closest.distance_squared(p)).abs() <= TOLERANCE);
+    }
+
+    #[test]
+    fn sphere_merge_contains_both(a in sphere(), b in sphere()) {
+        let merged = a.merge(&b);
+        for s in [a, b] {
+            prop_assert!(merged.center.distance(s.center) + s.radius <= merged.radius + TOLERANCE);
+        }
+        prop_assert!(merged.radius <= a.radius + b.radius + a.center.distance(b.center) + TOLERANCE);
+    }
+
+    #[test]
+    fn sphere_from_points_contains_all(points in points()) {
+        let sphere = Sphere::from_points(&points).unwrap();
+        for p in points {
+            prop_assert!(sphere.contains_point(p));
+        }
+    }
+
+    #[test]
+    fn sphere_overlaps_agree_with_distances(a in sphere(), b in sphere(), box_ in aabb()) {
+        let gap = a.center.distance(b.center) - a.radius - b.radius;
+        if gap.abs() > TOLERANCE {
+            prop_assert_eq!(a.intersects(&b), gap < 0.0);
+            prop_assert_eq!(gjk::intersects(&a, &b), gap < 0.0);
+        }
+        if gap > TOLERANCE {
+            prop_assert!((gjk::distance(&a, &b) - gap).abs() <= 1e-2);
+        }
+        let gap = box_.distance_squared_to_point(a.center).sqrt() - a.radius;
+        if gap.abs() > TOLERANCE {
+            prop_assert_eq!(a.intersects_aabb(&box_), gap < 0.0);
+            prop_assert_eq!(gjk::intersects(&a, &box_), gap < 0.0);
+        }
+    }
+
+    #[test]
+    fn bounding_volumes_enclose_shapes(
+        o in obb(),
+        c in capsule(),
+        t in triangle(),
+        h in hull(),
+        direction in vec3(1.0),
+    ) {
+        prop_assume!(direction.length_squared() > 0.01);
+        let check = |support: Vec3, aabb: Aabb, sphere: Sphere| {
+            aabb.grow(TOLERANCE).contains_point(support)
+                && sphere.center.distance(support) <= sphere.radius + TOLERANCE
+        };
+        prop_assert!(check(o.support(direction), o.aabb(), o.bounding_sphere()));
+        prop_assert!(check(c.support(direction), c.aabb(), c.bounding_sphere()));
+        prop_assert!(check(t.support(direction), t.aabb(), t.bounding_sphere()));
+        prop_assert!(check(h.support(direction), h.aabb(), h.bounding_sphere()));
+    }
+
+    #[test]
+    fn segment_closest_points_are_nearest(
+        a in vec3(10.0),
+        b in vec3(10.0),
+        c in vec3(10.0),
+        d in vec3(10.0),
+        s in unit(),
+        t in unit(),
+    ) {
+        let (first, second) = (Segment::new(a, b), Segment::new(c, d));
+        let (p, q) = first.closest_points(&second);
+        prop_assert!(first.distance_squared_to_point(p) <= TOLERANCE);
+        prop_assert!(second.distance_squared_to_point(q) <= TOLERANCE);
+        prop_assert!(p.distance(q) <= first.at(s).distance(second.at(t)) + TOLERANCE);
+    }
+
+    #[test]
+    fn triangle_closest_point_is_nearest(t in triangle(), p in vec3(20.0), u in unit(), v in unit()) {
+        let weights = t.closest_weights(p);
+        prop_assert!(weights.iter().all(|&w| w >= 0.0));
+        prop_assert!((weights.iter().sum::<f32>() - 1.0).abs() <= TOLERANCE);
+        let (u, v) = if u + v > 1.0 { (1.0 - u, 1.0 - v) } else { (u, v) };
+        let sample = t.a + (t.b - t.a) * u + (t.c - t.a) * v;
+        prop_assert!(t.closest_point(p).distance(p) <= sample.distance(p) + TOLERANCE);
+    }
+
+    #[test]
+    fn triangle_ray_hits_inside(t in triangle(), origin in vec3(20.0), u in unit(), v in unit()) {
+        prop_assume!(t.area() > 0.5);
+        let (u, v) = if u + v > 1.0 { (1.0 - u, 1.0 - v) } else { (u, v) };
+        let target = t.a + (t.b - t.a) * u + (t.c - t.a) * v;
+        let Some((ray, distance)) = aimed(origin, target) else { return Ok(()) };
+        // Grazing rays can miss by rounding.
+        prop_assume!(ray.direction.dot(t.normal()).abs() > 0.1);
+        let hit = t.cast_ray(&ray, f32::INFINITY);
+        prop_assert!(hit.is_some());
+        let hit = hit.unwrap();
+        prop_assert!((hit.distance - distance).abs() <= 1e-2);
+        prop_assert!(hit.normal.dot(ray.direction) <= 0.0);
+    }
+
+    #[test]
+    fn plane_closest_point_and_ray(
+        point in vec3(10.0),
+        normal in vec3(1.0),
+        p in vec3(20.0),
+        ray in ray(),
+    ) {
+        prop_assume!(normal.length_squared() > 0.01);
+        let plane = Plane::from_point_normal(point, normal);
+        prop_assert!(plane.signed_distance(plane.closest_point(p)).abs() <= TOLERANCE);
+        if let Some(hit) = plane.cast_ray(&ray, f32::INFINITY) {
+            // Grazing rays hit far away, where rounding grows.
+            prop_assert!(plane.signed_distance(hit.point).abs() <= 1e-5 * hit.distance.max(100.0));
+            prop_assert!(hit.normal.dot(ray.direction) <= 0.0);
+        }
+    }
+
+    #[test]
+    fn obb_sat_agrees_with_gjk(a in obb(), b in obb()) {
+        let grow = |o: Obb, factor: f32| Obb::new(o.center, o.half_extents * factor, o.rotation);
+        if a.intersects(&b) {
+            prop_assert!(gjk::distance(&a, &b) <= 1e-2);
+        } else {
+            prop_assert!(grow(a, 1.01).intersects(&grow(b, 1.01)) || !gjk::intersects(&a, &b));
+        }
+        if gjk::intersects(&grow(a, 0.99), &grow(b, 0.99)) {
+            prop_assert!(a.intersects(&b));
+        }
+    }
+
+    #[test]
+    fn capsule_overlaps_agree_with_gjk(a in capsule(), b in capsule(), s in sphere()) {
+        let gap = a.segment().distance_squared_to_segment(&b.segment()).sqrt() - a.radius - b.radius;
+        if gap.abs() > 1e-2 {
+            prop_assert_eq!(a.intersects(&b), gap < 0.0);
+            prop_assert_eq!(gjk::intersects(&a, &b), gap < 0.0);
+        }
+        let gap = a.distance_to_point(s.center) - s.radius;
+        if gap.abs() > 1e-2 {
+            prop_assert_eq!(a.intersects_sphere(&s), gap < 0.0);
+        }
+    }
+
+    #[test]
+    fn hull_contains_its_points(points in points()) {
+        let Ok(hull) = ConvexHull::new(&points) else { return Ok(()) };
+        for p in points {
+            prop_assert!(hull.distance_squared_to_point(p) <= 1e-6);
+        }
+        for plane in hull.planes() {
+            for p in hull.points() {
+                prop_assert!(plane.signed_distance(*p) <= TOLERANCE);
+            }
+        }
+        prop_assert!(hull.volume() > 0.0);
+        prop_assert!(hull.contains_point(hull.centroid()));
+    }
+
+    #[test]
+    fn hull_closest_point_is_nearest(h in hull(), p in vec3(20.0), direction in vec3(1.0)) {
+        let closest = h.closest_point(p);
+        prop_assert!(h.distance_squared_to_point(closest) <= 1e-6);
+        let support = h.support(direction);
+        prop_assert!(closest.distance(p) <= support.distance(p) + TOLERANCE);
+    }
+
+    #[test]
+    fn hull_agrees_with_gjk_and_boxes(h in hull(), a in aabb(), offset in vec3(3.0)) {
+        let box_hull = ConvexHull::new(&a.corners()).unwrap();
+        prop_assert!((box_hull.volume() - a.volume()).abs() <= 1e-2 * a.volume());
+        prop_assert!(box_hull.centroid().abs_diff_eq(a.center(), 1e-2));
+        let p = a.center() + offset;
+        prop_assert_eq!(box_hull.contains_point(p), a.contains_point(p));
+
+        if let Some((p, q)) = gjk::closest_points(&h, &a) {
+            prop_assert!(h.distance_squared_to_point(p) <= 1e-4);
+            prop_assert!(a.distance_squared_to_point(q) <= 1e-4);
+            prop_assert!(!h.contains_point(a.center()));
+        } else {
+            let hull_box = h.aabb();
+            prop_assert!(hull_box.grow(TOLERANCE).intersects(&a));
+        }
+    }
+
+    #[test]
+    fn hull_survives_transforms_and_serde(
+        h in hull(),
+        translation in vec3(10.0),
+        rotation in quat(),
+        p in vec3(20.0),
+    ) {
+        let transform = Transform::from_translation(translation).with_rotation(rotation);
+        let moved = h.transformed(&transform.compute_affine());
+        let local = transform.compute_affine().inverse().transform_point3(p);
+        prop_assert!((moved.distance_squared_to_point(p).sqrt()
+            - h.distance_squared_to_point(local).sqrt()).abs() <= 1e-2);
+
+        let text = ron::to_string(&h).unwrap();
+        let loaded: ConvexHull = ron::from_str(&text).unwrap();
+        prop_assert!((loaded.volume() - h.volume()).abs() <= 1e-2 * h.volume());
+    }
+
+    #[test]
+    fn ray_hits_land_on_shapes(
+        a in aabb(),
+        s in sphere(),
+        o in obb(),
+        c in capsule(),
+        h in hull(),
+        ray in ray(),
+    ) {
+        let distance_to = |p: Vec3| [
+            a.distance_squared_to_point(p).sqrt(),
+            s.distance_to_point(p),
+            o.distance_squared_to_point(p).sqrt(),
+            c.distance_to_point(p),
+            h.distance_squared_to_point(p).sqrt(),
+        ];
+        let hits = [
+            a.cast_ray(&ray, f32::INFINITY),
+            s.cast_ray(&ray, f32::INFINITY),
+            o.cast_ray(&ray, f32::INFINITY),
+            c.cast_ray(&ray, f32::INFINITY),
+            h.cast_ray(&ray, f32::INFINITY),
+        ];
+        for (i, hit) in hits.into_iter().enumerate() {
+            let Some(hit) = hit else { continue };
+            prop_assert!(distance_to(hit.point)[i] <= 1e-2, "shape {} hit off its surface", i);
+            prop_assert!((hit.normal.length() - 1.0).abs() <= TOLERANCE);
+            prop_assert!(hit.normal.dot(ray.direction) <= TOLERANCE);
+            if hit.distance > 0.1 {
+                prop_assert!(distance_to(ray.at(hit.distance - 0.05))[i] > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn rays_aimed_inside_hit(
+        a in aabb(),
+        s in sphere(),
+        o in obb(),
+        c in capsule(),
+        h in hull(),
+        origin in vec3(30.0),
+    ) {
+        let targets = [a.center(), s.center, o.center, c.segment().center(), h.centroid()];
+        let hits = targets.map(|target| aimed(origin, target));
+        let casts: [&dyn RayCast; 5] = [&a, &s, &o, &c, &h];
+        for (shape, aimed) in casts.iter().zip(hits) {
+            let Some((ray, distance)) = aimed else { continue };
+            let hit = shape.cast_ray(&ray, f32::INFINITY);
+            prop_assert!(hit.is_some_and(|hit| hit.distance <= distance + TOLERANCE));
+            prop_assert!(shape.cast_ray(&ray, distance * 0.5).is_none_or(|hit| hit.distance <= distance * 0.5));
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::f32::consts::FRAC_PI_2;
+use std::time::Duration;
+
+use peano_engine::prelude::*;
+
+fn children(world: &World, id: EntityId) -> Vec<EntityId> {
+    world
+        .get(id)
+        .unwrap()
+        .get_component::<Children>()
+        .map_or_else(Vec::new, |c| c.to_vec())
+}
+
+fn parent(world: &World, id: EntityId) -> Option<EntityId> {
+    world
+        .get(id)
+        .unwrap()
+        .get_component::<Parent>()
+        .map(Parent::get)
+}
+
+#[test]
+fn set_parent_links_both_ways() {
+    let mut world = World::new();
+    let root = world.spawn();
+    let a = world.spawn();
+    let b = world.spawn();
+    assert!(world.set_parent(a, root));
+    assert!(world.set_parent(b, root));
+    assert_eq!(children(&world, root), [a, b]);
+    assert_eq!(parent(&world, a), Some(root));
+
+    // Reparenting detaches from the old parent.
+    assert!(world.set_parent(a, b));
+    assert_eq!(children(&world, root), [b]);
+    assert_eq!(children(&world, b), [a]);
+
+    assert!(world.remove_parent(a));
+    assert!(!world.remove_parent(a));
+    assert_eq!(parent(&world, a), None);
+    assert!(!world.get(b).unwrap().has_component::<Children>());
+}
+
+#[test]
+fn cycles_are_refused() {
+    let mut world = World::new();
+    let root = world.spawn();
+    let child = world.spawn();
+    let grandchild = world.spawn();
+    world.set_parent(child, root);
+    world.set_parent(grandchild, child);
+
+    assert!(!world.set_parent(root, grandchild));
+    assert!(!world.set_parent(root, root));
+    assert_eq!(parent(&world, root), None);
+
+    let stale = world.spawn();
+    world.despawn(stale);
+    assert!(!world.set_parent(child, stale));
+    assert_eq!(parent(&world, child), Some(root));
+}
+
+#[test]
+fn despawn_orphans_children() {
+    let mut world = World::new();
+    let root = world.spawn();
+    let middle = world.spawn();
+    let leaf = world.spawn();
+    world.set_parent(middle, root);
+    world.set_parent(leaf, middle);
+
+    assert!(world.despawn(middle));
+    assert!(world.contains(leaf));
+    assert_eq!(parent(&world, leaf), None);
+    assert!(children(&world, root).is_empty());
+}
+
+#[test]
+fn despawn_recursive_takes_descendants() {
+    let mut world = World::new();
+    let root = world.spawn();
+    let middle = world.spawn();
+    let leaves = [world.spawn(), world.spawn()];
+    let sibling = world.spawn();
+    world.set_parent(middle, root);
+    world.set_parent(sibling, root);
+    for leaf in leaves {
+        world.set_parent(leaf, middle);
+    }
+
+    assert!(world.despawn_recursive(middle));
+    assert!(!world.contains(middle));
+    assert!(leaves.iter().all(|&leaf| !world.contains(leaf)));
+    assert_eq!(children(&world, root), [sibling]);
+    assert!(!world.despawn_recursive(middle));
+}
+
+#[test]
+fn commands_edit_the_hierarchy() {
+    let mut world = World::new();
+    let root = world.spawn();
+    world.add_system(move |mut commands: Commands| {
+        let child = commands.spawn().set_parent(root).id();
+        commands.spawn().set_parent(child);
+    });
+    world.run_systems();
+    let child = children(&world, root)[0];
+    assert_eq!(children(&world, child).len(), 1);
+}
+
+#[derive(Resource, Default)]
+struct Log(Vec<(&'static str, EntityId)>);
+
+fn parent_added(trigger: Trigger<OnAdd, Parent>, mut log: ResMut<Log>) {
+    log.0.push(("parent added", trigger.entity()));
+}
+
+fn parent_removed(trigger: Trigger<OnRemove, Parent>, mut log: ResMut<Log>) {
+    log.0.push(("parent removed", trigger.entity()));
+}
+
+fn children_added(trigger: Trigger<OnAdd, Children>, mut log: ResMut<Log>) {
+    log.0.push(("children added", trigger.entity()));
+}
+
+fn children_removed(trigger: Trigger<OnRemove, Children>, mut log: ResMut<Log>) {
+    log.0.push(("children removed", trigger.entity()));
+}
+
+#[test]
+fn hierarchy_changes_run_observers() {
+    let mut world = World::new();
+    world.init_resource::<Log>().unwrap();
+    world.observe(parent_added);
+    world.observe(parent_removed);
+    world.observe(children_added);
+    world.observe(children_removed);
+    let root = world.spawn();
+    let child = world.spawn();
+    let grandchild = world.spawn();
+    world.set_parent(child, root);
+    world.set_parent(grandchild, child);
+    let log = std::mem::take(&mut world.resource_mut::<Log>().unwrap().0);
+    assert_eq!(
+        log,
+        [
+            ("parent added", child),
+            ("children added", root),
+            ("parent added", grandchild),
+            ("children added", child),
+        ]
+    );
+
+    // Despawning the middle entity updates its parent and child through
+    // their components' observers too.
+    world.despawn(child);
+    let log = std::mem::take(&mut world.resource_mut::<Log>().unwrap().0);
+    assert_eq!(log.len(), 4);
+    assert!(log[..2].contains(&("parent removed", child)));
+    assert!(log[..2].contains(&("children removed", child)));
+    assert_eq!(
+        log[2..],
+        [("children removed", root), ("parent removed", grandchild)]
+    );
+    assert_eq!(world.get(root).unwrap().get_component::<Children>(), None);
+}
+
+fn spawn_node(world: &mut World, transform: Transform) -> EntityId {
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    entity.set_component(transform);
+    entity.set_component(GlobalTransform::default());
+    id
+}
+
+fn global(world: &World, id: EntityId) -> Vec3 {
+    world
+        .get(id)
+        .unwrap()
+        .get_component::<GlobalTransform>()
+        .unwrap()
+        .translation()
+}
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-5, "{a:?} != {b:?}");
+}
+
+#[test]
+fn transforms_propagate_down_the_tree() {
+    let mut app = App::new();
+    app.add_plugin(TransformPlugin);
+    let world = app.world_mut();
+    let root = spawn_node(
+        world,
+        Transform::from_xyz(1.0, 0.0, 0.0)
+            .with_rotation(Quat::from_rotation_z(FRAC_PI_2))
+            .with_scale(Vec3::splat(2.0)),
+    );
+    let child = spawn_node(world, Transform::from_xyz(1.0, 0.0, 0.0));
+    let grandchild = spawn_node(world, Transform::from_xyz(0.0, 1.0, 0.0));
+    world.set_parent(child, root);
+    world.set_parent(grandchild, child);
+    app.update_by(Duration::from_millis(16));
+
+    let world = app.world();
+    assert_close(global(world, root), Vec3::new(1.0, 0.0, 0.0));
+    assert_close(global(world, child), Vec3::new(1.0, 2.0, 0.0));
+    assert_close(global(world, grandchild), Vec3::new(-1.0, 2.0, 0.0));
+
+    // Moving the root moves the whole tree on the next frame.
+    let world = app.world_mut();
+    world
+        .get_mut(root)
+        .unwrap()
+        .set_component(Transform::IDENTITY);
+    app.update_by(Duration::from_millis(16));
+    assert_close(global(app.world(), grandchild), Vec3::new(1.0, 1.0, 0.0));
+}
+
+#[test]
+fn entities_without_transform_break_the_chain() {
+    let mut app = App::new();
+    app.add_plugin(TransformPlugin);
+    let world = app.world_mut();
+    let root = spawn_node(world, Transform::from_xyz(1.0, 0.0, 0.0));
+    let gap = world.spawn();
+    let below = spawn_node(world, Transform::from_xyz(0.0, 1.0, 0.0));
+    world.set_parent(gap, root);
+    world.set_parent(below, gap);
+    app.update_by(Duration::from_millis(16));
+
+    assert_close(global(app.world(), root), Vec3::new(1.0, 0.0, 0.0));
+    assert_close(global(app.world(), below), Vec3::ZERO);
+}
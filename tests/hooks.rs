@@ -0,0 +1,108 @@
+use peano_engine::prelude::*;
+
+#[derive(Resource, Default)]
+struct Log(Vec<&'static str>);
+
+fn log(world: &mut World, entry: &'static str) {
+    world.resource_mut::<Log>().unwrap().0.push(entry);
+}
+
+/**
+ * Despawns its entity when it is replaced.
+ */
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+#[component(on_replace = despawn_on_replace)]
+struct Fragile(u32);
+
+fn despawn_on_replace(world: &mut World, entity: EntityId) {
+    log(world, "replace");
+    world.despawn(entity);
+}
+
+/**
+ * Removes itself when it is replaced.
+ */
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+#[component(on_add = log_add, on_replace = remove_on_replace)]
+struct Fleeting(u32);
+
+fn log_add(world: &mut World, _: EntityId) {
+    log(world, "add");
+}
+
+fn remove_on_replace(world: &mut World, entity: EntityId) {
+    log(world, "replace");
+    world
+        .get_mut(entity)
+        .unwrap()
+        .remove_component::<Fleeting>();
+}
+
+/**
+ * Despawns its entity as soon as it is added.
+ */
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+#[component(on_add = despawn_on_add)]
+struct Doomed;
+
+fn despawn_on_add(world: &mut World, entity: EntityId) {
+    log(world, "add");
+    world.despawn(entity);
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Other;
+
+fn world() -> World {
+    let mut world = World::new();
+    world.init_resource::<Log>().unwrap();
+    world
+}
+
+#[test]
+fn replace_hook_despawning_drops_new_value() {
+    let mut world = world();
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    entity.set_component(Fragile(1));
+    entity.set_component(Fragile(2));
+    assert_eq!(entity.add_component(Other), None);
+    entity.set_component(Other);
+
+    // Once for the new value and once for the despawn.
+    assert!(!world.contains(id));
+    assert_eq!(world.resource::<Log>().unwrap().0, ["replace", "replace"]);
+    assert_eq!(world.query::<(&Fragile,), ()>().count(), 0);
+}
+
+#[test]
+fn replace_hook_removing_adds_again() {
+    let mut world = world();
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    entity.set_component(Fleeting(1));
+    entity.set_component(Fleeting(2));
+    assert_eq!(entity.get_component::<Fleeting>(), Some(&Fleeting(2)));
+
+    // The removal replaces it too, and with the old value gone the
+    // new one is added again.
+    assert_eq!(
+        world.resource::<Log>().unwrap().0,
+        ["add", "replace", "replace", "add"]
+    );
+}
+
+#[test]
+fn add_hook_despawning_stops_later_inserts() {
+    let mut world = world();
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    assert_eq!(entity.add_component(Doomed), Some(0));
+    assert_eq!(entity.add_component(Doomed), None);
+    entity.set_component(Doomed);
+    entity.set_component(Other);
+
+    assert!(!world.contains(id));
+    assert_eq!(world.resource::<Log>().unwrap().0, ["add"]);
+    assert_eq!(world.query::<(&Other,), ()>().count(), 0);
+}
@@ -0,0 +1,94 @@
+use peano_engine::prelude::*;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Emitter(u32);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Marker;
+
+fn emitters(world: &World, id: EntityId) -> Vec<u32> {
+    world
+        .get(id)
+        .unwrap()
+        .get_components::<Emitter>()
+        .map(|e| e.0)
+        .collect()
+}
+
+#[test]
+fn entities_hold_several_instances() {
+    let mut world = World::new();
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    assert_eq!(entity.add_component(Emitter(1)), Some(0));
+    assert_eq!(entity.add_component(Emitter(2)), Some(1));
+    assert_eq!(entity.add_component(Emitter(3)), Some(2));
+    assert_eq!(entity.component_count::<Emitter>(), 3);
+
+    // Setting only replaces the first instance.
+    entity.set_component(Emitter(10));
+    assert_eq!(emitters(&world, id), [10, 2, 3]);
+}
+
+#[test]
+fn instances_are_removed_one_at_a_time_or_together() {
+    let mut world = World::new();
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    for i in 0..4 {
+        entity.add_component(Emitter(i));
+    }
+    assert_eq!(entity.remove_component_at::<Emitter>(1), Some(Emitter(1)));
+    assert!(entity.remove_component_at::<Emitter>(5).is_none());
+    assert_eq!(emitters(&world, id).len(), 3);
+    assert!(emitters(&world, id).contains(&0));
+
+    let mut entity = world.get_mut(id).unwrap();
+    assert_eq!(entity.remove_component::<Emitter>(), Some(Emitter(0)));
+    assert!(!entity.has_component::<Emitter>());
+    assert_eq!(entity.component_count::<Emitter>(), 0);
+}
+
+#[test]
+fn instances_survive_archetype_moves() {
+    let mut world = World::new();
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    entity.add_component(Emitter(1));
+    entity.add_component(Emitter(2));
+    entity.set_component(Marker);
+    assert_eq!(emitters(&world, id), [1, 2]);
+    world.get_mut(id).unwrap().remove_component::<Marker>();
+    assert_eq!(emitters(&world, id), [1, 2]);
+}
+
+#[test]
+fn many_queries_yield_every_instance() {
+    let mut world = World::new();
+    let single = world.spawn();
+    world.get_mut(single).unwrap().add_component(Emitter(1));
+    let double = world.spawn();
+    let mut entity = world.get_mut(double).unwrap();
+    entity.add_component(Emitter(2));
+    entity.add_component(Emitter(3));
+    world.spawn();
+
+    let mut found: Vec<(EntityId, Vec<u32>)> = world
+        .query::<(EntityId, Many<&Emitter>), ()>()
+        .map(|(id, instances)| (id, instances.iter().map(|e| e.0).collect()))
+        .collect();
+    found.sort();
+    assert_eq!(found, [(single, vec![1]), (double, vec![2, 3])]);
+
+    for (mut instances,) in world.query_mut::<(Many<&mut Emitter>,), ()>() {
+        for emitter in instances.iter_mut() {
+            emitter.0 *= 10;
+        }
+    }
+    assert_eq!(emitters(&world, double), [20, 30]);
+
+    // A plain query sees the first instance only.
+    let mut firsts: Vec<u32> = world.query::<(&Emitter,), ()>().map(|(e,)| e.0).collect();
+    firsts.sort();
+    assert_eq!(firsts, [10, 20]);
+}
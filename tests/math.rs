@@ -0,0 +1,229 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+use std::mem::{align_of, size_of};
+
+use peano_engine::math::{Isometry, Mat3, Mat4, Quat, Transform, Vec2, Vec3, Vec4};
+use proptest::prelude::*;
+
+const TOLERANCE: f32 = 1e-3;
+
+fn vec3(range: f32) -> impl Strategy<Value = Vec3> {
+    (-range..range, -range..range, -range..range).prop_map(|(x, y, z)| Vec3::new(x, y, z))
+}
+
+fn quat() -> impl Strategy<Value = Quat> {
+    (vec3(1.0), -1.0f32..1.0)
+        .prop_filter("too short to normalize", |(v, w)| {
+            v.length_squared() + w * w > 0.01
+        })
+        .prop_map(|(v, w)| Quat::from_xyzw(v.x, v.y, v.z, w).normalize())
+}
+
+/**
+ * A scale far enough from zero on every axis to invert.
+ */
+fn scale() -> impl Strategy<Value = Vec3> {
+    vec3(3.0).prop_map(|s| s.signum() * (s.abs() + Vec3::splat(0.5)))
+}
+
+fn transform() -> impl Strategy<Value = Transform> {
+    (vec3(10.0), quat(), scale()).prop_map(|(translation, rotation, scale)| Transform {
+        translation,
+        rotation,
+        scale,
+    })
+}
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!(a.abs_diff_eq(b, 1e-5), "{a:?} != {b:?}");
+}
+
+#[test]
+fn layout_matches_arrays() {
+    assert_eq!(size_of::<Vec2>(), 8);
+    assert_eq!(size_of::<Vec3>(), 12);
+    assert_eq!((size_of::<Vec4>(), align_of::<Vec4>()), (16, 16));
+    assert_eq!((size_of::<Quat>(), align_of::<Quat>()), (16, 16));
+    assert_eq!(size_of::<Mat3>(), 36);
+    assert_eq!(size_of::<Mat4>(), 64);
+
+    let mut v = Vec4::from_array([1.0, 2.0, 3.0, 4.0]);
+    v[2] = 5.0;
+    assert_eq!(v.to_array(), [1.0, 2.0, 5.0, 4.0]);
+    assert_eq!(v.truncate(), Vec3::new(1.0, 2.0, 5.0));
+    assert_eq!(
+        Vec2::new(1.0, 2.0).extend(3.0).extend(4.0),
+        Vec4::new(1.0, 2.0, 3.0, 4.0)
+    );
+}
+
+#[test]
+fn vector_products() {
+    assert_eq!(Vec3::X.cross(Vec3::Y), Vec3::Z);
+    assert_eq!(Vec3::Y.cross(Vec3::X), -Vec3::Z);
+    assert_eq!(Vec3::new(1.0, 2.0, 3.0).dot(Vec3::new(4.0, 5.0, 6.0)), 32.0);
+    assert_eq!(Vec2::X.perp(), Vec2::Y);
+    assert_eq!(Vec2::X.perp_dot(Vec2::Y), 1.0);
+    assert_close(
+        Vec3::new(2.0, 3.0, 0.0).project_onto(Vec3::X),
+        Vec3::new(2.0, 0.0, 0.0),
+    );
+    assert_close(
+        Vec3::new(2.0, 3.0, 0.0).reject_from(Vec3::X),
+        Vec3::new(0.0, 3.0, 0.0),
+    );
+    assert!((Vec3::X.angle_between(Vec3::Y) - FRAC_PI_2).abs() < 1e-6);
+}
+
+#[test]
+fn normalizing_zero_is_refused() {
+    assert_eq!(Vec3::ZERO.try_normalize(), None);
+    assert_eq!(Vec3::ZERO.normalize_or_zero(), Vec3::ZERO);
+    assert_eq!(Vec3::new(0.0, 3.0, 4.0).length(), 5.0);
+    assert!(Vec3::new(0.0, 3.0, 4.0).normalize().is_normalized());
+    assert_eq!(
+        Vec3::new(5.0, -5.0, 0.5).clamp(Vec3::ZERO, Vec3::ONE),
+        Vec3::new(1.0, 0.0, 0.5)
+    );
+}
+
+#[test]
+fn converts_to_and_from_egui() {
+    let v = Vec2::new(1.5, -2.0);
+    let egui_vec: egui::Vec2 = v.into();
+    let egui_pos: egui::Pos2 = v.into();
+    assert_eq!(egui_vec, egui::vec2(1.5, -2.0));
+    assert_eq!(egui_pos, egui::pos2(1.5, -2.0));
+    assert_eq!(Vec2::from(egui_vec), v);
+    assert_eq!(Vec2::from(egui_pos), v);
+}
+
+#[test]
+fn rotations_follow_the_right_hand_rule() {
+    assert_close(Quat::from_rotation_z(FRAC_PI_2) * Vec3::X, Vec3::Y);
+    assert_close(Quat::from_rotation_x(FRAC_PI_2) * Vec3::Y, Vec3::Z);
+    assert_close(Quat::from_rotation_y(FRAC_PI_2) * Vec3::Z, Vec3::X);
+    assert_close(Quat::from_rotation_arc(Vec3::X, Vec3::Z) * Vec3::X, Vec3::Z);
+    // Opposite vectors still give a half turn.
+    assert_close(
+        Quat::from_rotation_arc(Vec3::X, -Vec3::X) * Vec3::X,
+        -Vec3::X,
+    );
+
+    let (axis, angle) = Quat::from_axis_angle(Vec3::Y, 0.5).to_axis_angle();
+    assert_close(axis, Vec3::Y);
+    assert!((angle - 0.5).abs() < 1e-5);
+    assert!((Quat::IDENTITY.angle_between(Quat::from_rotation_x(PI)) - PI).abs() < 1e-5);
+}
+
+#[test]
+fn transform_axes_and_look_at() {
+    let transform = Transform::from_rotation(Quat::from_rotation_y(FRAC_PI_2));
+    assert_close(transform.right(), -Vec3::Z);
+    assert_close(transform.up(), Vec3::Y);
+    assert_close(transform.forward(), -Vec3::X);
+
+    let mut eye = Transform::from_xyz(0.0, 0.0, 5.0);
+    eye.look_at(Vec3::new(5.0, 0.0, 5.0), Vec3::Y);
+    assert_close(eye.forward(), Vec3::X);
+    assert_close(eye.up(), Vec3::Y);
+
+    let view = Mat4::look_at_rh(eye.translation, Vec3::new(5.0, 0.0, 5.0), Vec3::Y);
+    assert!(view.abs_diff_eq(&eye.compute_matrix().inverse(), 1e-5));
+}
+
+#[test]
+fn types_round_trip_through_serde() {
+    let transform = Transform::from_xyz(1.0, 2.0, 3.0)
+        .with_rotation(Quat::from_rotation_z(0.25))
+        .with_scale(Vec3::new(1.0, 2.0, 0.5));
+    let source = ron::to_string(&transform).unwrap();
+    assert_eq!(ron::from_str::<Transform>(&source).unwrap(), transform);
+
+    let matrix = transform.compute_matrix();
+    let source = ron::to_string(&matrix).unwrap();
+    assert_eq!(ron::from_str::<Mat4>(&source).unwrap(), matrix);
+
+    let isometry = Isometry::from(transform);
+    let source = ron::to_string(&isometry).unwrap();
+    assert_eq!(ron::from_str::<Isometry>(&source).unwrap(), isometry);
+}
+
+proptest! {
+    #[test]
+    fn rotation_preserves_length(q in quat(), v in vec3(10.0)) {
+        let rotated = q * v;
+        prop_assert!((rotated.length() - v.length()).abs() <= TOLERANCE);
+        prop_assert!((q * q.inverse()).abs_diff_eq(Quat::IDENTITY, TOLERANCE));
+        prop_assert!((q.inverse() * rotated).abs_diff_eq(v, TOLERANCE));
+    }
+
+    #[test]
+    fn quat_agrees_with_mat3(a in quat(), b in quat(), v in vec3(10.0)) {
+        let m = Mat3::from_quat(a);
+        prop_assert!((m * v).abs_diff_eq(a * v, TOLERANCE));
+        prop_assert!((Mat3::from_quat(a * b) * v).abs_diff_eq(m * (Mat3::from_quat(b) * v), TOLERANCE));
+        prop_assert!((m.determinant() - 1.0).abs() <= TOLERANCE);
+        prop_assert!(m.transpose().abs_diff_eq(&m.inverse(), TOLERANCE));
+
+        // `q` and `-q` are the same rotation.
+        let back = Quat::from_mat3(&m);
+        prop_assert!(back.abs_diff_eq(a, TOLERANCE) || back.abs_diff_eq(-a, TOLERANCE));
+    }
+
+    #[test]
+    fn slerp_hits_both_ends(a in quat(), b in quat(), t in 0.0f32..=1.0) {
+        prop_assert!(a.slerp(b, 0.0).abs_diff_eq(a, TOLERANCE));
+        let end = a.slerp(b, 1.0);
+        prop_assert!(end.abs_diff_eq(b, TOLERANCE) || end.abs_diff_eq(-b, TOLERANCE));
+        let mid = a.slerp(b, t);
+        prop_assert!(mid.is_normalized());
+        prop_assert!(a.angle_between(mid) <= a.angle_between(b) + TOLERANCE);
+    }
+
+    #[test]
+    fn matrices_invert(t in transform(), p in vec3(10.0)) {
+        let m = t.compute_matrix();
+        prop_assert!((m * m.inverse()).abs_diff_eq(&Mat4::IDENTITY, TOLERANCE));
+        prop_assert!(m.inverse().transform_point3(m.transform_point3(p)).abs_diff_eq(p, 1e-2));
+        let expected = t.scale.element_product();
+        prop_assert!((m.determinant() - expected).abs() <= expected.abs() * TOLERANCE);
+    }
+
+    #[test]
+    fn transform_agrees_with_matrices(t in transform(), p in vec3(10.0), v in vec3(10.0)) {
+        let expected = t.transform_point(p);
+        let affine = t.compute_affine();
+        let matrix = t.compute_matrix();
+        prop_assert!(affine.transform_point3(p).abs_diff_eq(expected, TOLERANCE));
+        prop_assert!(matrix.transform_point3(p).abs_diff_eq(expected, TOLERANCE));
+        prop_assert!(Mat4::from(affine).abs_diff_eq(&matrix, TOLERANCE));
+        prop_assert!(affine.transform_vector3(v).abs_diff_eq(matrix.transform_vector3(v), TOLERANCE));
+        prop_assert!(affine.inverse().transform_point3(expected).abs_diff_eq(p, 1e-2));
+    }
+
+    #[test]
+    fn transforms_compose(child in transform(), rotation in quat(), translation in vec3(10.0), p in vec3(10.0)) {
+        // A uniform parent scale, so the combination is exact.
+        let parent = Transform { translation, rotation, scale: Vec3::splat(1.5) };
+        let combined = parent.mul_transform(&child);
+        let expected = parent.transform_point(child.transform_point(p));
+        prop_assert!(combined.transform_point(p).abs_diff_eq(expected, 1e-2));
+
+        let product = parent.compute_affine() * child.compute_affine();
+        prop_assert!(product.transform_point3(p).abs_diff_eq(expected, 1e-2));
+    }
+
+    #[test]
+    fn isometries_invert(a in quat(), b in quat(), ta in vec3(10.0), tb in vec3(10.0), p in vec3(10.0)) {
+        let first = Isometry::new(ta, a);
+        let second = Isometry::new(tb, b);
+        let moved = first.transform_point(p);
+        prop_assert!(first.inverse().transform_point(moved).abs_diff_eq(p, TOLERANCE));
+        prop_assert!(first.inverse_transform_point(moved).abs_diff_eq(p, TOLERANCE));
+        prop_assert!((first * second).transform_point(p).abs_diff_eq(first.transform_point(second.transform_point(p)), TOLERANCE));
+
+        let transform = Transform::from(first);
+        prop_assert_eq!(transform.scale, Vec3::ONE);
+        prop_assert!(transform.transform_point(p).abs_diff_eq(moved, TOLERANCE));
+    }
+}
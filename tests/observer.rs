@@ -0,0 +1,110 @@
+use peano_engine::prelude::*;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Chain(u32);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Marker;
+
+#[derive(Resource, Default)]
+struct Log(Vec<&'static str>);
+
+/**
+ * Logs its insert and replace hooks.
+ */
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+#[component(on_insert = hook_insert, on_replace = hook_replace)]
+struct Hooked(u32);
+
+fn hook_insert(world: &mut World, _: EntityId) {
+    world.resource_mut::<Log>().unwrap().0.push("hook insert");
+}
+
+fn hook_replace(world: &mut World, _: EntityId) {
+    world.resource_mut::<Log>().unwrap().0.push("hook replace");
+}
+
+fn spawn_next(trigger: Trigger<OnAdd, Chain>, chains: Query<&Chain>, mut commands: Commands) {
+    let Some(&Chain(n)) = chains.get(trigger.entity()) else {
+        return;
+    };
+    if n > 0 {
+        commands.add(move |world| {
+            let id = world.spawn();
+            world.get_mut(id).unwrap().set_component(Chain(n - 1));
+        });
+    }
+}
+
+fn count_chain(_: Trigger<OnAdd, Chain>, mut log: ResMut<Log>) {
+    log.0.push("chain");
+}
+
+fn count_marker(_: Trigger<OnAdd, Marker>, mut log: ResMut<Log>) {
+    log.0.push("marker");
+}
+
+#[test]
+fn nested_triggers_run_every_observer() {
+    let mut world = World::new();
+    world.init_resource::<Log>().unwrap();
+    world.observe(spawn_next);
+    world.observe(count_chain);
+
+    let id = world.spawn();
+    world.get_mut(id).unwrap().set_component(Chain(3));
+
+    assert_eq!(world.query::<(&Chain,), ()>().count(), 4);
+    assert_eq!(world.resource::<Log>().unwrap().0, ["chain"; 4]);
+}
+
+#[test]
+fn observers_added_while_running_are_kept() {
+    let mut world = World::new();
+    world.init_resource::<Log>().unwrap();
+    world.observe(
+        |_: Trigger<OnAdd, Marker>, mut log: ResMut<Log>, mut commands: Commands| {
+            log.0.push("first");
+            commands.add(|world| world.observe(count_marker));
+        },
+    );
+
+    let a = world.spawn();
+    world.get_mut(a).unwrap().set_component(Marker);
+    assert_eq!(world.resource::<Log>().unwrap().0, ["first"]);
+
+    let b = world.spawn();
+    world.get_mut(b).unwrap().set_component(Marker);
+    assert_eq!(
+        world.resource::<Log>().unwrap().0,
+        ["first", "first", "marker"]
+    );
+}
+
+#[test]
+fn lifecycle_events_run_hooks_then_observers() {
+    let mut world = World::new();
+    world.init_resource::<Log>().unwrap();
+    world.observe(|_: Trigger<OnAdd, Hooked>, mut log: ResMut<Log>| log.0.push("add"));
+    world.observe(|_: Trigger<OnInsert, Hooked>, mut log: ResMut<Log>| log.0.push("insert"));
+    world.observe(
+        |trigger: Trigger<OnReplace, Hooked>, hooked: Query<&Hooked>, mut log: ResMut<Log>| {
+            // The old value is still in place.
+            assert_eq!(hooked.get(trigger.entity()), Some(&Hooked(1)));
+            log.0.push("replace");
+        },
+    );
+    world.observe(|_: Trigger<OnRemove, Hooked>, mut log: ResMut<Log>| log.0.push("remove"));
+
+    let id = world.spawn();
+    world.get_mut(id).unwrap().set_component(Hooked(1));
+    assert_eq!(
+        std::mem::take(&mut world.resource_mut::<Log>().unwrap().0),
+        ["add", "hook insert", "insert"]
+    );
+    world.get_mut(id).unwrap().set_component(Hooked(2));
+    assert_eq!(
+        std::mem::take(&mut world.resource_mut::<Log>().unwrap().0),
+        ["hook replace", "replace", "hook insert", "insert"]
+    );
+}
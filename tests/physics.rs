@@ -0,0 +1,302 @@
+use peano_engine::physics::*;
+use peano_engine::prelude::*;
+
+fn spawn(
+    app: &mut App,
+    collider: Collider,
+    transform: Transform,
+    body: Option<RigidBody>,
+) -> EntityId {
+    let world = app.world_mut();
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    entity.set_component(transform);
+    if let Some(body) = body {
+        entity.set_component(body);
+    }
+    entity.set_component(collider);
+    id
+}
+
+fn set<T: Component>(app: &mut App, id: EntityId, component: T) {
+    app.world_mut()
+        .get_mut(id)
+        .unwrap()
+        .set_component(component);
+}
+
+fn get<T: Component + Copy>(app: &App, id: EntityId) -> T {
+    *app.world().get(id).unwrap().get_component::<T>().unwrap()
+}
+
+fn step(app: &mut App, steps: usize) {
+    for _ in 0..steps {
+        app.update_by(FixedTime::DEFAULT_STEP);
+    }
+}
+
+/**
+ * An app with a static floor whose top is at zero.
+ */
+fn ground() -> (App, EntityId) {
+    let mut app = App::new();
+    app.add_plugin(PhysicsPlugin);
+    let floor = spawn(
+        &mut app,
+        Collider::cuboid(Vec3::new(20.0, 0.5, 20.0)),
+        Transform::from_xyz(0.0, -0.5, 0.0),
+        Some(RigidBody::Static),
+    );
+    (app, floor)
+}
+
+fn unit_box(app: &mut App, translation: Vec3) -> EntityId {
+    spawn(
+        app,
+        Collider::cuboid(Vec3::splat(0.5)),
+        Transform::from_translation(translation),
+        Some(RigidBody::Dynamic),
+    )
+}
+
+#[test]
+fn box_settles_on_ground() {
+    let (mut app, _) = ground();
+    let cube = unit_box(&mut app, Vec3::new(1.0, 1.5, -2.0));
+    step(&mut app, 300);
+
+    let transform = get::<Transform>(&app, cube);
+    assert!(
+        (transform.translation.y - 0.5).abs() < 0.02,
+        "{transform:?}"
+    );
+    assert!(
+        (transform.translation.x - 1.0).abs() < 1e-3,
+        "{transform:?}"
+    );
+    assert!(
+        (transform.translation.z + 2.0).abs() < 1e-3,
+        "{transform:?}"
+    );
+    let velocity = get::<Velocity>(&app, cube);
+    assert!(velocity.linear.length() < 0.01, "{velocity:?}");
+    assert!(velocity.angular.length() < 0.01, "{velocity:?}");
+}
+
+#[test]
+fn stack_settles() {
+    let (mut app, _) = ground();
+    let boxes: Vec<_> = (0..5)
+        .map(|i| unit_box(&mut app, Vec3::new(0.0, 0.5 + i as f32, 0.0)))
+        .collect();
+    step(&mut app, 600);
+
+    for (i, &cube) in boxes.iter().enumerate() {
+        let translation = get::<Transform>(&app, cube).translation;
+        assert!(
+            (translation.y - (0.5 + i as f32)).abs() < 0.05,
+            "{i} {translation:?}"
+        );
+        assert!(translation.x.abs() < 0.05, "{i} {translation:?}");
+        assert!(translation.z.abs() < 0.05, "{i} {translation:?}");
+    }
+}
+
+/**
+ * The highest the ball's center gets once it has landed, dropped
+ * with its bottom `height` above the ground.
+ */
+fn rebound(restitution: f32, height: f32) -> f32 {
+    let (mut app, _) = ground();
+    let ball = spawn(
+        &mut app,
+        Collider::sphere(0.5),
+        Transform::from_xyz(0.0, height + 0.5, 0.0),
+        Some(RigidBody::Dynamic),
+    );
+    set(&mut app, ball, PhysicsMaterial::new(0.5, restitution));
+
+    let mut landed = false;
+    let mut highest = 0.0f32;
+    for _ in 0..240 {
+        step(&mut app, 1);
+        let y = get::<Transform>(&app, ball).translation.y;
+        landed |= y < 0.55;
+        if landed {
+            highest = highest.max(y);
+        }
+    }
+    assert!(landed);
+    highest
+}
+
+#[test]
+fn restitution_sets_bounce_height() {
+    // Kept speed squared, so the height scales by the restitution
+    // squared.
+    let height = rebound(0.8, 3.5) - 0.5;
+    assert!((height - 3.5 * 0.64).abs() < 0.25, "{height}");
+    let height = rebound(0.5, 3.5) - 0.5;
+    assert!((height - 3.5 * 0.25).abs() < 0.15, "{height}");
+    // Without restitution the ball does not come back up.
+    assert!(rebound(0.0, 3.5) < 0.55);
+}
+
+#[test]
+fn friction_stops_sliding_box() {
+    let (mut app, _) = ground();
+    let cube = unit_box(&mut app, Vec3::new(0.0, 0.5, 0.0));
+    step(&mut app, 1);
+    set(&mut app, cube, Velocity::linear(Vec3::new(3.0, 0.0, 0.0)));
+    step(&mut app, 240);
+
+    // Sliding at 3 under a friction of 0.5 takes v² / (2 μ g) to stop.
+    let translation = get::<Transform>(&app, cube).translation;
+    let distance = 9.0 / (2.0 * 0.5 * 9.81);
+    assert!((translation.x - distance).abs() < 0.1, "{translation:?}");
+    assert!((translation.y - 0.5).abs() < 0.02, "{translation:?}");
+    assert!(translation.z.abs() < 1e-3, "{translation:?}");
+    let velocity = get::<Velocity>(&app, cube);
+    assert!(velocity.linear.length() < 0.01, "{velocity:?}");
+
+    // Without friction it keeps going.
+    let (mut app, floor) = ground();
+    set(&mut app, floor, PhysicsMaterial::new(0.0, 0.0));
+    let cube = unit_box(&mut app, Vec3::new(0.0, 0.5, 0.0));
+    set(&mut app, cube, PhysicsMaterial::new(0.0, 0.0));
+    step(&mut app, 1);
+    set(&mut app, cube, Velocity::linear(Vec3::new(3.0, 0.0, 0.0)));
+    step(&mut app, 60);
+    assert!((get::<Velocity>(&app, cube).linear.x - 3.0).abs() < 0.01);
+}
+
+#[test]
+fn kinematic_body_pushes_dynamic_body() {
+    let (mut app, _) = ground();
+    let pusher = spawn(
+        &mut app,
+        Collider::cuboid(Vec3::splat(0.5)),
+        Transform::from_xyz(-3.0, 0.5, 0.0),
+        Some(RigidBody::Kinematic),
+    );
+    set(&mut app, pusher, Velocity::linear(Vec3::new(1.0, 0.0, 0.0)));
+    let pushed = spawn(
+        &mut app,
+        Collider::sphere(0.5),
+        Transform::from_xyz(-1.0, 0.5, 0.0),
+        Some(RigidBody::Dynamic),
+    );
+    step(&mut app, 240);
+
+    // The kinematic body keeps its velocity, ignoring gravity and the
+    // ball in its way.
+    let pusher = get::<Transform>(&app, pusher).translation;
+    assert!((pusher.x - 1.0).abs() < 1e-3, "{pusher:?}");
+    assert!((pusher.y - 0.5).abs() < 1e-3, "{pusher:?}");
+    let pushed = get::<Transform>(&app, pushed).translation;
+    assert!(pushed.x > pusher.x + 0.9, "{pushed:?}");
+}
+
+#[test]
+fn warm_start_reuses_impulses() {
+    let (mut app, floor) = ground();
+    let cube = unit_box(&mut app, Vec3::new(0.0, 0.5, 0.0));
+    step(&mut app, 120);
+
+    // Resting, the contact holds up the box's weight over a step.
+    let weight = get::<Mass>(&app, cube).mass * 9.81 * FixedTime::default().step_secs();
+    let cache = app.world().resource::<ContactCache>().unwrap();
+    let impulse = cache.normal_impulse(cube, floor);
+    assert!(
+        (impulse - weight).abs() < 0.01 * weight,
+        "{impulse} {weight}"
+    );
+    assert_eq!(impulse, cache.normal_impulse(floor, cube));
+
+    // With no iterations, only the cached impulses hold the box up.
+    app.world_mut()
+        .resource_mut::<SolverSettings>()
+        .unwrap()
+        .iterations = 0;
+    step(&mut app, 60);
+    let translation = get::<Transform>(&app, cube).translation;
+    assert!((translation.y - 0.5).abs() < 0.02, "{translation:?}");
+
+    // Once they are forgotten the box starts falling.
+    app.world_mut()
+        .resource_mut::<ContactCache>()
+        .unwrap()
+        .clear();
+    step(&mut app, 1);
+    assert!(get::<Velocity>(&app, cube).linear.y < -0.1);
+}
+
+/**
+ * Drops a small pile of mixed shapes, some on top of each other.
+ */
+fn pile() -> App {
+    let (mut app, _) = ground();
+    let hull = [
+        Vec3::new(-0.4, -0.3, -0.4),
+        Vec3::new(0.5, -0.3, -0.3),
+        Vec3::new(0.0, -0.3, 0.5),
+        Vec3::new(0.1, 0.5, 0.0),
+    ];
+    let colliders = [
+        Collider::sphere(0.4),
+        Collider::cuboid(Vec3::new(0.5, 0.3, 0.4)),
+        Collider::capsule(0.3, 0.25),
+        Collider::convex_hull(&hull).unwrap(),
+    ];
+    for (i, collider) in colliders.into_iter().cycle().take(12).enumerate() {
+        let i = i as f32;
+        let transform = Transform::from_xyz(i % 3.0 - 1.0, 0.6 + i * 0.7, (i * 0.37) % 1.0)
+            .with_rotation(Quat::from_euler_yxz(i, i * 0.5, 0.0));
+        spawn(&mut app, collider, transform, Some(RigidBody::Dynamic));
+    }
+    app
+}
+
+/**
+ * The transform and velocity of every body, and the impulse cached
+ * for every contact, all bit for bit.
+ */
+fn state(app: &App) -> Vec<u32> {
+    let world = app.world();
+    let mut bodies: Vec<_> = world
+        .query::<(EntityId, &Transform, &Velocity), ()>()
+        .collect();
+    bodies.sort_by_key(|(id, ..)| *id);
+    let cache = world.resource::<ContactCache>().unwrap();
+    let impulses = world
+        .resource::<Contacts>()
+        .unwrap()
+        .pairs()
+        .iter()
+        .map(|pair| cache.normal_impulse(pair.entity_a, pair.entity_b));
+    bodies
+        .into_iter()
+        .flat_map(|(_, transform, velocity)| {
+            let mut values = transform.translation.to_array().to_vec();
+            values.extend(transform.rotation.to_array());
+            values.extend(velocity.linear.to_array());
+            values.extend(velocity.angular.to_array());
+            values
+        })
+        .chain(impulses)
+        .map(f32::to_bits)
+        .collect()
+}
+
+#[test]
+fn piles_replay_identically() {
+    let mut runs = [pile(), pile()];
+    for _ in 0..4 {
+        for app in &mut runs {
+            step(app, 45);
+        }
+        assert_eq!(state(&runs[0]), state(&runs[1]));
+    }
+    let contacts = runs[0].world().resource::<Contacts>().unwrap().len();
+    assert!(contacts >= 12, "{contacts}");
+}
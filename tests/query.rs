@@ -0,0 +1,159 @@
+use peano_engine::ecs::query::QueryState;
+use peano_engine::ecs::query::{With, Without};
+use peano_engine::ecs::world::World;
+use peano_engine::prelude::*;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Position(f32);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Velocity(f32);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Collider;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Static;
+
+/**
+ * Spawns one entity per combination of `Velocity`, `Collider` and
+ * `Static`, each with a `Position` holding its number.
+ */
+fn world() -> (World, Vec<EntityId>) {
+    let mut world = World::new();
+    let ids = (0..8)
+        .map(|i| {
+            let id = world.spawn();
+            let mut entity = world.get_mut(id).unwrap();
+            entity.set_component(Position(i as f32));
+            if i & 1 != 0 {
+                entity.set_component(Velocity(1.0));
+            }
+            if i & 2 != 0 {
+                entity.set_component(Collider);
+            }
+            if i & 4 != 0 {
+                entity.set_component(Static);
+            }
+            id
+        })
+        .collect();
+    (world, ids)
+}
+
+fn positions<'w>(items: impl Iterator<Item = &'w Position>) -> Vec<f32> {
+    let mut xs: Vec<f32> = items.map(|p| p.0).collect();
+    xs.sort_by(f32::total_cmp);
+    xs
+}
+
+#[test]
+fn filters_select_archetypes() {
+    let (world, _) = world();
+    assert_eq!(
+        positions(world.query::<(&Position,), ()>().map(|(p,)| p)),
+        [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]
+    );
+    assert_eq!(
+        positions(
+            world
+                .query::<(&Position, &Velocity), With<Collider>>()
+                .map(|(p, _)| p)
+        ),
+        [3.0, 7.0]
+    );
+    assert_eq!(
+        positions(
+            world
+                .query::<(&Position, &Velocity), (With<Collider>, Without<Static>)>()
+                .map(|(p, _)| p)
+        ),
+        [3.0]
+    );
+}
+
+#[test]
+fn optional_items_match_every_entity() {
+    let (world, _) = world();
+    let mut items: Vec<_> = world
+        .query::<(&Position, Option<&Velocity>), Without<Static>>()
+        .map(|(p, v)| (p.0, v.is_some()))
+        .collect();
+    items.sort_by(|a, b| a.0.total_cmp(&b.0));
+    assert_eq!(
+        items,
+        [(0.0, false), (1.0, true), (2.0, false), (3.0, true)]
+    );
+}
+
+#[test]
+fn entity_ids_are_yielded() {
+    let (world, ids) = world();
+    let mut found: Vec<EntityId> = world
+        .query::<(EntityId, &Static), ()>()
+        .map(|(id, _)| id)
+        .collect();
+    found.sort();
+    assert_eq!(found, ids[4..]);
+}
+
+#[test]
+fn mutable_queries_write_through() {
+    let (mut world, ids) = world();
+    for (mut p, v) in world.query_mut::<(&mut Position, &Velocity), ()>() {
+        p.0 += v.0;
+    }
+    let state = QueryState::<(&Position,), ()>::new();
+    assert_eq!(state.get(world.table(), ids[1]).unwrap().0.0, 2.0);
+    assert_eq!(state.get(world.table(), ids[2]).unwrap().0.0, 2.0);
+}
+
+#[test]
+fn get_respects_filters() {
+    let (world, ids) = world();
+    let state = QueryState::<(&Position,), Without<Static>>::new();
+    assert!(state.get(world.table(), ids[3]).is_some());
+    assert!(state.get(world.table(), ids[4]).is_none());
+}
+
+#[test]
+fn access_records_reads_and_writes() {
+    let state = QueryState::<(&mut Position, &Velocity), With<Collider>>::new();
+    let position = get_component_id::<Position>();
+    let velocity = get_component_id::<Velocity>();
+    // Filters on presence alone read nothing.
+    assert_eq!(state.access().writes(), [position]);
+    assert_eq!(state.access().reads(), [velocity]);
+
+    let reader = QueryState::<(&Position,), ()>::new();
+    let other = QueryState::<(&Velocity, &Collider), ()>::new();
+    assert!(!state.access().is_compatible(reader.access()));
+    assert!(state.access().is_compatible(other.access()));
+}
+
+#[test]
+fn filters_rule_queries_apart() {
+    let with = QueryState::<(&mut Position,), With<Collider>>::new();
+    let without = QueryState::<(&Position,), Without<Collider>>::new();
+    let any = QueryState::<(&Position, Option<&Collider>), ()>::new();
+    let (with, without, any) = (
+        with.filtered_access(),
+        without.filtered_access(),
+        any.filtered_access(),
+    );
+    assert!(with.is_disjoint(without));
+    assert!(with.is_compatible(without));
+    assert!(!with.is_compatible(any));
+}
+
+#[test]
+#[should_panic(expected = "accessed both mutably and immutably")]
+fn aliasing_queries_panic() {
+    QueryState::<(&mut Position, &Position), ()>::new();
+}
+
+#[test]
+#[should_panic(expected = "accessed mutably more than once")]
+fn double_mutable_queries_panic() {
+    QueryState::<(&mut Position, Option<&mut Position>), ()>::new();
+}
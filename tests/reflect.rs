@@ -0,0 +1,110 @@
+use std::any::Any;
+use std::mem::offset_of;
+
+use peano_engine::prelude::*;
+use typeid::ConstTypeId;
+
+#[derive(Component, Clone, PartialEq, Debug)]
+struct Stats {
+    health: u32,
+    speed: f32,
+    name: String,
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Pair(u8, i64);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Marker;
+
+fn registration<T: Component>() -> &'static ComponentRegistration {
+    component_registration(get_component_id::<T>()).unwrap()
+}
+
+#[test]
+fn fields_are_listed_in_order() {
+    let fields = registration::<Stats>().fields;
+    let names: Vec<_> = fields.iter().map(|f| f.name).collect();
+    assert_eq!(names, ["health", "speed", "name"]);
+
+    let speed = registration::<Stats>().field("speed").unwrap();
+    assert_eq!((speed.type_name)(), "f32");
+    assert_eq!(speed.type_id, ConstTypeId::of::<f32>());
+    assert_eq!(speed.offset, offset_of!(Stats, speed));
+    assert!(registration::<Stats>().field("missing").is_none());
+}
+
+#[test]
+fn tuple_fields_are_named_by_index() {
+    let registration = registration::<Pair>();
+    let names: Vec<_> = registration.fields.iter().map(|f| f.name).collect();
+    assert_eq!(names, ["0", "1"]);
+    assert_eq!(registration.field("1").unwrap().offset, offset_of!(Pair, 1));
+    assert!(self::registration::<Marker>().fields.is_empty());
+}
+
+#[test]
+fn fields_are_read_and_written_by_name() {
+    let mut stats = Stats {
+        health: 10,
+        speed: 1.5,
+        name: "goblin".to_string(),
+    };
+    let registration = registration::<Stats>();
+    let health = registration.field("health").unwrap();
+    let name = registration.field("name").unwrap();
+
+    assert_eq!(
+        (health.get)(&stats).unwrap().downcast_ref::<u32>(),
+        Some(&10)
+    );
+    *(health.get_mut)(&mut stats)
+        .unwrap()
+        .downcast_mut::<u32>()
+        .unwrap() += 5;
+    (name.set)(&mut stats, Box::new("orc".to_string())).unwrap();
+    assert_eq!(stats.health, 15);
+    assert_eq!(stats.name, "orc");
+}
+
+#[test]
+fn mismatched_types_are_refused() {
+    let mut stats = Stats {
+        health: 10,
+        speed: 1.5,
+        name: String::new(),
+    };
+    let health = registration::<Stats>().field("health").unwrap();
+
+    // A value of the wrong type is handed back untouched.
+    let value = (health.set)(&mut stats, Box::new(3.0f32)).unwrap_err();
+    assert_eq!(value.downcast_ref::<f32>(), Some(&3.0));
+    assert_eq!(stats.health, 10);
+
+    // So is the value when the component is of the wrong type.
+    let mut pair = Pair(1, 2);
+    assert!((health.set)(&mut pair, Box::new(3u32)).is_err());
+    assert!((health.get)(&pair).is_none());
+    assert!((health.get_mut)(&mut pair as &mut dyn Any).is_none());
+}
+
+#[test]
+fn components_in_the_world_are_edited_by_id() {
+    let mut world = World::new();
+    let id = world.spawn();
+    world.get_mut(id).unwrap().set_component(Pair(1, 2));
+
+    let component = get_component_id::<Pair>();
+    let field = component_registration(component)
+        .unwrap()
+        .field("1")
+        .unwrap();
+    let mut entity = world.get_mut(id).unwrap();
+    let pair = entity.get_mut_by_id(component).unwrap();
+    (field.set)(pair, Box::new(-7i64)).unwrap();
+
+    let entity = world.get(id).unwrap();
+    let value = (field.get)(entity.get_by_id(component).unwrap()).unwrap();
+    assert_eq!(value.downcast_ref::<i64>(), Some(&-7));
+    assert_eq!(entity.get_component::<Pair>(), Some(&Pair(1, -7)));
+}
@@ -0,0 +1,70 @@
+use std::any::Any;
+
+use peano_engine::ecs::world::ResourceError;
+use peano_engine::prelude::*;
+
+#[derive(Resource, Default, PartialEq, Debug)]
+struct Score(u32);
+
+/**
+ * A resource implemented by hand, so it never gets an id
+ */
+struct Unregistered;
+
+impl Resource for Unregistered {
+    fn get_type_id(&self) -> usize {
+        usize::MAX
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[test]
+fn insert_get_and_remove() {
+    let mut world = World::new();
+    assert_eq!(world.insert_resource(Score(1)), Ok(None));
+    assert_eq!(world.insert_resource(Score(2)), Ok(Some(Score(1))));
+    assert_eq!(world.resource::<Score>(), Ok(&Score(2)));
+
+    world.resource_mut::<Score>().unwrap().0 += 1;
+    assert!(world.contains_resource::<Score>());
+    assert_eq!(world.remove_resource::<Score>(), Ok(Score(3)));
+    assert!(!world.contains_resource::<Score>());
+}
+
+#[test]
+fn init_keeps_existing_values() {
+    let mut world = World::new();
+    assert_eq!(world.init_resource::<Score>(), Ok(&mut Score(0)));
+    world.resource_mut::<Score>().unwrap().0 = 5;
+    assert_eq!(world.init_resource::<Score>(), Ok(&mut Score(5)));
+}
+
+#[test]
+fn missing_resources_are_errors() {
+    let mut world = World::new();
+    let missing = ResourceError::Missing(std::any::type_name::<Score>());
+    assert_eq!(world.resource::<Score>(), Err(missing));
+    assert_eq!(world.resource_mut::<Score>(), Err(missing));
+    assert_eq!(world.remove_resource::<Score>(), Err(missing));
+    assert!(missing.to_string().contains("does not exist"));
+}
+
+#[test]
+fn unregistered_resources_are_errors() {
+    let mut world = World::new();
+    let error = world.insert_resource(Unregistered).err().unwrap();
+    assert!(matches!(error, ResourceError::NotRegistered(_)));
+    assert!(error.to_string().contains("derive `Resource`"));
+    assert!(matches!(
+        world.resource::<Unregistered>(),
+        Err(ResourceError::NotRegistered(_))
+    ));
+    assert!(!world.contains_resource::<Unregistered>());
+}
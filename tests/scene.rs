@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use peano_engine::ecs::scene::SceneError;
+use peano_engine::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Component, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[component(scene)]
+struct Name(String);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[component(scene)]
+struct Health(u32);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[component(scene)]
+struct Stat<T>(T);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[component(scene)]
+struct Hostile;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[component(map_entities)]
+struct Target(EntityId);
+
+impl MapEntities for Target {
+    fn map_entities(self, map: &dyn Fn(EntityId) -> Option<EntityId>) -> Option<Self> {
+        map(self.0).map(Self)
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Unsaved;
+
+/**
+ * A player holding a sword, targeting a goblin that targets it back.
+ *
+ * Returns the world and the player, sword and goblin.
+ */
+fn world() -> (World, [EntityId; 3]) {
+    let mut world = World::new();
+    let ids = ["player", "sword", "goblin"].map(|name| {
+        let id = world.spawn();
+        world
+            .get_mut(id)
+            .unwrap()
+            .set_component(Name(name.to_string()));
+        id
+    });
+    let [player, sword, goblin] = ids;
+    world.set_parent(sword, player);
+
+    let mut entity = world.get_mut(player).unwrap();
+    entity.add_component(Health(10));
+    entity.add_component(Health(5));
+    entity.set_component(Stat(3u8));
+    entity.set_component(Target(goblin));
+    entity.set_component(Unsaved);
+    let mut entity = world.get_mut(goblin).unwrap();
+    entity.set_component(Target(player));
+    entity.set_component(Hostile);
+    (world, ids)
+}
+
+fn find(world: &World, name: &str) -> EntityId {
+    world
+        .iter()
+        .find(|e| e.get_component::<Name>().is_some_and(|n| n.0 == name))
+        .map(|e| e.id())
+        .unwrap()
+}
+
+/**
+ * Checks the loaded copies of the entities of `world()`, found by name.
+ */
+fn check_loaded(world: &World, ids: &HashMap<EntityId, EntityId>, saved: [EntityId; 3]) {
+    let [player, sword, goblin] = ["player", "sword", "goblin"].map(|n| find(world, n));
+    assert_eq!(ids[&saved[0]], player);
+    assert_eq!(ids[&saved[1]], sword);
+    assert_eq!(ids[&saved[2]], goblin);
+    assert_ne!(player, saved[0]);
+
+    let entity = world.get(player).unwrap();
+    let health: Vec<_> = entity.get_components::<Health>().copied().collect();
+    assert_eq!(health, [Health(10), Health(5)]);
+    assert_eq!(entity.get_component::<Stat<u8>>(), Some(&Stat(3)));
+    assert_eq!(entity.get_component::<Target>(), Some(&Target(goblin)));
+    assert_eq!(
+        entity.get_component::<Children>().unwrap().to_vec(),
+        [sword]
+    );
+    assert!(!entity.has_component::<Unsaved>());
+
+    let parent = world.get(sword).unwrap().get_component::<Parent>().copied();
+    assert_eq!(parent.map(|p| p.get()), Some(player));
+    let entity = world.get(goblin).unwrap();
+    assert_eq!(entity.get_component::<Target>(), Some(&Target(player)));
+    assert!(entity.has_component::<Hostile>());
+}
+
+/**
+ * A world that already has entities, so loaded ones get different ids.
+ */
+fn target_world() -> World {
+    let mut target = World::new();
+    for _ in 0..5 {
+        target.spawn();
+    }
+    target
+}
+
+#[test]
+fn ron_round_trip() {
+    let (world, saved) = world();
+    let source = Scene::from_world(&world).unwrap().to_ron().unwrap();
+
+    let mut target = target_world();
+    let ids = Scene::from_ron(&source)
+        .unwrap()
+        .spawn(&mut target)
+        .unwrap();
+    assert_eq!(target.len(), 8);
+    check_loaded(&target, &ids, saved);
+}
+
+#[test]
+fn toml_round_trip() {
+    let (world, saved) = world();
+    let source = Scene::from_world(&world).unwrap().to_toml().unwrap();
+
+    let mut target = target_world();
+    let ids = Scene::from_toml(&source)
+        .unwrap()
+        .spawn(&mut target)
+        .unwrap();
+    check_loaded(&target, &ids, saved);
+}
+
+#[test]
+fn bincode_round_trip() {
+    let (world, saved) = world();
+    let bytes = BinaryScene::from_world(&world)
+        .unwrap()
+        .to_bincode()
+        .unwrap();
+
+    let mut target = target_world();
+    let ids = BinaryScene::from_bincode(&bytes)
+        .unwrap()
+        .spawn(&mut target)
+        .unwrap();
+    check_loaded(&target, &ids, saved);
+}
+
+#[test]
+fn references_outside_the_scene_are_dropped() {
+    let (mut world, [_, sword, goblin]) = world();
+    let scene: Scene = Scene::from_entities(&world, [goblin, sword, goblin]).unwrap();
+    assert_eq!(scene.entities.len(), 2);
+
+    let ids = scene.spawn(&mut world).unwrap();
+    let copy = world.get(ids[&goblin]).unwrap();
+    assert!(!copy.has_component::<Target>());
+    assert!(copy.has_component::<Hostile>());
+    assert!(!world.get(ids[&sword]).unwrap().has_component::<Parent>());
+}
+
+#[test]
+fn unknown_components_spawn_nothing() {
+    let (world, _) = world();
+    let source = Scene::from_world(&world)
+        .unwrap()
+        .to_ron()
+        .unwrap()
+        .replace("\"scene::Name\"", "\"scene::Nickname\"");
+
+    let mut target = World::new();
+    let error = Scene::from_ron(&source)
+        .unwrap()
+        .spawn(&mut target)
+        .unwrap_err();
+    assert_eq!(
+        error,
+        SceneError::UnknownComponent("scene::Nickname".to_string())
+    );
+    assert!(target.is_empty());
+}
+
+#[test]
+fn malformed_values_spawn_nothing() {
+    let (world, _) = world();
+    let source = Scene::from_world(&world)
+        .unwrap()
+        .to_ron()
+        .unwrap()
+        .replace("\"goblin\"", "17");
+
+    let mut target = World::new();
+    let error = Scene::from_ron(&source)
+        .unwrap()
+        .spawn(&mut target)
+        .unwrap_err();
+    assert!(matches!(error, SceneError::Deserialize(_)));
+    assert!(target.is_empty());
+    assert!(matches!(
+        Scene::from_ron("not a scene"),
+        Err(SceneError::Deserialize(_))
+    ));
+}
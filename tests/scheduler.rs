@@ -0,0 +1,140 @@
+use peano_engine::ecs::scheduler::Schedule;
+use peano_engine::ecs::world::World;
+use peano_engine::prelude::*;
+
+#[derive(Resource, Default)]
+struct Order(Vec<&'static str>);
+
+#[derive(Resource, Default)]
+struct Counter(u32);
+
+#[derive(Component, Clone, Copy, Debug)]
+struct Spawned;
+
+fn first(mut order: ResMut<Order>) {
+    order.0.push("first");
+}
+
+fn second(mut order: ResMut<Order>) {
+    order.0.push("second");
+}
+
+fn third(mut order: ResMut<Order>) {
+    order.0.push("third");
+}
+
+fn read_a(_: Res<Order>) {}
+
+fn read_b(_: Res<Order>) {}
+
+fn count(mut counter: ResMut<Counter>) {
+    counter.0 += 1;
+}
+
+fn world() -> World {
+    let mut world = World::new();
+    world.init_resource::<Order>().unwrap();
+    world.init_resource::<Counter>().unwrap();
+    world
+}
+
+#[test]
+fn explicit_order_wins_over_insertion_order() {
+    let mut world = world();
+    let mut schedule = Schedule::new();
+    schedule.add_system(third).label("third").after("second");
+    schedule.add_system(first).label("first");
+    schedule.add_system(second).label("second").after("first");
+    schedule.run(&mut world);
+
+    assert_eq!(
+        world.resource::<Order>().unwrap().0,
+        ["first", "second", "third"]
+    );
+    assert!(schedule.ambiguities().is_empty());
+}
+
+#[test]
+fn unordered_conflicts_are_listed() {
+    let mut world = world();
+    let mut schedule = Schedule::new();
+    schedule.add_system(first);
+    schedule.add_system(second);
+    schedule.add_system(read_a);
+    schedule.add_system(count);
+    schedule.run(&mut world);
+
+    // Unordered conflicting systems run in the order they were added.
+    assert_eq!(world.resource::<Order>().unwrap().0, ["first", "second"]);
+    let names: Vec<_> = schedule
+        .ambiguities()
+        .iter()
+        .map(|(a, b)| {
+            let short = |name: &str| name.rsplit("::").next().unwrap().to_string();
+            (short(a), short(b))
+        })
+        .collect();
+    assert_eq!(
+        names,
+        [
+            ("first".to_string(), "second".to_string()),
+            ("first".to_string(), "read_a".to_string()),
+            ("second".to_string(), "read_a".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn readers_are_not_ambiguous() {
+    let mut world = world();
+    world.add_system(read_a);
+    world.add_system(read_b);
+    world.add_system(count);
+    world.run_systems();
+    assert!(world.schedule().ambiguities().is_empty());
+    assert_eq!(world.resource::<Counter>().unwrap().0, 1);
+}
+
+#[test]
+fn sync_points_apply_commands() {
+    let mut world = world();
+    world.add_system(|mut commands: Commands| {
+        commands.add(|world| {
+            let id = world.spawn();
+            world.get_mut(id).unwrap().set_component(Spawned);
+        });
+    });
+    world.add_system(|spawned: Query<&Spawned>, mut counter: ResMut<Counter>| {
+        counter.0 = spawned.iter().count() as u32;
+    });
+    world.add_sync_point();
+    world.add_system(|spawned: Query<&Spawned>, mut order: ResMut<Order>| {
+        if spawned.iter().count() == 1 {
+            order.0.push("after sync");
+        }
+    });
+    world.run_systems();
+
+    assert_eq!(world.resource::<Counter>().unwrap().0, 0);
+    assert_eq!(world.resource::<Order>().unwrap().0, ["after sync"]);
+    assert_eq!(world.query::<(&Spawned,), ()>().count(), 1);
+}
+
+#[test]
+#[should_panic(expected = "cycle")]
+fn cycles_panic() {
+    let mut world = world();
+    let mut schedule = Schedule::new();
+    schedule.add_system(first).label("first").after("second");
+    schedule.add_system(second).label("second").after("first");
+    schedule.run(&mut world);
+}
+
+#[test]
+#[should_panic(expected = "unknown label")]
+fn unknown_labels_panic() {
+    let mut world = world();
+    let mut schedule = Schedule::new();
+    schedule.add_system(first).after("missing");
+    schedule.run(&mut world);
+}
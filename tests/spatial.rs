@@ -0,0 +1,252 @@
+use peano_engine::ecs::mappings::Query;
+use peano_engine::ecs::mappings::spatial::{
+    Nearest, Overlapping, Spatial, SpatialGrid, WithinRadius,
+};
+use peano_engine::math::geometry::Aabb;
+use peano_engine::prelude::*;
+use proptest::prelude::*;
+
+#[derive(Component, Clone, Copy, Debug)]
+struct Point(Vec3);
+
+impl Spatial for Point {
+    fn bounds(&self) -> Aabb {
+        Aabb::from_center_half_extents(self.0, Vec3::splat(0.1))
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+#[component(storage = "sparse")]
+struct Marker(Vec3);
+
+impl Spatial for Marker {
+    fn bounds(&self) -> Aabb {
+        Aabb::from_center_half_extents(self.0, Vec3::splat(0.1))
+    }
+}
+
+fn sorted(mut ids: Vec<EntityId>) -> Vec<EntityId> {
+    ids.sort();
+    ids
+}
+
+#[test]
+fn sync_follows_the_world() {
+    let mut world = World::new();
+    let ids: Vec<_> = (0..3)
+        .map(|i| {
+            let id = world.spawn();
+            world
+                .get_mut(id)
+                .unwrap()
+                .set_component(Point(Vec3::new(i as f32 * 4.0, 0.0, 0.0)));
+            id
+        })
+        .collect();
+    let mut grid = SpatialGrid::new(1.0);
+    grid.sync::<Point>(&world);
+    assert_eq!(grid.len(), 3);
+
+    world
+        .get_mut(ids[0])
+        .unwrap()
+        .set_component(Point(Vec3::new(8.0, 0.5, 0.0)));
+    world.despawn(ids[1]);
+    grid.sync::<Point>(&world);
+    assert_eq!(grid.len(), 2);
+    assert!(!grid.contains(ids[1]));
+    assert_eq!(
+        sorted(
+            WithinRadius {
+                center: Vec3::new(8.0, 0.0, 0.0),
+                radius: 1.0,
+            }
+            .query(&grid)
+        ),
+        [ids[0], ids[2]]
+    );
+}
+
+#[test]
+fn sync_only_visits_changes() {
+    let mut world = World::new();
+    let id = world.spawn();
+    world.get_mut(id).unwrap().set_component(Point(Vec3::ZERO));
+    let mut grid = SpatialGrid::new(1.0);
+    grid.sync::<Point>(&world);
+
+    // Bounds set by hand survive a sync while the component is untouched.
+    let moved = Aabb::from_center_half_extents(Vec3::splat(5.0), Vec3::ONE);
+    grid.update(id, moved);
+    grid.sync::<Point>(&world);
+    assert_eq!(grid.bounds(id), Some(moved));
+
+    world
+        .get_mut(id)
+        .unwrap()
+        .set_component(Point(Vec3::new(2.0, 0.0, 0.0)));
+    grid.sync::<Point>(&world);
+    assert_eq!(grid.bounds(id).unwrap().center(), Vec3::new(2.0, 0.0, 0.0));
+
+    // Removals the world already forgot make the next sync rebuild.
+    world.despawn(id);
+    world.clear_trackers();
+    world.clear_trackers();
+    assert!(grid.contains(id));
+    grid.sync::<Point>(&world);
+    assert!(grid.is_empty());
+}
+
+#[test]
+fn sync_indexes_sparse_components() {
+    let mut world = World::new();
+    let id = world.spawn();
+    world
+        .get_mut(id)
+        .unwrap()
+        .set_component(Marker(Vec3::new(2.0, 2.0, 2.0)));
+    world.spawn();
+
+    let mut grid = SpatialGrid::new(1.0);
+    grid.sync::<Marker>(&world);
+    assert_eq!(grid.len(), 1);
+    assert_eq!(
+        Overlapping(Aabb::from_center_half_extents(Vec3::splat(2.0), Vec3::ONE)).query(&grid),
+        [id]
+    );
+
+    world.get_mut(id).unwrap().remove_component::<Marker>();
+    grid.sync::<Marker>(&world);
+    assert!(grid.is_empty());
+}
+
+#[test]
+fn sync_encloses_every_instance() {
+    let mut world = World::new();
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    entity.add_component(Point(Vec3::ZERO));
+    entity.add_component(Point(Vec3::new(5.0, 0.0, 0.0)));
+
+    let mut grid = SpatialGrid::new(1.0);
+    grid.sync::<Point>(&world);
+    let bounds = grid.bounds(id).unwrap();
+    assert_eq!(bounds.min, Vec3::new(-0.1, -0.1, -0.1));
+    assert_eq!(bounds.max, Vec3::new(5.1, 0.1, 0.1));
+    // The second instance is the one nearby.
+    let nearest = Nearest {
+        point: Vec3::new(5.0, 3.0, 0.0),
+        k: 1,
+    }
+    .query(&grid);
+    assert_eq!(nearest[0].0, id);
+    assert!((nearest[0].1 - 2.9).abs() < 1e-5);
+}
+
+fn overlapping(grid: &SpatialGrid, bounds: Aabb) -> Vec<EntityId> {
+    sorted(Overlapping(bounds).query(grid))
+}
+
+fn cube(center: Vec3, half: f32) -> Aabb {
+    Aabb::from_center_half_extents(center, Vec3::splat(half))
+}
+
+fn brute_nearest(bounds: &[(EntityId, Aabb)], point: Vec3, k: usize) -> Vec<f32> {
+    let mut distances: Vec<f32> = bounds
+        .iter()
+        .map(|(_, b)| b.distance_squared_to_point(point).sqrt())
+        .collect();
+    distances.sort_by(f32::total_cmp);
+    distances.truncate(k);
+    distances
+}
+
+proptest! {
+    #[test]
+    fn nearest_matches_brute_force(
+        boxes in prop::collection::vec(
+            ((-50.0f32..50.0, -50.0f32..50.0, -50.0f32..50.0), 0.0f32..3.0),
+            1..40,
+        ),
+        point in (-60.0f32..60.0, -60.0f32..60.0, -60.0f32..60.0),
+        k in 1usize..8,
+    ) {
+        let mut world = World::new();
+        let mut grid = SpatialGrid::new(2.0);
+        let bounds: Vec<_> = boxes
+            .into_iter()
+            .map(|((x, y, z), half)| (world.spawn(), cube(Vec3::new(x, y, z), half)))
+            .collect();
+        for &(id, b) in &bounds {
+            grid.update(id, b);
+        }
+        let point = Vec3::new(point.0, point.1, point.2);
+
+        let found: Vec<f32> = Nearest { point, k }.query(&grid).into_iter().map(|(_, d)| d).collect();
+        prop_assert_eq!(found, brute_nearest(&bounds, point, k));
+    }
+}
+
+#[test]
+fn huge_and_infinite_bounds() {
+    let mut world = World::new();
+    let mut grid = SpatialGrid::new(1.0);
+    let small = world.spawn();
+    let huge = world.spawn();
+    let infinite = world.spawn();
+    let broken = world.spawn();
+    grid.update(small, cube(Vec3::ZERO, 0.5));
+    grid.update(huge, cube(Vec3::new(1e30, 0.0, 0.0), 1e29));
+    grid.update(
+        infinite,
+        Aabb {
+            min: Vec3::new(f32::NEG_INFINITY, -1.0, f32::NEG_INFINITY),
+            max: Vec3::new(f32::INFINITY, 0.0, f32::INFINITY),
+        },
+    );
+    grid.update(broken, cube(Vec3::ZERO, 1.0));
+    grid.update(broken, cube(Vec3::splat(f32::NAN), 1.0));
+    assert!(!grid.contains(broken));
+    assert_eq!(grid.len(), 3);
+
+    assert_eq!(overlapping(&grid, cube(Vec3::ZERO, 0.1)), [small, infinite]);
+    assert_eq!(
+        overlapping(&grid, cube(Vec3::new(1e30, 0.0, 0.0), 1.0)),
+        [huge, infinite]
+    );
+    assert_eq!(
+        overlapping(
+            &grid,
+            Aabb {
+                min: Vec3::splat(f32::NEG_INFINITY),
+                max: Vec3::splat(f32::INFINITY),
+            }
+        ),
+        [small, huge, infinite]
+    );
+    assert_eq!(
+        sorted(
+            WithinRadius {
+                center: Vec3::new(0.0, 5.0, 0.0),
+                radius: 5.5,
+            }
+            .query(&grid)
+        ),
+        [small, infinite]
+    );
+    let nearest = Nearest {
+        point: Vec3::new(0.0, 10.0, 0.0),
+        k: 2,
+    }
+    .query(&grid);
+    assert_eq!(nearest, [(small, 9.5), (infinite, 10.0)]);
+
+    // Shrinking moves an entity back into the cells.
+    grid.update(huge, cube(Vec3::new(3.0, 0.0, 0.0), 0.5));
+    assert_eq!(
+        overlapping(&grid, cube(Vec3::new(3.0, 0.0, 0.0), 0.1)),
+        [huge, infinite]
+    );
+    assert!(grid.remove(infinite));
+    assert_eq!(overlapping(&grid, cube(Vec3::ZERO, 10.0)), [small, huge]);
+}
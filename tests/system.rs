@@ -0,0 +1,157 @@
+use peano_engine::prelude::*;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Position(f32);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Velocity(f32);
+
+#[derive(Resource, Default)]
+struct Gravity(f32);
+
+#[derive(Resource, Default)]
+struct Moved(usize);
+
+#[derive(Resource, Default)]
+struct Unused;
+
+fn fall(gravity: Res<Gravity>, mut velocities: Query<(&mut Velocity,)>) {
+    for (mut v,) in velocities.iter_mut() {
+        v.0 -= gravity.0;
+    }
+}
+
+fn integrate(mut positions: Query<(&mut Position, &Velocity)>, mut moved: ResMut<Moved>) {
+    for (mut p, v) in positions.iter_mut() {
+        p.0 += v.0;
+        moved.0 += 1;
+    }
+}
+
+fn world() -> (World, EntityId) {
+    let mut world = World::new();
+    world.insert_resource(Gravity(2.0)).unwrap();
+    world.init_resource::<Moved>().unwrap();
+    let id = world.spawn();
+    let mut entity = world.get_mut(id).unwrap();
+    entity.set_component(Position(0.0));
+    entity.set_component(Velocity(0.0));
+    (world, id)
+}
+
+#[test]
+fn function_systems_receive_their_params() {
+    let (mut world, id) = world();
+    world.add_system(fall);
+    world.add_system(integrate);
+    world.run_systems();
+    world.run_systems();
+
+    let entity = world.get(id).unwrap();
+    assert_eq!(entity.get_component::<Velocity>(), Some(&Velocity(-4.0)));
+    assert_eq!(entity.get_component::<Position>(), Some(&Position(-6.0)));
+    assert_eq!(world.resource::<Moved>().unwrap().0, 2);
+}
+
+#[test]
+fn systems_declare_their_access() {
+    let mut world = World::new();
+    let mut fall = fall.into_system();
+    let mut integrate = integrate.into_system();
+    fall.initialize(&mut world);
+    integrate.initialize(&mut world);
+
+    let velocity = get_component_id::<Velocity>();
+    let position = get_component_id::<Position>();
+    assert_eq!(fall.access().components.writes(), [velocity]);
+    assert_eq!(
+        fall.access().resources.reads(),
+        [get_resource_id::<Gravity>()]
+    );
+    assert_eq!(integrate.access().components.writes(), [position]);
+    assert_eq!(integrate.access().components.reads(), [velocity]);
+    assert_eq!(
+        integrate.access().resources.writes(),
+        [get_resource_id::<Moved>()]
+    );
+    assert!(!fall.access().is_compatible(integrate.access()));
+
+    let mut reader = (|_: Query<(&Position,)>, _: Res<Unused>| {}).into_system();
+    reader.initialize(&mut world);
+    assert!(reader.access().is_compatible(fall.access()));
+    assert!(!reader.access().is_compatible(integrate.access()));
+}
+
+#[test]
+fn system_names_come_from_the_function() {
+    assert!(fall.into_system().name().ends_with("::fall"));
+}
+
+#[test]
+fn commands_are_applied_after_the_run() {
+    let mut world = World::new();
+    let mut system = (|mut commands: Commands| {
+        commands.add(|world| {
+            world.spawn();
+        });
+    })
+    .into_system();
+    system.initialize(&mut world);
+    unsafe { system.run(&mut world) };
+    assert!(world.is_empty());
+    system.apply(&mut world);
+    assert_eq!(world.len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "does not exist in the world")]
+fn missing_resources_panic_on_run() {
+    let mut world = World::new();
+    world.add_system(|_: Res<Unused>| {});
+    world.run_systems();
+}
+
+fn split(
+    mut moving: Query<(&mut Position,), With<Velocity>>,
+    mut still: Query<(&mut Position,), Without<Velocity>>,
+) {
+    for (mut p,) in moving.iter_mut() {
+        p.0 += 1.0;
+    }
+    for (mut p,) in still.iter_mut() {
+        p.0 -= 1.0;
+    }
+}
+
+#[test]
+fn disjoint_queries_share_a_system() {
+    let (mut world, id) = world();
+    let still = world.spawn();
+    world.get_mut(still).unwrap().set_component(Position(0.0));
+    world.add_system(split);
+    world.run_systems();
+
+    let position = |id| *world.get(id).unwrap().get_component::<Position>().unwrap();
+    assert_eq!(position(id), Position(1.0));
+    assert_eq!(position(still), Position(-1.0));
+}
+
+#[test]
+#[should_panic(expected = "conflicts with another query")]
+fn overlapping_queries_panic() {
+    let mut world = World::new();
+    let mut system =
+        (|_: Query<(&mut Position,), With<Velocity>>, _: Query<(&Position,)>| {}).into_system();
+    system.initialize(&mut world);
+}
+
+#[test]
+fn removed_components_are_read() {
+    let mut world = World::new();
+    let mut system = (|_: RemovedComponents<Velocity>| {}).into_system();
+    system.initialize(&mut world);
+    assert_eq!(
+        system.access().components.reads(),
+        [get_component_id::<Velocity>()]
+    );
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use peano_engine::ecs::mappings::table::Table;
+use peano_engine::prelude::*;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Position(f32, f32);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Velocity(f32, f32);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Tag;
+
+#[derive(Component, Clone, Debug)]
+struct Tracked(Arc<()>);
+
+fn entity(index: u64) -> EntityId {
+    EntityId::from_bits(index)
+}
+
+#[test]
+fn entities_move_between_archetypes() {
+    let mut table = Table::new();
+    let a = entity(0);
+    let b = entity(1);
+    assert!(table.insert_entity(a));
+    assert!(table.insert_entity(b));
+    assert!(!table.insert_entity(a));
+
+    table.insert(a, Position(1.0, 2.0)).unwrap();
+    table.insert(b, Position(3.0, 4.0)).unwrap();
+    let shared = table.location(a).unwrap().archetype;
+    assert_eq!(table.location(b).unwrap().archetype, shared);
+
+    table.insert(a, Velocity(5.0, 6.0)).unwrap();
+    let moved = table.location(a).unwrap().archetype;
+    assert_ne!(moved, shared);
+    assert_eq!(table.get::<Position>(a), Some(&Position(1.0, 2.0)));
+    assert_eq!(table.get::<Velocity>(a), Some(&Velocity(5.0, 6.0)));
+    assert_eq!(table.get::<Position>(b), Some(&Position(3.0, 4.0)));
+
+    assert_eq!(table.remove::<Velocity>(a), Some(Velocity(5.0, 6.0)));
+    assert_eq!(table.location(a).unwrap().archetype, shared);
+    assert_eq!(table.get::<Position>(a), Some(&Position(1.0, 2.0)));
+    assert!(table.remove::<Velocity>(a).is_none());
+}
+
+#[test]
+fn components_are_stored_contiguously() {
+    let mut table = Table::new();
+    for i in 0..4 {
+        let id = entity(i);
+        table.insert_entity(id);
+        table.insert(id, Position(i as f32, 0.0)).unwrap();
+        table.insert(id, Tag).unwrap();
+    }
+    let location = table.location(entity(2)).unwrap();
+    let archetype = table.archetype(location.archetype);
+    assert_eq!(archetype.len(), 4);
+    assert_eq!(archetype.entities()[location.row], entity(2));
+    let xs: Vec<f32> = archetype
+        .column::<Position>()
+        .unwrap()
+        .iter()
+        .map(|p| p.0)
+        .collect();
+    assert_eq!(xs, [0.0, 1.0, 2.0, 3.0]);
+    assert!(archetype.column::<Velocity>().is_none());
+}
+
+#[test]
+fn removing_a_row_keeps_the_other_rows() {
+    let mut table = Table::new();
+    let ids: Vec<_> = (0..3).map(entity).collect();
+    for (i, &id) in ids.iter().enumerate() {
+        table.insert_entity(id);
+        table.insert(id, Position(i as f32, 0.0)).unwrap();
+    }
+
+    // The last row is swapped into the removed one.
+    assert!(table.remove_entity(ids[0]));
+    assert!(!table.contains(ids[0]));
+    assert!(!table.remove_entity(ids[0]));
+    assert_eq!(table.get::<Position>(ids[1]), Some(&Position(1.0, 0.0)));
+    assert_eq!(table.get::<Position>(ids[2]), Some(&Position(2.0, 0.0)));
+    assert_eq!(table.location(ids[2]).unwrap().row, 0);
+}
+
+#[test]
+fn insert_replaces_and_returns_the_old_value() {
+    let mut table = Table::new();
+    let id = entity(0);
+    assert_eq!(table.insert(id, Tag), Err(Tag));
+    table.insert_entity(id);
+    assert_eq!(table.insert(id, Position(1.0, 1.0)), Ok(None));
+    assert_eq!(
+        table.insert(id, Position(2.0, 2.0)),
+        Ok(Some(Position(1.0, 1.0)))
+    );
+    assert_eq!(table.get::<Position>(id), Some(&Position(2.0, 2.0)));
+}
+
+#[test]
+fn components_are_dropped_once() {
+    let counter = Arc::new(());
+    let mut table = Table::new();
+    let a = entity(0);
+    let b = entity(1);
+    table.insert_entity(a);
+    table.insert_entity(b);
+    table.insert(a, Tracked(counter.clone())).unwrap();
+    table.insert(b, Tracked(counter.clone())).unwrap();
+    assert_eq!(Arc::strong_count(&counter), 3);
+
+    // Moving to another archetype must not drop or duplicate.
+    table.insert(a, Tag).unwrap();
+    table.remove::<Tag>(a);
+    assert_eq!(Arc::strong_count(&counter), 3);
+    assert!(Arc::ptr_eq(&table.get::<Tracked>(a).unwrap().0, &counter));
+
+    table.remove_by_id(b, get_component_id::<Tracked>());
+    assert_eq!(Arc::strong_count(&counter), 2);
+    table.remove_entity(a);
+    assert_eq!(Arc::strong_count(&counter), 1);
+
+    table.insert(b, Tracked(counter.clone())).unwrap();
+    drop(table);
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
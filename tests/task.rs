@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use peano_engine::ecs::task::{TaskHandle, Tasks};
+use peano_engine::ecs::world::World;
+use peano_engine::prelude::*;
+use tokio::runtime::{Builder, Runtime};
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Loaded(u32);
+
+/**
+ * A single-threaded runtime, so tasks only make progress while a
+ * test drives it.
+ */
+fn runtime() -> Runtime {
+    Builder::new_current_thread().enable_all().build().unwrap()
+}
+
+/**
+ * Drives the runtime until the task is done, failing after a second.
+ */
+fn wait(runtime: &Runtime, handle: &TaskHandle) {
+    runtime.block_on(async {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while !handle.is_finished() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("the task never finished");
+    });
+}
+
+fn loaded(world: &World, id: EntityId) -> Option<Loaded> {
+    world.get(id).unwrap().get_component::<Loaded>().copied()
+}
+
+#[test]
+fn results_wait_for_apply() {
+    let runtime = runtime();
+    let tasks = Tasks::with_handle(runtime.handle().clone());
+    let mut world = World::new();
+    let entity = world.spawn();
+    let other = world.spawn();
+    let component = tasks.spawn_component(entity, async { Loaded(7) });
+    let callback = tasks.spawn(async { 3 }, move |world, value| {
+        world.get_mut(other).unwrap().set_component(Loaded(value));
+    });
+    world.insert_resource(tasks).unwrap();
+
+    wait(&runtime, &component);
+    wait(&runtime, &callback);
+    assert_eq!(loaded(&world, entity), None);
+    Tasks::apply(&mut world);
+    assert_eq!(loaded(&world, entity), Some(Loaded(7)));
+    assert_eq!(loaded(&world, other), Some(Loaded(3)));
+}
+
+#[test]
+fn run_systems_applies_finished_tasks() {
+    let runtime = runtime();
+    let tasks = Tasks::with_handle(runtime.handle().clone());
+    let mut world = World::new();
+    let entity = world.spawn();
+    let handle = tasks.spawn_component(entity, async { Loaded(1) });
+    world.insert_resource(tasks).unwrap();
+
+    wait(&runtime, &handle);
+    world.run_systems();
+    assert_eq!(loaded(&world, entity), Some(Loaded(1)));
+}
+
+#[test]
+fn despawning_owner_cancels_the_task() {
+    let runtime = runtime();
+    let tasks = Tasks::with_handle(runtime.handle().clone());
+    let mut world = World::new();
+    let owner = world.spawn();
+    let witness = world.spawn();
+    let handle = tasks.spawn_owned(
+        owner,
+        async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            5
+        },
+        move |world, _, value| {
+            world.get_mut(witness).unwrap().set_component(Loaded(value));
+        },
+    );
+    world.insert_resource(tasks).unwrap();
+
+    world.despawn(owner);
+    wait(&runtime, &handle);
+    Tasks::apply(&mut world);
+    assert_eq!(loaded(&world, witness), None);
+}
+
+#[test]
+fn results_for_despawned_owners_are_dropped() {
+    let runtime = runtime();
+    let tasks = Tasks::with_handle(runtime.handle().clone());
+    let mut world = World::new();
+    let owner = world.spawn();
+    let witness = world.spawn();
+    let handle = tasks.spawn_owned(owner, async { 5 }, move |world, _, value| {
+        world.get_mut(witness).unwrap().set_component(Loaded(value));
+    });
+    world.insert_resource(tasks).unwrap();
+
+    // The task already finished, so despawning cannot cancel it.
+    wait(&runtime, &handle);
+    world.despawn(owner);
+    Tasks::apply(&mut world);
+    assert_eq!(loaded(&world, witness), None);
+}
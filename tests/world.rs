@@ -0,0 +1,78 @@
+use peano_engine::ecs::world::World;
+use peano_engine::prelude::*;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+struct Health(u32);
+
+#[test]
+fn despawned_slots_are_reused_with_a_new_generation() {
+    let mut world = World::new();
+    let first = world.spawn();
+    let second = world.spawn();
+    assert_ne!(first, second);
+
+    assert!(world.despawn(first));
+    let reused = world.spawn();
+    assert_eq!(reused.index(), first.index());
+    assert_eq!(reused.generation(), first.generation() + 1);
+    assert_ne!(reused, first);
+    assert_eq!(world.len(), 2);
+}
+
+#[test]
+fn stale_handles_are_rejected() {
+    let mut world = World::new();
+    let stale = world.spawn();
+    world.get_mut(stale).unwrap().set_component(Health(10));
+    world.despawn(stale);
+    let fresh = world.spawn();
+    world.get_mut(fresh).unwrap().set_component(Health(3));
+
+    assert!(!world.contains(stale));
+    assert!(world.get(stale).is_none());
+    assert!(world.get_mut(stale).is_none());
+    assert!(!world.despawn(stale));
+    // The slot's new owner is untouched by the stale handle.
+    assert_eq!(
+        world.get(fresh).unwrap().get_component::<Health>(),
+        Some(&Health(3))
+    );
+}
+
+#[test]
+fn despawn_drops_components() {
+    let mut world = World::new();
+    let id = world.spawn();
+    world.get_mut(id).unwrap().set_component(Health(1));
+    world.despawn(id);
+    let reused = world.spawn();
+    assert!(
+        world
+            .get(reused)
+            .unwrap()
+            .get_component::<Health>()
+            .is_none()
+    );
+}
+
+#[test]
+fn iter_visits_live_entities() {
+    let mut world = World::new();
+    let ids: Vec<_> = (0..4).map(|_| world.spawn()).collect();
+    world.despawn(ids[1]);
+    world.despawn(ids[3]);
+
+    let live: Vec<_> = world.iter().map(|e| e.id()).collect();
+    assert_eq!(live, [ids[0], ids[2]]);
+    assert_eq!(world.len(), 2);
+    assert!(!world.is_empty());
+}
+
+#[test]
+fn ids_round_trip_through_bits() {
+    let mut world = World::new();
+    let id = world.spawn();
+    world.despawn(id);
+    let id = world.spawn();
+    assert_eq!(EntityId::from_bits(id.to_bits()), id);
+}